@@ -8,7 +8,10 @@ use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
 use env_logger::Env;
 use log::info;
 
-use fidget::context::{Context, Node};
+use fidget::{
+    context::{Context, Node},
+    var::VarRegistry,
+};
 
 /// Simple test program
 #[derive(Parser)]
@@ -110,6 +113,7 @@ enum RenderMode3D {
     BlurredOcclusion { denoise: bool },
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for RenderMode3DArg {
     fn default() -> Self {
         Self::Heightmap
@@ -218,6 +222,8 @@ enum ScriptType {
     Rhai,
     /// Raw VM instructions
     Vm,
+    /// Infix math expression
+    Expr,
 }
 
 fn parse_vec3(s: &str) -> Result<[f32; 3]> {
@@ -542,10 +548,11 @@ fn load_script(settings: &ScriptSettings) -> Result<(Context, Node)> {
             match ext.as_deref() {
                 Some("rhai") => ScriptType::Rhai,
                 Some("vm") => ScriptType::Vm,
+                Some("expr") => ScriptType::Expr,
                 Some(s) => {
                     bail!(
-                        "Unknown extension '{s}', should be '.rhai' or '.vm' \
-                         (or specify --type)"
+                        "Unknown extension '{s}', should be '.rhai', '.vm', \
+                         or '.expr' (or specify --type)"
                     )
                 }
                 None => bail!("cannot detect script type without extension"),
@@ -568,6 +575,17 @@ fn load_script(settings: &ScriptSettings) -> Result<(Context, Node)> {
             let node = ctx.import(&out.shapes[0].tree);
             (ctx, node)
         }
+        ScriptType::Expr => {
+            let mut script = String::new();
+            file.read_to_string(&mut script)
+                .context("failed to read script to string")?;
+            let mut vars = VarRegistry::new();
+            let tree = fidget::expr::parse_with_vars(&script, &mut vars)?;
+            let mut ctx = Context::new();
+            let node = ctx.import(&tree);
+            ctx.var_registry_mut().merge(&vars)?;
+            (ctx, node)
+        }
         ScriptType::Auto => unreachable!(),
     };
     info!("Loaded file in {:?}", now.elapsed());
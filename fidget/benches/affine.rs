@@ -0,0 +1,68 @@
+use criterion::{
+    black_box, criterion_group, criterion_main, BenchmarkId, Criterion,
+};
+use fidget::{
+    eval::MathFunction,
+    shape::{EzShape, Shape},
+    vm::{VmAffineShape, VmShape},
+};
+
+const MODELS: [(&str, &str); 6] = [
+    ("bear", include_str!("../../models/bear.vm")),
+    ("colonnade", include_str!("../../models/colonnade.vm")),
+    ("hi", include_str!("../../models/hi.vm")),
+    ("prospero", include_str!("../../models/prospero.vm")),
+    ("quarter", include_str!("../../models/quarter.vm")),
+    ("tanglecube", include_str!("../../models/tanglecube.vm")),
+];
+
+/// Number of tiles along each axis of the `[-1, 1]` square
+const TILES: usize = 32;
+
+/// Evaluates every tile in the `z = 0` plane, returning the number of tiles
+/// that are entirely inside or outside of the shape
+fn pruned<F: MathFunction>(shape: &Shape<F>) -> usize {
+    let tape = shape.ez_interval_tape();
+    let mut eval = Shape::<F>::new_interval_eval();
+    let mut count = 0;
+    let step = 2.0 / TILES as f32;
+    for i in 0..TILES {
+        let x = -1.0 + step * i as f32;
+        for j in 0..TILES {
+            let y = -1.0 + step * j as f32;
+            let (out, _trace) = eval
+                .eval(&tape, [x, x + step], [y, y + step], [0.0, 0.0])
+                .unwrap();
+            if out.upper() < 0.0 || out.lower() > 0.0 {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+pub fn tile_pruning(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tile pruning (32 x 32, 2d)");
+    for (name, text) in MODELS {
+        let (ctx, root) = fidget::Context::from_text(text.as_bytes()).unwrap();
+        let shape_vm = &VmShape::new(&ctx, root).unwrap();
+        let shape_affine = &VmAffineShape::new(&ctx, root).unwrap();
+
+        let total = (TILES * TILES) as f32;
+        println!(
+            "{name}: {:.1}% of tiles pruned (interval), {:.1}% (affine)",
+            pruned(shape_vm) as f32 / total * 100.0,
+            pruned(shape_affine) as f32 / total * 100.0,
+        );
+
+        group.bench_function(BenchmarkId::new("interval", name), move |b| {
+            b.iter(|| black_box(pruned(shape_vm)))
+        });
+        group.bench_function(BenchmarkId::new("affine", name), move |b| {
+            b.iter(|| black_box(pruned(shape_affine)))
+        });
+    }
+}
+
+criterion_group!(benches, tile_pruning);
+criterion_main!(benches);
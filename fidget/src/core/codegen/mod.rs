@@ -0,0 +1,224 @@
+//! Source code generation for math expressions
+//!
+//! This module converts flattened expressions (in the form of an
+//! [`SsaTape`](crate::compiler::SsaTape)) into standalone source code, which
+//! can be compiled and evaluated without linking against Fidget.
+//!
+//! - [`shader`] generates a GLSL, WGSL, or HLSL function for use in GPU
+//!   pipelines; [`Context::to_shader`](crate::Context::to_shader) is a
+//!   convenience wrapper for a single node.
+//! - [`source`] generates a standalone C99 or `no_std` Rust function from a
+//!   register-allocated [`RegTape`](crate::compiler::RegTape), with point,
+//!   float slice, or interval semantics;
+//!   [`VmData::to_source`](crate::vm::VmData::to_source) is a convenience
+//!   wrapper.
+use crate::{
+    compiler::{RegOp, SsaOp},
+    context::{BinaryOpcode, TernaryOpcode, UnaryOpcode},
+};
+
+mod shader;
+mod source;
+
+pub use shader::{shader, ShaderLanguage, ShaderOptions};
+pub use source::{source, SourceLanguage, SourceMode, SourceOptions};
+
+/// Argument to an operation, which may be a register or an immediate
+#[derive(Copy, Clone, Debug)]
+enum Arg {
+    Reg(u32),
+    Imm(f32),
+}
+
+/// Normalized form of an [`SsaOp`] or [`RegOp`], with register / immediate
+/// variants merged
+///
+/// In-place operations are converted to read the previous value of their
+/// output register as an explicit argument.  For [`RegOp`], memory slots and
+/// registers share a single index space (as in the VM), so `Load` and `Store`
+/// become copies.
+#[derive(Copy, Clone, Debug)]
+enum Step {
+    Input(u32, u32),
+    Output(u32, u32),
+    Copy(u32, Arg),
+    Unary(u32, UnaryOpcode, u32),
+    Binary(u32, BinaryOpcode, Arg, Arg),
+    Ternary(u32, TernaryOpcode, Arg, Arg, Arg),
+}
+
+/// Converts a register index into a `u32`
+fn r<T: Into<u32>>(t: T) -> u32 {
+    t.into()
+}
+
+macro_rules! impl_step_from {
+    ($t:ident $(, $extra_pat:pat => $extra:expr)*) => {
+        impl From<$t> for Step {
+            fn from(op: $t) -> Self {
+                use Arg::{Imm, Reg};
+                use BinaryOpcode as B;
+                use UnaryOpcode as U;
+                match op {
+                    $t::Input(out, i) => Step::Input(r(out), i),
+                    $t::Output(arg, i) => Step::Output(r(arg), i),
+                    $t::CopyReg(out, arg) => Step::Copy(r(out), Reg(r(arg))),
+                    $t::CopyImm(out, imm) => Step::Copy(r(out), Imm(imm)),
+
+                    $t::NegReg(out, arg) => Step::Unary(r(out), U::Neg, r(arg)),
+                    $t::AbsReg(out, arg) => Step::Unary(r(out), U::Abs, r(arg)),
+                    $t::RecipReg(out, arg) => Step::Unary(r(out), U::Recip, r(arg)),
+                    $t::SqrtReg(out, arg) => Step::Unary(r(out), U::Sqrt, r(arg)),
+                    $t::SquareReg(out, arg) => Step::Unary(r(out), U::Square, r(arg)),
+                    $t::FloorReg(out, arg) => Step::Unary(r(out), U::Floor, r(arg)),
+                    $t::CeilReg(out, arg) => Step::Unary(r(out), U::Ceil, r(arg)),
+                    $t::RoundReg(out, arg) => Step::Unary(r(out), U::Round, r(arg)),
+                    $t::SinReg(out, arg) => Step::Unary(r(out), U::Sin, r(arg)),
+                    $t::CosReg(out, arg) => Step::Unary(r(out), U::Cos, r(arg)),
+                    $t::TanReg(out, arg) => Step::Unary(r(out), U::Tan, r(arg)),
+                    $t::AsinReg(out, arg) => Step::Unary(r(out), U::Asin, r(arg)),
+                    $t::AcosReg(out, arg) => Step::Unary(r(out), U::Acos, r(arg)),
+                    $t::AtanReg(out, arg) => Step::Unary(r(out), U::Atan, r(arg)),
+                    $t::ExpReg(out, arg) => Step::Unary(r(out), U::Exp, r(arg)),
+                    $t::LnReg(out, arg) => Step::Unary(r(out), U::Ln, r(arg)),
+                    $t::SinhReg(out, arg) => Step::Unary(r(out), U::Sinh, r(arg)),
+                    $t::CoshReg(out, arg) => Step::Unary(r(out), U::Cosh, r(arg)),
+                    $t::TanhReg(out, arg) => Step::Unary(r(out), U::Tanh, r(arg)),
+                    $t::Exp2Reg(out, arg) => Step::Unary(r(out), U::Exp2, r(arg)),
+                    $t::Log2Reg(out, arg) => Step::Unary(r(out), U::Log2, r(arg)),
+                    $t::Log10Reg(out, arg) => Step::Unary(r(out), U::Log10, r(arg)),
+                    $t::NotReg(out, arg) => Step::Unary(r(out), U::Not, r(arg)),
+
+                    $t::AddRegImm(out, arg, imm) => {
+                        Step::Binary(r(out), B::Add, Reg(r(arg)), Imm(imm))
+                    }
+                    $t::MulRegImm(out, arg, imm) => {
+                        Step::Binary(r(out), B::Mul, Reg(r(arg)), Imm(imm))
+                    }
+                    $t::DivRegImm(out, arg, imm) => {
+                        Step::Binary(r(out), B::Div, Reg(r(arg)), Imm(imm))
+                    }
+                    $t::DivImmReg(out, arg, imm) => {
+                        Step::Binary(r(out), B::Div, Imm(imm), Reg(r(arg)))
+                    }
+                    $t::SubImmReg(out, arg, imm) => {
+                        Step::Binary(r(out), B::Sub, Imm(imm), Reg(r(arg)))
+                    }
+                    $t::SubRegImm(out, arg, imm) => {
+                        Step::Binary(r(out), B::Sub, Reg(r(arg)), Imm(imm))
+                    }
+                    $t::ModRegImm(out, arg, imm) => {
+                        Step::Binary(r(out), B::Mod, Reg(r(arg)), Imm(imm))
+                    }
+                    $t::ModImmReg(out, arg, imm) => {
+                        Step::Binary(r(out), B::Mod, Imm(imm), Reg(r(arg)))
+                    }
+                    $t::AtanRegImm(out, arg, imm) => {
+                        Step::Binary(r(out), B::Atan, Reg(r(arg)), Imm(imm))
+                    }
+                    $t::AtanImmReg(out, arg, imm) => {
+                        Step::Binary(r(out), B::Atan, Imm(imm), Reg(r(arg)))
+                    }
+                    $t::PowRegImm(out, arg, imm) => {
+                        Step::Binary(r(out), B::Pow, Reg(r(arg)), Imm(imm))
+                    }
+                    $t::PowImmReg(out, arg, imm) => {
+                        Step::Binary(r(out), B::Pow, Imm(imm), Reg(r(arg)))
+                    }
+                    $t::CompareRegImm(out, arg, imm) => {
+                        Step::Binary(r(out), B::Compare, Reg(r(arg)), Imm(imm))
+                    }
+                    $t::CompareImmReg(out, arg, imm) => {
+                        Step::Binary(r(out), B::Compare, Imm(imm), Reg(r(arg)))
+                    }
+                    $t::MinRegImm(out, arg, imm) => {
+                        Step::Binary(r(out), B::Min, Reg(r(arg)), Imm(imm))
+                    }
+                    $t::MaxRegImm(out, arg, imm) => {
+                        Step::Binary(r(out), B::Max, Reg(r(arg)), Imm(imm))
+                    }
+                    $t::AndRegImm(out, arg, imm) => {
+                        Step::Binary(r(out), B::And, Reg(r(arg)), Imm(imm))
+                    }
+                    $t::OrRegImm(out, arg, imm) => {
+                        Step::Binary(r(out), B::Or, Reg(r(arg)), Imm(imm))
+                    }
+
+                    $t::AddRegReg(out, lhs, rhs) => {
+                        Step::Binary(r(out), B::Add, Reg(r(lhs)), Reg(r(rhs)))
+                    }
+                    $t::MulRegReg(out, lhs, rhs) => {
+                        Step::Binary(r(out), B::Mul, Reg(r(lhs)), Reg(r(rhs)))
+                    }
+                    $t::DivRegReg(out, lhs, rhs) => {
+                        Step::Binary(r(out), B::Div, Reg(r(lhs)), Reg(r(rhs)))
+                    }
+                    $t::SubRegReg(out, lhs, rhs) => {
+                        Step::Binary(r(out), B::Sub, Reg(r(lhs)), Reg(r(rhs)))
+                    }
+                    $t::CompareRegReg(out, lhs, rhs) => {
+                        Step::Binary(r(out), B::Compare, Reg(r(lhs)), Reg(r(rhs)))
+                    }
+                    $t::AtanRegReg(out, lhs, rhs) => {
+                        Step::Binary(r(out), B::Atan, Reg(r(lhs)), Reg(r(rhs)))
+                    }
+                    $t::PowRegReg(out, lhs, rhs) => {
+                        Step::Binary(r(out), B::Pow, Reg(r(lhs)), Reg(r(rhs)))
+                    }
+                    $t::ModRegReg(out, lhs, rhs) => {
+                        Step::Binary(r(out), B::Mod, Reg(r(lhs)), Reg(r(rhs)))
+                    }
+                    $t::MinRegReg(out, lhs, rhs) => {
+                        Step::Binary(r(out), B::Min, Reg(r(lhs)), Reg(r(rhs)))
+                    }
+                    $t::MaxRegReg(out, lhs, rhs) => {
+                        Step::Binary(r(out), B::Max, Reg(r(lhs)), Reg(r(rhs)))
+                    }
+                    $t::AndRegReg(out, lhs, rhs) => {
+                        Step::Binary(r(out), B::And, Reg(r(lhs)), Reg(r(rhs)))
+                    }
+                    $t::OrRegReg(out, lhs, rhs) => {
+                        Step::Binary(r(out), B::Or, Reg(r(lhs)), Reg(r(rhs)))
+                    }
+
+                    $t::SelectRegReg(out, cond, arg) => Step::Ternary(
+                        r(out),
+                        TernaryOpcode::Select,
+                        Reg(r(cond)),
+                        Reg(r(arg)),
+                        Reg(r(out)),
+                    ),
+                    $t::SelectRegImm(out, cond, imm) => Step::Ternary(
+                        r(out),
+                        TernaryOpcode::Select,
+                        Reg(r(cond)),
+                        Imm(imm),
+                        Reg(r(out)),
+                    ),
+                    $t::SmoothMinRegReg(out, lhs, rhs) => Step::Ternary(
+                        r(out),
+                        TernaryOpcode::SmoothMin,
+                        Reg(r(lhs)),
+                        Reg(r(rhs)),
+                        Reg(r(out)),
+                    ),
+                    $t::SmoothMinRegImm(out, arg, imm) => Step::Ternary(
+                        r(out),
+                        TernaryOpcode::SmoothMin,
+                        Reg(r(arg)),
+                        Imm(imm),
+                        Reg(r(out)),
+                    ),
+                    $($extra_pat => $extra,)*
+                }
+            }
+        }
+    };
+}
+
+impl_step_from!(SsaOp);
+impl_step_from!(
+    RegOp,
+    RegOp::Load(reg, mem) => Step::Copy(r(reg), Arg::Reg(mem)),
+    RegOp::Store(reg, mem) => Step::Copy(mem, Arg::Reg(r(reg)))
+);
@@ -0,0 +1,826 @@
+//! Shader code generation (GLSL, WGSL, and HLSL)
+use super::{Arg, Step};
+use crate::{
+    compiler::{integer_exponent, SsaTape},
+    context::{BinaryOpcode, TernaryOpcode, UnaryOpcode},
+    var::{Var, VarMap},
+    Error,
+};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+
+/// Target language for [`shader`] code generation
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum ShaderLanguage {
+    /// OpenGL Shading Language (version 3.30 or later)
+    #[default]
+    Glsl,
+    /// WebGPU Shading Language
+    Wgsl,
+    /// High-Level Shading Language (Direct3D)
+    Hlsl,
+}
+
+/// Options for [`shader`] code generation
+#[derive(Clone, Debug)]
+pub struct ShaderOptions {
+    /// Target language
+    pub language: ShaderLanguage,
+    /// Name of the generated function
+    pub name: String,
+    /// Return the gradient along with the value
+    ///
+    /// If this is `true`, the generated function returns a 4-component vector
+    /// of `(value, d/dx, d/dy, d/dz)` instead of a single value.
+    pub gradient: bool,
+}
+
+impl Default for ShaderOptions {
+    fn default() -> Self {
+        Self {
+            language: ShaderLanguage::default(),
+            name: "f".to_owned(),
+            gradient: false,
+        }
+    }
+}
+
+/// Generates a self-contained shader function from a single-output tape
+///
+/// The function takes the position as a 3-component vector `p` (e.g.
+/// `float f(vec3 p)` in GLSL), with `p.x`, `p.y`, and `p.z` bound to
+/// [`Var::X`], [`Var::Y`], and [`Var::Z`].  Any other variables become extra
+/// `float` parameters named `v{i}`, where `i` is the variable's index in
+/// `vars`; they are sorted by index.
+///
+/// Each operation in the tape becomes a single-assignment temporary.
+/// Operations with semantics that don't match a built-in function (e.g.
+/// `compare`, `and`, `or`, `mod`, `round`, and `pow` with a negative base) are
+/// expanded into equivalent expressions.
+///
+/// Returns [`Error::BadOutputCount`] if the tape does not have exactly one
+/// output.
+///
+/// ```
+/// # use fidget::{
+/// #     codegen::{shader, ShaderLanguage, ShaderOptions},
+/// #     compiler::SsaTape, Context
+/// # };
+/// let mut ctx = Context::new();
+/// let x = ctx.x();
+/// let y = ctx.y();
+/// let r = ctx.add(x, y).unwrap();
+/// let (tape, vars) = SsaTape::new(&ctx, &[r]).unwrap();
+/// let opts = ShaderOptions {
+///     language: ShaderLanguage::Wgsl,
+///     ..ShaderOptions::default()
+/// };
+/// let src = shader(&tape, &vars, &opts).unwrap();
+/// assert!(src.starts_with("fn f(p: vec3<f32>) -> f32 {"));
+/// ```
+pub fn shader(
+    tape: &SsaTape,
+    vars: &VarMap,
+    opts: &ShaderOptions,
+) -> Result<String, Error> {
+    if tape.output_count != 1 {
+        return Err(Error::BadOutputCount(tape.output_count));
+    }
+    let mut w = ShaderWriter {
+        opts,
+        vars,
+        body: String::new(),
+        names: HashMap::new(),
+        params: BTreeSet::new(),
+        result: None,
+        next: 0,
+    };
+    // The tape is stored in reverse order
+    for op in tape.tape.iter().rev() {
+        w.step(Step::from(*op));
+    }
+    Ok(w.finish())
+}
+
+/// Stateful writer for a single shader function
+struct ShaderWriter<'a> {
+    opts: &'a ShaderOptions,
+    vars: &'a VarMap,
+
+    /// Function body (one statement per line)
+    body: String,
+
+    /// Current name (or expression) for each register
+    ///
+    /// In-place operations write their output register more than once, so
+    /// each write produces a new temporary rather than reusing the register
+    /// number as a name.
+    names: HashMap<u32, String>,
+
+    /// Indices of non-XYZ variables, which become extra parameters
+    params: BTreeSet<u32>,
+
+    /// Name of the output value
+    result: Option<String>,
+
+    /// Index of the next temporary
+    next: usize,
+}
+
+impl ShaderWriter<'_> {
+    fn lang(&self) -> ShaderLanguage {
+        self.opts.language
+    }
+
+    /// Returns the scalar type name
+    fn float(&self) -> &'static str {
+        match self.lang() {
+            ShaderLanguage::Glsl | ShaderLanguage::Hlsl => "float",
+            ShaderLanguage::Wgsl => "f32",
+        }
+    }
+
+    fn vec3(&self) -> &'static str {
+        match self.lang() {
+            ShaderLanguage::Glsl => "vec3",
+            ShaderLanguage::Wgsl => "vec3<f32>",
+            ShaderLanguage::Hlsl => "float3",
+        }
+    }
+
+    fn vec4(&self) -> &'static str {
+        match self.lang() {
+            ShaderLanguage::Glsl => "vec4",
+            ShaderLanguage::Wgsl => "vec4<f32>",
+            ShaderLanguage::Hlsl => "float4",
+        }
+    }
+
+    /// Returns the type of a temporary (scalar or value + gradient)
+    fn ty(&self) -> &'static str {
+        if self.opts.gradient {
+            self.vec4()
+        } else {
+            self.float()
+        }
+    }
+
+    /// Declares a new temporary with the given type, returning its name
+    fn decl(&mut self, ty: &str, expr: String) -> String {
+        let name = format!("t{}", self.next);
+        self.next += 1;
+        match self.lang() {
+            ShaderLanguage::Glsl | ShaderLanguage::Hlsl => {
+                writeln!(&mut self.body, "    {ty} {name} = {expr};")
+            }
+            ShaderLanguage::Wgsl => {
+                writeln!(&mut self.body, "    let {name}: {ty} = {expr};")
+            }
+        }
+        .unwrap();
+        name
+    }
+
+    /// Returns a conditional expression
+    fn sel(&self, cond: &str, a: &str, b: &str) -> String {
+        match self.lang() {
+            ShaderLanguage::Glsl | ShaderLanguage::Hlsl => {
+                format!("({cond} ? {a} : {b})")
+            }
+            ShaderLanguage::Wgsl => format!("select({b}, {a}, {cond})"),
+        }
+    }
+
+    /// Returns a floating-point literal
+    fn lit(&self, f: f32) -> String {
+        if f.is_finite() {
+            if f.is_sign_negative() {
+                format!("({f:?})")
+            } else {
+                format!("{f:?}")
+            }
+        } else {
+            let bits = f.to_bits();
+            match self.lang() {
+                ShaderLanguage::Glsl => format!("uintBitsToFloat({bits:#x}u)"),
+                ShaderLanguage::Wgsl => format!("bitcast<f32>({bits:#x}u)"),
+                ShaderLanguage::Hlsl => format!("asfloat({bits:#x}u)"),
+            }
+        }
+    }
+
+    /// Builds a value + gradient vector
+    fn dual(&self, v: &str, g: &str) -> String {
+        format!("{}({v}, {g})", self.vec4())
+    }
+
+    fn zero3(&self) -> String {
+        format!("{}(0.0, 0.0, 0.0)", self.vec3())
+    }
+
+    /// Returns the name of a register's value, or an immediate
+    ///
+    /// In gradient mode, immediates are bound to a temporary vector with a
+    /// zero gradient, so that every argument has the same type.
+    fn arg(&mut self, a: Arg) -> String {
+        match a {
+            Arg::Reg(r) => self.names[&r].clone(),
+            Arg::Imm(f) if self.opts.gradient => {
+                let v = self.dual(&self.lit(f), &self.zero3());
+                self.decl(self.vec4(), v)
+            }
+            Arg::Imm(f) => self.lit(f),
+        }
+    }
+
+    /// Returns the value component of a temporary
+    fn val(&self, a: &str) -> String {
+        if self.opts.gradient {
+            format!("{a}.x")
+        } else {
+            a.to_owned()
+        }
+    }
+
+    fn step(&mut self, step: Step) {
+        let (out, name) = match step {
+            Step::Output(arg, _i) => {
+                self.result = Some(self.names[&arg].clone());
+                return;
+            }
+            Step::Input(out, i) => {
+                let index = Some(i as usize);
+                let (v, g) = if index == self.vars.get(&Var::X) {
+                    ("p.x".to_owned(), "1.0, 0.0, 0.0")
+                } else if index == self.vars.get(&Var::Y) {
+                    ("p.y".to_owned(), "0.0, 1.0, 0.0")
+                } else if index == self.vars.get(&Var::Z) {
+                    ("p.z".to_owned(), "0.0, 0.0, 1.0")
+                } else {
+                    self.params.insert(i);
+                    (format!("v{i}"), "0.0, 0.0, 0.0")
+                };
+                let name = if self.opts.gradient {
+                    let g = format!("{}({g})", self.vec3());
+                    self.decl(self.vec4(), self.dual(&v, &g))
+                } else {
+                    v
+                };
+                (out, name)
+            }
+            Step::Copy(out, Arg::Reg(r)) => (out, self.names[&r].clone()),
+            Step::Copy(out, Arg::Imm(f)) => {
+                let name = if self.opts.gradient {
+                    self.arg(Arg::Imm(f))
+                } else {
+                    self.decl(self.float(), self.lit(f))
+                };
+                (out, name)
+            }
+            Step::Unary(out, op, arg) => (out, self.unary(op, arg)),
+            Step::Binary(out, op, lhs, rhs) => (out, self.binary(op, lhs, rhs)),
+            Step::Ternary(out, op, a, b, c) => (out, self.ternary(op, a, b, c)),
+        };
+        self.names.insert(out, name);
+    }
+
+    /// Returns a scalar expression for a unary operation
+    fn unary_value(&self, op: UnaryOpcode, a: &str) -> String {
+        match op {
+            UnaryOpcode::Neg => format!("(-{a})"),
+            UnaryOpcode::Abs => format!("abs({a})"),
+            UnaryOpcode::Recip => format!("(1.0 / {a})"),
+            UnaryOpcode::Sqrt => format!("sqrt({a})"),
+            UnaryOpcode::Square => format!("({a} * {a})"),
+            UnaryOpcode::Floor => format!("floor({a})"),
+            UnaryOpcode::Ceil => format!("ceil({a})"),
+            // Built-in `round` functions may round half-way values to even
+            UnaryOpcode::Round => {
+                format!("(sign({a}) * floor(abs({a}) + 0.5))")
+            }
+            UnaryOpcode::Sin => format!("sin({a})"),
+            UnaryOpcode::Cos => format!("cos({a})"),
+            UnaryOpcode::Tan => format!("tan({a})"),
+            UnaryOpcode::Asin => format!("asin({a})"),
+            UnaryOpcode::Acos => format!("acos({a})"),
+            UnaryOpcode::Atan => format!("atan({a})"),
+            UnaryOpcode::Exp => format!("exp({a})"),
+            UnaryOpcode::Ln => format!("log({a})"),
+            UnaryOpcode::Sinh => format!("sinh({a})"),
+            UnaryOpcode::Cosh => format!("cosh({a})"),
+            UnaryOpcode::Tanh => format!("tanh({a})"),
+            UnaryOpcode::Exp2 => format!("exp2({a})"),
+            UnaryOpcode::Log2 => format!("log2({a})"),
+            UnaryOpcode::Log10 => {
+                format!("(log2({a}) * {})", self.lit(std::f32::consts::LOG10_2))
+            }
+            UnaryOpcode::Not => self.sel(&format!("{a} == 0.0"), "1.0", "0.0"),
+        }
+    }
+
+    fn unary(&mut self, op: UnaryOpcode, arg: u32) -> String {
+        let a = self.names[&arg].clone();
+        if !self.opts.gradient {
+            let v = self.unary_value(op, &a);
+            return self.decl(self.float(), v);
+        }
+        match op {
+            UnaryOpcode::Neg => {
+                return self.decl(self.vec4(), format!("(-{a})"))
+            }
+            UnaryOpcode::Abs => {
+                let v =
+                    self.sel(&format!("{a}.x < 0.0"), &format!("(-{a})"), &a);
+                return self.decl(self.vec4(), v);
+            }
+            _ => (),
+        }
+        let x = format!("{a}.x");
+        let v = self.unary_value(op, &x);
+        let v = self.decl(self.float(), v);
+
+        // Derivative of the operation with respect to its argument
+        let d = match op {
+            UnaryOpcode::Neg | UnaryOpcode::Abs => unreachable!(),
+            UnaryOpcode::Floor
+            | UnaryOpcode::Ceil
+            | UnaryOpcode::Round
+            | UnaryOpcode::Not => None,
+            UnaryOpcode::Recip => Some(format!("(-{v} * {v})")),
+            UnaryOpcode::Sqrt => Some(format!("(0.5 / {v})")),
+            UnaryOpcode::Square => Some(format!("(2.0 * {x})")),
+            UnaryOpcode::Sin => Some(format!("cos({x})")),
+            UnaryOpcode::Cos => Some(format!("(-sin({x}))")),
+            UnaryOpcode::Tan => Some(format!("(1.0 + {v} * {v})")),
+            UnaryOpcode::Asin => Some(format!("(1.0 / sqrt(1.0 - {x} * {x}))")),
+            UnaryOpcode::Acos => {
+                Some(format!("(-1.0 / sqrt(1.0 - {x} * {x}))"))
+            }
+            UnaryOpcode::Atan => Some(format!("(1.0 / (1.0 + {x} * {x}))")),
+            UnaryOpcode::Exp => Some(v.clone()),
+            UnaryOpcode::Ln => Some(format!("(1.0 / {x})")),
+            UnaryOpcode::Sinh => Some(format!("cosh({x})")),
+            UnaryOpcode::Cosh => Some(format!("sinh({x})")),
+            UnaryOpcode::Tanh => Some(format!("(1.0 - {v} * {v})")),
+            UnaryOpcode::Exp2 => {
+                Some(format!("({v} * {})", self.lit(std::f32::consts::LN_2)))
+            }
+            UnaryOpcode::Log2 => Some(format!(
+                "(1.0 / ({x} * {}))",
+                self.lit(std::f32::consts::LN_2)
+            )),
+            UnaryOpcode::Log10 => Some(format!(
+                "(1.0 / ({x} * {}))",
+                self.lit(std::f32::consts::LN_10)
+            )),
+        };
+        let g = match d {
+            Some(d) => format!("{d} * {a}.yzw"),
+            None => self.zero3(),
+        };
+        self.decl(self.vec4(), self.dual(&v, &g))
+    }
+
+    /// Returns an expression for `a` raised to the power `b`
+    ///
+    /// Small integer exponents are expanded into repeated multiplication,
+    /// matching the VM (which uses `f32::powi`).  Otherwise, built-in `pow`
+    /// functions are undefined for negative bases, so we handle them
+    /// explicitly (matching `f32::powf` for integer exponents).
+    fn pow(&self, a: &str, b: &str, imm: Option<f32>) -> String {
+        if let Some(n) = imm.and_then(integer_exponent) {
+            return Self::powi(a, n);
+        }
+        match imm {
+            Some(e) if e.fract() == 0.0 && (e / 2.0).fract() == 0.0 => {
+                format!("pow(abs({a}), {b})")
+            }
+            Some(e) if e.fract() == 0.0 => self.sel(
+                &format!("{a} < 0.0"),
+                &format!("(-pow(-{a}, {b}))"),
+                &format!("pow({a}, {b})"),
+            ),
+            Some(..) => format!("pow({a}, {b})"),
+            None => {
+                let sign = self.sel(
+                    &format!("floor({b} * 0.5) == {b} * 0.5"),
+                    "1.0",
+                    "(-1.0)",
+                );
+                self.sel(
+                    &format!("{a} < 0.0 && floor({b}) == {b}"),
+                    &format!("({sign} * pow(-{a}, {b}))"),
+                    &format!("pow({a}, {b})"),
+                )
+            }
+        }
+    }
+
+    /// Returns an expression for `a` raised to an integer power `n`
+    ///
+    /// This uses the same sequence of multiplications as `f32::powi` (square
+    /// and multiply, then a reciprocal for negative exponents), so results
+    /// are bit-for-bit identical.
+    fn powi(a: &str, n: i32) -> String {
+        let mut b = n.unsigned_abs();
+        let mut base = a.to_owned();
+        let mut out: Option<String> = None;
+        loop {
+            if b & 1 == 1 {
+                out = Some(match out {
+                    Some(r) => format!("({r} * {base})"),
+                    None => base.clone(),
+                });
+            }
+            b /= 2;
+            if b == 0 {
+                break;
+            }
+            base = format!("({base} * {base})");
+        }
+        let out = out.unwrap();
+        if n < 0 {
+            format!("(1.0 / {out})")
+        } else {
+            out
+        }
+    }
+
+    /// Returns a scalar expression for a binary operation
+    fn binary_value(
+        &self,
+        op: BinaryOpcode,
+        a: &str,
+        b: &str,
+        imm: Option<f32>,
+    ) -> String {
+        match op {
+            BinaryOpcode::Add => format!("({a} + {b})"),
+            BinaryOpcode::Sub => format!("({a} - {b})"),
+            BinaryOpcode::Mul => format!("({a} * {b})"),
+            BinaryOpcode::Div => format!("({a} / {b})"),
+            BinaryOpcode::Atan => match self.lang() {
+                ShaderLanguage::Glsl => format!("atan({a}, {b})"),
+                ShaderLanguage::Wgsl | ShaderLanguage::Hlsl => {
+                    format!("atan2({a}, {b})")
+                }
+            },
+            BinaryOpcode::Min => format!("min({a}, {b})"),
+            BinaryOpcode::Max => format!("max({a}, {b})"),
+            BinaryOpcode::Compare => {
+                // If neither comparison is true, then one argument is NaN and
+                // their sum propagates it.
+                let eq = self.sel(
+                    &format!("{a} == {b}"),
+                    "0.0",
+                    &format!("({a} + {b})"),
+                );
+                let gt = self.sel(&format!("{a} > {b}"), "1.0", &eq);
+                self.sel(&format!("{a} < {b}"), "(-1.0)", &gt)
+            }
+            BinaryOpcode::Mod => {
+                format!("({a} - abs({b}) * floor({a} / abs({b})))")
+            }
+            BinaryOpcode::And => self.sel(&format!("{a} == 0.0"), a, b),
+            BinaryOpcode::Or => self.sel(&format!("{a} != 0.0"), a, b),
+            BinaryOpcode::Pow => self.pow(a, b, imm),
+        }
+    }
+
+    fn binary(&mut self, op: BinaryOpcode, lhs: Arg, rhs: Arg) -> String {
+        let a = self.arg(lhs);
+        let b = self.arg(rhs);
+        let imm = match rhs {
+            Arg::Imm(f) => Some(f),
+            Arg::Reg(..) => None,
+        };
+        if !self.opts.gradient {
+            let v = self.binary_value(op, &a, &b, imm);
+            return self.decl(self.float(), v);
+        }
+
+        let (x, y) = (format!("{a}.x"), format!("{b}.x"));
+        let choose = |cond: String| self.sel(&cond, &a, &b);
+        let v = match op {
+            BinaryOpcode::Add => format!("({a} + {b})"),
+            BinaryOpcode::Sub => format!("({a} - {b})"),
+            BinaryOpcode::Min => choose(format!("{x} < {y}")),
+            BinaryOpcode::Max => choose(format!("{x} > {y}")),
+            BinaryOpcode::And => choose(format!("{x} == 0.0")),
+            BinaryOpcode::Or => choose(format!("{x} != 0.0")),
+            _ => String::new(),
+        };
+        if !v.is_empty() {
+            return self.decl(self.vec4(), v);
+        }
+
+        let value = self.binary_value(op, &x, &y, imm);
+        let v = self.decl(self.float(), value);
+        let g = match op {
+            BinaryOpcode::Mul => format!("{x} * {b}.yzw + {y} * {a}.yzw"),
+            BinaryOpcode::Div => {
+                format!("({y} * {a}.yzw - {x} * {b}.yzw) / ({y} * {y})")
+            }
+            BinaryOpcode::Atan => format!(
+                "({y} * {a}.yzw - {x} * {b}.yzw) / ({x} * {x} + {y} * {y})"
+            ),
+            BinaryOpcode::Compare => self.zero3(),
+            BinaryOpcode::Mod => {
+                format!("{a}.yzw - sign({y}) * floor({x} / abs({y})) * {b}.yzw")
+            }
+            BinaryOpcode::Pow => {
+                match imm {
+                    Some(e) => {
+                        let p = self.pow(&x, &self.lit(e - 1.0), Some(e - 1.0));
+                        format!("({y} * {p}) * {a}.yzw")
+                    }
+                    None => {
+                        let p = self.pow(&x, &format!("({y} - 1.0)"), None);
+                        format!("({y} * {p}) * {a}.yzw + ({v} * log({x})) * {b}.yzw")
+                    }
+                }
+            }
+            BinaryOpcode::Add
+            | BinaryOpcode::Sub
+            | BinaryOpcode::Min
+            | BinaryOpcode::Max
+            | BinaryOpcode::And
+            | BinaryOpcode::Or => unreachable!(),
+        };
+        self.decl(self.vec4(), self.dual(&v, &g))
+    }
+
+    fn ternary(&mut self, op: TernaryOpcode, a: Arg, b: Arg, c: Arg) -> String {
+        let a = self.arg(a);
+        let b = self.arg(b);
+        let c = self.arg(c);
+        match op {
+            TernaryOpcode::Select => {
+                let v = self.sel(&format!("{} != 0.0", self.val(&a)), &b, &c);
+                self.decl(self.ty(), v)
+            }
+            TernaryOpcode::SmoothMin if !self.opts.gradient => {
+                let h = self.decl(
+                    self.float(),
+                    format!("max({c} - abs({a} - {b}), 0.0)"),
+                );
+                let m = format!("min({a}, {b})");
+                let v = self.sel(
+                    &format!("{h} > 0.0"),
+                    &format!("({m} - {h} * {h} / (4.0 * {c}))"),
+                    &m,
+                );
+                self.decl(self.float(), v)
+            }
+            TernaryOpcode::SmoothMin => {
+                let m = self.sel(&format!("{a}.x < {b}.x"), &a, &b);
+                let m = self.decl(self.vec4(), m);
+                let d = self.decl(self.vec4(), format!("({a} - {b})"));
+                let abs =
+                    self.sel(&format!("{d}.x < 0.0"), &format!("(-{d})"), &d);
+                let h = self.decl(self.vec4(), format!("({c} - {abs})"));
+                let v = self.decl(
+                    self.float(),
+                    format!("({m}.x - {h}.x * {h}.x / (4.0 * {c}.x))"),
+                );
+                let g = format!(
+                    "{m}.yzw - ({h}.x / (2.0 * {c}.x)) * {h}.yzw \
+                     + ({h}.x * {h}.x / (4.0 * {c}.x * {c}.x)) * {c}.yzw"
+                );
+                let v =
+                    self.sel(&format!("{h}.x > 0.0"), &self.dual(&v, &g), &m);
+                self.decl(self.vec4(), v)
+            }
+        }
+    }
+
+    /// Wraps the body in a function definition
+    fn finish(self) -> String {
+        let name = &self.opts.name;
+        let ty = self.ty();
+        let result = self.result.as_deref().expect("tape must have an output");
+        let mut out = String::new();
+        match self.lang() {
+            ShaderLanguage::Glsl | ShaderLanguage::Hlsl => {
+                write!(&mut out, "{ty} {name}({} p", self.vec3()).unwrap();
+                for i in &self.params {
+                    write!(&mut out, ", float v{i}").unwrap();
+                }
+                writeln!(&mut out, ") {{").unwrap();
+            }
+            ShaderLanguage::Wgsl => {
+                write!(&mut out, "fn {name}(p: vec3<f32>").unwrap();
+                for i in &self.params {
+                    write!(&mut out, ", v{i}: f32").unwrap();
+                }
+                writeln!(&mut out, ") -> {ty} {{").unwrap();
+            }
+        }
+        out += &self.body;
+        writeln!(&mut out, "    return {result};\n}}").unwrap();
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{context::Node, Context};
+
+    /// Builds an expression which uses every opcode
+    fn build_all(ctx: &mut Context) -> Node {
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let v = ctx.var(Var::new());
+        let mut terms = vec![];
+        for op in [
+            UnaryOpcode::Neg,
+            UnaryOpcode::Abs,
+            UnaryOpcode::Recip,
+            UnaryOpcode::Sqrt,
+            UnaryOpcode::Square,
+            UnaryOpcode::Floor,
+            UnaryOpcode::Ceil,
+            UnaryOpcode::Round,
+            UnaryOpcode::Sin,
+            UnaryOpcode::Cos,
+            UnaryOpcode::Tan,
+            UnaryOpcode::Asin,
+            UnaryOpcode::Acos,
+            UnaryOpcode::Atan,
+            UnaryOpcode::Exp,
+            UnaryOpcode::Ln,
+            UnaryOpcode::Sinh,
+            UnaryOpcode::Cosh,
+            UnaryOpcode::Tanh,
+            UnaryOpcode::Exp2,
+            UnaryOpcode::Log2,
+            UnaryOpcode::Log10,
+            UnaryOpcode::Not,
+        ] {
+            terms.push(ctx.op_unary(x, op).unwrap());
+        }
+        for op in [
+            BinaryOpcode::Add,
+            BinaryOpcode::Sub,
+            BinaryOpcode::Mul,
+            BinaryOpcode::Div,
+            BinaryOpcode::Atan,
+            BinaryOpcode::Min,
+            BinaryOpcode::Max,
+            BinaryOpcode::Compare,
+            BinaryOpcode::Mod,
+            BinaryOpcode::And,
+            BinaryOpcode::Or,
+            BinaryOpcode::Pow,
+        ] {
+            terms.push(ctx.op_binary(y, z, op).unwrap());
+            let c = ctx.constant(-3.0);
+            terms.push(ctx.op_binary(y, c, op).unwrap());
+            // `and` / `or` with a constant LHS are collapsed by the builders
+            if !matches!(op, BinaryOpcode::And | BinaryOpcode::Or) {
+                terms.push(ctx.op_binary(c, v, op).unwrap());
+            }
+        }
+        terms.push(ctx.if_nonzero_else(x, y, 2.5).unwrap());
+        terms.push(ctx.if_nonzero_else(x, 0.5, z).unwrap());
+        terms.push(ctx.smooth_min(x, y, z).unwrap());
+        terms.push(ctx.smooth_min(x, 1.5, 0.25).unwrap());
+        terms.push(ctx.constant(f64::INFINITY));
+        terms.push(ctx.pow(x, 0.5).unwrap());
+        let mut out = terms[0];
+        for t in &terms[1..] {
+            out = ctx.add(out, *t).unwrap();
+        }
+        out
+    }
+
+    fn validate_wgsl(src: &str) {
+        let module = naga::front::wgsl::parse_str(src)
+            .unwrap_or_else(|e| panic!("{}\n{src}", e.emit_to_string(src)));
+        naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::all(),
+        )
+        .validate(&module)
+        .unwrap_or_else(|e| panic!("{e:?}\n{src}"));
+    }
+
+    fn validate_glsl(src: &str, args: &str) {
+        let full = format!(
+            "#version 450\n{src}\n\
+             layout(location = 0) out vec4 color;\n\
+             void main() {{ color = vec4(f(vec3(0.1, 0.2, 0.3){args})); }}\n"
+        );
+        let opts =
+            naga::front::glsl::Options::from(naga::ShaderStage::Fragment);
+        let module = naga::front::glsl::Frontend::default()
+            .parse(&opts, &full)
+            .unwrap_or_else(|e| panic!("{}\n{full}", e.emit_to_string(&full)));
+        naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::all(),
+        )
+        .validate(&module)
+        .unwrap_or_else(|e| panic!("{e:?}\n{full}"));
+    }
+
+    #[test]
+    fn shader_validates() {
+        let mut ctx = Context::new();
+        let root = build_all(&mut ctx);
+        for gradient in [false, true] {
+            let mut opts = ShaderOptions {
+                gradient,
+                ..ShaderOptions::default()
+            };
+            let src = ctx.to_shader(root, &opts).unwrap();
+            assert!(src.contains(", float v3)"), "{src}");
+            validate_glsl(&src, ", 0.5");
+
+            opts.language = ShaderLanguage::Wgsl;
+            let src = ctx.to_shader(root, &opts).unwrap();
+            validate_wgsl(&src);
+
+            opts.language = ShaderLanguage::Hlsl;
+            let src = ctx.to_shader(root, &opts).unwrap();
+            let ret = if gradient { "float4" } else { "float" };
+            assert!(
+                src.starts_with(&format!("{ret} f(float3 p, float v3) {{")),
+                "{src}"
+            );
+        }
+    }
+
+    #[test]
+    fn shader_simple() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let m = ctx.modulo(x, y).unwrap();
+        let opts = ShaderOptions {
+            name: "sdf".to_owned(),
+            ..ShaderOptions::default()
+        };
+        let src = ctx.to_shader(m, &opts).unwrap();
+        assert_eq!(
+            src,
+            "float sdf(vec3 p) {\n    \
+             float t0 = (p.x - abs(p.y) * floor(p.x / abs(p.y)));\n    \
+             return t0;\n}\n"
+        );
+
+        let c = ctx.constant(1.5);
+        let src = ctx.to_shader(c, &opts).unwrap();
+        assert_eq!(
+            src,
+            "float sdf(vec3 p) {\n    float t0 = 1.5;\n    return t0;\n}\n"
+        );
+        validate_glsl(&src.replace("sdf", "f"), "");
+    }
+
+    #[test]
+    fn shader_gradient() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let m = ctx.mul(x, y).unwrap();
+        let opts = ShaderOptions {
+            language: ShaderLanguage::Wgsl,
+            gradient: true,
+            ..ShaderOptions::default()
+        };
+        let src = ctx.to_shader(m, &opts).unwrap();
+        assert!(src.starts_with("fn f(p: vec3<f32>) -> vec4<f32> {"));
+        assert!(src.contains("t0.x * t1.yzw + t1.x * t0.yzw"), "{src}");
+        validate_wgsl(&src);
+    }
+
+    #[test]
+    fn shader_powi() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let p = ctx.pow(x, 3.0).unwrap();
+        let src = ctx.to_shader(p, &ShaderOptions::default()).unwrap();
+        assert!(src.contains("float t0 = (p.x * (p.x * p.x));"), "{src}");
+        validate_glsl(&src, "");
+
+        let p = ctx.pow(x, -2.0).unwrap();
+        let src = ctx.to_shader(p, &ShaderOptions::default()).unwrap();
+        assert!(src.contains("float t0 = (1.0 / (p.x * p.x));"), "{src}");
+        validate_glsl(&src, "");
+    }
+
+    #[test]
+    fn shader_output_count() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let (tape, vars) = SsaTape::new(&ctx, &[x, y]).unwrap();
+        assert!(matches!(
+            shader(&tape, &vars, &ShaderOptions::default()),
+            Err(Error::BadOutputCount(2))
+        ));
+    }
+}
@@ -0,0 +1,1575 @@
+//! Standalone C and Rust source generation
+use super::{Arg, Step};
+use crate::{
+    compiler::{integer_exponent, RegTape},
+    context::{BinaryOpcode, TernaryOpcode, UnaryOpcode},
+};
+use std::fmt::Write;
+
+/// Target language for [`source`] code generation
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum SourceLanguage {
+    /// C99, using `<math.h>` for transcendental functions
+    #[default]
+    C,
+    /// `no_std` Rust, using the [`libm`](https://docs.rs/libm) crate for
+    /// transcendental functions
+    Rust,
+}
+
+/// Evaluation semantics for [`source`] code generation
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum SourceMode {
+    /// Evaluate a single point, matching the
+    /// [`VmPointEval`](crate::vm::VmPointEval)
+    #[default]
+    Point,
+    /// Evaluate slices of points, with the same semantics as
+    /// [`SourceMode::Point`]
+    FloatSlice,
+    /// Evaluate intervals, matching the
+    /// [`VmIntervalEval`](crate::vm::VmIntervalEval)
+    Interval,
+}
+
+/// Options for [`source`] code generation
+#[derive(Clone, Debug)]
+pub struct SourceOptions {
+    /// Target language
+    pub language: SourceLanguage,
+    /// Evaluation semantics
+    pub mode: SourceMode,
+    /// Name of the generated function
+    pub name: String,
+}
+
+impl Default for SourceOptions {
+    fn default() -> Self {
+        Self {
+            language: SourceLanguage::default(),
+            mode: SourceMode::default(),
+            name: "f".to_owned(),
+        }
+    }
+}
+
+/// Generates a standalone function from a register-allocated tape
+///
+/// The generated function has no dependencies on Fidget; it stores
+/// intermediate values in a local array `v` with one entry per slot in the
+/// tape, so the number of locals is bounded by the register limit used when
+/// building the [`RegTape`].
+///
+/// Inputs are passed in a `vars` array, indexed by the tape's
+/// [`VarMap`](crate::var::VarMap); outputs are written to an `out` array,
+/// indexed by output number.  The signatures are as follows:
+///
+/// | Mode                       | C                                                               | Rust                                            |
+/// |----------------------------|-----------------------------------------------------------------|-------------------------------------------------|
+/// | [`SourceMode::Point`]      | `void f(const float *vars, float *out)`                         | `fn f(vars: &[f32], out: &mut [f32])`           |
+/// | [`SourceMode::FloatSlice`] | `void f(const float *const *vars, float *const *out, size_t n)` | `fn f(vars: &[&[f32]], out: &mut [&mut [f32]])` |
+/// | [`SourceMode::Interval`]   | `void f(const fidget_interval *vars, fidget_interval *out)`     | `fn f(vars: &[[f32; 2]], out: &mut [[f32; 2]])` |
+///
+/// C output includes `static inline` helper functions (and the
+/// `fidget_interval` type) behind include guards, so several generated
+/// functions may be concatenated into a single file; it must be linked with
+/// `-lm`.  Rust output nests its helper functions within the generated
+/// function, and calls into the `libm` crate.
+///
+/// ```
+/// # use fidget::{
+/// #     codegen::{source, SourceOptions},
+/// #     compiler::{RegTape, SsaTape}, Context
+/// # };
+/// let mut ctx = Context::new();
+/// let x = ctx.x();
+/// let y = ctx.y();
+/// let r = ctx.add(x, y).unwrap();
+/// let (ssa, _vars) = SsaTape::new(&ctx, &[r]).unwrap();
+/// let tape = RegTape::new::<8>(&ssa);
+/// let src = source(&tape, &SourceOptions::default());
+/// assert!(src.contains("void f(const float *vars, float *out) {"));
+/// ```
+pub fn source(tape: &RegTape, opts: &SourceOptions) -> String {
+    let mut w = SourceWriter {
+        opts,
+        out: String::new(),
+    };
+    w.run(tape);
+    w.out
+}
+
+const C_POINT_PRELUDE: &str = r#"#ifndef FIDGET_POINT_PRELUDE
+#define FIDGET_POINT_PRELUDE
+static inline float fidget_min(float a, float b) {
+    if (a < b) return a;
+    if (b < a) return b;
+    return (isnan(a) || isnan(b)) ? NAN : b;
+}
+static inline float fidget_max(float a, float b) {
+    if (a > b) return a;
+    if (b > a) return b;
+    return (isnan(a) || isnan(b)) ? NAN : b;
+}
+static inline float fidget_compare(float a, float b) {
+    if (a < b) return -1.0f;
+    if (a > b) return 1.0f;
+    if (a == b) return 0.0f;
+    return NAN;
+}
+static inline float fidget_rem_euclid(float a, float b) {
+    float r = fmodf(a, b);
+    return r < 0.0f ? r + fabsf(b) : r;
+}
+static inline float fidget_smooth_min(float a, float b, float k) {
+    float kk, h, m;
+    if (isnan(a) || isnan(b) || isnan(k)) return NAN;
+    kk = fmaxf(k, 0.0f);
+    if (a + kk < b) return a;
+    if (b + kk < a) return b;
+    h = k - fabsf(a - b);
+    m = fminf(a, b);
+    return h > 0.0f ? m - h * h / (4.0f * k) : m;
+}
+static inline float fidget_powi(float a, int n) {
+    unsigned b = n < 0 ? -n : n;
+    float r = 1.0f;
+    for (;;) {
+        if (b & 1) r *= a;
+        b /= 2;
+        if (b == 0) break;
+        a *= a;
+    }
+    return n < 0 ? 1.0f / r : r;
+}
+static inline float fidget_not(float a) { return a == 0.0f ? 1.0f : 0.0f; }
+static inline float fidget_and(float a, float b) { return a == 0.0f ? a : b; }
+static inline float fidget_or(float a, float b) { return a != 0.0f ? a : b; }
+static inline float fidget_select(float c, float a, float b) {
+    return c != 0.0f ? a : b;
+}
+#endif
+"#;
+
+const C_INTERVAL_PRELUDE: &str = r#"#ifndef FIDGET_INTERVAL_PRELUDE
+#define FIDGET_INTERVAL_PRELUDE
+#define FIDGET_PI 3.14159265358979323846f
+typedef struct {
+    float lower;
+    float upper;
+} fidget_interval;
+static inline fidget_interval fidget_i_new(float lower, float upper) {
+    fidget_interval out;
+    out.lower = lower;
+    out.upper = upper;
+    return out;
+}
+static inline fidget_interval fidget_i_imm(float v) {
+    return fidget_i_new(v, v);
+}
+static inline fidget_interval fidget_i_nan(void) { return fidget_i_imm(NAN); }
+static inline int fidget_i_has_nan(fidget_interval a) {
+    return isnan(a.lower) || isnan(a.upper);
+}
+static inline int fidget_i_contains(fidget_interval a, float v) {
+    return v >= a.lower && v <= a.upper;
+}
+static inline int fidget_i_is_zero(fidget_interval a) {
+    return a.lower == 0.0f && a.upper == 0.0f;
+}
+static inline fidget_interval fidget_i_neg(fidget_interval a) {
+    return fidget_i_new(-a.upper, -a.lower);
+}
+static inline fidget_interval fidget_i_abs(fidget_interval a) {
+    if (a.lower < 0.0f) {
+        if (a.upper > 0.0f) return fidget_i_new(0.0f, fmaxf(a.upper, -a.lower));
+        return fidget_i_new(-a.upper, -a.lower);
+    }
+    return a;
+}
+static inline fidget_interval fidget_i_recip(fidget_interval a) {
+    if (a.lower > 0.0f || a.upper < 0.0f) {
+        return fidget_i_new(1.0f / a.upper, 1.0f / a.lower);
+    }
+    return fidget_i_nan();
+}
+static inline fidget_interval fidget_i_sqrt(fidget_interval a) {
+    if (a.lower < 0.0f) return fidget_i_nan();
+    return fidget_i_new(sqrtf(a.lower), sqrtf(a.upper));
+}
+static inline fidget_interval fidget_i_square(fidget_interval a) {
+    float m;
+    if (a.upper < 0.0f) return fidget_i_new(a.upper * a.upper, a.lower * a.lower);
+    if (a.lower > 0.0f) return fidget_i_new(a.lower * a.lower, a.upper * a.upper);
+    if (fidget_i_has_nan(a)) return fidget_i_nan();
+    m = fmaxf(fabsf(a.lower), fabsf(a.upper));
+    return fidget_i_new(0.0f, m * m);
+}
+static inline fidget_interval fidget_i_floor(fidget_interval a) {
+    return fidget_i_new(floorf(a.lower), floorf(a.upper));
+}
+static inline fidget_interval fidget_i_ceil(fidget_interval a) {
+    return fidget_i_new(ceilf(a.lower), ceilf(a.upper));
+}
+static inline fidget_interval fidget_i_round(fidget_interval a) {
+    return fidget_i_new(roundf(a.lower), roundf(a.upper));
+}
+static inline fidget_interval fidget_i_sin(fidget_interval a) {
+    return fidget_i_has_nan(a) ? fidget_i_nan() : fidget_i_new(-1.0f, 1.0f);
+}
+static inline fidget_interval fidget_i_cos(fidget_interval a) {
+    return fidget_i_has_nan(a) ? fidget_i_nan() : fidget_i_new(-1.0f, 1.0f);
+}
+static inline fidget_interval fidget_i_tan(fidget_interval a) {
+    float lower, upper;
+    if (a.upper - a.lower >= FIDGET_PI) return fidget_i_nan();
+    lower = tanf(a.lower);
+    upper = tanf(a.upper);
+    return upper >= lower ? fidget_i_new(lower, upper) : fidget_i_nan();
+}
+static inline fidget_interval fidget_i_asin(fidget_interval a) {
+    if (a.lower < -1.0f || a.upper > 1.0f) return fidget_i_nan();
+    return fidget_i_new(asinf(a.lower), asinf(a.upper));
+}
+static inline fidget_interval fidget_i_acos(fidget_interval a) {
+    if (a.lower < -1.0f || a.upper > 1.0f) return fidget_i_nan();
+    return fidget_i_new(acosf(a.upper), acosf(a.lower));
+}
+static inline fidget_interval fidget_i_atan(fidget_interval a) {
+    return fidget_i_new(atanf(a.lower), atanf(a.upper));
+}
+static inline fidget_interval fidget_i_exp(fidget_interval a) {
+    return fidget_i_new(expf(a.lower), expf(a.upper));
+}
+static inline fidget_interval fidget_i_ln(fidget_interval a) {
+    if (a.lower <= 0.0f) return fidget_i_nan();
+    return fidget_i_new(logf(a.lower), logf(a.upper));
+}
+static inline fidget_interval fidget_i_sinh(fidget_interval a) {
+    return fidget_i_new(sinhf(a.lower), sinhf(a.upper));
+}
+static inline fidget_interval fidget_i_cosh(fidget_interval a) {
+    if (fidget_i_has_nan(a)) return fidget_i_nan();
+    if (a.lower > 0.0f) return fidget_i_new(coshf(a.lower), coshf(a.upper));
+    if (a.upper < 0.0f) return fidget_i_new(coshf(a.upper), coshf(a.lower));
+    return fidget_i_new(1.0f, fmaxf(coshf(a.lower), coshf(a.upper)));
+}
+static inline fidget_interval fidget_i_tanh(fidget_interval a) {
+    return fidget_i_new(tanhf(a.lower), tanhf(a.upper));
+}
+static inline fidget_interval fidget_i_exp2(fidget_interval a) {
+    return fidget_i_new(exp2f(a.lower), exp2f(a.upper));
+}
+static inline fidget_interval fidget_i_log2(fidget_interval a) {
+    if (a.lower <= 0.0f) return fidget_i_nan();
+    return fidget_i_new(log2f(a.lower), log2f(a.upper));
+}
+static inline fidget_interval fidget_i_log10(fidget_interval a) {
+    if (a.lower <= 0.0f) return fidget_i_nan();
+    return fidget_i_new(log10f(a.lower), log10f(a.upper));
+}
+static inline fidget_interval fidget_i_not(fidget_interval a) {
+    if (!fidget_i_contains(a, 0.0f) && !fidget_i_has_nan(a)) {
+        return fidget_i_imm(0.0f);
+    }
+    if (fidget_i_is_zero(a)) return fidget_i_imm(1.0f);
+    return fidget_i_new(0.0f, 1.0f);
+}
+static inline fidget_interval fidget_i_add(fidget_interval a, fidget_interval b) {
+    return fidget_i_new(a.lower + b.lower, a.upper + b.upper);
+}
+static inline fidget_interval fidget_i_sub(fidget_interval a, fidget_interval b) {
+    return fidget_i_new(a.lower - b.upper, a.upper - b.lower);
+}
+static inline fidget_interval fidget_i_corners(float a, float b, float c, float d) {
+    return fidget_i_new(fminf(fminf(fminf(a, b), c), d),
+                        fmaxf(fmaxf(fmaxf(a, b), c), d));
+}
+static inline fidget_interval fidget_i_mul(fidget_interval a, fidget_interval b) {
+    if (fidget_i_has_nan(a) || fidget_i_has_nan(b)) return fidget_i_nan();
+    return fidget_i_corners(a.lower * b.lower, a.lower * b.upper,
+                            a.upper * b.lower, a.upper * b.upper);
+}
+static inline fidget_interval fidget_i_mul_imm(fidget_interval a, float b) {
+    if (fidget_i_has_nan(a) || isnan(b)) return fidget_i_nan();
+    if (b < 0.0f) return fidget_i_new(a.upper * b, a.lower * b);
+    return fidget_i_new(a.lower * b, a.upper * b);
+}
+static inline fidget_interval fidget_i_div(fidget_interval a, fidget_interval b) {
+    if (fidget_i_has_nan(a)) return fidget_i_nan();
+    if (b.lower > 0.0f || b.upper < 0.0f) {
+        return fidget_i_corners(a.lower / b.lower, a.lower / b.upper,
+                                a.upper / b.lower, a.upper / b.upper);
+    }
+    return fidget_i_nan();
+}
+static inline fidget_interval fidget_i_atan2(fidget_interval a, fidget_interval b) {
+    if (fidget_i_has_nan(a) || fidget_i_has_nan(b)) return fidget_i_nan();
+    return fidget_i_new(-FIDGET_PI, FIDGET_PI);
+}
+static inline fidget_interval fidget_i_pow(fidget_interval a, fidget_interval b) {
+    fidget_interval out;
+    int integer;
+    if (fidget_i_has_nan(a) || fidget_i_has_nan(b)) return fidget_i_nan();
+    integer = b.lower == b.upper && b.lower - truncf(b.lower) == 0.0f;
+    if ((a.lower < 0.0f && !integer) ||
+        (b.lower < 0.0f && fidget_i_contains(a, 0.0f))) {
+        return fidget_i_nan();
+    }
+    out = fidget_i_corners(powf(a.lower, b.lower), powf(a.lower, b.upper),
+                           powf(a.upper, b.lower), powf(a.upper, b.upper));
+    if (integer && b.lower > 0.0f && fmodf(b.lower, 2.0f) == 0.0f &&
+        a.lower < 0.0f && a.upper > 0.0f) {
+        out.lower = 0.0f;
+    }
+    return out;
+}
+static inline fidget_interval fidget_i_powi(fidget_interval a, int n) {
+    float lo, hi;
+    if (fidget_i_has_nan(a) || (n < 0 && fidget_i_contains(a, 0.0f))) {
+        return fidget_i_nan();
+    }
+    lo = fidget_powi(a.lower, n);
+    hi = fidget_powi(a.upper, n);
+    if (n > 0 && n % 2 == 0 && a.lower < 0.0f && a.upper > 0.0f) {
+        return fidget_i_new(0.0f, fmaxf(lo, hi));
+    }
+    return fidget_i_new(fminf(lo, hi), fmaxf(lo, hi));
+}
+static inline fidget_interval fidget_i_mod(fidget_interval a, fidget_interval b) {
+    float lo, hi, m;
+    if (fidget_i_has_nan(a) || fidget_i_has_nan(b) || fidget_i_contains(b, 0.0f)) {
+        return fidget_i_nan();
+    }
+    m = fidget_i_abs(b).upper;
+    if (b.lower == b.upper && b.lower > 0.0f) {
+        lo = a.lower / b.lower;
+        hi = a.upper / b.lower;
+        if (lo != floorf(lo) && floorf(lo) == floorf(hi)) {
+            return fidget_i_new(fidget_rem_euclid(a.lower, b.lower),
+                                fidget_rem_euclid(a.upper, b.lower));
+        }
+    }
+    return fidget_i_new(0.0f, m);
+}
+static inline fidget_interval fidget_i_compare(fidget_interval a, fidget_interval b) {
+    if (fidget_i_has_nan(a) || fidget_i_has_nan(b)) return fidget_i_nan();
+    if (a.upper < b.lower) return fidget_i_imm(-1.0f);
+    if (a.lower > b.upper) return fidget_i_imm(1.0f);
+    return fidget_i_new(-1.0f, 1.0f);
+}
+static inline fidget_interval fidget_i_min(fidget_interval a, fidget_interval b) {
+    if (fidget_i_has_nan(a) || fidget_i_has_nan(b)) return fidget_i_nan();
+    return fidget_i_new(fminf(a.lower, b.lower), fminf(a.upper, b.upper));
+}
+static inline fidget_interval fidget_i_max(fidget_interval a, fidget_interval b) {
+    if (fidget_i_has_nan(a) || fidget_i_has_nan(b)) return fidget_i_nan();
+    return fidget_i_new(fmaxf(a.lower, b.lower), fmaxf(a.upper, b.upper));
+}
+static inline fidget_interval fidget_i_and(fidget_interval a, fidget_interval b) {
+    if (fidget_i_has_nan(a) || fidget_i_has_nan(b)) return fidget_i_nan();
+    if (fidget_i_is_zero(a)) return fidget_i_imm(0.0f);
+    if (!fidget_i_contains(a, 0.0f)) return b;
+    return fidget_i_new(fminf(b.lower, 0.0f), fmaxf(b.upper, 0.0f));
+}
+static inline fidget_interval fidget_i_or(fidget_interval a, fidget_interval b) {
+    if (fidget_i_has_nan(a) || fidget_i_has_nan(b)) return fidget_i_nan();
+    if (!fidget_i_contains(a, 0.0f)) return a;
+    if (fidget_i_is_zero(a)) return b;
+    return fidget_i_new(fminf(a.lower, b.lower), fmaxf(a.upper, b.upper));
+}
+static inline fidget_interval fidget_i_select(fidget_interval c,
+                                              fidget_interval a,
+                                              fidget_interval b) {
+    if (fidget_i_has_nan(c)) return fidget_i_nan();
+    if (!fidget_i_contains(c, 0.0f)) return a;
+    if (fidget_i_is_zero(c)) return b;
+    if (fidget_i_has_nan(a) || fidget_i_has_nan(b)) return fidget_i_nan();
+    return fidget_i_new(fminf(a.lower, b.lower), fmaxf(a.upper, b.upper));
+}
+static inline fidget_interval fidget_i_smooth_min(fidget_interval a,
+                                                  fidget_interval b,
+                                                  fidget_interval k) {
+    float kk;
+    if (fidget_i_has_nan(a) || fidget_i_has_nan(b) || fidget_i_has_nan(k)) {
+        return fidget_i_nan();
+    }
+    kk = fmaxf(k.upper, 0.0f);
+    if (a.upper + kk < b.lower) return a;
+    if (b.upper + kk < a.lower) return b;
+    return fidget_i_new(fminf(a.lower, b.lower) - kk / 4.0f,
+                        fminf(a.upper, b.upper));
+}
+#endif
+"#;
+
+const RUST_POINT_PRELUDE: &str = r#"    fn fidget_min(a: f32, b: f32) -> f32 {
+        if a < b {
+            a
+        } else if b < a {
+            b
+        } else if a.is_nan() || b.is_nan() {
+            f32::NAN
+        } else {
+            b
+        }
+    }
+    fn fidget_max(a: f32, b: f32) -> f32 {
+        if a > b {
+            a
+        } else if b > a {
+            b
+        } else if a.is_nan() || b.is_nan() {
+            f32::NAN
+        } else {
+            b
+        }
+    }
+    fn fidget_compare(a: f32, b: f32) -> f32 {
+        if a < b {
+            -1.0
+        } else if a > b {
+            1.0
+        } else if a == b {
+            0.0
+        } else {
+            f32::NAN
+        }
+    }
+    fn fidget_rem_euclid(a: f32, b: f32) -> f32 {
+        let r = libm::fmodf(a, b);
+        if r < 0.0 { r + libm::fabsf(b) } else { r }
+    }
+    fn fidget_smooth_min(a: f32, b: f32, k: f32) -> f32 {
+        if a.is_nan() || b.is_nan() || k.is_nan() {
+            return f32::NAN;
+        }
+        let kk = libm::fmaxf(k, 0.0);
+        if a + kk < b {
+            a
+        } else if b + kk < a {
+            b
+        } else {
+            let h = k - libm::fabsf(a - b);
+            let m = libm::fminf(a, b);
+            if h > 0.0 { m - h * h / (4.0 * k) } else { m }
+        }
+    }
+    fn fidget_powi(mut a: f32, n: i32) -> f32 {
+        let mut b = n.unsigned_abs();
+        let mut r = 1.0;
+        loop {
+            if b & 1 == 1 {
+                r *= a;
+            }
+            b /= 2;
+            if b == 0 {
+                break;
+            }
+            a *= a;
+        }
+        if n < 0 { 1.0 / r } else { r }
+    }
+    fn fidget_not(a: f32) -> f32 {
+        if a == 0.0 { 1.0 } else { 0.0 }
+    }
+    fn fidget_and(a: f32, b: f32) -> f32 {
+        if a == 0.0 { a } else { b }
+    }
+    fn fidget_or(a: f32, b: f32) -> f32 {
+        if a != 0.0 { a } else { b }
+    }
+    fn fidget_select(c: f32, a: f32, b: f32) -> f32 {
+        if c != 0.0 { a } else { b }
+    }
+"#;
+
+const RUST_INTERVAL_PRELUDE: &str = r#"    type I = [f32; 2];
+    const NAN: I = [f32::NAN, f32::NAN];
+    const PI: f32 = core::f32::consts::PI;
+    fn fidget_i_imm(v: f32) -> I {
+        [v, v]
+    }
+    fn fidget_i_has_nan(a: I) -> bool {
+        a[0].is_nan() || a[1].is_nan()
+    }
+    fn fidget_i_contains(a: I, v: f32) -> bool {
+        v >= a[0] && v <= a[1]
+    }
+    fn fidget_i_is_zero(a: I) -> bool {
+        a[0] == 0.0 && a[1] == 0.0
+    }
+    fn fidget_i_neg(a: I) -> I {
+        [-a[1], -a[0]]
+    }
+    fn fidget_i_abs(a: I) -> I {
+        if a[0] < 0.0 {
+            if a[1] > 0.0 {
+                [0.0, libm::fmaxf(a[1], -a[0])]
+            } else {
+                [-a[1], -a[0]]
+            }
+        } else {
+            a
+        }
+    }
+    fn fidget_i_recip(a: I) -> I {
+        if a[0] > 0.0 || a[1] < 0.0 {
+            [1.0 / a[1], 1.0 / a[0]]
+        } else {
+            NAN
+        }
+    }
+    fn fidget_i_sqrt(a: I) -> I {
+        if a[0] < 0.0 {
+            NAN
+        } else {
+            [libm::sqrtf(a[0]), libm::sqrtf(a[1])]
+        }
+    }
+    fn fidget_i_square(a: I) -> I {
+        if a[1] < 0.0 {
+            [a[1] * a[1], a[0] * a[0]]
+        } else if a[0] > 0.0 {
+            [a[0] * a[0], a[1] * a[1]]
+        } else if fidget_i_has_nan(a) {
+            NAN
+        } else {
+            let m = libm::fmaxf(libm::fabsf(a[0]), libm::fabsf(a[1]));
+            [0.0, m * m]
+        }
+    }
+    fn fidget_i_floor(a: I) -> I {
+        [libm::floorf(a[0]), libm::floorf(a[1])]
+    }
+    fn fidget_i_ceil(a: I) -> I {
+        [libm::ceilf(a[0]), libm::ceilf(a[1])]
+    }
+    fn fidget_i_round(a: I) -> I {
+        [libm::roundf(a[0]), libm::roundf(a[1])]
+    }
+    fn fidget_i_sin(a: I) -> I {
+        if fidget_i_has_nan(a) { NAN } else { [-1.0, 1.0] }
+    }
+    fn fidget_i_cos(a: I) -> I {
+        if fidget_i_has_nan(a) { NAN } else { [-1.0, 1.0] }
+    }
+    fn fidget_i_tan(a: I) -> I {
+        if a[1] - a[0] >= PI {
+            return NAN;
+        }
+        let lower = libm::tanf(a[0]);
+        let upper = libm::tanf(a[1]);
+        if upper >= lower { [lower, upper] } else { NAN }
+    }
+    fn fidget_i_asin(a: I) -> I {
+        if a[0] < -1.0 || a[1] > 1.0 {
+            NAN
+        } else {
+            [libm::asinf(a[0]), libm::asinf(a[1])]
+        }
+    }
+    fn fidget_i_acos(a: I) -> I {
+        if a[0] < -1.0 || a[1] > 1.0 {
+            NAN
+        } else {
+            [libm::acosf(a[1]), libm::acosf(a[0])]
+        }
+    }
+    fn fidget_i_atan(a: I) -> I {
+        [libm::atanf(a[0]), libm::atanf(a[1])]
+    }
+    fn fidget_i_exp(a: I) -> I {
+        [libm::expf(a[0]), libm::expf(a[1])]
+    }
+    fn fidget_i_ln(a: I) -> I {
+        if a[0] <= 0.0 {
+            NAN
+        } else {
+            [libm::logf(a[0]), libm::logf(a[1])]
+        }
+    }
+    fn fidget_i_sinh(a: I) -> I {
+        [libm::sinhf(a[0]), libm::sinhf(a[1])]
+    }
+    fn fidget_i_cosh(a: I) -> I {
+        if fidget_i_has_nan(a) {
+            NAN
+        } else if a[0] > 0.0 {
+            [libm::coshf(a[0]), libm::coshf(a[1])]
+        } else if a[1] < 0.0 {
+            [libm::coshf(a[1]), libm::coshf(a[0])]
+        } else {
+            [1.0, libm::fmaxf(libm::coshf(a[0]), libm::coshf(a[1]))]
+        }
+    }
+    fn fidget_i_tanh(a: I) -> I {
+        [libm::tanhf(a[0]), libm::tanhf(a[1])]
+    }
+    fn fidget_i_exp2(a: I) -> I {
+        [libm::exp2f(a[0]), libm::exp2f(a[1])]
+    }
+    fn fidget_i_log2(a: I) -> I {
+        if a[0] <= 0.0 {
+            NAN
+        } else {
+            [libm::log2f(a[0]), libm::log2f(a[1])]
+        }
+    }
+    fn fidget_i_log10(a: I) -> I {
+        if a[0] <= 0.0 {
+            NAN
+        } else {
+            [libm::log10f(a[0]), libm::log10f(a[1])]
+        }
+    }
+    fn fidget_i_not(a: I) -> I {
+        if !fidget_i_contains(a, 0.0) && !fidget_i_has_nan(a) {
+            [0.0, 0.0]
+        } else if fidget_i_is_zero(a) {
+            [1.0, 1.0]
+        } else {
+            [0.0, 1.0]
+        }
+    }
+    fn fidget_i_add(a: I, b: I) -> I {
+        [a[0] + b[0], a[1] + b[1]]
+    }
+    fn fidget_i_sub(a: I, b: I) -> I {
+        [a[0] - b[1], a[1] - b[0]]
+    }
+    fn fidget_i_corners(c: [f32; 4]) -> I {
+        let lower = libm::fminf(libm::fminf(libm::fminf(c[0], c[1]), c[2]), c[3]);
+        let upper = libm::fmaxf(libm::fmaxf(libm::fmaxf(c[0], c[1]), c[2]), c[3]);
+        [lower, upper]
+    }
+    fn fidget_i_mul(a: I, b: I) -> I {
+        if fidget_i_has_nan(a) || fidget_i_has_nan(b) {
+            NAN
+        } else {
+            fidget_i_corners([a[0] * b[0], a[0] * b[1], a[1] * b[0], a[1] * b[1]])
+        }
+    }
+    fn fidget_i_mul_imm(a: I, b: f32) -> I {
+        if fidget_i_has_nan(a) || b.is_nan() {
+            NAN
+        } else if b < 0.0 {
+            [a[1] * b, a[0] * b]
+        } else {
+            [a[0] * b, a[1] * b]
+        }
+    }
+    fn fidget_i_div(a: I, b: I) -> I {
+        if fidget_i_has_nan(a) {
+            NAN
+        } else if b[0] > 0.0 || b[1] < 0.0 {
+            fidget_i_corners([a[0] / b[0], a[0] / b[1], a[1] / b[0], a[1] / b[1]])
+        } else {
+            NAN
+        }
+    }
+    fn fidget_i_atan2(a: I, b: I) -> I {
+        if fidget_i_has_nan(a) || fidget_i_has_nan(b) { NAN } else { [-PI, PI] }
+    }
+    fn fidget_i_pow(a: I, b: I) -> I {
+        if fidget_i_has_nan(a) || fidget_i_has_nan(b) {
+            return NAN;
+        }
+        let integer = b[0] == b[1] && b[0] - libm::truncf(b[0]) == 0.0;
+        if (a[0] < 0.0 && !integer) || (b[0] < 0.0 && fidget_i_contains(a, 0.0)) {
+            return NAN;
+        }
+        let mut out = fidget_i_corners([
+            libm::powf(a[0], b[0]),
+            libm::powf(a[0], b[1]),
+            libm::powf(a[1], b[0]),
+            libm::powf(a[1], b[1]),
+        ]);
+        if integer
+            && b[0] > 0.0
+            && libm::fmodf(b[0], 2.0) == 0.0
+            && a[0] < 0.0
+            && a[1] > 0.0
+        {
+            out[0] = 0.0;
+        }
+        out
+    }
+    fn fidget_i_powi(a: I, n: i32) -> I {
+        if fidget_i_has_nan(a) || (n < 0 && fidget_i_contains(a, 0.0)) {
+            return NAN;
+        }
+        let lo = fidget_powi(a[0], n);
+        let hi = fidget_powi(a[1], n);
+        if n > 0 && n % 2 == 0 && a[0] < 0.0 && a[1] > 0.0 {
+            [0.0, libm::fmaxf(lo, hi)]
+        } else {
+            [libm::fminf(lo, hi), libm::fmaxf(lo, hi)]
+        }
+    }
+    fn fidget_i_mod(a: I, b: I) -> I {
+        if fidget_i_has_nan(a) || fidget_i_has_nan(b) || fidget_i_contains(b, 0.0) {
+            return NAN;
+        }
+        if b[0] == b[1] && b[0] > 0.0 {
+            let lo = a[0] / b[0];
+            let hi = a[1] / b[0];
+            if lo != libm::floorf(lo) && libm::floorf(lo) == libm::floorf(hi) {
+                return [fidget_rem_euclid(a[0], b[0]), fidget_rem_euclid(a[1], b[0])];
+            }
+        }
+        [0.0, fidget_i_abs(b)[1]]
+    }
+    fn fidget_i_compare(a: I, b: I) -> I {
+        if fidget_i_has_nan(a) || fidget_i_has_nan(b) {
+            NAN
+        } else if a[1] < b[0] {
+            [-1.0, -1.0]
+        } else if a[0] > b[1] {
+            [1.0, 1.0]
+        } else {
+            [-1.0, 1.0]
+        }
+    }
+    fn fidget_i_min(a: I, b: I) -> I {
+        if fidget_i_has_nan(a) || fidget_i_has_nan(b) {
+            NAN
+        } else {
+            [libm::fminf(a[0], b[0]), libm::fminf(a[1], b[1])]
+        }
+    }
+    fn fidget_i_max(a: I, b: I) -> I {
+        if fidget_i_has_nan(a) || fidget_i_has_nan(b) {
+            NAN
+        } else {
+            [libm::fmaxf(a[0], b[0]), libm::fmaxf(a[1], b[1])]
+        }
+    }
+    fn fidget_i_and(a: I, b: I) -> I {
+        if fidget_i_has_nan(a) || fidget_i_has_nan(b) {
+            NAN
+        } else if fidget_i_is_zero(a) {
+            [0.0, 0.0]
+        } else if !fidget_i_contains(a, 0.0) {
+            b
+        } else {
+            [libm::fminf(b[0], 0.0), libm::fmaxf(b[1], 0.0)]
+        }
+    }
+    fn fidget_i_or(a: I, b: I) -> I {
+        if fidget_i_has_nan(a) || fidget_i_has_nan(b) {
+            NAN
+        } else if !fidget_i_contains(a, 0.0) {
+            a
+        } else if fidget_i_is_zero(a) {
+            b
+        } else {
+            [libm::fminf(a[0], b[0]), libm::fmaxf(a[1], b[1])]
+        }
+    }
+    fn fidget_i_select(c: I, a: I, b: I) -> I {
+        if fidget_i_has_nan(c) {
+            NAN
+        } else if !fidget_i_contains(c, 0.0) {
+            a
+        } else if fidget_i_is_zero(c) {
+            b
+        } else if fidget_i_has_nan(a) || fidget_i_has_nan(b) {
+            NAN
+        } else {
+            [libm::fminf(a[0], b[0]), libm::fmaxf(a[1], b[1])]
+        }
+    }
+    fn fidget_i_smooth_min(a: I, b: I, k: I) -> I {
+        if fidget_i_has_nan(a) || fidget_i_has_nan(b) || fidget_i_has_nan(k) {
+            return NAN;
+        }
+        let kk = libm::fmaxf(k[1], 0.0);
+        if a[1] + kk < b[0] {
+            a
+        } else if b[1] + kk < a[0] {
+            b
+        } else {
+            [libm::fminf(a[0], b[0]) - kk / 4.0, libm::fminf(a[1], b[1])]
+        }
+    }
+"#;
+
+/// Helper type to accumulate generated source
+struct SourceWriter<'a> {
+    opts: &'a SourceOptions,
+    out: String,
+}
+
+impl SourceWriter<'_> {
+    fn run(&mut self, tape: &RegTape) {
+        let slots = tape.slot_count().max(1);
+        let lang = self.opts.language;
+        let mode = self.opts.mode;
+        let name = &self.opts.name;
+
+        let o = &mut self.out;
+        match lang {
+            SourceLanguage::C => {
+                o.push_str("#include <math.h>\n#include <stddef.h>\n\n");
+                o.push_str(C_POINT_PRELUDE);
+                if mode == SourceMode::Interval {
+                    o.push_str(C_INTERVAL_PRELUDE);
+                }
+                o.push('\n');
+                match mode {
+                    SourceMode::Point => writeln!(
+                        o,
+                        "void {name}(const float *vars, float *out) {{\n    \
+                         float v[{slots}];"
+                    ),
+                    SourceMode::FloatSlice => writeln!(
+                        o,
+                        "void {name}(const float *const *vars, \
+                         float *const *out, size_t n) {{\n    \
+                         float v[{slots}];\n    \
+                         size_t j;\n    \
+                         for (j = 0; j < n; ++j) {{"
+                    ),
+                    SourceMode::Interval => writeln!(
+                        o,
+                        "void {name}(const fidget_interval *vars, \
+                         fidget_interval *out) {{\n    \
+                         fidget_interval v[{slots}];"
+                    ),
+                }
+                .unwrap();
+            }
+            SourceLanguage::Rust => {
+                o.push_str("#[allow(unused, clippy::all)]\n");
+                match mode {
+                    SourceMode::Point => writeln!(
+                        o,
+                        "pub fn {name}(vars: &[f32], out: &mut [f32]) {{"
+                    ),
+                    SourceMode::FloatSlice => writeln!(
+                        o,
+                        "pub fn {name}(vars: &[&[f32]], out: &mut [&mut [f32]]) {{"
+                    ),
+                    SourceMode::Interval => writeln!(
+                        o,
+                        "pub fn {name}(vars: &[[f32; 2]], out: &mut [[f32; 2]]) {{"
+                    ),
+                }
+                .unwrap();
+                o.push_str(RUST_POINT_PRELUDE);
+                if mode == SourceMode::Interval {
+                    o.push_str(RUST_INTERVAL_PRELUDE);
+                }
+                o.push('\n');
+                match mode {
+                    SourceMode::Point => {
+                        writeln!(o, "    let mut v = [0.0f32; {slots}];")
+                    }
+                    SourceMode::FloatSlice => writeln!(
+                        o,
+                        "    let mut v = [0.0f32; {slots}];\n    \
+                         let n = out.first().map_or(0, |o| o.len());\n    \
+                         for j in 0..n {{"
+                    ),
+                    SourceMode::Interval => {
+                        writeln!(o, "    let mut v = [[0.0f32; 2]; {slots}];")
+                    }
+                }
+                .unwrap();
+            }
+        }
+
+        let indent = if mode == SourceMode::FloatSlice {
+            "        "
+        } else {
+            "    "
+        };
+        let suffix = if mode == SourceMode::FloatSlice {
+            "[j]"
+        } else {
+            ""
+        };
+        for op in tape.iter().rev() {
+            let line = match Step::from(*op) {
+                Step::Input(out, i) => format!("v[{out}] = vars[{i}]{suffix}"),
+                Step::Output(arg, i) => format!("out[{i}]{suffix} = v[{arg}]"),
+                Step::Copy(out, arg) => format!("v[{out}] = {}", self.arg(arg)),
+                Step::Unary(out, op, arg) => {
+                    format!("v[{out}] = {}", self.unary(op, arg))
+                }
+                Step::Binary(out, op, lhs, rhs) => {
+                    format!("v[{out}] = {}", self.binary(op, lhs, rhs))
+                }
+                Step::Ternary(out, op, a, b, c) => {
+                    let name = match op {
+                        TernaryOpcode::Select => "select",
+                        TernaryOpcode::SmoothMin => "smooth_min",
+                    };
+                    format!(
+                        "v[{out}] = {}",
+                        self.helper(name, &[a, b, c].map(|a| self.arg(a)))
+                    )
+                }
+            };
+            writeln!(self.out, "{indent}{line};").unwrap();
+        }
+        if mode == SourceMode::FloatSlice {
+            self.out.push_str("    }\n");
+        }
+        self.out.push_str("}\n");
+    }
+
+    /// Formats a floating-point literal
+    fn literal(&self, f: f32) -> String {
+        match self.opts.language {
+            SourceLanguage::C => {
+                if f.is_nan() {
+                    "NAN".to_owned()
+                } else if f.is_infinite() {
+                    if f < 0.0 { "-INFINITY" } else { "INFINITY" }.to_owned()
+                } else {
+                    format!("{f:?}f")
+                }
+            }
+            SourceLanguage::Rust => {
+                if f.is_nan() {
+                    "f32::NAN".to_owned()
+                } else if f.is_infinite() {
+                    if f < 0.0 {
+                        "f32::NEG_INFINITY"
+                    } else {
+                        "f32::INFINITY"
+                    }
+                    .to_owned()
+                } else {
+                    format!("{f:?}f32")
+                }
+            }
+        }
+    }
+
+    /// Formats an argument, promoting immediates to intervals if needed
+    fn arg(&self, a: Arg) -> String {
+        match a {
+            Arg::Reg(r) => format!("v[{r}]"),
+            Arg::Imm(f) if self.opts.mode == SourceMode::Interval => {
+                format!("fidget_i_imm({})", self.literal(f))
+            }
+            Arg::Imm(f) => self.literal(f),
+        }
+    }
+
+    /// Calls a helper function from the prelude
+    ///
+    /// In interval mode, this uses the interval version of the helper
+    fn helper(&self, name: &str, args: &[String]) -> String {
+        let prefix = if self.opts.mode == SourceMode::Interval {
+            "fidget_i_"
+        } else {
+            "fidget_"
+        };
+        format!("{prefix}{name}({})", args.join(", "))
+    }
+
+    /// Calls a function from the math library
+    fn libm(&self, name: &str, args: &[String]) -> String {
+        let prefix = match self.opts.language {
+            SourceLanguage::C => "",
+            SourceLanguage::Rust => "libm::",
+        };
+        format!("{prefix}{name}({})", args.join(", "))
+    }
+
+    fn unary(&self, op: UnaryOpcode, arg: u32) -> String {
+        let a = format!("v[{arg}]");
+        if self.opts.mode == SourceMode::Interval {
+            let name = format!("{op:?}").to_lowercase();
+            return self.helper(&name, &[a]);
+        }
+        let f = match op {
+            UnaryOpcode::Neg => return format!("-{a}"),
+            UnaryOpcode::Square => return format!("{a} * {a}"),
+            UnaryOpcode::Recip => {
+                return format!("{} / {a}", self.literal(1.0));
+            }
+            UnaryOpcode::Not => return self.helper("not", &[a]),
+            UnaryOpcode::Abs => "fabsf",
+            UnaryOpcode::Sqrt => "sqrtf",
+            UnaryOpcode::Floor => "floorf",
+            UnaryOpcode::Ceil => "ceilf",
+            UnaryOpcode::Round => "roundf",
+            UnaryOpcode::Sin => "sinf",
+            UnaryOpcode::Cos => "cosf",
+            UnaryOpcode::Tan => "tanf",
+            UnaryOpcode::Asin => "asinf",
+            UnaryOpcode::Acos => "acosf",
+            UnaryOpcode::Atan => "atanf",
+            UnaryOpcode::Exp => "expf",
+            UnaryOpcode::Ln => "logf",
+            UnaryOpcode::Sinh => "sinhf",
+            UnaryOpcode::Cosh => "coshf",
+            UnaryOpcode::Tanh => "tanhf",
+            UnaryOpcode::Exp2 => "exp2f",
+            UnaryOpcode::Log2 => "log2f",
+            UnaryOpcode::Log10 => "log10f",
+        };
+        self.libm(f, &[a])
+    }
+
+    fn binary(&self, op: BinaryOpcode, lhs: Arg, rhs: Arg) -> String {
+        // Small integer exponents use repeated multiplication (like `powi`),
+        // matching the VM evaluators
+        if let (BinaryOpcode::Pow, Arg::Imm(f)) = (op, rhs) {
+            if let Some(n) = integer_exponent(f) {
+                return self.helper("powi", &[self.arg(lhs), n.to_string()]);
+            }
+        }
+        if self.opts.mode == SourceMode::Interval {
+            // Multiplication by a constant has tighter bounds
+            if op == BinaryOpcode::Mul {
+                match (lhs, rhs) {
+                    (Arg::Reg(r), Arg::Imm(f)) | (Arg::Imm(f), Arg::Reg(r)) => {
+                        return format!(
+                            "fidget_i_mul_imm(v[{r}], {})",
+                            self.literal(f)
+                        );
+                    }
+                    _ => (),
+                }
+            }
+            let name = match op {
+                BinaryOpcode::Atan => "atan2".to_owned(),
+                _ => format!("{op:?}").to_lowercase(),
+            };
+            return self.helper(&name, &[self.arg(lhs), self.arg(rhs)]);
+        }
+        let a = self.arg(lhs);
+        let b = self.arg(rhs);
+        match op {
+            BinaryOpcode::Add => format!("{a} + {b}"),
+            BinaryOpcode::Sub => format!("{a} - {b}"),
+            BinaryOpcode::Mul => format!("{a} * {b}"),
+            BinaryOpcode::Div => format!("{a} / {b}"),
+            BinaryOpcode::Atan => self.libm("atan2f", &[a, b]),
+            BinaryOpcode::Pow => self.libm("powf", &[a, b]),
+            BinaryOpcode::Mod => self.helper("rem_euclid", &[a, b]),
+            BinaryOpcode::Min => self.helper("min", &[a, b]),
+            BinaryOpcode::Max => self.helper("max", &[a, b]),
+            BinaryOpcode::Compare => self.helper("compare", &[a, b]),
+            BinaryOpcode::And => self.helper("and", &[a, b]),
+            BinaryOpcode::Or => self.helper("or", &[a, b]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        compiler::RegOp,
+        context::{Context, Node},
+        eval::{Function, MathFunction, TracingEvaluator},
+        types::Interval,
+        var::Var,
+        vm::GenericVmFunction,
+    };
+    use std::{fmt::Write, path::PathBuf, process::Command};
+
+    /// Small register count, to force `Load` and `Store` operations
+    type F = GenericVmFunction<4>;
+
+    const POINTS: [[f32; 3]; 6] = [
+        [0.0, 0.0, 0.0],
+        [0.5, -1.5, 2.0],
+        [-2.25, 3.0, -0.75],
+        [1.0, 1.0, 0.1],
+        [-0.3, 0.0, -4.0],
+        [7.5, -2.0, 1.0],
+    ];
+
+    const INTERVALS: [[[f32; 2]; 3]; 5] = [
+        [[-1.0, 1.0], [0.5, 2.0], [-3.0, -0.5]],
+        [[0.0, 0.0], [2.0, 2.0], [1.0, 3.0]],
+        [[0.25, 0.75], [-2.0, 5.0], [-0.5, -0.5]],
+        [[1.0, 1.0], [-1.0, -1.0], [3.0, 3.0]],
+        [[-4.0, -1.0], [0.0, 1.0], [2.0, 2.0]],
+    ];
+
+    /// Builds one output per opcode, so that `NaN` values stay isolated
+    fn build_all(ctx: &mut Context) -> Vec<Node> {
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let mut out = vec![];
+        for op in [
+            UnaryOpcode::Neg,
+            UnaryOpcode::Abs,
+            UnaryOpcode::Recip,
+            UnaryOpcode::Sqrt,
+            UnaryOpcode::Square,
+            UnaryOpcode::Floor,
+            UnaryOpcode::Ceil,
+            UnaryOpcode::Round,
+            UnaryOpcode::Sin,
+            UnaryOpcode::Cos,
+            UnaryOpcode::Tan,
+            UnaryOpcode::Asin,
+            UnaryOpcode::Acos,
+            UnaryOpcode::Atan,
+            UnaryOpcode::Exp,
+            UnaryOpcode::Ln,
+            UnaryOpcode::Sinh,
+            UnaryOpcode::Cosh,
+            UnaryOpcode::Tanh,
+            UnaryOpcode::Exp2,
+            UnaryOpcode::Log2,
+            UnaryOpcode::Log10,
+            UnaryOpcode::Not,
+        ] {
+            out.push(ctx.op_unary(x, op).unwrap());
+        }
+        for op in [
+            BinaryOpcode::Add,
+            BinaryOpcode::Sub,
+            BinaryOpcode::Mul,
+            BinaryOpcode::Div,
+            BinaryOpcode::Atan,
+            BinaryOpcode::Min,
+            BinaryOpcode::Max,
+            BinaryOpcode::Compare,
+            BinaryOpcode::Mod,
+            BinaryOpcode::And,
+            BinaryOpcode::Or,
+            BinaryOpcode::Pow,
+        ] {
+            out.push(ctx.op_binary(y, z, op).unwrap());
+            let c = ctx.constant(-3.0);
+            out.push(ctx.op_binary(y, c, op).unwrap());
+            let c = ctx.constant(2.0);
+            out.push(ctx.op_binary(x, c, op).unwrap());
+            // `and` / `or` with a constant LHS are collapsed by the builders
+            if !matches!(op, BinaryOpcode::And | BinaryOpcode::Or) {
+                out.push(ctx.op_binary(c, z, op).unwrap());
+            }
+        }
+        out.push(ctx.if_nonzero_else(x, y, 2.5).unwrap());
+        out.push(ctx.if_nonzero_else(x, 0.5, z).unwrap());
+        out.push(ctx.smooth_min(x, y, z).unwrap());
+        out.push(ctx.smooth_min(x, 1.5, 0.25).unwrap());
+        out.push(ctx.constant(f64::INFINITY));
+        out.push(ctx.constant(f64::NAN));
+        let s = ctx.add(x, y).unwrap();
+        let s = ctx.mul(s, z).unwrap();
+        out.push(ctx.sub(s, x).unwrap());
+        out
+    }
+
+    /// Generated functions and expected output, in the order printed by the
+    /// test harnesses
+    struct Setup {
+        outputs: usize,
+        points: Vec<[f32; 3]>,
+        intervals: Vec<[[f32; 2]; 3]>,
+        sources: String,
+        expected: Vec<f32>,
+    }
+
+    fn setup(language: SourceLanguage) -> Setup {
+        let mut ctx = Context::new();
+        let nodes = build_all(&mut ctx);
+        let f = F::new(&ctx, &nodes).unwrap();
+        assert!(f.data().iter_asm().any(|op| matches!(op, RegOp::Store(..))));
+
+        // Reorder inputs to match the tape's variable indices
+        let index = [Var::X, Var::Y, Var::Z].map(|v| f.vars().get(&v).unwrap());
+        let points: Vec<[f32; 3]> = POINTS
+            .iter()
+            .map(|p| {
+                let mut out = [0.0; 3];
+                for (i, v) in p.iter().enumerate() {
+                    out[index[i]] = *v;
+                }
+                out
+            })
+            .collect();
+        let intervals: Vec<[[f32; 2]; 3]> = INTERVALS
+            .iter()
+            .map(|p| {
+                let mut out = [[0.0; 2]; 3];
+                for (i, v) in p.iter().enumerate() {
+                    out[index[i]] = *v;
+                }
+                out
+            })
+            .collect();
+
+        let mut expected = vec![];
+        let mut eval = F::new_point_eval();
+        let tape = f.point_tape(Default::default());
+        for p in &points {
+            let (out, _) = eval.eval(&tape, p).unwrap();
+            expected.extend_from_slice(out);
+        }
+
+        // The bulk evaluator only supports single-output tapes, so we expect
+        // float slice evaluation to match point evaluation
+        expected.extend_from_within(..);
+
+        let mut eval = F::new_interval_eval();
+        let tape = f.interval_tape(Default::default());
+        for p in &intervals {
+            let p = p.map(Interval::from);
+            let (out, _) = eval.eval(&tape, &p).unwrap();
+            for i in out {
+                expected.extend([i.lower(), i.upper()]);
+            }
+        }
+
+        let mut sources = String::new();
+        for (mode, name) in [
+            (SourceMode::Point, "f_point"),
+            (SourceMode::FloatSlice, "f_slice"),
+            (SourceMode::Interval, "f_interval"),
+        ] {
+            let opts = SourceOptions {
+                language,
+                mode,
+                name: name.to_owned(),
+            };
+            sources += &f.data().to_source(&opts);
+        }
+        Setup {
+            outputs: nodes.len(),
+            points,
+            intervals,
+            sources,
+            expected,
+        }
+    }
+
+    /// Returns a scratch directory for compiling generated code
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("fidget-source-{}-{name}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Runs a test harness, returning printed values
+    fn run(exe: &PathBuf) -> Vec<f32> {
+        let out = Command::new(exe).output().unwrap();
+        assert!(out.status.success());
+        String::from_utf8(out.stdout)
+            .unwrap()
+            .lines()
+            .map(|s| f32::from_bits(u32::from_str_radix(s, 16).unwrap()))
+            .collect()
+    }
+
+    fn check(expected: &[f32], actual: &[f32]) {
+        assert_eq!(expected.len(), actual.len());
+        for (i, (a, b)) in expected.iter().zip(actual).enumerate() {
+            assert!(
+                a == b || (a.is_nan() && b.is_nan()),
+                "mismatch at {i}: expected {a}, got {b}"
+            );
+        }
+    }
+
+    /// Returns a stand-in for the `libm` crate, using the standard library
+    fn libm_stub() -> String {
+        let mut src = "#[allow(unused)]\nmod libm {\n".to_owned();
+        for f in [
+            "fabsf:abs",
+            "sqrtf:sqrt",
+            "floorf:floor",
+            "ceilf:ceil",
+            "roundf:round",
+            "truncf:trunc",
+            "sinf:sin",
+            "cosf:cos",
+            "tanf:tan",
+            "asinf:asin",
+            "acosf:acos",
+            "atanf:atan",
+            "expf:exp",
+            "logf:ln",
+            "sinhf:sinh",
+            "coshf:cosh",
+            "tanhf:tanh",
+            "exp2f:exp2",
+            "log2f:log2",
+            "log10f:log10",
+        ] {
+            let (a, b) = f.split_once(':').unwrap();
+            writeln!(src, "    pub fn {a}(x: f32) -> f32 {{ x.{b}() }}")
+                .unwrap();
+        }
+        for f in ["fminf:min", "fmaxf:max", "atan2f:atan2", "powf:powf"] {
+            let (a, b) = f.split_once(':').unwrap();
+            writeln!(
+                src,
+                "    pub fn {a}(x: f32, y: f32) -> f32 {{ x.{b}(y) }}"
+            )
+            .unwrap();
+        }
+        src += "    pub fn fmodf(x: f32, y: f32) -> f32 { x % y }\n}\n\n";
+        src
+    }
+
+    #[test]
+    fn source_c() {
+        if Command::new("cc").arg("--version").output().is_err() {
+            eprintln!("skipping test: no C compiler found");
+            return;
+        }
+        let s = setup(SourceLanguage::C);
+        let n = s.outputs;
+        let p = s.points.len();
+        let q = s.intervals.len();
+
+        let mut src = s.sources.clone();
+        src += "\n#include <stdio.h>\n#include <string.h>\n\n";
+        src += "static void print(float f) {\n    \
+                unsigned int b;\n    \
+                memcpy(&b, &f, sizeof(b));\n    \
+                printf(\"%08x\\n\", b);\n}\n\n";
+        src += "int main(void) {\n";
+        writeln!(src, "    static const float points[{p}][3] = {{").unwrap();
+        for v in &s.points {
+            writeln!(src, "        {{{:?}f, {:?}f, {:?}f}},", v[0], v[1], v[2])
+                .unwrap();
+        }
+        src += "    };\n";
+        writeln!(src, "    static float cols[3][{p}];").unwrap();
+        writeln!(src, "    static float slices[{n}][{p}];").unwrap();
+        writeln!(src, "    static const fidget_interval ints[{q}][3] = {{")
+            .unwrap();
+        for v in &s.intervals {
+            let v = v.map(|i| format!("{{{:?}f, {:?}f}}", i[0], i[1]));
+            writeln!(src, "        {{{}}},", v.join(", ")).unwrap();
+        }
+        src += "    };\n";
+        writeln!(src, "    float out[{n}];").unwrap();
+        writeln!(src, "    fidget_interval iout[{n}];").unwrap();
+        writeln!(src, "    const float *vars[3];").unwrap();
+        writeln!(src, "    float *outs[{n}];").unwrap();
+        src += "    int i, j;\n";
+        writeln!(
+            src,
+            "    for (i = 0; i < {p}; ++i) {{\n        \
+             f_point(points[i], out);\n        \
+             for (j = 0; j < {n}; ++j) print(out[j]);\n    \
+             }}"
+        )
+        .unwrap();
+        writeln!(
+            src,
+            "    for (i = 0; i < {p}; ++i) {{\n        \
+             for (j = 0; j < 3; ++j) cols[j][i] = points[i][j];\n    \
+             }}\n    \
+             for (j = 0; j < 3; ++j) vars[j] = cols[j];\n    \
+             for (j = 0; j < {n}; ++j) outs[j] = slices[j];\n    \
+             f_slice(vars, outs, {p});\n    \
+             for (i = 0; i < {p}; ++i) {{\n        \
+             for (j = 0; j < {n}; ++j) print(slices[j][i]);\n    \
+             }}"
+        )
+        .unwrap();
+        writeln!(
+            src,
+            "    for (i = 0; i < {q}; ++i) {{\n        \
+             f_interval(ints[i], iout);\n        \
+             for (j = 0; j < {n}; ++j) {{\n            \
+             print(iout[j].lower);\n            \
+             print(iout[j].upper);\n        \
+             }}\n    \
+             }}"
+        )
+        .unwrap();
+        src += "    return 0;\n}\n";
+
+        let dir = scratch("c");
+        let file = dir.join("main.c");
+        let exe = dir.join("main");
+        std::fs::write(&file, &src).unwrap();
+        let status = Command::new("cc")
+            .args(["-std=c99", "-O1", "-ffp-contract=off", "-o"])
+            .arg(&exe)
+            .arg(&file)
+            .arg("-lm")
+            .status()
+            .unwrap();
+        assert!(status.success(), "failed to compile generated C");
+        check(&s.expected, &run(&exe));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn source_rust() {
+        let rustc = std::env::var("RUSTC").unwrap_or("rustc".to_owned());
+        if Command::new(&rustc).arg("--version").output().is_err() {
+            eprintln!("skipping test: no Rust compiler found");
+            return;
+        }
+        let s = setup(SourceLanguage::Rust);
+        let n = s.outputs;
+        let p = s.points.len();
+
+        let mut src = libm_stub();
+        src += &s.sources;
+        src += "\nfn main() {\n";
+        src += "    let print = |f: f32| println!(\"{:08x}\", f.to_bits());\n";
+        writeln!(src, "    let points: [[f32; 3]; {p}] = {:?};", s.points)
+            .unwrap();
+        writeln!(
+            src,
+            "    let ints: [[[f32; 2]; 3]; {}] = {:?};",
+            s.intervals.len(),
+            s.intervals
+        )
+        .unwrap();
+        writeln!(
+            src,
+            "    let mut out = [0.0f32; {n}];\n    \
+             for p in &points {{\n        \
+             f_point(p, &mut out);\n        \
+             out.iter().for_each(|f| print(*f));\n    \
+             }}\n    \
+             let cols: Vec<Vec<f32>> = (0..3)\n        \
+             .map(|i| points.iter().map(|p| p[i]).collect())\n        \
+             .collect();\n    \
+             let cols: Vec<&[f32]> = cols.iter().map(|c| c.as_slice()).collect();\n    \
+             let mut slices = vec![vec![0.0f32; {p}]; {n}];\n    \
+             let mut outs: Vec<&mut [f32]> =\n        \
+             slices.iter_mut().map(|c| c.as_mut_slice()).collect();\n    \
+             f_slice(&cols, &mut outs);\n    \
+             for i in 0..{p} {{\n        \
+             slices.iter().for_each(|s| print(s[i]));\n    \
+             }}\n    \
+             let mut iout = [[0.0f32; 2]; {n}];\n    \
+             for p in &ints {{\n        \
+             f_interval(p, &mut iout);\n        \
+             iout.iter().flatten().for_each(|f| print(*f));\n    \
+             }}\n\
+             }}"
+        )
+        .unwrap();
+
+        let dir = scratch("rust");
+        let file = dir.join("main.rs");
+        let exe = dir.join("main");
+        std::fs::write(&file, &src).unwrap();
+        let out = Command::new(&rustc)
+            .args(["--edition", "2021", "-o"])
+            .arg(&exe)
+            .arg(&file)
+            .output()
+            .unwrap();
+        assert!(
+            out.status.success(),
+            "failed to compile generated Rust:\n{}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        check(&s.expected, &run(&exe));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn source_powi() {
+        // `powf` and `powi` round differently for some of these values, and
+        // generated code must match the VM bit-for-bit
+        const XS: [f32; 3] = [0.1, 0.01, -0.3];
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let nodes = [3.0, -3.0, 16.0].map(|n| ctx.pow(x, n).unwrap());
+        let f = F::new(&ctx, &nodes).unwrap();
+
+        let mut expected = vec![];
+        let mut eval = crate::vm::VmPointEval::<4>::new();
+        let tape = f.point_tape(Default::default());
+        for x in XS {
+            let (out, _) = eval.eval(&tape, &[x]).unwrap();
+            expected.extend_from_slice(out);
+        }
+
+        let rustc = std::env::var("RUSTC").unwrap_or("rustc".to_owned());
+        if Command::new(&rustc).arg("--version").output().is_ok() {
+            let opts = SourceOptions {
+                language: SourceLanguage::Rust,
+                ..SourceOptions::default()
+            };
+            let mut src = libm_stub();
+            src += &f.data().to_source(&opts);
+            writeln!(
+                src,
+                "fn main() {{\n    \
+                 let mut out = [0.0f32; {}];\n    \
+                 for x in {XS:?} {{\n        \
+                 f(&[x], &mut out);\n        \
+                 out.iter().for_each(|f| println!(\"{{:08x}}\", f.to_bits()));\n    \
+                 }}\n\
+                 }}",
+                nodes.len()
+            )
+            .unwrap();
+
+            let dir = scratch("rust-powi");
+            let file = dir.join("main.rs");
+            let exe = dir.join("main");
+            std::fs::write(&file, &src).unwrap();
+            let status = Command::new(&rustc)
+                .args(["--edition", "2021", "-o"])
+                .arg(&exe)
+                .arg(&file)
+                .status()
+                .unwrap();
+            assert!(status.success(), "failed to compile generated Rust");
+            check(&expected, &run(&exe));
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+
+        if Command::new("cc").arg("--version").output().is_ok() {
+            let mut src = f.data().to_source(&SourceOptions::default());
+            src += "\n#include <stdio.h>\n#include <string.h>\n\n";
+            src += "int main(void) {\n";
+            writeln!(
+                src,
+                "    static const float xs[{}] = {{{}}};\n    \
+                 float out[{}];\n    \
+                 unsigned int b;\n    \
+                 int i, j;\n    \
+                 for (i = 0; i < {}; ++i) {{\n        \
+                 f(&xs[i], out);\n        \
+                 for (j = 0; j < {}; ++j) {{\n            \
+                 memcpy(&b, &out[j], sizeof(b));\n            \
+                 printf(\"%08x\\n\", b);\n        \
+                 }}\n    \
+                 }}\n    \
+                 return 0;\n}}",
+                XS.len(),
+                XS.map(|x| format!("{x:?}f")).join(", "),
+                nodes.len(),
+                XS.len(),
+                nodes.len(),
+            )
+            .unwrap();
+
+            let dir = scratch("c-powi");
+            let file = dir.join("main.c");
+            let exe = dir.join("main");
+            std::fs::write(&file, &src).unwrap();
+            let status = Command::new("cc")
+                .args(["-std=c99", "-O1", "-ffp-contract=off", "-o"])
+                .arg(&exe)
+                .arg(&file)
+                .arg("-lm")
+                .status()
+                .unwrap();
+            assert!(status.success(), "failed to compile generated C");
+            check(&expected, &run(&exe));
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+    }
+}
@@ -256,6 +256,12 @@ impl<const N: usize> RegisterAllocator<N> {
             SsaOp::AtanReg(out, arg) => (out, arg, RegOp::AtanReg),
             SsaOp::ExpReg(out, arg) => (out, arg, RegOp::ExpReg),
             SsaOp::LnReg(out, arg) => (out, arg, RegOp::LnReg),
+            SsaOp::SinhReg(out, arg) => (out, arg, RegOp::SinhReg),
+            SsaOp::CoshReg(out, arg) => (out, arg, RegOp::CoshReg),
+            SsaOp::TanhReg(out, arg) => (out, arg, RegOp::TanhReg),
+            SsaOp::Exp2Reg(out, arg) => (out, arg, RegOp::Exp2Reg),
+            SsaOp::Log2Reg(out, arg) => (out, arg, RegOp::Log2Reg),
+            SsaOp::Log10Reg(out, arg) => (out, arg, RegOp::Log10Reg),
             SsaOp::NotReg(out, arg) => (out, arg, RegOp::NotReg),
             SsaOp::CopyReg(out, arg) => (out, arg, RegOp::CopyReg),
             _ => panic!("Bad opcode: {op:?}"),
@@ -288,6 +294,12 @@ impl<const N: usize> RegisterAllocator<N> {
             | SsaOp::AtanReg(..)
             | SsaOp::ExpReg(..)
             | SsaOp::LnReg(..)
+            | SsaOp::SinhReg(..)
+            | SsaOp::CoshReg(..)
+            | SsaOp::TanhReg(..)
+            | SsaOp::Exp2Reg(..)
+            | SsaOp::Log2Reg(..)
+            | SsaOp::Log10Reg(..)
             | SsaOp::NotReg(..) => self.op_reg(op),
 
             SsaOp::AddRegImm(..)
@@ -298,6 +310,8 @@ impl<const N: usize> RegisterAllocator<N> {
             | SsaOp::DivImmReg(..)
             | SsaOp::AtanImmReg(..)
             | SsaOp::AtanRegImm(..)
+            | SsaOp::PowImmReg(..)
+            | SsaOp::PowRegImm(..)
             | SsaOp::MinRegImm(..)
             | SsaOp::MaxRegImm(..)
             | SsaOp::CompareRegImm(..)
@@ -312,12 +326,18 @@ impl<const N: usize> RegisterAllocator<N> {
             | SsaOp::MulRegReg(..)
             | SsaOp::DivRegReg(..)
             | SsaOp::AtanRegReg(..)
+            | SsaOp::PowRegReg(..)
             | SsaOp::MinRegReg(..)
             | SsaOp::MaxRegReg(..)
             | SsaOp::CompareRegReg(..)
             | SsaOp::ModRegReg(..)
             | SsaOp::AndRegReg(..)
             | SsaOp::OrRegReg(..) => self.op_reg_reg(op),
+
+            SsaOp::SelectRegReg(..)
+            | SsaOp::SelectRegImm(..)
+            | SsaOp::SmoothMinRegReg(..)
+            | SsaOp::SmoothMinRegImm(..) => self.op_in_place(op),
         }
     }
 
@@ -497,6 +517,9 @@ impl<const N: usize> RegisterAllocator<N> {
             SsaOp::AtanRegReg(out, lhs, rhs) => {
                 (out, lhs, rhs, RegOp::AtanRegReg)
             }
+            SsaOp::PowRegReg(out, lhs, rhs) => {
+                (out, lhs, rhs, RegOp::PowRegReg)
+            }
             SsaOp::MinRegReg(out, lhs, rhs) => {
                 (out, lhs, rhs, RegOp::MinRegReg)
             }
@@ -625,6 +648,12 @@ impl<const N: usize> RegisterAllocator<N> {
             SsaOp::AtanImmReg(out, arg, imm) => {
                 (out, arg, imm, RegOp::AtanImmReg)
             }
+            SsaOp::PowRegImm(out, arg, imm) => {
+                (out, arg, imm, RegOp::PowRegImm)
+            }
+            SsaOp::PowImmReg(out, arg, imm) => {
+                (out, arg, imm, RegOp::PowImmReg)
+            }
             SsaOp::MinRegImm(out, arg, imm) => {
                 (out, arg, imm, RegOp::MinRegImm)
             }
@@ -652,6 +681,69 @@ impl<const N: usize> RegisterAllocator<N> {
         self.op_reg_fn(out, arg, |out, arg| op(out, arg, imm));
     }
 
+    /// Lowers an in-place operation into a [`RegOp`], pushing it to the
+    /// internal tape.
+    ///
+    /// Unlike other operations, the output register is also read: it contains
+    /// a third argument (the other branch of a select, or the blend radius of a
+    /// smooth minimum), which was written by an earlier `CopyReg` or `CopyImm`
+    /// into the same SSA slot.  As such, the output register remains bound
+    /// after this operation, and inputs are never assigned to it.
+    #[inline(always)]
+    fn op_in_place(&mut self, op: SsaOp) {
+        let r_x = match op {
+            SsaOp::SelectRegReg(out, ..)
+            | SsaOp::SelectRegImm(out, ..)
+            | SsaOp::SmoothMinRegReg(out, ..)
+            | SsaOp::SmoothMinRegImm(out, ..) => self.get_out_reg(out),
+            _ => panic!("Bad opcode: {op:?}"),
+        };
+        let op = match op {
+            SsaOp::SelectRegReg(_out, cond, arg) => {
+                let r_c = self.get_input_reg(cond);
+                let r_a = self.get_input_reg(arg);
+                RegOp::SelectRegReg(r_x, r_c, r_a)
+            }
+            SsaOp::SelectRegImm(_out, cond, imm) => {
+                let r_c = self.get_input_reg(cond);
+                RegOp::SelectRegImm(r_x, r_c, imm)
+            }
+            SsaOp::SmoothMinRegReg(_out, lhs, rhs) => {
+                let r_a = self.get_input_reg(lhs);
+                let r_b = self.get_input_reg(rhs);
+                RegOp::SmoothMinRegReg(r_x, r_a, r_b)
+            }
+            SsaOp::SmoothMinRegImm(_out, arg, imm) => {
+                let r_a = self.get_input_reg(arg);
+                RegOp::SmoothMinRegImm(r_x, r_a, imm)
+            }
+            _ => unreachable!(),
+        };
+        self.out.push(op);
+    }
+
+    /// Returns a register that is bound to the given SSA input
+    ///
+    /// If the input is in memory or unassigned, then it is bound to a new
+    /// register (which may evict the oldest register).
+    #[inline]
+    fn get_input_reg(&mut self, arg: u32) -> u8 {
+        match self.get_allocation(arg) {
+            Allocation::Register(r_y) => r_y,
+            Allocation::Memory(m_y) => {
+                let r_a = self.get_register();
+                self.push_store(r_a, m_y);
+                self.bind_register(arg, r_a);
+                r_a
+            }
+            Allocation::Unassigned => {
+                let r_a = self.get_register();
+                self.bind_register(arg, r_a);
+                r_a
+            }
+        }
+    }
+
     #[inline(always)]
     fn op_out_only(&mut self, out: u32, op: impl Fn(u8) -> RegOp) {
         let r_x = self.get_out_reg(out);
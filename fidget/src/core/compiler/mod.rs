@@ -22,6 +22,21 @@ mod ssa_tape;
 pub use reg_tape::RegTape;
 pub use ssa_tape::SsaTape;
 
+/// Largest integer exponent which is evaluated with repeated multiplication
+///
+/// Each multiplication can add a rounding error, so larger exponents fall back
+/// to `powf`.
+const MAX_INTEGER_EXPONENT: i32 = 16;
+
+/// Checks whether a `pow` immediate is a small non-zero integer
+///
+/// If so, evaluators may use repeated multiplication (e.g. [`f32::powi`])
+/// instead of a general-purpose `powf`.
+pub(crate) fn integer_exponent(imm: f32) -> Option<i32> {
+    let n = imm as i32;
+    (n as f32 == imm && n != 0 && n.abs() <= MAX_INTEGER_EXPONENT).then_some(n)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -30,4 +45,16 @@ mod test {
         assert_eq!(std::mem::size_of::<RegOp>(), 8);
         assert_eq!(std::mem::size_of::<SsaOp>(), 16);
     }
+
+    #[test]
+    fn test_integer_exponent() {
+        assert_eq!(integer_exponent(3.0), Some(3));
+        assert_eq!(integer_exponent(-2.0), Some(-2));
+        assert_eq!(integer_exponent(16.0), Some(16));
+        assert_eq!(integer_exponent(17.0), None);
+        assert_eq!(integer_exponent(0.0), None);
+        assert_eq!(integer_exponent(2.5), None);
+        assert_eq!(integer_exponent(f32::NAN), None);
+        assert_eq!(integer_exponent(f32::INFINITY), None);
+    }
 }
@@ -66,6 +66,18 @@ macro_rules! opcodes {
             ExpReg($t, $t),
             #[doc = "Computes the natural log of the given register"]
             LnReg($t, $t),
+            #[doc = "Computes the hyperbolic sine of the given register"]
+            SinhReg($t, $t),
+            #[doc = "Computes the hyperbolic cosine of the given register"]
+            CoshReg($t, $t),
+            #[doc = "Computes the hyperbolic tangent of the given register"]
+            TanhReg($t, $t),
+            #[doc = "Computes the base-2 exponential function of the given register"]
+            Exp2Reg($t, $t),
+            #[doc = "Computes the base-2 log of the given register"]
+            Log2Reg($t, $t),
+            #[doc = "Computes the base-10 log of the given register"]
+            Log10Reg($t, $t),
             #[doc = "Computes the logical negation of the given register\n\nEquivalent to `if arg == 0 { 1 } else { 0 }`"]
             NotReg($t, $t),
 
@@ -88,6 +100,8 @@ macro_rules! opcodes {
             ModRegImm($t, $t, f32),
             #[doc = "atan2 of a position `(y, x)` specified as register, immediate"]
             AtanRegImm($t, $t, f32),
+            #[doc = "Raises a register to the power of an immediate"]
+            PowRegImm($t, $t, f32),
             #[doc = "Compares a register with an immediate"]
             CompareRegImm($t, $t, f32),
 
@@ -106,6 +120,8 @@ macro_rules! opcodes {
             ModImmReg($t, $t, f32),
             #[doc = "atan2 of a position `(y, x)` specified as immediate, register"]
             AtanImmReg($t, $t, f32),
+            #[doc = "Raises an immediate to the power of a register"]
+            PowImmReg($t, $t, f32),
             #[doc = "Compares an immediate with a register"]
             CompareImmReg($t, $t, f32),
 
@@ -122,6 +138,8 @@ macro_rules! opcodes {
             CompareRegReg($t, $t, $t),
             #[doc = "atan2 of a position `(y, x)` specified as register, register"]
             AtanRegReg($t, $t, $t),
+            #[doc = "Raises one register to the power of another"]
+            PowRegReg($t, $t, $t),
 
             // RegReg opcodes (with a choice)
             #[doc = "Take the minimum of two registers"]
@@ -133,6 +151,16 @@ macro_rules! opcodes {
             #[doc = "Logical `OR` (short-circuiting)\n\nThis is equivalent to `if lhs != 0 { lhs } else { rhs }`"]
             OrRegReg($t, $t, $t),
 
+            // In-place opcodes (with a choice), which also read the output
+            #[doc = "Conditionally selects a register\n\nThis is equivalent to `if cond != 0 { arg } else { out }`, i.e. the other branch must already be in the output register.  Arguments are `(out, cond, arg)`."]
+            SelectRegReg($t, $t, $t),
+            #[doc = "Conditionally selects an immediate\n\nThis is equivalent to `if cond != 0 { imm } else { out }`, i.e. the other branch must already be in the output register.  Arguments are `(out, cond, imm)`."]
+            SelectRegImm($t, $t, f32),
+            #[doc = "Polynomial smooth minimum of two registers\n\nThe blend radius `k` must already be in the output register.  Arguments are `(out, lhs, rhs)`."]
+            SmoothMinRegReg($t, $t, $t),
+            #[doc = "Polynomial smooth minimum of a register and an immediate\n\nThe blend radius `k` must already be in the output register.  Arguments are `(out, arg, imm)`."]
+            SmoothMinRegImm($t, $t, f32),
+
             $(
                 $(#[$($a)*])*
                 $foo($($i),*)
@@ -179,6 +207,12 @@ impl SsaOp {
             | SsaOp::AtanReg(out, ..)
             | SsaOp::ExpReg(out, ..)
             | SsaOp::LnReg(out, ..)
+            | SsaOp::SinhReg(out, ..)
+            | SsaOp::CoshReg(out, ..)
+            | SsaOp::TanhReg(out, ..)
+            | SsaOp::Exp2Reg(out, ..)
+            | SsaOp::Log2Reg(out, ..)
+            | SsaOp::Log10Reg(out, ..)
             | SsaOp::NotReg(out, ..)
             | SsaOp::AddRegImm(out, ..)
             | SsaOp::MulRegImm(out, ..)
@@ -193,6 +227,9 @@ impl SsaOp {
             | SsaOp::AtanRegReg(out, ..)
             | SsaOp::AtanRegImm(out, ..)
             | SsaOp::AtanImmReg(out, ..)
+            | SsaOp::PowRegReg(out, ..)
+            | SsaOp::PowRegImm(out, ..)
+            | SsaOp::PowImmReg(out, ..)
             | SsaOp::MinRegImm(out, ..)
             | SsaOp::MaxRegImm(out, ..)
             | SsaOp::MinRegReg(out, ..)
@@ -206,7 +243,11 @@ impl SsaOp {
             | SsaOp::AndRegImm(out, ..)
             | SsaOp::AndRegReg(out, ..)
             | SsaOp::OrRegImm(out, ..)
-            | SsaOp::OrRegReg(out, ..) => Some(*out),
+            | SsaOp::OrRegReg(out, ..)
+            | SsaOp::SelectRegReg(out, ..)
+            | SsaOp::SelectRegImm(out, ..)
+            | SsaOp::SmoothMinRegReg(out, ..)
+            | SsaOp::SmoothMinRegImm(out, ..) => Some(*out),
             SsaOp::Output(..) => None,
         }
     }
@@ -233,6 +274,12 @@ impl SsaOp {
             | SsaOp::AtanReg(..)
             | SsaOp::ExpReg(..)
             | SsaOp::LnReg(..)
+            | SsaOp::SinhReg(..)
+            | SsaOp::CoshReg(..)
+            | SsaOp::TanhReg(..)
+            | SsaOp::Exp2Reg(..)
+            | SsaOp::Log2Reg(..)
+            | SsaOp::Log10Reg(..)
             | SsaOp::NotReg(..)
             | SsaOp::AddRegImm(..)
             | SsaOp::MulRegImm(..)
@@ -247,6 +294,9 @@ impl SsaOp {
             | SsaOp::AtanRegReg(..)
             | SsaOp::AtanRegImm(..)
             | SsaOp::AtanImmReg(..)
+            | SsaOp::PowRegReg(..)
+            | SsaOp::PowRegImm(..)
+            | SsaOp::PowImmReg(..)
             | SsaOp::CompareRegReg(..)
             | SsaOp::CompareRegImm(..)
             | SsaOp::CompareImmReg(..)
@@ -260,7 +310,11 @@ impl SsaOp {
             | SsaOp::AndRegImm(..)
             | SsaOp::AndRegReg(..)
             | SsaOp::OrRegImm(..)
-            | SsaOp::OrRegReg(..) => true,
+            | SsaOp::OrRegReg(..)
+            | SsaOp::SelectRegReg(..)
+            | SsaOp::SelectRegImm(..)
+            | SsaOp::SmoothMinRegReg(..)
+            | SsaOp::SmoothMinRegImm(..) => true,
         }
     }
 }
@@ -1,7 +1,7 @@
 //use crate::vm::{RegisterAllocator, Tape as VmTape};
 use crate::{
     compiler::SsaOp,
-    context::{BinaryOpcode, Node, Op, UnaryOpcode},
+    context::{BinaryOpcode, Node, Op, TernaryOpcode, UnaryOpcode},
     var::VarMap,
     Context, Error,
 };
@@ -37,6 +37,20 @@ impl SsaTape {
     /// This should always succeed unless the `root` is from a different
     /// `Context`, in which case `Error::BadNode` will be returned.
     pub fn new(ctx: &Context, roots: &[Node]) -> Result<(Self, VarMap), Error> {
+        Self::new_with_immediates(ctx, roots, |c| c as f32)
+    }
+
+    /// Flattens a subtree of the graph, using a custom conversion from
+    /// constants to immediates
+    ///
+    /// This is used by evaluators which need more than `f32` precision: they
+    /// can map each constant to a unique key, then look up the original value
+    /// during evaluation.
+    pub(crate) fn new_with_immediates<F: FnMut(f64) -> f32>(
+        ctx: &Context,
+        roots: &[Node],
+        mut to_imm: F,
+    ) -> Result<(Self, VarMap), Error> {
         let mut mapping = HashMap::new();
         let mut parent_count: HashMap<Node, usize> = HashMap::new();
         let mut slot_count = 0;
@@ -59,11 +73,14 @@ impl SsaTape {
             let op = ctx.get_op(node).ok_or(Error::BadNode)?;
             let prev = match op {
                 Op::Const(c) => {
-                    mapping.insert(node, Slot::Immediate(c.0 as f32))
+                    mapping.insert(node, Slot::Immediate(to_imm(c.0)))
                 }
                 _ => {
                     if let Op::Input(v) = op {
                         vars.insert(*v);
+                        if let Some(info) = ctx.var_registry().get(v) {
+                            vars.set_info(*v, info.clone());
+                        }
                     }
                     let i = slot_count;
                     slot_count += 1;
@@ -150,6 +167,11 @@ impl SsaTape {
                             SsaOp::AtanRegImm,
                             SsaOp::AtanImmReg,
                         ),
+                        BinaryOpcode::Pow => (
+                            SsaOp::PowRegReg,
+                            SsaOp::PowRegImm,
+                            SsaOp::PowImmReg,
+                        ),
                         BinaryOpcode::Min => (
                             SsaOp::MinRegReg,
                             SsaOp::MinRegImm,
@@ -209,6 +231,52 @@ impl SsaTape {
                         }
                     }
                 }
+                Op::Ternary(TernaryOpcode::Select, cond, a, b) => {
+                    let cond = match mapping[cond] {
+                        Slot::Reg(r) => r,
+                        Slot::Immediate(..) => {
+                            panic!("Cannot handle select(imm, ..)")
+                        }
+                    };
+                    choice_count += 1;
+
+                    // The `b` branch is copied into the output slot, which is
+                    // then conditionally overwritten by the `a` branch.  The
+                    // tape is in reverse order, so the select comes first.
+                    tape.push(match mapping[a] {
+                        Slot::Reg(a) => SsaOp::SelectRegReg(i, cond, a),
+                        Slot::Immediate(imm) => {
+                            SsaOp::SelectRegImm(i, cond, imm)
+                        }
+                    });
+                    match mapping[b] {
+                        Slot::Reg(b) => SsaOp::CopyReg(i, b),
+                        Slot::Immediate(imm) => SsaOp::CopyImm(i, imm),
+                    }
+                }
+                Op::Ternary(TernaryOpcode::SmoothMin, a, b, k) => {
+                    choice_count += 1;
+
+                    // The blend radius is copied into the output slot, then
+                    // used by the smooth minimum operation (which is
+                    // symmetric, so we can put an immediate on either side)
+                    tape.push(match (mapping[a], mapping[b]) {
+                        (Slot::Reg(a), Slot::Reg(b)) => {
+                            SsaOp::SmoothMinRegReg(i, a, b)
+                        }
+                        (Slot::Reg(arg), Slot::Immediate(imm))
+                        | (Slot::Immediate(imm), Slot::Reg(arg)) => {
+                            SsaOp::SmoothMinRegImm(i, arg, imm)
+                        }
+                        (Slot::Immediate(..), Slot::Immediate(..)) => {
+                            panic!("Cannot handle smooth_min(imm, imm, ..)")
+                        }
+                    });
+                    match mapping[k] {
+                        Slot::Reg(k) => SsaOp::CopyReg(i, k),
+                        Slot::Immediate(imm) => SsaOp::CopyImm(i, imm),
+                    }
+                }
                 Op::Unary(op, lhs) => {
                     let lhs = match mapping[lhs] {
                         Slot::Reg(r) => r,
@@ -233,6 +301,12 @@ impl SsaTape {
                         UnaryOpcode::Atan => SsaOp::AtanReg,
                         UnaryOpcode::Exp => SsaOp::ExpReg,
                         UnaryOpcode::Ln => SsaOp::LnReg,
+                        UnaryOpcode::Sinh => SsaOp::SinhReg,
+                        UnaryOpcode::Cosh => SsaOp::CoshReg,
+                        UnaryOpcode::Tanh => SsaOp::TanhReg,
+                        UnaryOpcode::Exp2 => SsaOp::Exp2Reg,
+                        UnaryOpcode::Log2 => SsaOp::Log2Reg,
+                        UnaryOpcode::Log10 => SsaOp::Log10Reg,
                         UnaryOpcode::Not => SsaOp::NotReg,
                     };
                     op(i, lhs)
@@ -300,6 +374,12 @@ impl SsaTape {
                 | SsaOp::AtanReg(out, arg)
                 | SsaOp::ExpReg(out, arg)
                 | SsaOp::LnReg(out, arg)
+                | SsaOp::SinhReg(out, arg)
+                | SsaOp::CoshReg(out, arg)
+                | SsaOp::TanhReg(out, arg)
+                | SsaOp::Exp2Reg(out, arg)
+                | SsaOp::Log2Reg(out, arg)
+                | SsaOp::Log10Reg(out, arg)
                 | SsaOp::NotReg(out, arg) => {
                     let op = match op {
                         SsaOp::NegReg(..) => "NEG",
@@ -318,6 +398,12 @@ impl SsaTape {
                         SsaOp::AtanReg(..) => "ATAN",
                         SsaOp::ExpReg(..) => "EXP",
                         SsaOp::LnReg(..) => "LN",
+                        SsaOp::SinhReg(..) => "SINH",
+                        SsaOp::CoshReg(..) => "COSH",
+                        SsaOp::TanhReg(..) => "TANH",
+                        SsaOp::Exp2Reg(..) => "EXP2",
+                        SsaOp::Log2Reg(..) => "LOG2",
+                        SsaOp::Log10Reg(..) => "LOG10",
                         SsaOp::NotReg(..) => "NOT",
                         SsaOp::CopyReg(..) => "COPY",
                         _ => unreachable!(),
@@ -334,12 +420,14 @@ impl SsaTape {
                 | SsaOp::ModRegReg(out, lhs, rhs)
                 | SsaOp::AndRegReg(out, lhs, rhs)
                 | SsaOp::AtanRegReg(out, lhs, rhs)
+                | SsaOp::PowRegReg(out, lhs, rhs)
                 | SsaOp::OrRegReg(out, lhs, rhs) => {
                     let op = match op {
                         SsaOp::AddRegReg(..) => "ADD",
                         SsaOp::MulRegReg(..) => "MUL",
                         SsaOp::DivRegReg(..) => "DIV",
                         SsaOp::AtanRegReg(..) => "ATAN",
+                        SsaOp::PowRegReg(..) => "POW",
                         SsaOp::SubRegReg(..) => "SUB",
                         SsaOp::MinRegReg(..) => "MIN",
                         SsaOp::MaxRegReg(..) => "MAX",
@@ -359,6 +447,8 @@ impl SsaTape {
                 | SsaOp::SubRegImm(out, arg, imm)
                 | SsaOp::AtanRegImm(out, arg, imm)
                 | SsaOp::AtanImmReg(out, arg, imm)
+                | SsaOp::PowRegImm(out, arg, imm)
+                | SsaOp::PowImmReg(out, arg, imm)
                 | SsaOp::MinRegImm(out, arg, imm)
                 | SsaOp::MaxRegImm(out, arg, imm)
                 | SsaOp::ModRegImm(out, arg, imm)
@@ -374,6 +464,8 @@ impl SsaTape {
                         SsaOp::SubRegImm(..) => ("SUB", false),
                         SsaOp::AtanImmReg(..) => ("ATAN", true),
                         SsaOp::AtanRegImm(..) => ("ATAN", false),
+                        SsaOp::PowImmReg(..) => ("POW", true),
+                        SsaOp::PowRegImm(..) => ("POW", false),
                         SsaOp::MinRegImm(..) => ("MIN", false),
                         SsaOp::MaxRegImm(..) => ("MAX", false),
                         SsaOp::ModRegImm(..) => ("MOD", false),
@@ -397,6 +489,18 @@ impl SsaTape {
                 SsaOp::CompareImmReg(out, arg, imm) => {
                     println!("${out} = COMPARE {imm} {arg}")
                 }
+                SsaOp::SelectRegReg(out, cond, arg) => {
+                    println!("${out} = SELECT ${cond} ${arg} ${out}")
+                }
+                SsaOp::SelectRegImm(out, cond, imm) => {
+                    println!("${out} = SELECT ${cond} {imm} ${out}")
+                }
+                SsaOp::SmoothMinRegReg(out, lhs, rhs) => {
+                    println!("${out} = SMOOTH_MIN ${lhs} ${rhs} ${out}")
+                }
+                SsaOp::SmoothMinRegImm(out, arg, imm) => {
+                    println!("${out} = SMOOTH_MIN ${arg} {imm} ${out}")
+                }
                 SsaOp::CopyImm(out, imm) => {
                     println!("${out} = COPY {imm}");
                 }
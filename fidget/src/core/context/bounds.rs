@@ -0,0 +1,411 @@
+//! Static range analysis of [`Context`] graphs
+//!
+//! This performs interval arithmetic directly on the graph, without building a
+//! tape or function, so it's cheap enough to use when sizing bounding boxes or
+//! checking whether a subexpression can ever be negative.
+use super::{BinaryOpcode, Context, Node, Op, TernaryOpcode, UnaryOpcode};
+use crate::{types::Interval, var::Var, vm::Choice, Error};
+
+use std::collections::HashMap;
+
+/// Result of calling [`Context::bounds`]
+///
+/// This stores a guaranteed range for every node reachable from the root, and
+/// a [`Choice`] for every branching node (`min`, `max`, `and`, `or`, selects,
+/// and smooth minimums).
+#[derive(Clone, Debug, Default)]
+pub struct Bounds {
+    ranges: HashMap<Node, Interval>,
+    choices: HashMap<Node, Choice>,
+}
+
+impl Bounds {
+    /// Returns the range of the given node, if it was reachable from the root
+    pub fn get(&self, n: Node) -> Option<Interval> {
+        self.ranges.get(&n).cloned()
+    }
+
+    /// Returns the choice made at the given node
+    ///
+    /// This is `None` if the node is unreachable or isn't a branching node.
+    pub fn choice(&self, n: Node) -> Option<Choice> {
+        self.choices.get(&n).cloned()
+    }
+
+    /// Iterates over every `(node, range)` pair
+    pub fn iter(&self) -> impl Iterator<Item = (Node, Interval)> + '_ {
+        self.ranges.iter().map(|(n, i)| (*n, *i))
+    }
+
+    /// Iterates over branching nodes which are decided for the whole domain
+    ///
+    /// Each node is returned along with the branch that it always takes (either
+    /// [`Choice::Left`] or [`Choice::Right`]).
+    pub fn decided(&self) -> impl Iterator<Item = (Node, Choice)> + '_ {
+        self.choices
+            .iter()
+            .filter(|(_n, c)| matches!(c, Choice::Left | Choice::Right))
+            .map(|(n, c)| (*n, *c))
+    }
+
+    /// Returns the number of nodes with a stored range
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    /// Checks whether the bounds are empty
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+}
+
+impl Context {
+    /// Computes guaranteed ranges for a node and all of its children
+    ///
+    /// `vars` must contain a range for every variable reachable from `root`;
+    /// otherwise, this returns [`Error::MissingVar`].
+    ///
+    /// The analysis uses the same [`Interval`] arithmetic as the interval
+    /// evaluators, with each node's range computed once.  Branching nodes
+    /// also record a [`Choice`], so callers can see which branches are
+    /// statically decided over the whole domain (see [`Bounds::decided`]).
+    ///
+    /// ```
+    /// # use fidget::{context::Context, types::Interval, var::Var, vm::Choice};
+    /// let mut ctx = Context::new();
+    /// let x = ctx.x();
+    /// let x2 = ctx.square(x).unwrap();
+    /// let r = ctx.add(x2, 1.0).unwrap();
+    /// let y = ctx.y();
+    /// let m = ctx.max(r, y).unwrap(); // always picks `r`
+    ///
+    /// let vars = [
+    ///     (Var::X, Interval::new(-2.0, 1.0)),
+    ///     (Var::Y, Interval::new(-1.0, 0.5)),
+    /// ];
+    /// let b = ctx.bounds(m, &vars.into()).unwrap();
+    /// assert_eq!(b.get(r), Some(Interval::new(1.0, 5.0)));
+    /// assert_eq!(b.choice(m), Some(Choice::Left));
+    /// assert_eq!(b.get(m), Some(Interval::new(1.0, 5.0)));
+    /// ```
+    pub fn bounds(
+        &self,
+        root: Node,
+        vars: &HashMap<Var, Interval>,
+    ) -> Result<Bounds, Error> {
+        let mut out = Bounds::default();
+        for n in self.reachable_nodes(&[root])? {
+            let get = |c: &Node| out.ranges[c];
+            let (v, choice) = match self.get_op(n).unwrap() {
+                Op::Input(v) => {
+                    (*vars.get(v).ok_or(Error::MissingVar(*v))?, None)
+                }
+                Op::Const(c) => (Interval::from(c.0 as f32), None),
+                Op::Unary(op, a) => (unary(*op, get(a)), None),
+                Op::Binary(op, a, b) => binary(*op, get(a), get(b)),
+                Op::Ternary(op, a, b, c) => {
+                    let (a, b, c) = (get(a), get(b), get(c));
+                    let (v, c) = match op {
+                        TernaryOpcode::Select => a.select_choice(b, c),
+                        TernaryOpcode::SmoothMin => a.smooth_min_choice(b, c),
+                    };
+                    (v, Some(c))
+                }
+            };
+            out.ranges.insert(n, v);
+            if let Some(c) = choice {
+                out.choices.insert(n, c);
+            }
+        }
+        Ok(out)
+    }
+
+    /// Specializes a node for a particular domain
+    ///
+    /// This runs [`Context::bounds`], then rebuilds the graph with every
+    /// statically decided branch (`min`, `max`, `and`, `or`, selects, and
+    /// smooth minimums) replaced by the branch that it always takes.  The
+    /// result is equal to `root` everywhere in the domain given by `vars`, but
+    /// may be significantly smaller.
+    ///
+    /// This is the graph-level equivalent of
+    /// [`Function::simplify`](crate::eval::Function::simplify).  The original
+    /// nodes are left in place; use [`Context::gc`] to remove nodes which are
+    /// no longer needed.
+    ///
+    /// ```
+    /// # use fidget::{context::Context, types::Interval, var::Var};
+    /// let mut ctx = Context::new();
+    /// let x = ctx.x();
+    /// let y = ctx.y();
+    /// let m = ctx.min(x, y).unwrap();
+    ///
+    /// let vars = [
+    ///     (Var::X, Interval::new(-2.0, -1.0)),
+    ///     (Var::Y, Interval::new(0.0, 1.0)),
+    /// ];
+    /// let out = ctx.specialize(m, &vars.into()).unwrap();
+    /// assert_eq!(out, x);
+    /// ```
+    pub fn specialize(
+        &mut self,
+        root: Node,
+        vars: &HashMap<Var, Interval>,
+    ) -> Result<Node, Error> {
+        let bounds = self.bounds(root, vars)?;
+
+        // Only follow branches which are still taken after pruning
+        let nodes = self.reachable_nodes_by(&[root], |n, todo| {
+            match self.pick(n, &bounds) {
+                Some(c) => todo.push(c),
+                None => todo.extend(self.get_op(n).unwrap().iter_children()),
+            }
+        })?;
+        let mut remap = HashMap::new();
+        for n in nodes {
+            let out = match self.pick(n, &bounds) {
+                Some(c) => remap[&c],
+                None => {
+                    let op = self.get_op(n).unwrap().remap(|c| remap[&c]);
+                    self.insert_op(op)?
+                }
+            };
+            remap.insert(n, out);
+        }
+        Ok(remap[&root])
+    }
+
+    /// Returns the child which is always picked by a branching node
+    fn pick(&self, n: Node, bounds: &Bounds) -> Option<Node> {
+        let (a, b) = match *self.get_op(n)? {
+            Op::Binary(
+                BinaryOpcode::Min
+                | BinaryOpcode::Max
+                | BinaryOpcode::And
+                | BinaryOpcode::Or,
+                a,
+                b,
+            ) => (a, b),
+            Op::Ternary(TernaryOpcode::Select, _cond, a, b) => (a, b),
+            Op::Ternary(TernaryOpcode::SmoothMin, a, b, _k) => (a, b),
+            _ => return None,
+        };
+        match bounds.choice(n)? {
+            Choice::Left => Some(a),
+            Choice::Right => Some(b),
+            Choice::Both | Choice::Unknown => None,
+        }
+    }
+}
+
+fn unary(op: UnaryOpcode, a: Interval) -> Interval {
+    match op {
+        UnaryOpcode::Neg => -a,
+        UnaryOpcode::Abs => a.abs(),
+        UnaryOpcode::Recip => a.recip(),
+        UnaryOpcode::Sqrt => a.sqrt(),
+        UnaryOpcode::Square => a.square(),
+        UnaryOpcode::Floor => a.floor(),
+        UnaryOpcode::Ceil => a.ceil(),
+        UnaryOpcode::Round => a.round(),
+        UnaryOpcode::Sin => a.sin(),
+        UnaryOpcode::Cos => a.cos(),
+        UnaryOpcode::Tan => a.tan(),
+        UnaryOpcode::Asin => a.asin(),
+        UnaryOpcode::Acos => a.acos(),
+        UnaryOpcode::Atan => a.atan(),
+        UnaryOpcode::Exp => a.exp(),
+        UnaryOpcode::Ln => a.ln(),
+        UnaryOpcode::Sinh => a.sinh(),
+        UnaryOpcode::Cosh => a.cosh(),
+        UnaryOpcode::Tanh => a.tanh(),
+        UnaryOpcode::Exp2 => a.exp2(),
+        UnaryOpcode::Log2 => a.log2(),
+        UnaryOpcode::Log10 => a.log10(),
+        UnaryOpcode::Not => {
+            if !a.contains(0.0) && !a.has_nan() {
+                Interval::new(0.0, 0.0)
+            } else if a.lower() == 0.0 && a.upper() == 0.0 {
+                Interval::new(1.0, 1.0)
+            } else {
+                Interval::new(0.0, 1.0)
+            }
+        }
+    }
+}
+
+fn binary(
+    op: BinaryOpcode,
+    a: Interval,
+    b: Interval,
+) -> (Interval, Option<Choice>) {
+    let v = match op {
+        BinaryOpcode::Add => a + b,
+        BinaryOpcode::Sub => a - b,
+        BinaryOpcode::Mul => a * b,
+        BinaryOpcode::Div => a / b,
+        BinaryOpcode::Atan => a.atan2(b),
+        BinaryOpcode::Mod => a.rem_euclid(b),
+        BinaryOpcode::Pow => a.pow(b),
+        BinaryOpcode::Compare => {
+            if a.has_nan() || b.has_nan() {
+                f32::NAN.into()
+            } else if a.upper() < b.lower() {
+                Interval::from(-1.0)
+            } else if a.lower() > b.upper() {
+                Interval::from(1.0)
+            } else {
+                Interval::new(-1.0, 1.0)
+            }
+        }
+        BinaryOpcode::Min => return split(a.min_choice(b)),
+        BinaryOpcode::Max => return split(a.max_choice(b)),
+        BinaryOpcode::And => return split(a.and_choice(b)),
+        BinaryOpcode::Or => return split(a.or_choice(b)),
+    };
+    (v, None)
+}
+
+fn split((v, c): (Interval, Choice)) -> (Interval, Option<Choice>) {
+    (v, Some(c))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bounds() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let a = ctx.sub(x, y).unwrap();
+        let b = ctx.mul(a, z).unwrap();
+        let c = ctx.min(b, 10.0).unwrap();
+        let d = ctx.sqrt(z).unwrap();
+        let root = ctx.add(c, d).unwrap();
+        let unused = ctx.neg(root).unwrap();
+
+        let vars = [
+            (Var::X, Interval::new(0.0, 1.0)),
+            (Var::Y, Interval::new(-1.0, 2.0)),
+            (Var::Z, Interval::new(1.0, 4.0)),
+        ]
+        .into();
+        let bounds = ctx.bounds(root, &vars).unwrap();
+        assert_eq!(bounds.len(), 9);
+        assert_eq!(bounds.get(a), Some(Interval::new(-2.0, 2.0)));
+        assert_eq!(bounds.get(b), Some(Interval::new(-8.0, 8.0)));
+        assert_eq!(bounds.get(c), Some(Interval::new(-8.0, 8.0)));
+        assert_eq!(bounds.get(d), Some(Interval::new(1.0, 2.0)));
+        assert_eq!(bounds.get(root), Some(Interval::new(-7.0, 10.0)));
+        assert_eq!(bounds.get(unused), None);
+
+        // The min is always decided, and no other nodes have choices
+        assert_eq!(bounds.choice(c), Some(Choice::Left));
+        assert_eq!(bounds.choice(b), None);
+        assert_eq!(
+            bounds.decided().collect::<Vec<_>>(),
+            vec![(c, Choice::Left)]
+        );
+
+        // With a wider domain, the min is undecided
+        let vars = [
+            (Var::X, Interval::new(0.0, 10.0)),
+            (Var::Y, Interval::new(-1.0, 2.0)),
+            (Var::Z, Interval::new(1.0, 4.0)),
+        ]
+        .into();
+        let bounds = ctx.bounds(root, &vars).unwrap();
+        assert_eq!(bounds.choice(c), Some(Choice::Both));
+        assert_eq!(bounds.decided().count(), 0);
+
+        // The range always contains the point-wise value
+        for (x, y, z) in [(0.0, -1.0, 1.0), (1.0, 2.0, 4.0), (0.5, 0.5, 2.0)] {
+            let v = ctx.eval_xyz(root, x, y, z).unwrap() as f32;
+            assert!(bounds.get(root).unwrap().contains(v));
+        }
+    }
+
+    #[test]
+    fn test_bounds_branches() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let v = Var::new();
+        let r = ctx.var(v);
+        let cond = ctx.less_than(x, r).unwrap();
+        let sel = ctx.if_nonzero_else(cond, x, r).unwrap();
+        let s = ctx.smooth_min(x, r, 0.5).unwrap();
+        let root = ctx.add(sel, s).unwrap();
+
+        let vars = [
+            (Var::X, Interval::new(-2.0, -1.0)),
+            (v, Interval::new(1.0, 3.0)),
+        ]
+        .into();
+        let bounds = ctx.bounds(root, &vars).unwrap();
+        assert_eq!(bounds.get(cond), Some(Interval::new(1.0, 1.0)));
+        assert_eq!(bounds.choice(sel), Some(Choice::Left));
+        assert_eq!(bounds.choice(s), Some(Choice::Left));
+        assert_eq!(bounds.get(root), Some(Interval::new(-4.0, -2.0)));
+
+        // Missing variables are an error
+        let vars = [(Var::X, Interval::new(-2.0, -1.0))].into();
+        assert!(matches!(
+            ctx.bounds(root, &vars),
+            Err(Error::MissingVar(u)) if u == v
+        ));
+        assert!(ctx.bounds(Node(1000), &vars).is_err());
+    }
+
+    #[test]
+    fn test_specialize() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let a = ctx.sin(x).unwrap();
+        let b = ctx.sub(y, 3.0).unwrap();
+        let m = ctx.max(a, b).unwrap();
+        let c = ctx.less_than(z, 0.0).unwrap();
+        let s = ctx.if_nonzero_else(c, m, y).unwrap();
+        let root = ctx.mul(s, 2.0).unwrap();
+
+        // When z < 0 and y < 2, the select and the max are both decided
+        let vars = [
+            (Var::X, Interval::new(-1.0, 1.0)),
+            (Var::Y, Interval::new(0.0, 1.0)),
+            (Var::Z, Interval::new(-2.0, -1.0)),
+        ]
+        .into();
+        let out = ctx.specialize(root, &vars).unwrap();
+        assert_eq!(out, ctx.mul(a, 2.0).unwrap());
+
+        // When z straddles zero, only the max is decided
+        let vars = [
+            (Var::X, Interval::new(-1.0, 1.0)),
+            (Var::Y, Interval::new(0.0, 1.0)),
+            (Var::Z, Interval::new(-1.0, 1.0)),
+        ]
+        .into();
+        let out = ctx.specialize(root, &vars).unwrap();
+        let expected = ctx.if_nonzero_else(c, a, y).unwrap();
+        assert_eq!(out, ctx.mul(expected, 2.0).unwrap());
+        for (x, y, z) in [(0.5, 0.25, -0.5), (-0.5, 0.75, 0.5)] {
+            assert_eq!(
+                ctx.eval_xyz(out, x, y, z).unwrap(),
+                ctx.eval_xyz(root, x, y, z).unwrap()
+            );
+        }
+
+        // Nothing is decided over a large domain
+        let vars = [
+            (Var::X, Interval::new(-10.0, 10.0)),
+            (Var::Y, Interval::new(-10.0, 10.0)),
+            (Var::Z, Interval::new(-10.0, 10.0)),
+        ]
+        .into();
+        assert_eq!(ctx.specialize(root, &vars).unwrap(), root);
+    }
+}
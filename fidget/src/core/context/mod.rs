@@ -15,17 +15,30 @@
 //!   [`Function`](crate::eval::Function) objects for evaluation.
 //!
 //! In other words, the typical workflow is `Tree → (Context, Node) → Function`.
+mod bounds;
 mod indexed;
 mod op;
+mod print;
+mod rewrite;
+mod shared;
+mod structural;
 mod tree;
 
+pub use bounds::Bounds;
 use indexed::{define_index, Index, IndexMap, IndexVec};
-pub use op::{BinaryOpcode, Op, UnaryOpcode};
+pub use op::{BinaryOpcode, Op, TernaryOpcode, UnaryOpcode};
+#[cfg(test)]
+pub(crate) use print::check_round_trip;
+pub use rewrite::RewriteReport;
+pub use shared::SharedContext;
 pub use tree::{Tree, TreeOp};
 
-use crate::{var::Var, Error};
+use crate::{
+    var::{Var, VarInfo, VarRegistry},
+    Error,
+};
 
-use std::collections::{BTreeMap, HashMap};
+use std::collections::HashMap;
 use std::fmt::Write;
 use std::io::{BufRead, BufReader, Read};
 use std::sync::Arc;
@@ -40,14 +53,18 @@ define_index!(Node, "An index in the `Context::ops` map");
 ///
 /// It should be used like an arena allocator: it grows over time, then frees
 /// all of its contents when dropped.  There is no reference counting within the
-/// context.
+/// context; unused nodes can be dropped explicitly with [`Context::gc`].
 ///
 /// Items in the context are accessed with [`Node`] keys, which are simple
 /// handles into an internal map.  Inside the context, operations are
 /// represented with the [`Op`] type.
+///
+/// The context also owns a [`VarRegistry`], which attaches names and metadata
+/// to generic variables; see [`Context::named_var`].
 #[derive(Debug, Default)]
 pub struct Context {
     ops: IndexMap<Op, Node>,
+    vars: VarRegistry,
 }
 
 impl Context {
@@ -69,6 +86,7 @@ impl Context {
     /// ```
     pub fn clear(&mut self) {
         self.ops.clear();
+        self.vars.clear();
     }
 
     /// Returns the number of [`Op`] nodes in the context
@@ -92,6 +110,76 @@ impl Context {
         self.ops.is_empty()
     }
 
+    /// Drops every node which is not reachable from the given roots
+    ///
+    /// Surviving nodes are renumbered to compact the context; the returned map
+    /// goes from old to new [`Node`] handles.  Any handle which is not in the
+    /// map is invalidated.
+    ///
+    /// ```
+    /// # use fidget::context::Context;
+    /// let mut ctx = Context::new();
+    /// let x = ctx.x();
+    /// let y = ctx.y();
+    /// let _unused = ctx.mul(x, y).unwrap();
+    /// let sum = ctx.add(x, 1.0).unwrap();
+    /// assert_eq!(ctx.len(), 5);
+    ///
+    /// let remap = ctx.gc(&[sum]).unwrap();
+    /// assert_eq!(ctx.len(), 3);
+    /// assert!(!remap.contains_key(&y));
+    ///
+    /// let sum = remap[&sum];
+    /// assert_eq!(ctx.eval_xyz(sum, 2.0, 0.0, 0.0).unwrap(), 3.0);
+    /// ```
+    pub fn gc(&mut self, roots: &[Node]) -> Result<HashMap<Node, Node>, Error> {
+        let mut ops = IndexMap::default();
+        let mut remap = HashMap::new();
+        for n in self.reachable_nodes(roots)? {
+            let op = self.get_op(n).unwrap().remap(|c| remap[&c]);
+            remap.insert(n, ops.insert(op));
+        }
+        self.ops = ops;
+        Ok(remap)
+    }
+
+    /// Returns every node reachable from `roots`, in topological order
+    ///
+    /// Children always have lower indices than their parents (a node can only
+    /// be built from existing nodes), so sorting by index places each node
+    /// after all of its children.  Callers can then evaluate or rebuild the
+    /// graph in a single pass over the returned nodes.
+    fn reachable_nodes(&self, roots: &[Node]) -> Result<Vec<Node>, Error> {
+        self.reachable_nodes_by(roots, |n, todo| {
+            todo.extend(self.get_op(n).unwrap().iter_children())
+        })
+    }
+
+    /// Returns nodes reachable from `roots` through a custom set of children,
+    /// in topological order
+    ///
+    /// `children(n, todo)` pushes the children of `n` which should be followed
+    /// onto `todo`; see [`Context::reachable_nodes`] for ordering details.
+    fn reachable_nodes_by<F: FnMut(Node, &mut Vec<Node>)>(
+        &self,
+        roots: &[Node],
+        mut children: F,
+    ) -> Result<Vec<Node>, Error> {
+        let mut keep = vec![false; self.len()];
+        let mut todo = vec![];
+        for &r in roots {
+            self.check_node(r)?;
+            todo.push(r);
+        }
+        while let Some(n) = todo.pop() {
+            if std::mem::replace(&mut keep[n.0], true) {
+                continue;
+            }
+            children(n, &mut todo);
+        }
+        Ok(self.ops.keys().filter(|n| keep[n.0]).collect())
+    }
+
     /// Checks whether the given [`Node`] is valid in this context
     fn check_node(&self, node: Node) -> Result<(), Error> {
         self.get_op(node).ok_or(Error::BadNode).map(|_| ())
@@ -180,6 +268,45 @@ impl Context {
         self.ops.insert(Op::Input(v))
     }
 
+    /// Constructs or finds a variable input node with the given name
+    ///
+    /// If the name is already in this context's [`VarRegistry`], then its
+    /// variable is reused; otherwise, a new variable is created with
+    /// [`Var::new`] and registered with default [`VarInfo`].
+    ///
+    /// ```
+    /// # use fidget::context::Context;
+    /// let mut ctx = Context::new();
+    /// let r1 = ctx.named_var("radius").unwrap();
+    /// let r2 = ctx.named_var("radius").unwrap();
+    /// assert_eq!(r1, r2);
+    ///
+    /// let v = ctx.get_var(r1).unwrap();
+    /// assert_eq!(ctx.var_registry().name(&v), Some("radius"));
+    /// ```
+    pub fn named_var(&mut self, name: &str) -> Result<Node, Error> {
+        let v = match self.vars.find(name) {
+            Some(v) => v,
+            None => {
+                let v = Var::new();
+                self.vars.insert(v, VarInfo::new(name))?;
+                v
+            }
+        };
+        Ok(self.var(v))
+    }
+
+    /// Returns the registry of variable names and metadata
+    pub fn var_registry(&self) -> &VarRegistry {
+        &self.vars
+    }
+
+    /// Returns a mutable reference to the registry of variable names and
+    /// metadata
+    pub fn var_registry_mut(&mut self) -> &mut VarRegistry {
+        &mut self.vars
+    }
+
     /// Returns a 3-element array of `X`, `Y`, `Z` nodes
     pub fn axes(&mut self) -> [Node; 3] {
         [self.x(), self.y(), self.z()]
@@ -199,7 +326,11 @@ impl Context {
     // Helper functions to create nodes with constant folding
     /// Find or create a [Node] for the given unary operation, with constant
     /// folding.
-    fn op_unary(&mut self, a: Node, op: UnaryOpcode) -> Result<Node, Error> {
+    pub(crate) fn op_unary(
+        &mut self,
+        a: Node,
+        op: UnaryOpcode,
+    ) -> Result<Node, Error> {
         let op_a = *self.get_op(a).ok_or(Error::BadNode)?;
         let n = self.ops.insert(Op::Unary(op, a));
         let out = if matches!(op_a, Op::Const(_)) {
@@ -213,7 +344,7 @@ impl Context {
     }
     /// Find or create a [Node] for the given binary operation, with constant
     /// folding.
-    fn op_binary(
+    pub(crate) fn op_binary(
         &mut self,
         a: Node,
         b: Node,
@@ -245,6 +376,33 @@ impl Context {
         Ok(out)
     }
 
+    /// Find or create a [Node] for the given ternary operation, with constant
+    /// folding.
+    fn op_ternary(
+        &mut self,
+        a: Node,
+        b: Node,
+        c: Node,
+        op: TernaryOpcode,
+    ) -> Result<Node, Error> {
+        let op_a = *self.get_op(a).ok_or(Error::BadNode)?;
+        let op_b = *self.get_op(b).ok_or(Error::BadNode)?;
+        let op_c = *self.get_op(c).ok_or(Error::BadNode)?;
+
+        let n = self.ops.insert(Op::Ternary(op, a, b, c));
+        let out = if matches!(
+            (op_a, op_b, op_c),
+            (Op::Const(_), Op::Const(_), Op::Const(_))
+        ) {
+            let v = self.eval(n, &Default::default())?;
+            self.pop().unwrap(); // removes `n`
+            self.constant(v)
+        } else {
+            n
+        };
+        Ok(out)
+    }
+
     /// Find or create a [Node] for the given commutative operation, with
     /// constant folding; deduplication is encouraged by sorting `a` and `b`.
     fn op_binary_commutative(
@@ -354,6 +512,85 @@ impl Context {
         }
     }
 
+    /// Builds a smooth minimum node, blending within a radius `k`
+    ///
+    /// This uses the polynomial smooth minimum:
+    /// ```text
+    /// h = max(k - abs(a - b), 0)
+    /// out = min(a, b) - h² / 4k
+    /// ```
+    ///
+    /// When the two arguments are at least `k` apart, the result is exactly
+    /// `min(a, b)`, so this node can be simplified using a tracing evaluator
+    /// (in the same way as [`min`](Self::min)).  If `k` is a constant that is
+    /// less than or equal to zero, this is a plain `min` node.
+    /// ```
+    /// # let mut ctx = fidget::context::Context::new();
+    /// let x = ctx.x();
+    /// let op = ctx.smooth_min(x, 1.0, 0.5).unwrap();
+    /// let v = ctx.eval_xyz(op, -2.0, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, -2.0);
+    /// let v = ctx.eval_xyz(op, 1.0, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, 0.875);
+    /// ```
+    pub fn smooth_min<A: IntoNode, B: IntoNode, K: IntoNode>(
+        &mut self,
+        a: A,
+        b: B,
+        k: K,
+    ) -> Result<Node, Error> {
+        let a = a.into_node(self)?;
+        let b = b.into_node(self)?;
+        let k = k.into_node(self)?;
+        match (self.get_const(a), self.get_const(b), self.get_const(k)) {
+            (_, _, Ok(k)) if k <= 0.0 => self.min(a, b),
+            (Ok(_), Ok(_), Err(_)) => {
+                // Tapes can't store two immediates in a single operation, so
+                // build the blend out of simpler operations instead.
+                let m = self.min(a, b)?;
+                let d = self.sub(a, b)?;
+                let d = self.abs(d)?;
+                let h = self.sub(k, d)?;
+                let h = self.max(h, 0.0)?;
+                let h2 = self.square(h)?;
+                let k4 = self.mul(k, 4.0)?;
+                let c = self.div(h2, k4)?;
+                let s = self.sub(m, c)?;
+                self.if_nonzero_else(h, s, m)
+            }
+            _ => {
+                self.op_ternary(a.min(b), a.max(b), k, TernaryOpcode::SmoothMin)
+            }
+        }
+    }
+
+    /// Builds a smooth maximum node, blending within a radius `k`
+    ///
+    /// This is implemented as `-smooth_min(-a, -b, k)`; see
+    /// [`smooth_min`](Self::smooth_min) for details.
+    /// ```
+    /// # let mut ctx = fidget::context::Context::new();
+    /// let x = ctx.x();
+    /// let op = ctx.smooth_max(x, 1.0, 0.5).unwrap();
+    /// let v = ctx.eval_xyz(op, 2.0, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, 2.0);
+    /// let v = ctx.eval_xyz(op, 1.0, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, 1.125);
+    /// ```
+    pub fn smooth_max<A: IntoNode, B: IntoNode, K: IntoNode>(
+        &mut self,
+        a: A,
+        b: B,
+        k: K,
+    ) -> Result<Node, Error> {
+        let a = a.into_node(self)?;
+        let b = b.into_node(self)?;
+        let a = self.neg(a)?;
+        let b = self.neg(b)?;
+        let v = self.smooth_min(a, b, k)?;
+        self.neg(v)
+    }
+
     /// Builds an `and` node
     ///
     /// If both arguments are non-zero, returns the right-hand argument.
@@ -553,6 +790,42 @@ impl Context {
         self.op_unary(a, UnaryOpcode::Ln)
     }
 
+    /// Builds a node which calculates the hyperbolic sine of its input
+    pub fn sinh<A: IntoNode>(&mut self, a: A) -> Result<Node, Error> {
+        let a = a.into_node(self)?;
+        self.op_unary(a, UnaryOpcode::Sinh)
+    }
+
+    /// Builds a node which calculates the hyperbolic cosine of its input
+    pub fn cosh<A: IntoNode>(&mut self, a: A) -> Result<Node, Error> {
+        let a = a.into_node(self)?;
+        self.op_unary(a, UnaryOpcode::Cosh)
+    }
+
+    /// Builds a node which calculates the hyperbolic tangent of its input
+    pub fn tanh<A: IntoNode>(&mut self, a: A) -> Result<Node, Error> {
+        let a = a.into_node(self)?;
+        self.op_unary(a, UnaryOpcode::Tanh)
+    }
+
+    /// Builds a node which calculates the base-2 exponent of its input
+    pub fn exp2<A: IntoNode>(&mut self, a: A) -> Result<Node, Error> {
+        let a = a.into_node(self)?;
+        self.op_unary(a, UnaryOpcode::Exp2)
+    }
+
+    /// Builds a node which calculates the base-2 log of its input
+    pub fn log2<A: IntoNode>(&mut self, a: A) -> Result<Node, Error> {
+        let a = a.into_node(self)?;
+        self.op_unary(a, UnaryOpcode::Log2)
+    }
+
+    /// Builds a node which calculates the base-10 log of its input
+    pub fn log10<A: IntoNode>(&mut self, a: A) -> Result<Node, Error> {
+        let a = a.into_node(self)?;
+        self.op_unary(a, UnaryOpcode::Log10)
+    }
+
     ////////////////////////////////////////////////////////////////////////////
     // Derived functions
     /// Builds a node which squares its input
@@ -766,10 +1039,43 @@ impl Context {
         self.op_binary(a, b, BinaryOpcode::Mod)
     }
 
+    /// Builds a node that raises `a` to the power of `b`
+    ///
+    /// Negative bases are valid if `b` is an integer; otherwise, the result is
+    /// `NaN`.  If `b` is a constant `0`, `1`, `2`, or `-1`, then this is
+    /// lowered to a cheaper operation.
+    /// ```
+    /// # let mut ctx = fidget::context::Context::new();
+    /// let x = ctx.x();
+    /// let op = ctx.pow(x, 3.0).unwrap();
+    /// let v = ctx.eval_xyz(op, -2.0, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, -8.0);
+    /// let op = ctx.pow(x, 0.5).unwrap();
+    /// let v = ctx.eval_xyz(op, 4.0, 0.0, 0.0).unwrap();
+    /// assert_eq!(v, 2.0);
+    /// ```
+    pub fn pow<A: IntoNode, B: IntoNode>(
+        &mut self,
+        a: A,
+        b: B,
+    ) -> Result<Node, Error> {
+        let a = a.into_node(self)?;
+        let b = b.into_node(self)?;
+        match self.get_const(b) {
+            Ok(0.0) => Ok(self.constant(1.0)),
+            Ok(1.0) => Ok(a),
+            Ok(2.0) => self.square(a),
+            Ok(-1.0) => self.recip(a),
+            _ => self.op_binary(a, b, BinaryOpcode::Pow),
+        }
+    }
+
     /// Builds a node that returns the first node if the condition is not
     /// equal to zero, else returns the other node
     ///
-    /// The result is `a` if `condition != 0`, else the result is `b`.
+    /// The result is `a` if `condition != 0`, else the result is `b`.  This is
+    /// a single [`TernaryOpcode::Select`] node, so interval evaluation can
+    /// record which branch is taken and simplification can drop the other one.
     /// ```
     /// # let mut ctx = fidget::context::Context::new();
     /// let x = ctx.x();
@@ -793,10 +1099,13 @@ impl Context {
         let a = a.into_node(self)?;
         let b = b.into_node(self)?;
 
-        let lhs = self.and(condition, a)?;
-        let n_condition = self.not(condition)?;
-        let rhs = self.and(n_condition, b)?;
-        self.or(lhs, rhs)
+        if a == b {
+            Ok(a)
+        } else if let Ok(c) = self.get_const(condition) {
+            Ok(if c != 0.0 { a } else { b })
+        } else {
+            self.op_ternary(condition, a, b, TernaryOpcode::Select)
+        }
     }
 
     ////////////////////////////////////////////////////////////////////////////
@@ -874,6 +1183,7 @@ impl Context {
                         .map(|i| i as i8 as f64)
                         .unwrap_or(f64::NAN),
                     BinaryOpcode::Mod => a.rem_euclid(b),
+                    BinaryOpcode::Pow => a.powf(b),
                     BinaryOpcode::And => {
                         if a == 0.0 {
                             a
@@ -891,6 +1201,29 @@ impl Context {
                 }
             }
 
+            Op::Ternary(op, a, b, c) => {
+                let a = get(*a)?;
+                let b = get(*b)?;
+                let c = get(*c)?;
+                match op {
+                    TernaryOpcode::Select => {
+                        if a != 0.0 {
+                            b
+                        } else {
+                            c
+                        }
+                    }
+                    TernaryOpcode::SmoothMin => {
+                        let h = (c - (a - b).abs()).max(0.0);
+                        if h > 0.0 {
+                            a.min(b) - h * h / (4.0 * c)
+                        } else {
+                            a.min(b)
+                        }
+                    }
+                }
+            }
+
             // Unary operations
             Op::Unary(op, a) => {
                 let a = get(*a)?;
@@ -911,6 +1244,12 @@ impl Context {
                     UnaryOpcode::Atan => a.atan(),
                     UnaryOpcode::Exp => a.exp(),
                     UnaryOpcode::Ln => a.ln(),
+                    UnaryOpcode::Sinh => a.sinh(),
+                    UnaryOpcode::Cosh => a.cosh(),
+                    UnaryOpcode::Tanh => a.tanh(),
+                    UnaryOpcode::Exp2 => a.exp2(),
+                    UnaryOpcode::Log2 => a.log2(),
+                    UnaryOpcode::Log10 => a.log10(),
                     UnaryOpcode::Not => (a == 0.0).into(),
                 }
             }
@@ -938,71 +1277,319 @@ impl Context {
     /// assert_eq!(ctx.len(), 7);
     /// ```
     ///
-    /// This representation is loosely defined and only intended for use in
-    /// quick experiments.
+    /// Each line is of the form `name opcode [args...]`, where arguments are
+    /// the names of previously defined nodes (or a value, for `const`).  The
+    /// last line in the file is the root of the tree.  Everything after a `#`
+    /// is a comment, and the first line may be a version header of the form
+    /// `# fidget-vm 2` (see [`Context::write_text`]); version 1 files (which
+    /// do not include variable metadata) are also accepted.
+    ///
+    /// Generic variables are written as `var name`.  Names written by
+    /// [`Context::write_text`] (e.g. `V(1f2e3d)`) are parsed back into the same
+    /// [`Var`]; any other distinct name is assigned a new [`Var`].  Use
+    /// [`Context::from_text_with_vars`] to recover the mapping from names to
+    /// variables.
+    ///
+    /// The first line declaring a named variable may also contain its
+    /// [`VarInfo`] metadata, as `default=1.5`, `range=0,10`, and
+    /// `description=...` tokens.  In the description, whitespace, `#`, and `%`
+    /// are percent-encoded (e.g. `outer%20radius`).
+    ///
+    /// Malformed input returns an [`Error::ParseError`] with the line and
+    /// column of the problem.
     pub fn from_text<R: Read>(r: R) -> Result<(Self, Node), Error> {
+        Self::from_text_with_vars(r).map(|(ctx, node, _vars)| (ctx, node))
+    }
+
+    /// Parses a flat text representation of a math tree, returning named
+    /// variables
+    ///
+    /// See [`Context::from_text`] for details on the format.
+    ///
+    /// ```
+    /// # use fidget::{context::Context, var::Var};
+    /// # use std::collections::HashMap;
+    /// let txt = "
+    /// _0 var-x
+    /// _1 var radius
+    /// _2 sub _0 _1
+    /// ";
+    /// let (ctx, node, vars) =
+    ///     Context::from_text_with_vars(txt.as_bytes()).unwrap();
+    /// let r = vars["radius"];
+    /// let v = ctx.eval(node, &HashMap::from([(Var::X, 3.0), (r, 1.0)]));
+    /// assert_eq!(v.unwrap(), 2.0);
+    /// ```
+    pub fn from_text_with_vars<R: Read>(
+        r: R,
+    ) -> Result<(Self, Node, HashMap<String, Var>), Error> {
         let reader = BufReader::new(r);
         let mut ctx = Self::new();
-        let mut seen = BTreeMap::new();
+        let mut seen = HashMap::new();
+        let mut vars = HashMap::new();
         let mut last = None;
 
-        for line in reader.lines().map(|line| line.unwrap()) {
-            if line.is_empty() || line.starts_with('#') {
-                continue;
+        for (i, line) in reader.lines().enumerate() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) if e.kind() == std::io::ErrorKind::InvalidData => {
+                    return Err(Error::ParseError {
+                        line: i + 1,
+                        column: 1,
+                        reason: "line is not valid UTF-8".to_owned(),
+                    });
+                }
+                Err(e) => return Err(e.into()),
+            };
+            let mut p = TextLine::new(i + 1, &line);
+            if i == 0 {
+                p.check_header()?;
             }
-            let mut iter = line.split_whitespace();
-            let i: String = iter.next().unwrap().to_owned();
-            let opcode = iter.next().unwrap();
-
-            let mut pop = || {
-                let txt = iter.next().unwrap();
-                seen.get(txt)
-                    .cloned()
-                    .ok_or_else(|| Error::UnknownVariable(txt.to_string()))
+
+            let Ok((name_col, name)) = p.next("node name") else {
+                continue;
             };
+            if seen.contains_key(name) {
+                return Err(
+                    p.error(name_col, format!("duplicate node `{name}`"))
+                );
+            }
+            let (op_col, opcode) = p.next("opcode")?;
+
             let node = match opcode {
-                "const" => ctx.constant(iter.next().unwrap().parse().unwrap()),
+                "const" => {
+                    let (col, v) = p.next("constant value")?;
+                    let v = v.parse().map_err(|_| {
+                        p.error(col, format!("invalid constant `{v}`"))
+                    })?;
+                    ctx.constant(v)
+                }
                 "var-x" => ctx.x(),
                 "var-y" => ctx.y(),
                 "var-z" => ctx.z(),
-                "abs" => ctx.abs(pop()?)?,
-                "neg" => ctx.neg(pop()?)?,
-                "sqrt" => ctx.sqrt(pop()?)?,
-                "square" => ctx.square(pop()?)?,
-                "floor" => ctx.floor(pop()?)?,
-                "ceil" => ctx.ceil(pop()?)?,
-                "round" => ctx.round(pop()?)?,
-                "sin" => ctx.sin(pop()?)?,
-                "cos" => ctx.cos(pop()?)?,
-                "tan" => ctx.tan(pop()?)?,
-                "asin" => ctx.asin(pop()?)?,
-                "acos" => ctx.acos(pop()?)?,
-                "atan" => ctx.atan(pop()?)?,
-                "ln" => ctx.ln(pop()?)?,
-                "not" => ctx.not(pop()?)?,
-                "exp" => ctx.exp(pop()?)?,
-                "add" => ctx.add(pop()?, pop()?)?,
-                "mul" => ctx.mul(pop()?, pop()?)?,
-                "min" => ctx.min(pop()?, pop()?)?,
-                "max" => ctx.max(pop()?, pop()?)?,
-                "div" => ctx.div(pop()?, pop()?)?,
-                "atan2" => ctx.atan2(pop()?, pop()?)?,
-                "sub" => ctx.sub(pop()?, pop()?)?,
-                "compare" => ctx.compare(pop()?, pop()?)?,
-                "mod" => ctx.modulo(pop()?, pop()?)?,
-                "and" => ctx.and(pop()?, pop()?)?,
-                "or" => ctx.or(pop()?, pop()?)?,
-                op => return Err(Error::UnknownOpcode(op.to_owned())),
+                "var" => {
+                    let (col, name) = p.next("variable name")?;
+                    let v = match vars.get(name) {
+                        Some(v) => *v,
+                        None => {
+                            let v = match name.parse() {
+                                Ok(v @ Var::V(..)) => v,
+                                Ok(..) => Var::new(),
+                                Err(..) => {
+                                    let v = Var::new();
+                                    let info = p.var_info(name)?;
+                                    ctx.vars.insert(v, info).map_err(|e| {
+                                        p.error(col, e.to_string())
+                                    })?;
+                                    v
+                                }
+                            };
+                            vars.insert(name.to_owned(), v);
+                            v
+                        }
+                    };
+                    ctx.var(v)
+                }
+                "abs" => ctx.abs(p.node(&seen)?)?,
+                "neg" => ctx.neg(p.node(&seen)?)?,
+                "recip" => ctx.recip(p.node(&seen)?)?,
+                "sqrt" => ctx.sqrt(p.node(&seen)?)?,
+                "square" => ctx.square(p.node(&seen)?)?,
+                "floor" => ctx.floor(p.node(&seen)?)?,
+                "ceil" => ctx.ceil(p.node(&seen)?)?,
+                "round" => ctx.round(p.node(&seen)?)?,
+                "sin" => ctx.sin(p.node(&seen)?)?,
+                "cos" => ctx.cos(p.node(&seen)?)?,
+                "tan" => ctx.tan(p.node(&seen)?)?,
+                "asin" => ctx.asin(p.node(&seen)?)?,
+                "acos" => ctx.acos(p.node(&seen)?)?,
+                "atan" => ctx.atan(p.node(&seen)?)?,
+                "ln" => ctx.ln(p.node(&seen)?)?,
+                "sinh" => ctx.sinh(p.node(&seen)?)?,
+                "cosh" => ctx.cosh(p.node(&seen)?)?,
+                "tanh" => ctx.tanh(p.node(&seen)?)?,
+                "exp2" => ctx.exp2(p.node(&seen)?)?,
+                "log2" => ctx.log2(p.node(&seen)?)?,
+                "log10" => ctx.log10(p.node(&seen)?)?,
+                "not" => ctx.not(p.node(&seen)?)?,
+                "exp" => ctx.exp(p.node(&seen)?)?,
+                "add" => ctx.add(p.node(&seen)?, p.node(&seen)?)?,
+                "mul" => ctx.mul(p.node(&seen)?, p.node(&seen)?)?,
+                "min" => ctx.min(p.node(&seen)?, p.node(&seen)?)?,
+                "max" => ctx.max(p.node(&seen)?, p.node(&seen)?)?,
+                "div" => ctx.div(p.node(&seen)?, p.node(&seen)?)?,
+                "atan2" => ctx.atan2(p.node(&seen)?, p.node(&seen)?)?,
+                "sub" => ctx.sub(p.node(&seen)?, p.node(&seen)?)?,
+                "compare" => ctx.compare(p.node(&seen)?, p.node(&seen)?)?,
+                "mod" => ctx.modulo(p.node(&seen)?, p.node(&seen)?)?,
+                "pow" => ctx.pow(p.node(&seen)?, p.node(&seen)?)?,
+                "and" => ctx.and(p.node(&seen)?, p.node(&seen)?)?,
+                "or" => ctx.or(p.node(&seen)?, p.node(&seen)?)?,
+                "select" => ctx.if_nonzero_else(
+                    p.node(&seen)?,
+                    p.node(&seen)?,
+                    p.node(&seen)?,
+                )?,
+                "smooth_min" => ctx.smooth_min(
+                    p.node(&seen)?,
+                    p.node(&seen)?,
+                    p.node(&seen)?,
+                )?,
+                op => {
+                    return Err(
+                        p.error(op_col, format!("unknown opcode `{op}`"))
+                    )
+                }
             };
-            seen.insert(i, node);
+            p.finish()?;
+            seen.insert(name.to_owned(), node);
             last = Some(node);
         }
         match last {
-            Some(node) => Ok((ctx, node)),
+            Some(node) => Ok((ctx, node, vars)),
             None => Err(Error::EmptyFile),
         }
     }
 
+    /// Writes the subgraph rooted at `node` in the text format parsed by
+    /// [`Context::from_text`]
+    ///
+    /// The output begins with a version header, followed by one line per
+    /// reachable node (children before parents), with the root last.  Nodes
+    /// are renamed to `_0`, `_1`, etc (in hexadecimal).  Generic variables use
+    /// their name from the [`VarRegistry`] if present, or their
+    /// [`Display`](std::fmt::Display) implementation otherwise; registered
+    /// names and their [`VarInfo`] metadata are restored when parsing.
+    ///
+    /// ```
+    /// # use fidget::context::Context;
+    /// let mut ctx = Context::new();
+    /// let x = ctx.x();
+    /// let y = ctx.y();
+    /// let sum = ctx.add(x, y).unwrap();
+    ///
+    /// let mut out = vec![];
+    /// ctx.write_text(sum, &mut out).unwrap();
+    /// let (ctx2, sum2) = Context::from_text(out.as_slice()).unwrap();
+    /// assert_eq!(ctx2.eval_xyz(sum2, 1.0, 2.0, 0.0).unwrap(), 3.0);
+    /// ```
+    pub fn write_text<W: std::io::Write>(
+        &self,
+        node: Node,
+        mut w: W,
+    ) -> Result<(), Error> {
+        let nodes = self.reachable_nodes(&[node])?;
+        writeln!(w, "# fidget-vm {TEXT_FORMAT_VERSION}")?;
+
+        let mut names = HashMap::new();
+        for n in nodes {
+            let name = format!("_{:x}", names.len());
+            write!(w, "{name} ")?;
+            match self.get_op(n).unwrap() {
+                Op::Const(c) => write!(w, "const {}", c.0)?,
+                Op::Input(Var::X) => write!(w, "var-x")?,
+                Op::Input(Var::Y) => write!(w, "var-y")?,
+                Op::Input(Var::Z) => write!(w, "var-z")?,
+                Op::Input(v @ Var::V(..)) => match self.vars.get(v) {
+                    Some(info) => {
+                        write!(w, "var {}", info.name)?;
+                        if info.default != 0.0 {
+                            write!(w, " default={}", info.default)?;
+                        }
+                        if let Some((lo, hi)) = info.range {
+                            write!(w, " range={lo},{hi}")?;
+                        }
+                        if !info.description.is_empty() {
+                            write!(
+                                w,
+                                " description={}",
+                                escape_text(&info.description)
+                            )?;
+                        }
+                    }
+                    None => write!(w, "var {v}")?,
+                },
+                Op::Unary(op, a) => {
+                    let op = match op {
+                        UnaryOpcode::Neg => "neg",
+                        UnaryOpcode::Abs => "abs",
+                        UnaryOpcode::Recip => "recip",
+                        UnaryOpcode::Sqrt => "sqrt",
+                        UnaryOpcode::Square => "square",
+                        UnaryOpcode::Floor => "floor",
+                        UnaryOpcode::Ceil => "ceil",
+                        UnaryOpcode::Round => "round",
+                        UnaryOpcode::Sin => "sin",
+                        UnaryOpcode::Cos => "cos",
+                        UnaryOpcode::Tan => "tan",
+                        UnaryOpcode::Asin => "asin",
+                        UnaryOpcode::Acos => "acos",
+                        UnaryOpcode::Atan => "atan",
+                        UnaryOpcode::Exp => "exp",
+                        UnaryOpcode::Ln => "ln",
+                        UnaryOpcode::Sinh => "sinh",
+                        UnaryOpcode::Cosh => "cosh",
+                        UnaryOpcode::Tanh => "tanh",
+                        UnaryOpcode::Exp2 => "exp2",
+                        UnaryOpcode::Log2 => "log2",
+                        UnaryOpcode::Log10 => "log10",
+                        UnaryOpcode::Not => "not",
+                    };
+                    write!(w, "{op} {}", names[a])?
+                }
+                Op::Binary(op, a, b) => {
+                    let op = match op {
+                        BinaryOpcode::Add => "add",
+                        BinaryOpcode::Sub => "sub",
+                        BinaryOpcode::Mul => "mul",
+                        BinaryOpcode::Div => "div",
+                        BinaryOpcode::Atan => "atan2",
+                        BinaryOpcode::Min => "min",
+                        BinaryOpcode::Max => "max",
+                        BinaryOpcode::Compare => "compare",
+                        BinaryOpcode::Mod => "mod",
+                        BinaryOpcode::And => "and",
+                        BinaryOpcode::Or => "or",
+                        BinaryOpcode::Pow => "pow",
+                    };
+                    write!(w, "{op} {} {}", names[a], names[b])?
+                }
+                Op::Ternary(op, a, b, c) => {
+                    let op = match op {
+                        TernaryOpcode::Select => "select",
+                        TernaryOpcode::SmoothMin => "smooth_min",
+                    };
+                    write!(w, "{op} {} {} {}", names[a], names[b], names[c])?
+                }
+            }
+            writeln!(w)?;
+            names.insert(n, name);
+        }
+        Ok(())
+    }
+
+    /// Converts the given node into a standalone shader function
+    ///
+    /// This is a wrapper around [`codegen::shader`](crate::codegen::shader),
+    /// which describes the generated code.
+    ///
+    /// ```
+    /// # use fidget::{codegen::ShaderOptions, Context};
+    /// let mut ctx = Context::new();
+    /// let x = ctx.x();
+    /// let r = ctx.sin(x).unwrap();
+    /// let src = ctx.to_shader(r, &ShaderOptions::default()).unwrap();
+    /// assert!(src.starts_with("float f(vec3 p) {"));
+    /// ```
+    pub fn to_shader(
+        &self,
+        node: Node,
+        opts: &crate::codegen::ShaderOptions,
+    ) -> Result<String, Error> {
+        let (tape, vars) = crate::compiler::SsaTape::new(self, &[node])?;
+        crate::codegen::shader(&tape, &vars, opts)
+    }
+
     /// Converts the entire context into a GraphViz drawing
     pub fn dot(&self) -> String {
         let mut out = "digraph mygraph{\n".to_owned();
@@ -1039,6 +1626,7 @@ impl Context {
                 BinaryOpcode::Mod => out += "mod",
                 BinaryOpcode::And => out += "and",
                 BinaryOpcode::Or => out += "or",
+                BinaryOpcode::Pow => out += "pow",
             },
             Op::Unary(op, ..) => match op {
                 UnaryOpcode::Neg => out += "neg",
@@ -1057,8 +1645,18 @@ impl Context {
                 UnaryOpcode::Atan => out += "atan",
                 UnaryOpcode::Exp => out += "exp",
                 UnaryOpcode::Ln => out += "ln",
+                UnaryOpcode::Sinh => out += "sinh",
+                UnaryOpcode::Cosh => out += "cosh",
+                UnaryOpcode::Tanh => out += "tanh",
+                UnaryOpcode::Exp2 => out += "exp2",
+                UnaryOpcode::Log2 => out += "log2",
+                UnaryOpcode::Log10 => out += "log10",
                 UnaryOpcode::Not => out += "not",
             },
+            Op::Ternary(op, ..) => match op {
+                TernaryOpcode::Select => out += "select",
+                TernaryOpcode::SmoothMin => out += "smooth_min",
+            },
         };
         write!(
             out,
@@ -1092,13 +1690,22 @@ impl Context {
             Pop,
             /// Pops the latest affine frame
             PopAffine,
+            /// Pops the latest substitution frame (and its axis frame)
+            PopSubstitute,
         }
         let mut axes = vec![(self.x(), self.y(), self.z())];
+        // Substitution frames, as a unique ID and a map of replaced variables.
+        // Nested substitutions are flattened into a single map, so lookups
+        // don't need to walk a chain of frames.
+        let mut subs: Vec<(usize, Arc<HashMap<Var, Node>>)> =
+            vec![(0, Default::default())];
+        let mut next_frame = 1;
         let mut todo = vec![Action::Down(tree.arc())];
         let mut stack = vec![];
         let mut affine: Vec<Matrix4<f64>> = vec![];
 
-        // Cache of TreeOp -> Node mapping under a particular frame (axes)
+        // Cache of TreeOp -> Node mapping under a particular frame (axes and
+        // substitutions)
         //
         // This isn't required for correctness, but can be a speed optimization
         // (because it means we don't have to walk the same tree twice).
@@ -1111,11 +1718,15 @@ impl Context {
                     // we can return the previous Node.
                     if matches!(
                         t.as_ref(),
-                        TreeOp::Unary(..) | TreeOp::Binary(..)
+                        TreeOp::Unary(..)
+                            | TreeOp::Binary(..)
+                            | TreeOp::Ternary(..)
                     ) {
-                        if let Some(p) =
-                            seen.get(&(*axes.last().unwrap(), Arc::as_ptr(t)))
-                        {
+                        if let Some(p) = seen.get(&(
+                            *axes.last().unwrap(),
+                            subs.last().unwrap().0,
+                            Arc::as_ptr(t),
+                        )) {
                             stack.push(*p);
                             continue;
                         }
@@ -1130,7 +1741,12 @@ impl Context {
                                 Var::X => axes.0,
                                 Var::Y => axes.1,
                                 Var::Z => axes.2,
-                                v @ Var::V(..) => self.var(v),
+                                v @ Var::V(..) => {
+                                    match subs.last().unwrap().1.get(&v) {
+                                        Some(n) => *n,
+                                        None => self.var(v),
+                                    }
+                                }
                             });
                         }
                         TreeOp::Unary(_op, arg) => {
@@ -1142,6 +1758,12 @@ impl Context {
                             todo.push(Action::Down(lhs));
                             todo.push(Action::Down(rhs));
                         }
+                        TreeOp::Ternary(_op, a, b, c) => {
+                            todo.push(Action::Up(t));
+                            todo.push(Action::Down(a));
+                            todo.push(Action::Down(b));
+                            todo.push(Action::Down(c));
+                        }
                         TreeOp::RemapAxes { target: _, x, y, z } => {
                             // Action::Up(t) does the remapping and target eval
                             todo.push(Action::Up(t));
@@ -1179,6 +1801,14 @@ impl Context {
                             }
                             todo.push(Action::Down(target));
                         }
+                        TreeOp::Substitute { target: _, vars } => {
+                            // Action::Up(t) does the substitution and target
+                            // eval, after importing replacements in this frame
+                            todo.push(Action::Up(t));
+                            for (_v, r) in vars {
+                                todo.push(Action::Down(r));
+                            }
+                        }
                     }
                 }
                 Action::Up(t) => {
@@ -1197,12 +1827,33 @@ impl Context {
                             let out = self.op_binary(lhs, rhs, *op).unwrap();
                             if Arc::strong_count(t) > 1 {
                                 seen.insert(
-                                    (*axes.last().unwrap(), Arc::as_ptr(t)),
+                                    (
+                                        *axes.last().unwrap(),
+                                        subs.last().unwrap().0,
+                                        Arc::as_ptr(t),
+                                    ),
                                     out,
                                 );
                             }
                             stack.push(out);
                         }
+                        TreeOp::Ternary(op, ..) => {
+                            let a = stack.pop().unwrap();
+                            let b = stack.pop().unwrap();
+                            let c = stack.pop().unwrap();
+                            // Use the builders here (instead of `op_ternary`),
+                            // because they fold away constant conditions
+                            let out = match op {
+                                TernaryOpcode::Select => {
+                                    self.if_nonzero_else(a, b, c)
+                                }
+                                TernaryOpcode::SmoothMin => {
+                                    self.smooth_min(a, b, c)
+                                }
+                            }
+                            .unwrap();
+                            stack.push(out);
+                        }
                         TreeOp::RemapAxes { target, .. } => {
                             let x = stack.pop().unwrap();
                             let y = stack.pop().unwrap();
@@ -1211,6 +1862,26 @@ impl Context {
                             todo.push(Action::Pop);
                             todo.push(Action::Down(target));
                         }
+                        TreeOp::Substitute { target, vars } => {
+                            let (mut x, mut y, mut z) = *axes.last().unwrap();
+                            let mut map = subs.last().unwrap().1.clone();
+                            for (v, _r) in vars {
+                                let n = stack.pop().unwrap();
+                                match v {
+                                    Var::X => x = n,
+                                    Var::Y => y = n,
+                                    Var::Z => z = n,
+                                    Var::V(..) => {
+                                        Arc::make_mut(&mut map).insert(*v, n);
+                                    }
+                                }
+                            }
+                            axes.push((x, y, z));
+                            subs.push((next_frame, map));
+                            next_frame += 1;
+                            todo.push(Action::PopSubstitute);
+                            todo.push(Action::Down(target));
+                        }
                     }
                     // Update the cache with the new tree, if relevant
                     //
@@ -1219,11 +1890,17 @@ impl Context {
                     // isn't perfect, but it doesn't need to be for correctness.
                     if matches!(
                         t.as_ref(),
-                        TreeOp::Unary(..) | TreeOp::Binary(..)
+                        TreeOp::Unary(..)
+                            | TreeOp::Binary(..)
+                            | TreeOp::Ternary(..)
                     ) && Arc::strong_count(t) > 1
                     {
                         seen.insert(
-                            (*axes.last().unwrap(), Arc::as_ptr(t)),
+                            (
+                                *axes.last().unwrap(),
+                                subs.last().unwrap().0,
+                                Arc::as_ptr(t),
+                            ),
                             *stack.last().unwrap(),
                         );
                     }
@@ -1234,29 +1911,223 @@ impl Context {
                 Action::PopAffine => {
                     affine.pop().unwrap();
                 }
+                Action::PopSubstitute => {
+                    axes.pop().unwrap();
+                    subs.pop().unwrap();
+                }
             }
         }
         assert_eq!(stack.len(), 1);
         stack.pop().unwrap()
     }
 
-    /// Converts from a context-specific node into a standalone [`Tree`]
-    pub fn export(&self, n: Node) -> Result<Tree, Error> {
-        if self.get_op(n).is_none() {
-            return Err(Error::BadNode);
+    /// Copies a node (and its children) from another context into this one
+    ///
+    /// The copied nodes are deduplicated against existing nodes in this
+    /// context, and metadata for copied variables is added to this context's
+    /// [`VarRegistry`].  Returns the imported root.
+    ///
+    /// If a copied variable's name is already used by a different variable
+    /// in this context, returns [`Error::DuplicateVarName`] without modifying
+    /// the context.
+    ///
+    /// ```
+    /// # use fidget::context::Context;
+    /// let mut a = Context::new();
+    /// let x = a.x();
+    /// let sum = a.add(x, 1.0).unwrap();
+    ///
+    /// let mut b = Context::new();
+    /// let y = b.y();
+    /// let sum = b.import_from(&a, sum).unwrap();
+    /// let out = b.add(sum, y).unwrap();
+    /// assert_eq!(b.eval_xyz(out, 1.0, 2.0, 0.0).unwrap(), 4.0);
+    /// ```
+    pub fn import_from(
+        &mut self,
+        other: &Context,
+        node: Node,
+    ) -> Result<Node, Error> {
+        let nodes = other.reachable_nodes(&[node])?;
+        for (v, info) in other.named_inputs(&nodes, &self.vars)? {
+            self.vars.insert(v, info.clone())?;
         }
-
-        // Do recursion on the heap to avoid stack overflows for deep trees
-        enum Action {
-            /// Pushes `Up(n)` followed by `Down(n)` for each child
-            Down(Node),
-            /// Consumes trees from the stack and pushes a new tree
-            Up(Node, Op),
+        let mut remap = HashMap::new();
+        for n in nodes {
+            let op = other.get_op(n).unwrap().remap(|c| remap[&c]);
+            remap.insert(n, self.insert_op(op)?);
         }
-        let mut todo = vec![Action::Down(n)];
-        let mut stack = vec![];
+        Ok(remap[&node])
+    }
 
-        // Cache of Node -> Tree mapping, for Tree deduplication
+    /// Returns metadata for the named variables among `nodes`
+    ///
+    /// Returns [`Error::DuplicateVarName`] if any of their names is already
+    /// used by a different variable in `target`, so that imports can check
+    /// every name before modifying anything.
+    fn named_inputs(
+        &self,
+        nodes: &[Node],
+        target: &VarRegistry,
+    ) -> Result<Vec<(Var, &VarInfo)>, Error> {
+        let mut out = vec![];
+        for n in nodes {
+            let Some(Op::Input(v)) = self.get_op(*n) else {
+                continue;
+            };
+            if let Some(info) = self.vars.get(v) {
+                if target.find(&info.name).is_some_and(|w| w != *v) {
+                    return Err(Error::DuplicateVarName(info.name.clone()));
+                }
+                out.push((*v, info));
+            }
+        }
+        Ok(out)
+    }
+
+    /// Replaces variables within the given node
+    ///
+    /// Each `(var, node)` pair replaces every instance of `var` in the graph
+    /// reachable from `root` with `node`.  Substitutions are simultaneous, so
+    /// replacement nodes are not themselves rewritten; if the same variable
+    /// appears more than once, the last replacement wins.
+    ///
+    /// This is the eager equivalent of [`Tree::substitute`].
+    ///
+    /// ```
+    /// # use fidget::{context::Context, var::Var};
+    /// let mut ctx = Context::new();
+    /// let r = Var::new();
+    /// let rn = ctx.var(r);
+    /// let x = ctx.x();
+    /// let f = ctx.sub(x, rn).unwrap();
+    ///
+    /// let y = ctx.y();
+    /// let two_y = ctx.mul(y, 2.0).unwrap();
+    /// let g = ctx.substitute(f, &[(r, two_y)]).unwrap();
+    /// assert_eq!(ctx.eval_xyz(g, 5.0, 1.0, 0.0).unwrap(), 3.0);
+    /// ```
+    pub fn substitute(
+        &mut self,
+        root: Node,
+        vars: &[(Var, Node)],
+    ) -> Result<Node, Error> {
+        let mut map = HashMap::new();
+        for &(v, n) in vars {
+            self.check_node(n)?;
+            map.insert(v, n);
+        }
+        let mut remap = HashMap::new();
+        for n in self.reachable_nodes(&[root])? {
+            let op = self.get_op(n).unwrap().remap(|c| remap[&c]);
+            let out = match op {
+                Op::Input(v) if map.contains_key(&v) => map[&v],
+                op => self.insert_op(op)?,
+            };
+            remap.insert(n, out);
+        }
+        Ok(remap[&root])
+    }
+
+    /// Replaces selected constants with fresh variables
+    ///
+    /// `filter` is called with each constant node reachable from `root` (and
+    /// its value); each constant for which it returns `true` is replaced by a
+    /// new [`Var::V`].  Constants are deduplicated within a `Context`, so every
+    /// use of a given value is replaced by the same variable.
+    ///
+    /// Returns the new root node and a map from each new variable to its
+    /// original value.  Once a shape is built from the new root, values can be
+    /// changed through [`ShapeVars`](crate::shape::ShapeVars) without
+    /// rebuilding or recompiling its tapes.
+    ///
+    /// ```
+    /// # use fidget::context::Context;
+    /// # use std::collections::HashMap;
+    /// let mut ctx = Context::new();
+    /// let x = ctx.x();
+    /// let y = ctx.y();
+    /// let sum = ctx.add(x, 2.5).unwrap();
+    /// let out = ctx.mul(sum, y).unwrap();
+    ///
+    /// let (out, vars) = ctx.parametrize_constants(out, |_n, v| v == 2.5).unwrap();
+    /// assert_eq!(vars.len(), 1);
+    /// let (&r, &value) = vars.iter().next().unwrap();
+    /// assert_eq!(value, 2.5);
+    ///
+    /// let mut values: HashMap<_, _> = vars.into_iter().collect();
+    /// values.insert(fidget::var::Var::X, 1.0);
+    /// values.insert(fidget::var::Var::Y, 2.0);
+    /// assert_eq!(ctx.eval(out, &values).unwrap(), 7.0);
+    /// values.insert(r, 0.5);
+    /// assert_eq!(ctx.eval(out, &values).unwrap(), 3.0);
+    /// ```
+    pub fn parametrize_constants<F: FnMut(Node, f64) -> bool>(
+        &mut self,
+        root: Node,
+        mut filter: F,
+    ) -> Result<(Node, HashMap<Var, f64>), Error> {
+        let mut vars = HashMap::new();
+        let mut remap = HashMap::new();
+        for n in self.reachable_nodes(&[root])? {
+            let op = self.get_op(n).unwrap().remap(|c| remap[&c]);
+            let out = match op {
+                Op::Const(c) if filter(n, c.0) => {
+                    let v = Var::new();
+                    vars.insert(v, c.0);
+                    self.var(v)
+                }
+                op => self.insert_op(op)?,
+            };
+            remap.insert(n, out);
+        }
+        Ok((remap[&root], vars))
+    }
+
+    /// Finds or creates a [`Node`] for the given operation, with constant
+    /// folding
+    ///
+    /// Children of the operation must already be valid in this context.
+    fn insert_op(&mut self, op: Op) -> Result<Node, Error> {
+        match op {
+            Op::Const(c) => Ok(self.constant(c.0)),
+            Op::Input(v) => Ok(self.var(v)),
+            Op::Unary(op, arg) => self.op_unary(arg, op),
+            // Re-sort arguments to commutative operations, because remapping
+            // may have changed their order
+            Op::Binary(
+                op @ (BinaryOpcode::Add
+                | BinaryOpcode::Mul
+                | BinaryOpcode::Min
+                | BinaryOpcode::Max),
+                lhs,
+                rhs,
+            ) => self.op_binary_commutative(lhs, rhs, op),
+            Op::Binary(op, lhs, rhs) => self.op_binary(lhs, rhs, op),
+            Op::Ternary(op, a, b, c) => match op {
+                TernaryOpcode::Select => self.if_nonzero_else(a, b, c),
+                TernaryOpcode::SmoothMin => self.smooth_min(a, b, c),
+            },
+        }
+    }
+
+    /// Converts from a context-specific node into a standalone [`Tree`]
+    pub fn export(&self, n: Node) -> Result<Tree, Error> {
+        if self.get_op(n).is_none() {
+            return Err(Error::BadNode);
+        }
+
+        // Do recursion on the heap to avoid stack overflows for deep trees
+        enum Action {
+            /// Pushes `Up(n)` followed by `Down(n)` for each child
+            Down(Node),
+            /// Consumes trees from the stack and pushes a new tree
+            Up(Node, Op),
+        }
+        let mut todo = vec![Action::Down(n)];
+        let mut stack = vec![];
+
+        // Cache of Node -> Tree mapping, for Tree deduplication
         let mut seen: HashMap<Node, Tree> = HashMap::new();
 
         while let Some(t) = todo.pop() {
@@ -1289,6 +2160,12 @@ impl Context {
                             todo.push(Action::Down(*lhs));
                             todo.push(Action::Down(*rhs));
                         }
+                        Op::Ternary(_op, a, b, c) => {
+                            todo.push(Action::Up(n, *op));
+                            todo.push(Action::Down(*a));
+                            todo.push(Action::Down(*b));
+                            todo.push(Action::Down(*c));
+                        }
                     }
                 }
                 Action::Up(n, op) => match op {
@@ -1311,6 +2188,19 @@ impl Context {
                         seen.insert(n, out.clone());
                         stack.push(out);
                     }
+                    Op::Ternary(op, ..) => {
+                        let a = stack.pop().unwrap();
+                        let b = stack.pop().unwrap();
+                        let c = stack.pop().unwrap();
+                        let out = Tree::from(TreeOp::Ternary(
+                            op,
+                            a.arc().clone(),
+                            b.arc().clone(),
+                            c.arc().clone(),
+                        ));
+                        seen.insert(n, out.clone());
+                        stack.push(out);
+                    }
                 },
             }
         }
@@ -1320,6 +2210,139 @@ impl Context {
 
     /// Takes the symbolic derivative of a node with respect to a variable
     pub fn deriv(&mut self, n: Node, v: Var) -> Result<Node, Error> {
+        self.deriv_with_cache(n, v, &mut HashMap::new())
+    }
+
+    /// Builds the gradient of a node with respect to the given variables
+    ///
+    /// Returns one partial derivative per variable, in the same order as
+    /// `vars`.  Each partial is simplified with [`Context::rewrite`] as it's
+    /// built; because the partials live in the same context, subexpressions
+    /// which are common between them are shared.
+    ///
+    /// The resulting nodes can be passed directly to a multi-output function
+    /// (e.g. [`VmData::new`](crate::vm::VmData::new)).
+    ///
+    /// ```
+    /// # use fidget::{context::Context, var::Var};
+    /// let mut ctx = Context::new();
+    /// let x = ctx.x();
+    /// let y = ctx.y();
+    /// let xy = ctx.mul(x, y).unwrap();
+    /// let f = ctx.add(xy, x).unwrap(); // x * y + x
+    ///
+    /// let g = ctx.gradient(f, &[Var::X, Var::Y]).unwrap();
+    /// assert_eq!(ctx.eval_xyz(g[0], 2.0, 3.0, 0.0).unwrap(), 4.0);
+    /// assert_eq!(ctx.eval_xyz(g[1], 2.0, 3.0, 0.0).unwrap(), 2.0);
+    /// ```
+    pub fn gradient(
+        &mut self,
+        n: Node,
+        vars: &[Var],
+    ) -> Result<Vec<Node>, Error> {
+        self.jacobian(&[n], vars)
+    }
+
+    /// Builds the Jacobian of a set of nodes with respect to the given
+    /// variables
+    ///
+    /// The result is stored in row-major order, with one row per node and one
+    /// column per variable, i.e. `d nodes[i] / d vars[j]` is at index
+    /// `i * vars.len() + j`.
+    ///
+    /// Partials with respect to each variable share a cache, so subtrees which
+    /// are common to multiple nodes are only differentiated once.  Each
+    /// partial is simplified with [`Context::rewrite`] as it's built.
+    ///
+    /// ```
+    /// # use fidget::{context::Context, var::Var};
+    /// let mut ctx = Context::new();
+    /// let x = ctx.x();
+    /// let y = ctx.y();
+    /// let a = ctx.mul(x, y).unwrap();
+    /// let b = ctx.sin(x).unwrap();
+    ///
+    /// let j = ctx.jacobian(&[a, b], &[Var::X, Var::Y]).unwrap();
+    /// assert_eq!(j.len(), 4);
+    /// assert_eq!(ctx.eval_xyz(j[0], 2.0, 3.0, 0.0).unwrap(), 3.0);
+    /// assert_eq!(ctx.eval_xyz(j[1], 2.0, 3.0, 0.0).unwrap(), 2.0);
+    /// assert_eq!(ctx.eval_xyz(j[2], 2.0, 3.0, 0.0).unwrap(), 2f64.cos());
+    /// assert_eq!(ctx.eval_xyz(j[3], 2.0, 3.0, 0.0).unwrap(), 0.0);
+    /// ```
+    pub fn jacobian(
+        &mut self,
+        nodes: &[Node],
+        vars: &[Var],
+    ) -> Result<Vec<Node>, Error> {
+        for &n in nodes {
+            self.check_node(n)?;
+        }
+        let mut out = vec![Node(0); nodes.len() * vars.len()];
+        for (j, &v) in vars.iter().enumerate() {
+            let mut seen = HashMap::new();
+            for (i, &n) in nodes.iter().enumerate() {
+                let d = self.deriv_with_cache(n, v, &mut seen)?;
+                out[i * vars.len() + j] = self.rewrite(d)?.node;
+            }
+        }
+        Ok(out)
+    }
+
+    /// Builds the Hessian of a node with respect to the given variables
+    ///
+    /// The result is a symmetric matrix stored in row-major order, i.e.
+    /// `d² n / (d vars[i] d vars[j])` is at index `i * vars.len() + j`.  Only
+    /// the upper triangle is computed; the lower triangle reuses the same
+    /// nodes.
+    ///
+    /// The (simplified) gradient is built first and then differentiated again,
+    /// sharing a cache between all of the second partials with respect to
+    /// each variable.
+    ///
+    /// ```
+    /// # use fidget::{context::Context, var::Var};
+    /// let mut ctx = Context::new();
+    /// let x = ctx.x();
+    /// let y = ctx.y();
+    /// let x2 = ctx.square(x).unwrap();
+    /// let f = ctx.mul(x2, y).unwrap(); // x² y
+    ///
+    /// let h = ctx.hessian(f, &[Var::X, Var::Y]).unwrap();
+    /// assert_eq!(ctx.eval_xyz(h[0], 2.0, 3.0, 0.0).unwrap(), 6.0);
+    /// assert_eq!(ctx.eval_xyz(h[1], 2.0, 3.0, 0.0).unwrap(), 4.0);
+    /// assert_eq!(h[1], h[2]);
+    /// assert_eq!(ctx.eval_xyz(h[3], 2.0, 3.0, 0.0).unwrap(), 0.0);
+    /// ```
+    pub fn hessian(
+        &mut self,
+        n: Node,
+        vars: &[Var],
+    ) -> Result<Vec<Node>, Error> {
+        let grad = self.gradient(n, vars)?;
+        let size = vars.len();
+        let mut out = vec![Node(0); size * size];
+        for (j, &v) in vars.iter().enumerate() {
+            let mut seen = HashMap::new();
+            for i in 0..=j {
+                let d = self.deriv_with_cache(grad[i], v, &mut seen)?;
+                let d = self.rewrite(d)?.node;
+                out[i * size + j] = d;
+                out[j * size + i] = d;
+            }
+        }
+        Ok(out)
+    }
+
+    /// Takes the symbolic derivative of a node with respect to a variable
+    ///
+    /// `seen` is a cache of derivatives with respect to `v`, which may be
+    /// shared between calls to differentiate multiple nodes.
+    fn deriv_with_cache(
+        &mut self,
+        n: Node,
+        v: Var,
+        seen: &mut HashMap<Node, Node>,
+    ) -> Result<Node, Error> {
         if self.get_op(n).is_none() {
             return Err(Error::BadNode);
         }
@@ -1335,9 +2358,6 @@ impl Context {
         let mut stack = vec![];
         let zero = self.constant(0.0);
 
-        // Cache of Node -> Node mapping, for deduplication
-        let mut seen: HashMap<Node, Node> = HashMap::new();
-
         while let Some(t) = todo.pop() {
             match t {
                 Action::Down(n) => {
@@ -1368,10 +2388,65 @@ impl Context {
                             todo.push(Action::Down(lhs));
                             todo.push(Action::Down(rhs));
                         }
+                        Op::Ternary(_op, a, b, c) => {
+                            todo.push(Action::Up(n, op));
+                            todo.push(Action::Down(a));
+                            todo.push(Action::Down(b));
+                            todo.push(Action::Down(c));
+                        }
                     }
                 }
                 Action::Up(n, op) => match op {
                     Op::Const(..) | Op::Input(..) => unreachable!(),
+                    Op::Ternary(op, v_a, v_b, v_c) => {
+                        let d_a = stack.pop().unwrap();
+                        let d_b = stack.pop().unwrap();
+                        let d_c = stack.pop().unwrap();
+                        let out = match op {
+                            TernaryOpcode::Select => {
+                                self.if_nonzero_else(v_a, d_b, d_c)
+                            }
+                            TernaryOpcode::SmoothMin => {
+                                let (v_k, d_k) = (v_c, d_c);
+                                let cond = self.less_than(v_a, v_b).unwrap();
+                                let d_min = self
+                                    .if_nonzero_else(cond, d_a, d_b)
+                                    .unwrap();
+
+                                // h = max(k - |a - b|, 0)
+                                let d = self.sub(v_a, v_b).unwrap();
+                                let d = self.abs(d).unwrap();
+                                let h = self.sub(v_k, d).unwrap();
+                                let h = self.max(h, 0.0).unwrap();
+
+                                // dh = dk - sign(a - b) * (da - db)
+                                let s = self.compare(v_a, v_b).unwrap();
+                                let dd = self.sub(d_a, d_b).unwrap();
+                                let dd = self.mul(s, dd).unwrap();
+                                let d_h = self.sub(d_k, dd).unwrap();
+
+                                // d(h² / 4k) = h dh / 2k - h² dk / 4k²
+                                let a = self.mul(h, d_h).unwrap();
+                                let b = self.mul(v_k, 2.0).unwrap();
+                                let a = self.div(a, b).unwrap();
+                                let c = self.square(h).unwrap();
+                                let c = self.mul(c, d_k).unwrap();
+                                let e = self.square(v_k).unwrap();
+                                let e = self.mul(e, 4.0).unwrap();
+                                let c = self.div(c, e).unwrap();
+                                let blend = self.sub(a, c).unwrap();
+
+                                // The blend term is only present if h > 0
+                                let blend = self
+                                    .if_nonzero_else(h, blend, zero)
+                                    .unwrap();
+                                self.sub(d_min, blend)
+                            }
+                        }
+                        .unwrap();
+                        seen.insert(n, out);
+                        stack.push(out);
+                    }
                     Op::Unary(op, v_arg) => {
                         let d_arg = stack.pop().unwrap();
                         let out = match op {
@@ -1437,6 +2512,37 @@ impl Context {
                             }
                             UnaryOpcode::Exp => self.mul(n, d_arg),
                             UnaryOpcode::Ln => self.div(d_arg, v_arg),
+                            UnaryOpcode::Sinh => {
+                                let c = self.cosh(v_arg).unwrap();
+                                self.mul(c, d_arg)
+                            }
+                            UnaryOpcode::Cosh => {
+                                let s = self.sinh(v_arg).unwrap();
+                                self.mul(s, d_arg)
+                            }
+                            UnaryOpcode::Tanh => {
+                                let v = self.square(n).unwrap();
+                                let v = self.sub(1.0, v).unwrap();
+                                self.mul(v, d_arg)
+                            }
+                            UnaryOpcode::Exp2 => {
+                                let v = self
+                                    .mul(n, std::f64::consts::LN_2)
+                                    .unwrap();
+                                self.mul(v, d_arg)
+                            }
+                            UnaryOpcode::Log2 => {
+                                let v = self
+                                    .mul(v_arg, std::f64::consts::LN_2)
+                                    .unwrap();
+                                self.div(d_arg, v)
+                            }
+                            UnaryOpcode::Log10 => {
+                                let v = self
+                                    .mul(v_arg, std::f64::consts::LN_10)
+                                    .unwrap();
+                                self.div(d_arg, v)
+                            }
                             UnaryOpcode::Not => Ok(zero),
                         }
                         .unwrap();
@@ -1521,6 +2627,21 @@ impl Context {
                                 let cond = self.compare(v_lhs, zero).unwrap();
                                 self.if_nonzero_else(cond, d_lhs, d_rhs)
                             }
+                            BinaryOpcode::Pow => {
+                                // d(a^b) = b * a^(b - 1) * da + a^b * ln(a) * db
+                                //
+                                // The second term is folded away if `b` is
+                                // constant, so negative bases still work.
+                                let e = self.sub(v_rhs, 1.0).unwrap();
+                                let p = self.pow(v_lhs, e).unwrap();
+                                let p = self.mul(v_rhs, p).unwrap();
+                                let a = self.mul(p, d_lhs).unwrap();
+
+                                let ln = self.ln(v_lhs).unwrap();
+                                let q = self.mul(n, ln).unwrap();
+                                let b = self.mul(q, d_rhs).unwrap();
+                                self.add(a, b)
+                            }
                         }
                         .unwrap();
                         seen.insert(n, out);
@@ -1556,6 +2677,173 @@ pub trait IntoNode {
     fn into_node(self, ctx: &mut Context) -> Result<Node, Error>;
 }
 
+/// Version of the text format written by [`Context::write_text`]
+///
+/// Version 2 added variable metadata; version 1 files are a subset of it.
+const TEXT_FORMAT_VERSION: u32 = 2;
+
+/// Percent-encodes whitespace, `#`, and `%`, so that `s` is a single token
+fn escape_text(s: &str) -> String {
+    let mut out = String::new();
+    for c in s.chars() {
+        if c.is_whitespace() || c.is_control() || c == '#' || c == '%' {
+            let mut buf = [0; 4];
+            for b in c.encode_utf8(&mut buf).bytes() {
+                out += &format!("%{b:02X}");
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Reverses [`escape_text`], returning `None` if the input is malformed
+fn unescape_text(s: &str) -> Option<String> {
+    let mut out = vec![];
+    let mut bytes = s.bytes();
+    while let Some(b) = bytes.next() {
+        if b == b'%' {
+            let hex = [bytes.next()?, bytes.next()?];
+            let hex = std::str::from_utf8(&hex).ok()?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+        } else {
+            out.push(b);
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+/// Returns the column (1-indexed) of `token`, which must be a slice of `text`
+fn column(text: &str, token: &str) -> usize {
+    let offset = token.as_ptr() as usize - text.as_ptr() as usize;
+    text[..offset].chars().count() + 1
+}
+
+/// Tokenizer for a single line of the text format
+struct TextLine<'a> {
+    /// Line number (1-indexed)
+    line: usize,
+    /// Full text of the line, including comments
+    text: &'a str,
+    /// Column (1-indexed) and text of each remaining token
+    tokens: std::vec::IntoIter<(usize, &'a str)>,
+    /// Column just past the last token, used when a token is missing
+    end: usize,
+}
+
+impl<'a> TextLine<'a> {
+    fn new(line: usize, text: &'a str) -> Self {
+        let body = text.split('#').next().unwrap();
+        let tokens: Vec<_> = body
+            .split_whitespace()
+            .map(|t| (column(text, t), t))
+            .collect();
+        let end = body.trim_end().chars().count() + 1;
+        Self {
+            line,
+            text,
+            tokens: tokens.into_iter(),
+            end,
+        }
+    }
+
+    fn error(&self, column: usize, reason: String) -> Error {
+        Error::ParseError {
+            line: self.line,
+            column,
+            reason,
+        }
+    }
+
+    /// Checks for an optional `# fidget-vm <version>` header
+    fn check_header(&self) -> Result<(), Error> {
+        let mut words = self.text.split_whitespace();
+        if words.next() != Some("#") || words.next() != Some("fidget-vm") {
+            return Ok(());
+        }
+        let Some(v) = words.next() else {
+            let end = self.text.trim_end().chars().count() + 1;
+            return Err(self.error(end, "expected format version".to_owned()));
+        };
+        let col = column(self.text, v);
+        match v.parse::<u32>() {
+            Ok(1..=TEXT_FORMAT_VERSION) => (),
+            Ok(_) => {
+                return Err(self
+                    .error(col, format!("unsupported format version `{v}`")))
+            }
+            Err(_) => {
+                return Err(
+                    self.error(col, format!("invalid format version `{v}`"))
+                )
+            }
+        }
+        match words.next() {
+            Some(t) => Err(self.error(
+                column(self.text, t),
+                format!("unexpected token `{t}`"),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the next token, or an error describing what was expected
+    fn next(&mut self, what: &str) -> Result<(usize, &'a str), Error> {
+        self.tokens
+            .next()
+            .ok_or_else(|| self.error(self.end, format!("expected {what}")))
+    }
+
+    /// Looks up the next token as a previously-defined node
+    fn node(&mut self, seen: &HashMap<String, Node>) -> Result<Node, Error> {
+        let (col, name) = self.next("argument")?;
+        seen.get(name)
+            .cloned()
+            .ok_or_else(|| self.error(col, format!("unknown node `{name}`")))
+    }
+
+    /// Parses optional `key=value` metadata tokens for a named variable
+    fn var_info(&mut self, name: &str) -> Result<VarInfo, Error> {
+        let mut info = VarInfo::new(name);
+        for (col, t) in self.tokens.by_ref() {
+            let err = |reason: String| Error::ParseError {
+                line: self.line,
+                column: col,
+                reason,
+            };
+            let (key, value) = t
+                .split_once('=')
+                .ok_or_else(|| err(format!("unexpected token `{t}`")))?;
+            let bad = || err(format!("invalid {key} `{value}`"));
+            match key {
+                "default" => info.default = value.parse().map_err(|_| bad())?,
+                "range" => {
+                    let (lo, hi) = value.split_once(',').ok_or_else(bad)?;
+                    let lo = lo.parse().map_err(|_| bad())?;
+                    let hi = hi.parse().map_err(|_| bad())?;
+                    info.range = Some((lo, hi));
+                }
+                "description" => {
+                    info.description = unescape_text(value).ok_or_else(bad)?
+                }
+                _ => return Err(err(format!("unknown metadata `{key}`"))),
+            }
+        }
+        Ok(info)
+    }
+
+    /// Checks that there are no unused tokens left on the line
+    fn finish(mut self) -> Result<(), Error> {
+        match self.tokens.next() {
+            Some((col, t)) => {
+                Err(self.error(col, format!("unexpected token `{t}`")))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
 impl IntoNode for Node {
     fn into_node(self, ctx: &mut Context) -> Result<Node, Error> {
         ctx.check_node(self)?;
@@ -1621,6 +2909,538 @@ mod test {
         assert_eq!(tape.vars.len(), 1);
     }
 
+    #[test]
+    fn test_import_constant_select() {
+        let x = Tree::x();
+        let t = Tree::constant(1.0).if_nonzero_else(x.clone(), x.sin());
+        let mut ctx = Context::new();
+        let root = ctx.import(&t);
+        assert_eq!(root, ctx.x());
+
+        // The folded condition can be compiled into a tape
+        let t = Tree::constant(0.0).if_nonzero_else(x.clone(), x.sin());
+        let root = ctx.import(&t);
+        let tape = VmData::<255>::new(&ctx, &[root]).unwrap();
+        assert_eq!(tape.len(), 3); // x, sin, output
+    }
+
+    #[test]
+    fn test_import_constant_smooth_min() {
+        // Tapes can't store two immediates in one operation, so importing a
+        // smooth minimum of two constants must expand it
+        let t = Tree::constant(1.0).smooth_min(2.0, Tree::x());
+        let mut ctx = Context::new();
+        let root = ctx.import(&t);
+        assert_eq!(ctx.eval_xyz(root, 2.0, 0.0, 0.0).unwrap(), 0.875);
+        VmData::<255>::new(&ctx, &[root]).unwrap();
+    }
+
+    #[test]
+    fn test_smooth_min_deriv() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let s = ctx.smooth_min(x, y, z).unwrap();
+        let dx = ctx.deriv(s, Var::X).unwrap();
+        let dy = ctx.deriv(s, Var::Y).unwrap();
+        let dz = ctx.deriv(s, Var::Z).unwrap();
+
+        // Outside of the blend region, this is a plain minimum
+        assert_eq!(ctx.eval_xyz(dx, 0.0, 2.0, 1.0).unwrap(), 1.0);
+        assert_eq!(ctx.eval_xyz(dy, 0.0, 2.0, 1.0).unwrap(), 0.0);
+        assert_eq!(ctx.eval_xyz(dz, 0.0, 2.0, 1.0).unwrap(), 0.0);
+
+        assert_eq!(ctx.eval_xyz(s, 0.0, 0.5, 1.0).unwrap(), -0.0625);
+        assert_eq!(ctx.eval_xyz(dx, 0.0, 0.5, 1.0).unwrap(), 0.75);
+        assert_eq!(ctx.eval_xyz(dy, 0.0, 0.5, 1.0).unwrap(), 0.25);
+        assert_eq!(ctx.eval_xyz(dz, 0.0, 0.5, 1.0).unwrap(), -0.1875);
+    }
+
+    #[test]
+    fn test_hessian() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let x2 = ctx.square(x).unwrap();
+        let y2 = ctx.square(y).unwrap();
+        let z2 = ctx.square(z).unwrap();
+        let r = ctx.add(x2, y2).unwrap();
+        let r = ctx.add(r, z2).unwrap();
+        let r = ctx.sqrt(r).unwrap();
+        let s = ctx.mul(x, y).unwrap();
+        let s = ctx.sin(s).unwrap();
+        let f = ctx.add(r, s).unwrap();
+
+        let vars = [Var::X, Var::Y, Var::Z];
+        let grad = ctx.gradient(f, &vars).unwrap();
+        let jac = ctx.jacobian(&[f, s], &vars).unwrap();
+        let hess = ctx.hessian(f, &vars).unwrap();
+        assert_eq!(grad.len(), 3);
+        assert_eq!(jac.len(), 6);
+        assert_eq!(hess.len(), 9);
+
+        // Compare against repeated calls to `deriv`
+        let mut naive = vec![];
+        for &u in &vars {
+            let d = ctx.deriv(f, u).unwrap();
+            for &v in &vars {
+                naive.push(ctx.deriv(d, v).unwrap());
+            }
+        }
+        for (x, y, z) in [(1.0, 2.0, 3.0), (-0.5, 0.25, 2.0), (3.0, -1.0, 0.5)]
+        {
+            for i in 0..3 {
+                let a = ctx.eval_xyz(grad[i], x, y, z).unwrap();
+                let b = ctx.eval_xyz(jac[i], x, y, z).unwrap();
+                let d = ctx.deriv(f, vars[i]).unwrap();
+                let e = ctx.eval_xyz(d, x, y, z).unwrap();
+                assert!((a - e).abs() < 1e-9, "{a} != {e}");
+                assert!((b - e).abs() < 1e-9, "{b} != {e}");
+
+                let a = ctx.eval_xyz(jac[3 + i], x, y, z).unwrap();
+                let d = ctx.deriv(s, vars[i]).unwrap();
+                let e = ctx.eval_xyz(d, x, y, z).unwrap();
+                assert!((a - e).abs() < 1e-9, "{a} != {e}");
+            }
+            for (h, n) in hess.iter().zip(&naive) {
+                let a = ctx.eval_xyz(*h, x, y, z).unwrap();
+                let e = ctx.eval_xyz(*n, x, y, z).unwrap();
+                assert!((a - e).abs() < 1e-9, "{a} != {e}");
+            }
+        }
+
+        // The Hessian is symmetric, and shares nodes between partials
+        for i in 0..3 {
+            for j in 0..3 {
+                assert_eq!(hess[i * 3 + j], hess[j * 3 + i]);
+            }
+        }
+        let count = |ctx: &Context, nodes: &[Node]| {
+            ctx.reachable_nodes(nodes).unwrap().len()
+        };
+        assert!(count(&ctx, &hess) <= count(&ctx, &naive));
+
+        // The results can be used directly as a multi-output function
+        let data = crate::vm::VmData::<255>::new(&ctx, &hess).unwrap();
+        assert_eq!(data.output_count(), 9);
+
+        // Constant partials are folded
+        let hess = ctx.hessian(x2, &vars).unwrap();
+        assert_eq!(ctx.get_const(hess[0]).unwrap(), 2.0);
+        for h in &hess[1..] {
+            assert_eq!(ctx.get_const(*h).unwrap(), 0.0);
+        }
+
+        assert!(ctx.gradient(Node(1000), &vars).is_err());
+        assert!(ctx.jacobian(&[f, Node(1000)], &vars).is_err());
+        assert!(ctx.hessian(Node(1000), &vars).is_err());
+    }
+
+    #[test]
+    fn test_gc() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let a = ctx.mul(x, z).unwrap();
+        let b = ctx.sin(y).unwrap();
+        let c = ctx.max(a, b).unwrap();
+        let d = ctx.smooth_min(c, y, 0.5).unwrap();
+        let unused = ctx.cos(a).unwrap();
+        let n = ctx.len();
+
+        let remap = ctx.gc(&[d, b]).unwrap();
+        assert_eq!(ctx.len(), n - 1);
+        assert!(!remap.contains_key(&unused));
+        for (x, y, z) in [(0.0, 1.0, 2.0), (1.0, -2.0, 0.5), (-1.0, 0.0, 3.0)] {
+            let s = ctx.eval_xyz(remap[&d], x, y, z).unwrap();
+            let c = f64::max(x * z, y.sin());
+            let h = 0.5 - (c - y).abs();
+            let m = c.min(y);
+            let e = if h > 0.0 { m - h * h / 2.0 } else { m };
+            assert_eq!(s, e);
+        }
+
+        // Nodes are still deduplicated after compaction
+        let b2 = ctx.sin(remap[&y]).unwrap();
+        assert_eq!(b2, remap[&b]);
+
+        // Only the root (and its children) survive
+        let remap = ctx.gc(&[remap[&b]]).unwrap();
+        assert_eq!(ctx.len(), 2);
+        assert_eq!(remap.len(), 2);
+
+        assert!(ctx.gc(&[Node(100)]).is_err());
+        ctx.gc(&[]).unwrap();
+        assert!(ctx.is_empty());
+    }
+
+    #[test]
+    fn test_import_from() {
+        let mut a = Context::new();
+        let x = a.x();
+        let y = a.y();
+        let s = a.sin(x).unwrap();
+        let p = a.mul(s, y).unwrap();
+        let q = a.if_nonzero_else(x, p, 2.0).unwrap();
+
+        let mut b = Context::new();
+        let y_b = b.y();
+        let x_b = b.x();
+        let s_b = b.sin(x_b).unwrap();
+        let n = b.len();
+        let q_b = b.import_from(&a, q).unwrap();
+
+        // x, sin(x), and y were already present in the target context
+        assert_eq!(b.len(), n + 3);
+        assert_eq!(b.import_from(&a, s).unwrap(), s_b);
+        assert_eq!(b.import_from(&a, y).unwrap(), y_b);
+
+        // Commutative operations are deduplicated, even though `x` and `y`
+        // have a different order in the target context
+        let m = a.mul(x, y).unwrap();
+        let m_b = b.mul(x_b, y_b).unwrap();
+        assert_eq!(b.import_from(&a, m).unwrap(), m_b);
+        for (x, y) in [(0.0, 1.0), (1.0, 2.0), (-3.0, 0.5)] {
+            assert_eq!(
+                a.eval_xyz(q, x, y, 0.0).unwrap(),
+                b.eval_xyz(q_b, x, y, 0.0).unwrap()
+            );
+        }
+
+        assert!(b.import_from(&a, Node(100)).is_err());
+    }
+
+    #[test]
+    fn test_parametrize_constants() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let a = ctx.mul(x, 2.0).unwrap();
+        let b = ctx.sub(a, 3.0).unwrap();
+        let c = ctx.max(b, 2.0).unwrap();
+
+        let (root, vars) =
+            ctx.parametrize_constants(c, |_n, v| v > 1.0).unwrap();
+        assert_eq!(vars.len(), 2);
+        let mut values = vars.clone();
+        let (two, three) = {
+            let mut vs: Vec<_> = vars.iter().collect();
+            vs.sort_by(|a, b| a.1.total_cmp(b.1));
+            (*vs[0].0, *vs[1].0)
+        };
+        assert_eq!(vars[&two], 2.0);
+        assert_eq!(vars[&three], 3.0);
+
+        for x in [-1.0, 0.5, 4.0] {
+            values.insert(Var::X, x);
+            assert_eq!(
+                ctx.eval(root, &values).unwrap(),
+                ctx.eval_xyz(c, x, 0.0, 0.0).unwrap(),
+            );
+        }
+        values.insert(Var::X, 4.0);
+        values.insert(two, 1.0);
+        assert_eq!(ctx.eval(root, &values).unwrap(), 1.0);
+        values.insert(three, 0.0);
+        assert_eq!(ctx.eval(root, &values).unwrap(), 4.0);
+
+        // Nothing is replaced if the filter never matches
+        let (root, vars) =
+            ctx.parametrize_constants(c, |_n, _v| false).unwrap();
+        assert!(vars.is_empty());
+        assert_eq!(root, c);
+
+        // The filter sees the original node
+        let k = ctx.constant(3.0);
+        let (root, vars) =
+            ctx.parametrize_constants(c, |n, _v| n == k).unwrap();
+        assert_eq!(vars.values().collect::<Vec<_>>(), [&3.0]);
+        assert_ne!(root, c);
+    }
+
+    #[test]
+    fn test_substitute() {
+        let mut ctx = Context::new();
+        let a = Var::new();
+        let b = Var::new();
+        let an = ctx.var(a);
+        let bn = ctx.var(b);
+        let x = ctx.x();
+        let y = ctx.y();
+        let s = ctx.sin(an).unwrap();
+        let root = ctx.add(s, bn).unwrap();
+
+        // Simultaneous substitution, so `b -> a` isn't rewritten by `a -> x`
+        let out = ctx.substitute(root, &[(a, x), (b, an)]).unwrap();
+        let vars = [(Var::X, 2.0), (a, 3.0)].into();
+        assert_eq!(ctx.eval(out, &vars).unwrap(), 2f64.sin() + 3.0);
+
+        // Substituting the same variable twice picks the last value
+        let out = ctx.substitute(root, &[(b, x), (b, y)]).unwrap();
+        let vars = [(Var::Y, 2.0), (a, 0.0)].into();
+        assert_eq!(ctx.eval(out, &vars).unwrap(), 2.0);
+
+        // Substituting an absent variable returns the same node
+        let c = Var::new();
+        assert_eq!(ctx.substitute(root, &[(c, x)]).unwrap(), root);
+
+        // Matches the lazy `Tree` equivalent
+        let t = ctx.export(root).unwrap();
+        let t = t.substitute(&[(a, Tree::x()), (b, Tree::from(a))]);
+        let expected = ctx.substitute(root, &[(a, x), (b, an)]).unwrap();
+        assert_eq!(ctx.import(&t), expected);
+
+        assert!(ctx.substitute(Node(100), &[]).is_err());
+        assert!(ctx.substitute(root, &[(a, Node(100))]).is_err());
+    }
+
+    #[test]
+    fn test_text_round_trip() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let v = Var::new();
+        let r = ctx.var(v);
+        let a = ctx.atan2(x, y).unwrap();
+        let b = ctx.recip(r).unwrap();
+        let c = ctx.pow(z, b).unwrap();
+        let d = ctx.smooth_min(a, c, 0.25).unwrap();
+        let e = ctx.if_nonzero_else(x, d, -1.5e-7).unwrap();
+        let f = ctx.modulo(e, y).unwrap();
+        let root = ctx.tanh(f).unwrap();
+        ctx.constant(1.0); // unreachable
+
+        let mut text = vec![];
+        ctx.write_text(root, &mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.starts_with("# fidget-vm 2\n"));
+        assert!(!text.contains("const 1\n"));
+
+        let (ctx2, root2, vars) =
+            Context::from_text_with_vars(text.as_bytes()).unwrap();
+        assert_eq!(ctx2.len(), ctx.len() - 1);
+        assert_eq!(vars.len(), 1);
+        assert_eq!(vars[&v.to_string()], v);
+
+        let mut again = vec![];
+        ctx2.write_text(root2, &mut again).unwrap();
+        assert_eq!(text, String::from_utf8(again).unwrap());
+
+        for (x, y, z, r) in [(1.0, 2.0, 3.0, 0.5), (0.0, -1.0, 0.5, 2.0)] {
+            let a = ctx
+                .eval(
+                    root,
+                    &[(Var::X, x), (Var::Y, y), (Var::Z, z), (v, r)].into(),
+                )
+                .unwrap();
+            let b = ctx2
+                .eval(
+                    root2,
+                    &[(Var::X, x), (Var::Y, y), (Var::Z, z), (v, r)].into(),
+                )
+                .unwrap();
+            assert_eq!(a, b);
+        }
+    }
+
+    #[test]
+    fn test_text_round_trip_models() {
+        for model in [
+            include_str!("../../../../models/bear.vm"),
+            include_str!("../../../../models/colonnade.vm"),
+            include_str!("../../../../models/hi.vm"),
+            include_str!("../../../../models/prospero.vm"),
+            include_str!("../../../../models/quarter.vm"),
+            include_str!("../../../../models/tanglecube.vm"),
+        ] {
+            let (ctx, root) = Context::from_text(model.as_bytes()).unwrap();
+            let mut text = vec![];
+            ctx.write_text(root, &mut text).unwrap();
+            let (ctx2, root2) = Context::from_text(text.as_slice()).unwrap();
+            assert_eq!(ctx.len(), ctx2.len());
+            for (x, y, z) in [(0.0, 0.0, 0.0), (0.3, -0.7, 0.1)] {
+                assert_eq!(
+                    ctx.eval_xyz(root, x, y, z).unwrap(),
+                    ctx2.eval_xyz(root2, x, y, z).unwrap()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_text_errors() {
+        let err = |txt: &str| match Context::from_text(txt.as_bytes()) {
+            Err(Error::ParseError {
+                line,
+                column,
+                reason,
+            }) => (line, column, reason),
+            Err(e) => panic!("unexpected error {e:?}"),
+            Ok(..) => panic!("unexpected success"),
+        };
+        let (line, column, reason) = err("_0 var-x\n_1 sqrt _2\n");
+        assert_eq!((line, column), (2, 9));
+        assert_eq!(reason, "unknown node `_2`");
+
+        let (line, column, reason) = err("# comment\n\n  _0 frob");
+        assert_eq!((line, column), (3, 6));
+        assert_eq!(reason, "unknown opcode `frob`");
+
+        let (line, column, reason) = err("_0 var-x\n_1 add _0 # _0");
+        assert_eq!((line, column), (2, 10));
+        assert_eq!(reason, "expected argument");
+
+        let (line, column, reason) = err("_0 const 1.2.3");
+        assert_eq!((line, column), (1, 10));
+        assert_eq!(reason, "invalid constant `1.2.3`");
+
+        let (line, column, reason) = err("_0 var-x _1");
+        assert_eq!((line, column), (1, 10));
+        assert_eq!(reason, "unexpected token `_1`");
+
+        let (line, column, reason) = err("_0 var-x\n_0 var-y");
+        assert_eq!((line, column), (2, 1));
+        assert_eq!(reason, "duplicate node `_0`");
+
+        let (line, column, reason) = err("_0");
+        assert_eq!((line, column), (1, 3));
+        assert_eq!(reason, "expected opcode");
+
+        let (line, column, reason) = err("# fidget-vm 3\n_0 var-x");
+        assert_eq!((line, column), (1, 13));
+        assert_eq!(reason, "unsupported format version `3`");
+
+        let (line, column, reason) = err("_0 var r default=x");
+        assert_eq!((line, column), (1, 10));
+        assert_eq!(reason, "invalid default `x`");
+
+        let (line, column, reason) = err("_0 var r range=1");
+        assert_eq!((line, column), (1, 10));
+        assert_eq!(reason, "invalid range `1`");
+
+        let (line, column, reason) = err("_0 var r color=red");
+        assert_eq!((line, column), (1, 10));
+        assert_eq!(reason, "unknown metadata `color`");
+
+        let r = Context::from_text(&b"_0 var-x\n_1 var \xff"[..]);
+        assert!(matches!(
+            r,
+            Err(Error::ParseError {
+                line: 2,
+                column: 1,
+                ..
+            })
+        ));
+        assert!(matches!(
+            Context::from_text("# fidget-vm 1\n# empty".as_bytes()),
+            Err(Error::EmptyFile)
+        ));
+    }
+
+    #[test]
+    fn test_text_comments_and_vars() {
+        let txt = "
+            # fidget-vm 1
+            _0 var a  # trailing comment
+            _1 var b
+            _2 var a
+            _3 sub _2 _1
+            _4 mul _3 _0
+        ";
+        let (ctx, root, vars) =
+            Context::from_text_with_vars(txt.as_bytes()).unwrap();
+        assert_eq!(vars.len(), 2);
+        assert_eq!(ctx.len(), 4);
+        let v = ctx
+            .eval(root, &[(vars["a"], 1.5), (vars["b"], 2.0)].into())
+            .unwrap();
+        assert_eq!(v, -0.75);
+        assert_eq!(ctx.var_registry().find("a"), Some(vars["a"]));
+        assert_eq!(ctx.var_registry().find("b"), Some(vars["b"]));
+    }
+
+    #[test]
+    fn test_named_vars() {
+        let mut ctx = Context::new();
+        let r = ctx.named_var("radius").unwrap();
+        let v = ctx.var(Var::new());
+        let x = ctx.x();
+        let out = ctx.sub(x, r).unwrap();
+        let out = ctx.mul(out, v).unwrap();
+        assert!(ctx.named_var("bad name").is_err());
+
+        // Names survive a trip through the text format
+        let mut txt = vec![];
+        ctx.write_text(out, &mut txt).unwrap();
+        assert!(std::str::from_utf8(&txt).unwrap().contains("var radius\n"));
+        let (ctx2, out2, vars) =
+            Context::from_text_with_vars(txt.as_slice()).unwrap();
+        let r2 = ctx2.var_registry().find("radius").unwrap();
+        assert_eq!(vars["radius"], r2);
+        assert_eq!(ctx2.var_registry().len(), 1);
+        let v = ctx.get_var(v).unwrap();
+        let vals = HashMap::from([(Var::X, 3.0), (r2, 1.0), (v, 2.0)]);
+        assert_eq!(ctx2.eval(out2, &vals).unwrap(), 4.0);
+
+        // ...and through import_from
+        let mut ctx3 = Context::new();
+        ctx3.import_from(&ctx2, out2).unwrap();
+        assert_eq!(ctx3.var_registry().find("radius"), Some(r2));
+
+        // Importing a different variable with the same name is an error,
+        // which leaves the target context unchanged
+        let mut ctx4 = Context::new();
+        ctx4.named_var("radius").unwrap();
+        assert!(ctx4.import_from(&ctx2, out2).is_err());
+        let mut ctx5 = Context::new();
+        let y = ctx5.y();
+        let r = ctx5.named_var("radius").unwrap();
+        let s = ctx5.sub(y, r).unwrap();
+        assert!(ctx4.import_from(&ctx5, s).is_err());
+        assert_eq!(ctx4.len(), 1);
+        assert_eq!(ctx4.var_registry().len(), 1);
+
+        ctx.clear();
+        assert!(ctx.var_registry().is_empty());
+    }
+
+    #[test]
+    fn test_var_info_text() {
+        let mut ctx = Context::new();
+        let r = ctx.var(Var::new());
+        let rv = ctx.get_var(r).unwrap();
+        let info = VarInfo {
+            default: 1.5,
+            range: Some((-0.5, 1e10)),
+            description: "outer radius (100% #1)\tµm".to_owned(),
+            ..VarInfo::new("radius")
+        };
+        ctx.vars.insert(rv, info.clone()).unwrap();
+        let h = ctx.named_var("height").unwrap();
+        let out = ctx.add(r, h).unwrap();
+
+        let mut txt = vec![];
+        ctx.write_text(out, &mut txt).unwrap();
+        let txt = String::from_utf8(txt).unwrap();
+        assert!(txt.contains("var height\n"), "{txt}");
+
+        let (ctx2, _out2, vars) =
+            Context::from_text_with_vars(txt.as_bytes()).unwrap();
+        assert_eq!(ctx2.var_registry().get(&vars["radius"]), Some(&info));
+        assert_eq!(
+            ctx2.var_registry().get(&vars["height"]),
+            Some(&VarInfo::new("height"))
+        );
+
+        // Version 1 files are still accepted
+        let (ctx3, _) =
+            Context::from_text("# fidget-vm 1\n_0 var a".as_bytes()).unwrap();
+        assert_eq!(ctx3.var_registry().len(), 1);
+    }
+
     #[test]
     fn test_export() {
         let mut ctx = Context::new();
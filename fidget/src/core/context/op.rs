@@ -3,10 +3,22 @@ use crate::{
     var::Var,
 };
 use ordered_float::OrderedFloat;
+use serde::{Deserialize, Serialize};
 
 /// A one-argument math operation
 #[allow(missing_docs)]
-#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Hash,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Serialize,
+    Deserialize,
+)]
 pub enum UnaryOpcode {
     Neg,
     Abs,
@@ -24,12 +36,29 @@ pub enum UnaryOpcode {
     Atan,
     Exp,
     Ln,
+    Sinh,
+    Cosh,
+    Tanh,
+    Exp2,
+    Log2,
+    Log10,
     Not,
 }
 
 /// A two-argument math operation
 #[allow(missing_docs)]
-#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Hash,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Serialize,
+    Deserialize,
+)]
 pub enum BinaryOpcode {
     Add,
     Sub,
@@ -42,6 +71,28 @@ pub enum BinaryOpcode {
     Mod,
     And,
     Or,
+    Pow,
+}
+
+/// A three-argument math operation
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Hash,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Serialize,
+    Deserialize,
+)]
+pub enum TernaryOpcode {
+    /// Returns the second argument if the first is non-zero, else the third
+    Select,
+    /// Polynomial smooth minimum of the first two arguments, with the third
+    /// argument as the blend radius
+    SmoothMin,
 }
 
 /// An operation in a math expression
@@ -60,6 +111,7 @@ pub enum Op {
     Const(OrderedFloat<f64>),
     Binary(BinaryOpcode, Node, Node),
     Unary(UnaryOpcode, Node),
+    Ternary(TernaryOpcode, Node, Node, Node),
 }
 
 fn dot_color_to_rgb(s: &str) -> &'static str {
@@ -78,9 +130,8 @@ impl Op {
         match self {
             Op::Const(..) => "green",
             Op::Input(..) => "red",
-            Op::Binary(BinaryOpcode::Min | BinaryOpcode::Max, ..) => {
-                "dodgerblue"
-            }
+            Op::Binary(BinaryOpcode::Min | BinaryOpcode::Max, ..)
+            | Op::Ternary(..) => "dodgerblue",
             Op::Binary(..) | Op::Unary(..) => "goldenrod",
         }
     }
@@ -90,20 +141,31 @@ impl Op {
         match self {
             Op::Const(..) => "oval",
             Op::Input(..) => "circle",
-            Op::Binary(..) | Op::Unary(..) => "box",
+            Op::Ternary(..) | Op::Binary(..) | Op::Unary(..) => "box",
         }
     }
 
-    /// Iterates over children, producing 0, 1, or 2 values
+    /// Iterates over children, producing 0, 1, 2, or 3 values
     pub fn iter_children(&self) -> impl Iterator<Item = Node> {
         let out = match self {
-            Op::Binary(_, a, b) => [Some(*a), Some(*b)],
-            Op::Unary(_, a) => [Some(*a), None],
-            Op::Input(..) | Op::Const(..) => [None, None],
+            Op::Ternary(_, a, b, c) => [Some(*a), Some(*b), Some(*c)],
+            Op::Binary(_, a, b) => [Some(*a), Some(*b), None],
+            Op::Unary(_, a) => [Some(*a), None, None],
+            Op::Input(..) | Op::Const(..) => [None, None, None],
         };
         out.into_iter().flatten()
     }
 
+    /// Returns a copy of this operation, with children mapped by `f`
+    pub(crate) fn remap<F: FnMut(Node) -> Node>(&self, mut f: F) -> Self {
+        match *self {
+            Op::Input(..) | Op::Const(..) => *self,
+            Op::Unary(op, a) => Op::Unary(op, f(a)),
+            Op::Binary(op, a, b) => Op::Binary(op, f(a), f(b)),
+            Op::Ternary(op, a, b, c) => Op::Ternary(op, f(a), f(b), f(c)),
+        }
+    }
+
     /// Returns a GraphViz string of edges from this node to its children
     pub fn dot_edges(&self, i: Node) -> String {
         let mut out = String::new();
@@ -0,0 +1,643 @@
+//! Human-readable printing of [`Tree`] and [`Context`] expressions
+//!
+//! Subexpressions which are used more than once (or which are deeply nested)
+//! are bound to names with `let` statements, so the printed form stays linear
+//! in the size of the graph.
+use super::{
+    tree::{TableOp, TreeTable},
+    BinaryOpcode, Context, Node, Op, TernaryOpcode, Tree, UnaryOpcode,
+};
+use crate::{var::Var, Error};
+
+use std::collections::{HashMap, HashSet};
+
+/// Maximum nesting depth of an inline expression before it's bound to a name
+///
+/// This keeps printed expressions readable, and keeps Rhai scripts below the
+/// engine's expression depth limit.
+const MAX_DEPTH: usize = 16;
+
+// Operator precedence, from loosest to tightest binding
+const PREC_ADD: u8 = 1;
+const PREC_MUL: u8 = 2;
+const PREC_POW: u8 = 3;
+const PREC_NEG: u8 = 4;
+const PREC_ATOM: u8 = 5;
+
+/// Output syntax
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Syntax {
+    /// Plain infix math, e.g. `sqrt(x^2 + radius)`
+    Infix,
+    /// Rhai script, which can be evaluated with [`crate::rhai::eval`]
+    Rhai,
+}
+
+/// Printed subexpression
+#[derive(Default)]
+struct Expr {
+    text: String,
+    prec: u8,
+    depth: usize,
+}
+
+impl Tree {
+    /// Prints the tree as a human-readable infix expression
+    ///
+    /// Shared subexpressions are bound with `let` statements, and lazy
+    /// remappings are printed as calls to `remap_xyz` or `substitute`.
+    /// Generic variables are printed with their [`Display`](std::fmt::Display)
+    /// representation.
+    ///
+    /// ```
+    /// # use fidget::context::Tree;
+    /// let (x, y, _z) = Tree::axes();
+    /// let r = x.square() + y.square();
+    /// let t = r.sqrt() - r.clone() * 2.0;
+    /// assert_eq!(t.to_infix(), "let t0 = square(x) + square(y);\nsqrt(t0) - t0 * 2");
+    /// ```
+    pub fn to_infix(&self) -> String {
+        print(&TreeTable::from(self).0, Syntax::Infix, |_| None)
+    }
+
+    /// Prints the tree as a Rhai script
+    ///
+    /// The script can be evaluated with [`fidget::rhai::eval`] to recover a
+    /// structurally equal tree.  Generic variables are written as
+    /// `var_index("…")`, using their [`Display`](std::fmt::Display)
+    /// representation, which refers to that exact [`Var`].
+    ///
+    /// [`fidget::rhai::eval`]: https://docs.rs/fidget/latest/fidget/rhai/fn.eval.html
+    ///
+    /// ```
+    /// # use fidget::context::Tree;
+    /// let (x, y, _z) = Tree::axes();
+    /// let t = (x.clone() * 2.0).min(y.clone() - 1.0).remap_xyz(y, x, Tree::z());
+    /// assert_eq!(t.to_rhai(), "remap_xyz(min(x * 2.0, y - 1.0), y, x, z)");
+    /// ```
+    pub fn to_rhai(&self) -> String {
+        print(&TreeTable::from(self).0, Syntax::Rhai, |_| None)
+    }
+}
+
+impl Context {
+    /// Prints a node as a human-readable infix expression
+    ///
+    /// This is equivalent to [`Tree::to_infix`], except that named variables
+    /// (see [`Context::named_var`]) are printed by name.
+    pub fn to_infix(&self, node: Node) -> Result<String, Error> {
+        let table = self.table(node)?;
+        let name = |v: Var| self.vars.name(&v).map(str::to_owned);
+        Ok(print(&table, Syntax::Infix, name))
+    }
+
+    /// Prints a node as a Rhai script
+    ///
+    /// This is equivalent to [`Tree::to_rhai`], except that named variables
+    /// (see [`Context::named_var`]) are declared by name.
+    ///
+    /// ```
+    /// # use fidget::context::Context;
+    /// let mut ctx = Context::new();
+    /// let x = ctx.x();
+    /// let r = ctx.named_var("radius").unwrap();
+    /// let sum = ctx.add(x, r).unwrap();
+    /// let out = ctx.sin(sum).unwrap();
+    /// assert_eq!(
+    ///     ctx.to_rhai(out).unwrap(),
+    ///     r#"sin(x + variable("radius"))"#
+    /// );
+    /// ```
+    pub fn to_rhai(&self, node: Node) -> Result<String, Error> {
+        let table = self.table(node)?;
+        let name = |v: Var| self.vars.name(&v).map(str::to_owned);
+        Ok(print(&table, Syntax::Rhai, name))
+    }
+
+    /// Flattens the graph below `node` into a table, with `node` last
+    fn table(&self, node: Node) -> Result<Vec<TableOp>, Error> {
+        let mut index = HashMap::new();
+        let mut out = vec![];
+        for n in self.reachable_nodes(&[node])? {
+            let i = |c: &Node| index[c];
+            let op = match self.get_op(n).unwrap() {
+                Op::Input(v) => TableOp::Input(*v),
+                Op::Const(c) => TableOp::Const(c.0),
+                Op::Unary(op, a) => TableOp::Unary(*op, i(a)),
+                Op::Binary(op, a, b) => TableOp::Binary(*op, i(a), i(b)),
+                Op::Ternary(op, a, b, c) => {
+                    TableOp::Ternary(*op, i(a), i(b), i(c))
+                }
+            };
+            index.insert(n, out.len());
+            out.push(op);
+        }
+        Ok(out)
+    }
+}
+
+/// Prints a flattened expression, whose root is the last item in the table
+fn print<F: Fn(Var) -> Option<String>>(
+    table: &[TableOp],
+    syntax: Syntax,
+    name: F,
+) -> String {
+    let mut uses = vec![0; table.len()];
+    for op in table {
+        for c in children(op) {
+            uses[c] += 1;
+        }
+    }
+
+    let mut p = Printer {
+        exprs: Vec::with_capacity(table.len()),
+        uses,
+        syntax,
+        name,
+    };
+    // Bound names must not shadow variables
+    let taken: HashSet<String> = table
+        .iter()
+        .filter_map(|op| match op {
+            TableOp::Input(v) => Some(p.var(*v)),
+            _ => None,
+        })
+        .collect();
+    let mut names = (0..)
+        .map(|i| format!("t{i}"))
+        .filter(|name| !taken.contains(name));
+
+    let mut out = String::new();
+    for (i, op) in table.iter().enumerate() {
+        let mut e = p.op(op);
+        let leaf = matches!(op, TableOp::Input(..) | TableOp::Const(..));
+        let root = i == table.len() - 1;
+        if !leaf && !root && (p.uses[i] > 1 || e.depth >= MAX_DEPTH) {
+            let name = names.next().unwrap();
+            out += &format!("let {name} = {};\n", e.text);
+            e = Expr {
+                text: name,
+                prec: PREC_ATOM,
+                depth: 0,
+            };
+        }
+        p.exprs.push(e);
+    }
+    out += &p.exprs.pop().unwrap().text;
+    out
+}
+
+/// Returns the children of a flattened op
+fn children(op: &TableOp) -> Vec<usize> {
+    match op {
+        TableOp::Input(..) | TableOp::Const(..) => vec![],
+        TableOp::Unary(_op, a) => vec![*a],
+        TableOp::Binary(_op, a, b) => vec![*a, *b],
+        TableOp::Ternary(_op, a, b, c) => vec![*a, *b, *c],
+        TableOp::RemapAxes { target, x, y, z } => vec![*target, *x, *y, *z],
+        TableOp::RemapAffine { target, .. } => vec![*target],
+        TableOp::Substitute { target, vars } => std::iter::once(*target)
+            .chain(vars.iter().map(|(_v, t)| *t))
+            .collect(),
+    }
+}
+
+struct Printer<F> {
+    /// Printed expressions for each item in the table
+    exprs: Vec<Expr>,
+    /// Number of parents for each item in the table
+    uses: Vec<usize>,
+    syntax: Syntax,
+    /// Looks up a name for generic variables
+    name: F,
+}
+
+impl<F: Fn(Var) -> Option<String>> Printer<F> {
+    /// Returns the text of an argument, parenthesized if necessary
+    ///
+    /// Arguments which are only used once are moved out of the table.
+    fn arg(&mut self, c: usize, prec: u8) -> String {
+        let e = &mut self.exprs[c];
+        let text = if self.uses[c] == 1 {
+            std::mem::take(&mut e.text)
+        } else {
+            e.text.clone()
+        };
+        if e.prec < prec {
+            format!("({text})")
+        } else {
+            text
+        }
+    }
+
+    /// Returns the maximum depth of the given arguments
+    fn depth(&self, args: &[usize]) -> usize {
+        args.iter().map(|c| self.exprs[*c].depth).max().unwrap_or(0) + 1
+    }
+
+    fn op(&mut self, op: &TableOp) -> Expr {
+        let depth = self.depth(&children(op));
+        let (text, prec) = match op {
+            TableOp::Input(v) => (self.var(*v), PREC_ATOM),
+            TableOp::Const(c) => {
+                let text = self.constant(*c);
+                let prec = if text.starts_with('-') {
+                    PREC_NEG
+                } else {
+                    PREC_ATOM
+                };
+                (text, prec)
+            }
+            TableOp::Unary(UnaryOpcode::Neg, a) => {
+                (format!("-{}", self.arg(*a, PREC_ATOM)), PREC_NEG)
+            }
+            TableOp::Unary(op, a) => {
+                let f = match op {
+                    UnaryOpcode::Neg => unreachable!(),
+                    UnaryOpcode::Abs => "abs",
+                    UnaryOpcode::Recip => "recip",
+                    UnaryOpcode::Sqrt => "sqrt",
+                    UnaryOpcode::Square => "square",
+                    UnaryOpcode::Floor => "floor",
+                    UnaryOpcode::Ceil => "ceil",
+                    UnaryOpcode::Round => "round",
+                    UnaryOpcode::Sin => "sin",
+                    UnaryOpcode::Cos => "cos",
+                    UnaryOpcode::Tan => "tan",
+                    UnaryOpcode::Asin => "asin",
+                    UnaryOpcode::Acos => "acos",
+                    UnaryOpcode::Atan => "atan",
+                    UnaryOpcode::Exp => "exp",
+                    UnaryOpcode::Ln => "ln",
+                    UnaryOpcode::Sinh => "sinh",
+                    UnaryOpcode::Cosh => "cosh",
+                    UnaryOpcode::Tanh => "tanh",
+                    UnaryOpcode::Exp2 => "exp2",
+                    UnaryOpcode::Log2 => "log2",
+                    UnaryOpcode::Log10 => "log10",
+                    UnaryOpcode::Not => "not",
+                };
+                (format!("{f}({})", self.arg(*a, 0)), PREC_ATOM)
+            }
+            TableOp::Binary(op, a, b) => {
+                // Infix operators are left-associative, so the right-hand
+                // argument must bind more tightly to preserve structure.
+                let infix = |p: &mut Self, s: &str, prec| {
+                    let a = p.arg(*a, prec);
+                    let b = p.arg(*b, prec + 1);
+                    (format!("{a} {s} {b}"), prec)
+                };
+                match op {
+                    BinaryOpcode::Add => infix(self, "+", PREC_ADD),
+                    BinaryOpcode::Sub => infix(self, "-", PREC_ADD),
+                    BinaryOpcode::Mul => infix(self, "*", PREC_MUL),
+                    BinaryOpcode::Div => infix(self, "/", PREC_MUL),
+                    BinaryOpcode::Mod => infix(self, "%", PREC_MUL),
+                    BinaryOpcode::Pow => {
+                        // Negation binds more loosely than `^` in infix
+                        // syntax, but more tightly than `**` in Rhai
+                        let (s, base) = match self.syntax {
+                            Syntax::Infix => ("^", PREC_ATOM),
+                            Syntax::Rhai => ("**", PREC_NEG),
+                        };
+                        let a = self.arg(*a, base);
+                        let b = self.arg(*b, PREC_NEG);
+                        (format!("{a} {s} {b}"), PREC_POW)
+                    }
+                    BinaryOpcode::Min
+                    | BinaryOpcode::Max
+                    | BinaryOpcode::Compare
+                    | BinaryOpcode::And
+                    | BinaryOpcode::Or
+                    | BinaryOpcode::Atan => {
+                        let f = match op {
+                            BinaryOpcode::Min => "min",
+                            BinaryOpcode::Max => "max",
+                            BinaryOpcode::Compare => "compare",
+                            BinaryOpcode::And => "and",
+                            BinaryOpcode::Or => "or",
+                            BinaryOpcode::Atan => "atan2",
+                            _ => unreachable!(),
+                        };
+                        self.call(f, &[*a, *b])
+                    }
+                }
+            }
+            TableOp::Ternary(op, a, b, c) => {
+                let f = match op {
+                    TernaryOpcode::Select => "if_nonzero_else",
+                    TernaryOpcode::SmoothMin => "smooth_min",
+                };
+                self.call(f, &[*a, *b, *c])
+            }
+            TableOp::RemapAxes { target, x, y, z } => {
+                self.call("remap_xyz", &[*target, *x, *y, *z])
+            }
+            TableOp::RemapAffine { target, mat }
+                if self.syntax == Syntax::Rhai =>
+            {
+                // Print the matrix directly, so that it round-trips exactly
+                let target = self.arg(*target, 0);
+                let mat = mat.matrix();
+                let terms = (0..3)
+                    .flat_map(|i| (0..4).map(move |j| mat[(i, j)]))
+                    .map(|k| self.constant(k))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                (format!("remap_affine({target}, [{terms}])"), PREC_ATOM)
+            }
+            TableOp::RemapAffine { target, mat } => {
+                let target = self.arg(*target, 0);
+                let mat = mat.matrix();
+                let rows = (0..3).map(|i| {
+                    let mut terms = vec![];
+                    for (j, axis) in ["x", "y", "z"].iter().enumerate() {
+                        match mat[(i, j)] {
+                            0.0 => (),
+                            1.0 => terms.push(axis.to_string()),
+                            k => terms
+                                .push(format!("{axis} * {}", self.constant(k))),
+                        }
+                    }
+                    let k = mat[(i, 3)];
+                    if k != 0.0 || terms.is_empty() {
+                        terms.push(self.constant(k));
+                    }
+                    terms.join(" + ")
+                });
+                let rows = rows.collect::<Vec<_>>().join(", ");
+                (format!("remap_xyz({target}, {rows})"), PREC_ATOM)
+            }
+            TableOp::Substitute { target, vars } => {
+                let target = self.arg(*target, 0);
+                let vars = vars
+                    .iter()
+                    .map(|(v, t)| {
+                        let name = self.var(*v);
+                        let t = self.arg(*t, 0);
+                        match self.syntax {
+                            Syntax::Infix => format!("{name} = {t}"),
+                            Syntax::Rhai => format!("[{name}, {t}]"),
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                // Rhai substitutions are printed as `[var, value]` pairs
+                // (rather than an object map keyed by name), so that unnamed
+                // variables can be written with `var_index`.
+                let text = match self.syntax {
+                    Syntax::Infix => format!("substitute({target}, {vars})"),
+                    Syntax::Rhai => format!("substitute({target}, [{vars}])"),
+                };
+                (text, PREC_ATOM)
+            }
+        };
+        Expr { text, prec, depth }
+    }
+
+    /// Prints a function call
+    fn call(&mut self, f: &str, args: &[usize]) -> (String, u8) {
+        let args = args
+            .iter()
+            .map(|c| self.arg(*c, 0))
+            .collect::<Vec<_>>()
+            .join(", ");
+        (format!("{f}({args})"), PREC_ATOM)
+    }
+
+    fn var(&self, v: Var) -> String {
+        match v {
+            Var::X => "x".to_owned(),
+            Var::Y => "y".to_owned(),
+            Var::Z => "z".to_owned(),
+            Var::V(..) => match (self.syntax, (self.name)(v)) {
+                (Syntax::Infix, name) => name.unwrap_or(v.to_string()),
+                (Syntax::Rhai, Some(name)) => {
+                    format!("variable({})", rhai_string(&name))
+                }
+                (Syntax::Rhai, None) => {
+                    format!("var_index({})", rhai_string(&v.to_string()))
+                }
+            },
+        }
+    }
+
+    fn constant(&self, c: f64) -> String {
+        match self.syntax {
+            Syntax::Infix => c.to_string(),
+            // Rhai doesn't have literals for infinities or NaN, and requires
+            // a decimal point in floating-point literals.
+            Syntax::Rhai if c.is_nan() => "(0.0 / 0.0)".to_owned(),
+            Syntax::Rhai if c == f64::INFINITY => "(1.0 / 0.0)".to_owned(),
+            Syntax::Rhai if c == f64::NEG_INFINITY => "(-1.0 / 0.0)".to_owned(),
+            Syntax::Rhai => {
+                let s = format!("{c:?}");
+                match s.find('e') {
+                    Some(i) if !s.contains('.') => {
+                        format!("{}.0{}", &s[..i], &s[i..])
+                    }
+                    _ => s,
+                }
+            }
+        }
+    }
+}
+
+/// Quotes a string as a Rhai string literal
+///
+/// Rust's `Debug` escapes (e.g. `\u{7f}`) aren't valid in Rhai, which only
+/// supports `\\`, `\"`, `\n`, `\r`, `\t`, and fixed-width `\x`, `\u`, and
+/// `\U` escapes.  Other characters (including non-ASCII) are written as-is.
+fn rhai_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out += &format!("\\u{:04x}", c as u32),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Checks that trees survive a round trip through printing and parsing
+///
+/// This is shared by the infix and Rhai parsers; `depth` sets the nesting
+/// depth of a large tree, which must be split up with `let` bindings.
+#[cfg(test)]
+pub(crate) fn check_round_trip(
+    print: impl Fn(&Tree) -> String,
+    parse: impl Fn(&str) -> Result<Tree, Error>,
+    depth: usize,
+) {
+    let (x, y, z) = Tree::axes();
+    let r = Tree::from(Var::new());
+    let v = Tree::from(Var::new());
+    let d = (x.square() + y.square() + z.square()).sqrt();
+    let trees = [
+        x.clone() - (y.clone() - z.clone()) * 2.5,
+        (d.clone() - r.clone()).max(v.clone() / d.clone()),
+        x.clone().neg().pow(y.clone().recip().modulo(3.0)),
+        x.clone().pow(2.0).neg() * Tree::constant(-1.5).pow(y.clone().neg()),
+        x.clone()
+            .if_nonzero_else(y.clone().smooth_min(z.clone(), 0.5), 1e20),
+        d.clone().remap_xyz(y.clone(), x.clone() * 2.0, z.clone()),
+        (d.clone() - r.clone())
+            .substitute(&[(Var::X, y.clone()), (r.var().unwrap(), v.clone())]),
+        x.clone().compare(y.clone()).and(v.not()).or(f64::NAN),
+    ];
+    for t in trees {
+        let s = print(&t);
+        let out = parse(&s).unwrap();
+        let mut ctx = Context::new();
+        assert_eq!(
+            ctx.import(&t),
+            ctx.import(&out),
+            "round-trip failed for {s}"
+        );
+    }
+
+    let mut t = Tree::x();
+    for i in 0..depth {
+        t = (t + Tree::y() * i as f64).sin();
+    }
+    let out = parse(&print(&t)).unwrap();
+    let mut ctx = Context::new();
+    assert_eq!(ctx.import(&t), ctx.import(&out));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_infix_precedence() {
+        let (x, y, z) = Tree::axes();
+        let t = (x.clone() + y.clone()) * z.clone();
+        assert_eq!(t.to_infix(), "(x + y) * z");
+
+        let t = x.clone() - (y.clone() - z.clone());
+        assert_eq!(t.to_infix(), "x - (y - z)");
+
+        let t = (x.clone() - y.clone()) - z.clone();
+        assert_eq!(t.to_infix(), "x - y - z");
+
+        let t = x.clone() / (y.clone() * 2.0);
+        assert_eq!(t.to_infix(), "x / (y * 2)");
+
+        let t = (x.clone() + 1.0).neg();
+        assert_eq!(t.to_infix(), "-(x + 1)");
+
+        let t = x.clone().neg().pow(y.clone() + 2.0) * -1.5;
+        assert_eq!(t.to_infix(), "(-x) ^ (y + 2) * -1.5");
+        assert_eq!(t.to_rhai(), "-x ** (y + 2.0) * -1.5");
+
+        let t = x.clone().pow(y.clone()).pow(z.clone());
+        assert_eq!(t.to_infix(), "(x ^ y) ^ z");
+    }
+
+    #[test]
+    fn test_infix_functions() {
+        let (x, y, z) = Tree::axes();
+        let t = x.clone().min(y.clone()).if_nonzero_else(z.sin(), 0.0);
+        assert_eq!(t.to_infix(), "if_nonzero_else(min(x, y), sin(z), 0)");
+
+        let t = x.clone().smooth_min(y.clone(), 0.5).recip();
+        assert_eq!(t.to_rhai(), "recip(smooth_min(x, y, 0.5))");
+
+        let t = x.atan2(y).compare(1e-10);
+        assert_eq!(t.to_rhai(), "compare(atan2(x, y), 1.0e-10)");
+        assert_eq!(t.to_infix(), "compare(atan2(x, y), 0.0000000001)");
+
+        let t = Tree::x() + f64::INFINITY;
+        assert_eq!(t.to_rhai(), "x + (1.0 / 0.0)");
+    }
+
+    #[test]
+    fn test_print_remap() {
+        let (x, y, z) = Tree::axes();
+        let t = x.clone().remap_xyz(y.clone() * 2.0, z.clone(), x.clone());
+        assert_eq!(t.to_infix(), "remap_xyz(x, y * 2, z, x)");
+
+        let mat =
+            nalgebra::Affine3::from_matrix_unchecked(nalgebra::Matrix4::new(
+                2.0, 0.0, 0.0, 1.0, //
+                0.0, 1.0, 0.0, 0.0, //
+                0.0, 0.0, 0.0, 0.0, //
+                0.0, 0.0, 0.0, 1.0,
+            ));
+        let t = (x + y + z).remap_affine(mat);
+        assert_eq!(t.to_infix(), "remap_xyz(x + y + z, x * 2 + 1, y, 0)");
+        assert_eq!(
+            t.to_rhai(),
+            "remap_affine(x + y + z, \
+             [2.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0])"
+        );
+    }
+
+    #[test]
+    fn test_print_bindings() {
+        let x = Tree::x();
+        let mut t = x.clone();
+        for _ in 0..MAX_DEPTH * 2 {
+            t = t.sin();
+        }
+        let s = t.to_infix();
+        assert_eq!(s.lines().count(), 3);
+        assert!(s.starts_with("let t0 = sin(sin("));
+        assert_eq!(s.lines().last().unwrap(), "sin(t1)");
+
+        // A single node which is used twice is bound once
+        let r = (x.clone() * 2.0).sqrt();
+        let t = r.clone() * r;
+        assert_eq!(t.to_infix(), "let t0 = sqrt(x * 2);\nt0 * t0");
+    }
+
+    #[test]
+    fn test_context_print() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let r = ctx.named_var("radius").unwrap();
+        let var = Var::new();
+        let v = ctx.var(var);
+        let a = ctx.sub(x, r).unwrap();
+        let b = ctx.max(a, v).unwrap();
+        let out = ctx.abs(b).unwrap();
+        let s = ctx.to_infix(out).unwrap();
+        assert_eq!(s, format!("abs(max({var}, x - radius))"));
+
+        // Bindings are renamed to avoid shadowing variables
+        let t0 = ctx.named_var("t0").unwrap();
+        let a = ctx.sin(t0).unwrap();
+        let b = ctx.cos(a).unwrap();
+        let out = ctx.sub(a, b).unwrap();
+        let s = ctx.to_infix(out).unwrap();
+        assert_eq!(s, "let t1 = sin(t0);\nt1 - cos(t1)");
+
+        assert!(ctx.to_rhai(Node(12345)).is_err());
+    }
+
+    #[test]
+    fn test_rhai_escape_names() {
+        let mut ctx = Context::new();
+        let v = ctx.named_var(r#"a"b\c"#).unwrap();
+        let out = ctx.sin(v).unwrap();
+        let s = ctx.to_rhai(out).unwrap();
+        assert_eq!(s, r#"sin(variable("a\"b\\c"))"#);
+
+        let v = ctx.named_var("é\u{1b}").unwrap();
+        let s = ctx.to_rhai(v).unwrap();
+        assert_eq!(s, r#"variable("é\u001b")"#);
+
+        let v = Var::new();
+        let n = ctx.var(v);
+        let s = ctx.to_rhai(n).unwrap();
+        assert_eq!(s, format!("var_index(\"{v}\")"));
+    }
+}
@@ -0,0 +1,787 @@
+//! Algebraic rewriting of [`Context`] graphs, using an e-graph
+//!
+//! An e-graph stores equivalence classes ("e-classes") of operations
+//! ("e-nodes").  Rewrite rules add equivalent forms of an expression without
+//! destroying the original, so rules can't get in each other's way.  Once
+//! the rules stop finding new equivalences (or the graph gets too large), we
+//! extract the cheapest representative of each class back into the
+//! [`Context`].
+//!
+//! The e-graph reuses [`Op`] for its e-nodes, with [`Node`] handles pointing
+//! to e-classes (rather than to nodes in a `Context`).
+use super::{BinaryOpcode, Context, Node, Op, UnaryOpcode};
+use crate::Error;
+
+use std::collections::{hash_map::Entry, BTreeMap, HashMap, HashSet};
+
+/// Maximum number of times to run the full rule set
+const ITER_LIMIT: usize = 16;
+
+/// Maximum number of terms in a linear combination
+///
+/// Larger combinations are treated as opaque, which keeps the cost of
+/// normalizing long affine chains bounded.
+const MAX_TERMS: usize = 32;
+
+/// Maximum recursion depth when building a linear combination
+const MAX_DEPTH: usize = 64;
+
+/// Result of calling [`Context::rewrite`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RewriteReport {
+    /// Root of the rewritten expression
+    ///
+    /// If the rewrite didn't find anything smaller, this is the original node.
+    pub node: Node,
+    /// Number of nodes reachable from the original root
+    pub before: usize,
+    /// Number of nodes reachable from the rewritten root
+    pub after: usize,
+}
+
+impl RewriteReport {
+    /// Returns the number of nodes removed by the rewrite
+    pub fn saved(&self) -> usize {
+        self.before - self.after
+    }
+}
+
+impl Context {
+    /// Rewrites an expression into a smaller equivalent form
+    ///
+    /// This runs a set of algebraic rules over the graph below `root`,
+    /// including
+    /// - `neg(neg(x)) → x`
+    /// - `x * x → square(x)`
+    /// - `min(a, a) → a` and `max(a, a) → a`
+    /// - `max(min(a, b), b) → b` and `min(max(a, b), b) → b`
+    /// - `abs(neg(x)) → abs(x)` and `square(neg(x)) → square(x)`
+    /// - Reassociating chains of additions, subtractions, and multiplication
+    ///   by constants into a single linear combination, which distributes and
+    ///   combines constants along the way.
+    ///
+    /// The rules are applied in an e-graph, then the smallest equivalent
+    /// expression is extracted and added to the context.  Note that these
+    /// rules assume real arithmetic, so the result may differ from the
+    /// original by floating-point rounding (or in its handling of infinities).
+    ///
+    /// The original nodes are left in place; use [`Context::gc`] to remove
+    /// nodes which are no longer needed.
+    ///
+    /// ```
+    /// # use fidget::context::Context;
+    /// let mut ctx = Context::new();
+    /// let x = ctx.x();
+    /// let a = ctx.mul(x, 2.0).unwrap();
+    /// let b = ctx.add(a, 1.0).unwrap();
+    /// let c = ctx.mul(b, 3.0).unwrap();
+    /// let d = ctx.add(c, x).unwrap(); // (x * 2 + 1) * 3 + x
+    ///
+    /// let r = ctx.rewrite(d).unwrap();
+    /// assert!(r.after < r.before);
+    /// assert_eq!(ctx.eval_xyz(r.node, 2.0, 0.0, 0.0).unwrap(), 17.0);
+    /// ```
+    pub fn rewrite(&mut self, root: Node) -> Result<RewriteReport, Error> {
+        let nodes = self.reachable_nodes(&[root])?;
+        let before = nodes.len();
+
+        // Populate the e-graph, one class per node
+        let mut egraph = EGraph::default();
+        let mut classes = HashMap::new();
+        for n in nodes {
+            let op = self.get_op(n).unwrap().remap(|c| classes[&c]);
+            classes.insert(n, egraph.add(op));
+        }
+        egraph.rebuild();
+
+        let limit = egraph.len() * 8 + 256;
+        for _ in 0..ITER_LIMIT {
+            let changed = egraph.apply_rules();
+            egraph.rebuild();
+            if !changed || egraph.len() > limit {
+                break;
+            }
+        }
+
+        let node = egraph.extract(self, classes[&root])?;
+        let after = self.reachable_nodes(&[node])?.len();
+        Ok(if after < before {
+            RewriteReport {
+                node,
+                before,
+                after,
+            }
+        } else {
+            RewriteReport {
+                node: root,
+                before,
+                after: before,
+            }
+        })
+    }
+}
+
+/// Linear combination of e-classes, plus a constant offset
+#[derive(Clone, Default)]
+struct Linear {
+    terms: BTreeMap<Node, f64>,
+    offset: f64,
+}
+
+impl Linear {
+    fn term(n: Node) -> Self {
+        Self {
+            terms: [(n, 1.0)].into_iter().collect(),
+            offset: 0.0,
+        }
+    }
+
+    fn scale(mut self, k: f64) -> Self {
+        for v in self.terms.values_mut() {
+            *v *= k;
+        }
+        self.offset *= k;
+        self
+    }
+
+    fn add(mut self, other: Self, k: f64) -> Self {
+        for (n, v) in other.terms {
+            *self.terms.entry(n).or_default() += v * k;
+        }
+        self.offset += other.offset * k;
+        self
+    }
+}
+
+/// An e-graph, using [`Node`] handles as e-class ids and [`Op`] as e-nodes
+///
+/// Unions are done eagerly, but the hash-cons (`memo`) is only made consistent
+/// by calling [`EGraph::rebuild`].
+#[derive(Default)]
+struct EGraph {
+    /// Union-find parent for each class
+    parent: Vec<Node>,
+    /// E-nodes in each class (only populated for canonical classes)
+    nodes: Vec<Vec<Op>>,
+    /// Constant value of each class, if known
+    value: Vec<Option<f64>>,
+    /// Map from canonical e-nodes to classes
+    memo: HashMap<Op, Node>,
+}
+
+impl EGraph {
+    fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    /// Returns the canonical class for the given class
+    fn find(&self, mut n: Node) -> Node {
+        while self.parent[n.0] != n {
+            n = self.parent[n.0];
+        }
+        n
+    }
+
+    /// Returns the e-nodes in the given class
+    fn class(&self, n: Node) -> &[Op] {
+        &self.nodes[self.find(n).0]
+    }
+
+    /// Returns the constant value of the given class, if known
+    fn value(&self, n: Node) -> Option<f64> {
+        self.value[self.find(n).0]
+    }
+
+    /// Canonicalizes the children of an e-node
+    ///
+    /// Children of commutative operations are sorted, so that (for example)
+    /// `a + b` and `b + a` are the same e-node.
+    fn canonicalize(&self, op: Op) -> Op {
+        match op.remap(|c| self.find(c)) {
+            Op::Binary(
+                op @ (BinaryOpcode::Add
+                | BinaryOpcode::Mul
+                | BinaryOpcode::Min
+                | BinaryOpcode::Max),
+                a,
+                b,
+            ) => Op::Binary(op, a.min(b), a.max(b)),
+            op => op,
+        }
+    }
+
+    /// Finds or creates a class containing the given e-node
+    fn add(&mut self, op: Op) -> Node {
+        let op = self.canonicalize(op);
+        if let Some(n) = self.memo.get(&op) {
+            return self.find(*n);
+        }
+        let n = Node(self.len());
+        self.parent.push(n);
+        self.nodes.push(vec![op]);
+        self.value.push(match op {
+            Op::Const(c) => Some(c.0),
+            _ => None,
+        });
+        self.memo.insert(op, n);
+        if let Some(v) = self.fold(op) {
+            let c = self.add(Op::Const(v.into()));
+            self.union(n, c);
+        }
+        self.find(n)
+    }
+
+    /// Evaluates an e-node whose children are all constant
+    fn fold(&self, op: Op) -> Option<f64> {
+        if op.iter_children().next().is_none()
+            || op.iter_children().any(|c| self.value(c).is_none())
+        {
+            return None;
+        }
+        let mut ctx = Context::new();
+        let op = op.remap(|c| ctx.constant(self.value(c).unwrap()));
+        let n = ctx.ops.insert(op);
+        ctx.eval(n, &Default::default()).ok()
+    }
+
+    /// Merges two classes, returning `true` if they were distinct
+    fn union(&mut self, a: Node, b: Node) -> bool {
+        let a = self.find(a);
+        let b = self.find(b);
+        if a == b {
+            return false;
+        }
+        // The older class becomes the root, which keeps original e-nodes at
+        // the front of each class.
+        let (root, child) = if a < b { (a, b) } else { (b, a) };
+        self.parent[child.0] = root;
+        let nodes = std::mem::take(&mut self.nodes[child.0]);
+        self.nodes[root.0].extend(nodes);
+        if self.value[root.0].is_none() {
+            self.value[root.0] = self.value[child.0];
+        }
+        true
+    }
+
+    /// Restores the hash-cons invariant, merging congruent classes
+    fn rebuild(&mut self) {
+        loop {
+            for i in 0..self.len() {
+                self.parent[i] = self.find(Node(i));
+            }
+            self.memo.clear();
+            let mut unions = vec![];
+            let mut folds = vec![];
+            for i in (0..self.len()).filter(|i| self.parent[*i].0 == *i) {
+                let mut nodes = std::mem::take(&mut self.nodes[i]);
+                for op in nodes.iter_mut() {
+                    *op = self.canonicalize(*op);
+                }
+                // Deduplicate while keeping the original order
+                let mut seen = HashSet::new();
+                nodes.retain(|op| seen.insert(*op));
+                for op in &nodes {
+                    match self.memo.entry(*op) {
+                        Entry::Occupied(e) => unions.push((*e.get(), Node(i))),
+                        Entry::Vacant(e) => {
+                            e.insert(Node(i));
+                        }
+                    }
+                }
+                if self.value[i].is_none() {
+                    if let Some(v) = nodes.iter().find_map(|op| self.fold(*op))
+                    {
+                        folds.push((Node(i), v));
+                    }
+                }
+                self.nodes[i] = nodes;
+            }
+
+            let mut changed = false;
+            for (a, b) in unions {
+                changed |= self.union(a, b);
+            }
+            for (n, v) in folds {
+                let c = self.add(Op::Const(v.into()));
+                changed |= self.union(n, c);
+            }
+            if !changed {
+                break;
+            }
+        }
+    }
+
+    /// Returns the arguments of every `opcode` e-node in the given class
+    fn unary_args(&self, n: Node, opcode: UnaryOpcode) -> Vec<Node> {
+        self.class(n)
+            .iter()
+            .filter_map(|op| match op {
+                Op::Unary(o, a) if *o == opcode => Some(*a),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the arguments of every `opcode` e-node in the given class
+    fn binary_args(&self, n: Node, opcode: BinaryOpcode) -> Vec<(Node, Node)> {
+        self.class(n)
+            .iter()
+            .filter_map(|op| match op {
+                Op::Binary(o, a, b) if *o == opcode => Some((*a, *b)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Applies every rule to every e-node
+    ///
+    /// Returns `true` if the e-graph was changed, either by adding new classes
+    /// or by merging existing classes.
+    fn apply_rules(&mut self) -> bool {
+        let start = self.len();
+        let todo: Vec<(Node, Op)> = (0..self.len())
+            .filter(|i| self.parent[*i].0 == *i)
+            .flat_map(|i| self.nodes[i].iter().map(move |op| (Node(i), *op)))
+            .collect();
+
+        let mut unions = vec![];
+        let mut linear = HashMap::new();
+        let mut done = HashSet::new();
+        for (n, op) in todo {
+            match op {
+                Op::Unary(UnaryOpcode::Neg, a) => {
+                    // neg(neg(x)) → x
+                    for b in self.unary_args(a, UnaryOpcode::Neg) {
+                        unions.push((n, b));
+                    }
+                }
+                Op::Unary(UnaryOpcode::Abs, a) => {
+                    // abs(neg(x)) → abs(x)
+                    for b in self.unary_args(a, UnaryOpcode::Neg) {
+                        unions.push((
+                            n,
+                            self.add(Op::Unary(UnaryOpcode::Abs, b)),
+                        ));
+                    }
+                    // abs(abs(x)) → abs(x), abs(square(x)) → square(x)
+                    if !self.unary_args(a, UnaryOpcode::Abs).is_empty()
+                        || !self.unary_args(a, UnaryOpcode::Square).is_empty()
+                    {
+                        unions.push((n, a));
+                    }
+                }
+                Op::Unary(UnaryOpcode::Square, a) => {
+                    // square(neg(x)) → square(x), square(abs(x)) → square(x)
+                    let mut args = self.unary_args(a, UnaryOpcode::Neg);
+                    args.extend(self.unary_args(a, UnaryOpcode::Abs));
+                    for b in args {
+                        let s = self.add(Op::Unary(UnaryOpcode::Square, b));
+                        unions.push((n, s));
+                    }
+                }
+                Op::Binary(BinaryOpcode::Mul, a, b) if a == b => {
+                    // x * x → square(x)
+                    unions
+                        .push((n, self.add(Op::Unary(UnaryOpcode::Square, a))));
+                }
+                Op::Binary(BinaryOpcode::Min | BinaryOpcode::Max, a, b)
+                    if a == b =>
+                {
+                    // min(a, a) → a, max(a, a) → a
+                    unions.push((n, a));
+                }
+                Op::Binary(
+                    op @ (BinaryOpcode::Min | BinaryOpcode::Max),
+                    a,
+                    b,
+                ) => {
+                    // max(min(a, b), b) → b, min(max(a, b), b) → b
+                    let inner = if op == BinaryOpcode::Min {
+                        BinaryOpcode::Max
+                    } else {
+                        BinaryOpcode::Min
+                    };
+                    for (p, q) in [(a, b), (b, a)] {
+                        let q = self.find(q);
+                        if self.binary_args(p, inner).into_iter().any(
+                            |(x, y)| self.find(x) == q || self.find(y) == q,
+                        ) {
+                            unions.push((n, q));
+                        }
+                    }
+                }
+                _ => (),
+            }
+
+            // Normalize affine chains into a single linear combination
+            if self.is_affine(op) && done.insert(n) {
+                if let Some(f) = self.linear(n, &mut linear, &mut vec![], 0) {
+                    let out = self.emit(&f);
+                    unions.push((n, out));
+                }
+            }
+        }
+
+        let mut changed = self.len() != start;
+        for (a, b) in unions {
+            changed |= self.union(a, b);
+        }
+        changed
+    }
+
+    /// Checks whether the given e-node is an affine operation
+    fn is_affine(&self, op: Op) -> bool {
+        match op {
+            Op::Unary(UnaryOpcode::Neg, ..)
+            | Op::Binary(BinaryOpcode::Add | BinaryOpcode::Sub, ..) => true,
+            Op::Binary(BinaryOpcode::Mul, a, b) => {
+                self.value(a).is_some() || self.value(b).is_some()
+            }
+            Op::Binary(BinaryOpcode::Div, _, b) => self.value(b).is_some(),
+            _ => false,
+        }
+    }
+
+    /// Builds a linear combination equivalent to the given class
+    ///
+    /// Non-affine children are treated as opaque terms.  Returns `None` if the
+    /// class isn't affine, or if the combination has too many terms.
+    fn linear(
+        &self,
+        n: Node,
+        cache: &mut HashMap<Node, Option<Linear>>,
+        stack: &mut Vec<Node>,
+        depth: usize,
+    ) -> Option<Linear> {
+        let n = self.find(n);
+        if let Some(v) = self.value(n) {
+            return Some(Linear {
+                terms: BTreeMap::new(),
+                offset: v,
+            });
+        }
+        if let Some(f) = cache.get(&n) {
+            return f.clone();
+        }
+        // Recursive classes and deep chains are treated as opaque
+        if depth > MAX_DEPTH || stack.contains(&n) {
+            return None;
+        }
+        let op = *self.class(n).iter().find(|op| self.is_affine(**op))?;
+
+        stack.push(n);
+        let mut get = |c| {
+            self.linear(c, cache, stack, depth + 1)
+                .unwrap_or_else(|| Linear::term(self.find(c)))
+        };
+        let out = match op {
+            Op::Unary(UnaryOpcode::Neg, a) => get(a).scale(-1.0),
+            Op::Binary(BinaryOpcode::Add, a, b) => get(a).add(get(b), 1.0),
+            Op::Binary(BinaryOpcode::Sub, a, b) => get(a).add(get(b), -1.0),
+            Op::Binary(BinaryOpcode::Mul, a, b) => match self.value(a) {
+                Some(k) => get(b).scale(k),
+                None => get(a).scale(self.value(b).unwrap()),
+            },
+            Op::Binary(BinaryOpcode::Div, a, b) => {
+                get(a).scale(1.0 / self.value(b).unwrap())
+            }
+            _ => unreachable!(),
+        };
+        stack.pop();
+
+        let out = Some(out).filter(|f| f.terms.len() <= MAX_TERMS);
+        cache.insert(n, out.clone());
+        out
+    }
+
+    /// Adds a linear combination to the e-graph, returning its class
+    fn emit(&mut self, f: &Linear) -> Node {
+        // Put positive terms first, so that negative terms become subtraction
+        let mut terms: Vec<(Node, f64)> = f
+            .terms
+            .iter()
+            .filter(|(_, k)| **k != 0.0)
+            .map(|(t, k)| (*t, *k))
+            .collect();
+        terms.sort_by_key(|(_, k)| k.is_sign_negative());
+
+        // If every coefficient has the same magnitude, then factor it out
+        let scale = terms.first().map(|(_, k)| k.abs()).unwrap_or(1.0);
+        let factor = terms.len() > 1
+            && scale != 1.0
+            && terms.iter().all(|(_, k)| k.abs() == scale);
+        if factor {
+            for (_, k) in terms.iter_mut() {
+                *k /= scale;
+            }
+        }
+
+        let mut out: Option<Node> = None;
+        for (t, k) in terms {
+            let (op, t) = if k == 1.0 {
+                (BinaryOpcode::Add, t)
+            } else if k == -1.0 && out.is_some() {
+                (BinaryOpcode::Sub, t)
+            } else if k == -1.0 {
+                (BinaryOpcode::Add, self.add(Op::Unary(UnaryOpcode::Neg, t)))
+            } else if k < 0.0 && out.is_some() {
+                let k = self.add(Op::Const((-k).into()));
+                (
+                    BinaryOpcode::Sub,
+                    self.add(Op::Binary(BinaryOpcode::Mul, t, k)),
+                )
+            } else {
+                let k = self.add(Op::Const(k.into()));
+                (
+                    BinaryOpcode::Add,
+                    self.add(Op::Binary(BinaryOpcode::Mul, t, k)),
+                )
+            };
+            out = Some(match out {
+                Some(prev) => self.add(Op::Binary(op, prev, t)),
+                None => t,
+            });
+        }
+        if factor {
+            let k = self.add(Op::Const(scale.into()));
+            out = out
+                .map(|prev| self.add(Op::Binary(BinaryOpcode::Mul, prev, k)));
+        }
+
+        match out {
+            None => self.add(Op::Const(f.offset.into())),
+            Some(prev) if f.offset == 0.0 => prev,
+            Some(prev) if f.offset < 0.0 => {
+                let c = self.add(Op::Const((-f.offset).into()));
+                self.add(Op::Binary(BinaryOpcode::Sub, prev, c))
+            }
+            Some(prev) => {
+                let c = self.add(Op::Const(f.offset.into()));
+                self.add(Op::Binary(BinaryOpcode::Add, prev, c))
+            }
+        }
+    }
+
+    /// Extracts the cheapest expression for the given class into a context
+    ///
+    /// Each e-node costs 1, plus the cost of its children; shared children are
+    /// counted multiple times, which is a standard approximation.
+    fn extract(&self, ctx: &mut Context, root: Node) -> Result<Node, Error> {
+        let mut best: Vec<Option<(usize, Op)>> = vec![None; self.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for i in (0..self.len()).filter(|i| self.parent[*i].0 == *i) {
+                for op in &self.nodes[i] {
+                    let cost = op.iter_children().try_fold(1usize, |acc, c| {
+                        best[self.find(c).0].map(|(v, _)| acc.saturating_add(v))
+                    });
+                    if let Some(cost) = cost {
+                        if best[i].is_none_or(|(prev, _)| cost < prev) {
+                            best[i] = Some((cost, *op));
+                            changed = true;
+                        }
+                    }
+                }
+            }
+        }
+
+        // Children always have a strictly lower cost than their parents, so
+        // the chosen e-nodes form a DAG and this traversal terminates.
+        let mut out = HashMap::new();
+        let mut todo = vec![(self.find(root), false)];
+        while let Some((n, ready)) = todo.pop() {
+            if out.contains_key(&n) {
+                continue;
+            }
+            let (_, op) = best[n.0].unwrap();
+            if ready {
+                let op = op.remap(|c| out[&self.find(c)]);
+                out.insert(n, ctx.insert_op(op)?);
+            } else {
+                todo.push((n, true));
+                todo.extend(op.iter_children().map(|c| (self.find(c), false)));
+            }
+        }
+        Ok(out[&self.find(root)])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::context::Tree;
+
+    /// Checks that two nodes evaluate to the same values
+    fn check_eq(ctx: &Context, a: Node, b: Node) {
+        for x in [-2.5, -1.0, 0.0, 0.5, 3.0] {
+            for y in [-1.5, 0.0, 2.0] {
+                for z in [-0.5, 1.0] {
+                    let va = ctx.eval_xyz(a, x, y, z).unwrap();
+                    let vb = ctx.eval_xyz(b, x, y, z).unwrap();
+                    assert!(
+                        (va - vb).abs() < 1e-9 || (va.is_nan() && vb.is_nan()),
+                        "mismatch at ({x}, {y}, {z}): {va} != {vb}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_neg_neg() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let a = ctx.neg(x).unwrap();
+        let b = ctx.neg(a).unwrap();
+        let r = ctx.rewrite(b).unwrap();
+        assert_eq!(r.node, x);
+        assert_eq!(r.before, 3);
+        assert_eq!(r.after, 1);
+        assert_eq!(r.saved(), 2);
+    }
+
+    #[test]
+    fn test_square() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let a = ctx.neg(x).unwrap();
+        let b = ctx.neg(a).unwrap();
+        let c = ctx.mul(b, x).unwrap();
+        let r = ctx.rewrite(c).unwrap();
+        assert_eq!(r.after, 2);
+        assert_eq!(
+            ctx.get_op(r.node),
+            Some(&Op::Unary(UnaryOpcode::Square, x))
+        );
+
+        // square(neg(x)) → square(x)
+        let s = ctx.square(a).unwrap();
+        let r = ctx.rewrite(s).unwrap();
+        assert_eq!(
+            ctx.get_op(r.node),
+            Some(&Op::Unary(UnaryOpcode::Square, x))
+        );
+    }
+
+    #[test]
+    fn test_min_max() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+
+        let a = ctx.neg(x).unwrap();
+        let b = ctx.neg(a).unwrap();
+        let m = ctx.min(b, x).unwrap();
+        assert_eq!(ctx.rewrite(m).unwrap().node, x);
+
+        // max(min(x, y), y) → y
+        let m = ctx.min(x, y).unwrap();
+        let n = ctx.max(m, y).unwrap();
+        let r = ctx.rewrite(n).unwrap();
+        assert_eq!(r.node, y);
+
+        let n = ctx.max(y, m).unwrap();
+        assert_eq!(ctx.rewrite(n).unwrap().node, y);
+
+        // min(max(x, y), x) → x
+        let m = ctx.max(x, y).unwrap();
+        let n = ctx.min(m, x).unwrap();
+        assert_eq!(ctx.rewrite(n).unwrap().node, x);
+
+        // max(min(x, y), x) → x, since either argument of the inner operation
+        // can be absorbed
+        let m = ctx.min(x, y).unwrap();
+        let n = ctx.max(m, x).unwrap();
+        assert_eq!(ctx.rewrite(n).unwrap().node, x);
+
+        // min(min(x, y), x) is not simplified, because absorption requires
+        // the inner and outer operations to differ
+        let n = ctx.min(m, x).unwrap();
+        let r = ctx.rewrite(n).unwrap();
+        assert_eq!(r.node, n);
+    }
+
+    #[test]
+    fn test_affine() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let a = ctx.mul(x, 2.0).unwrap();
+        let b = ctx.add(a, 1.0).unwrap();
+        let c = ctx.mul(b, 3.0).unwrap();
+        let d = ctx.add(c, x).unwrap();
+        let r = ctx.rewrite(d).unwrap();
+        assert_eq!(r.before, 8);
+        assert_eq!(r.after, 5); // x, 7, mul, 3, add
+        check_eq(&ctx, r.node, d);
+
+        // (x + 1) - x → 1
+        let a = ctx.add(x, 1.0).unwrap();
+        let b = ctx.sub(a, x).unwrap();
+        let r = ctx.rewrite(b).unwrap();
+        assert_eq!(ctx.get_const(r.node).unwrap(), 1.0);
+
+        // (x - y) + (y - x) / 2 → (x - y) / 2
+        let y = ctx.y();
+        let a = ctx.sub(x, y).unwrap();
+        let b = ctx.sub(y, x).unwrap();
+        let c = ctx.div(b, 2.0).unwrap();
+        let d = ctx.add(a, c).unwrap();
+        let r = ctx.rewrite(d).unwrap();
+        assert!(r.after < r.before);
+        check_eq(&ctx, r.node, d);
+    }
+
+    #[test]
+    fn test_remap() {
+        // Translating and scaling a sphere leaves a lot of redundant affine
+        // arithmetic in the graph.
+        let (x, y, z) = Tree::axes();
+        let sphere = (x.square() + y.square() + z.square()).sqrt() - 1.0;
+        let t = sphere.remap_xyz(x.clone() - 1.0, y.clone() * 2.0, z.clone());
+        let t = t.remap_xyz(x.clone() + 1.0, y.clone() * 0.5, z.clone() * -1.0);
+        let t = t.remap_xyz(x.clone() * 3.0, y.clone(), z.clone());
+
+        let mut ctx = Context::new();
+        let root = ctx.import(&t);
+        let r = ctx.rewrite(root).unwrap();
+        assert!(r.after < r.before, "{r:?}");
+        check_eq(&ctx, r.node, root);
+    }
+
+    #[test]
+    fn test_no_change() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let a = ctx.sin(x).unwrap();
+        let b = ctx.atan2(a, y).unwrap();
+        let r = ctx.rewrite(b).unwrap();
+        assert_eq!(r.node, b);
+        assert_eq!(r.saved(), 0);
+
+        assert!(ctx.rewrite(Node(100)).is_err());
+    }
+
+    #[test]
+    fn test_equivalence() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let a = ctx.sub(x, y).unwrap();
+        let b = ctx.neg(a).unwrap();
+        let c = ctx.abs(b).unwrap();
+        let d = ctx.mul(c, 4.0).unwrap();
+        let e = ctx.add(d, z).unwrap();
+        let f = ctx.max(e, b).unwrap();
+        let g = ctx.min(f, e).unwrap();
+        let h = ctx.smooth_min(g, z, 0.5).unwrap();
+        let i = ctx.if_nonzero_else(x, h, a).unwrap();
+        let j = ctx.square(b).unwrap();
+        let k = ctx.add(i, j).unwrap();
+        let r = ctx.rewrite(k).unwrap();
+        assert!(r.after < r.before);
+        check_eq(&ctx, r.node, k);
+    }
+}
@@ -0,0 +1,281 @@
+//! Thread-safe graph construction
+use super::{BinaryOpcode, Context, Node, Op, TernaryOpcode, Tree};
+use crate::{var::VarRegistry, Error};
+
+use std::collections::{hash_map::RandomState, HashMap};
+use std::hash::BuildHasher;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Mutex,
+};
+
+/// Number of independently-locked shards in the hash-consing table
+const SHARD_COUNT: usize = 64;
+
+/// A deduplicated math graph which can be built from multiple threads
+///
+/// A [`Context`] requires `&mut self` to add nodes, so it can only be built
+/// from one thread at a time.  A `SharedContext` can be shared between threads
+/// by reference: its hash-consing table is split into independently locked
+/// shards, so threads only contend when inserting into the same shard.
+///
+/// Expressions are added with [`import`](Self::import) or
+/// [`import_from`](Self::import_from), which return deduplicated [`Node`]
+/// handles.  Once construction is done, the `SharedContext` is converted into
+/// a [`Context`], in which every handle is still valid (so it can be used with
+/// [`SsaTape::new`](crate::compiler::SsaTape::new) or
+/// [`MathFunction::new`](crate::eval::MathFunction::new)).
+///
+/// ```
+/// # use fidget::context::{Context, SharedContext, Tree};
+/// let shared = SharedContext::new();
+/// let nodes: Vec<_> = std::thread::scope(|s| {
+///     let handles: Vec<_> = (0..4)
+///         .map(|i| {
+///             let shared = &shared;
+///             s.spawn(move || {
+///                 let (x, y, _z) = Tree::axes();
+///                 let t = (x.square() + y.square()).sqrt() - f64::from(i);
+///                 shared.import(&t)
+///             })
+///         })
+///         .collect();
+///     handles.into_iter().map(|h| h.join().unwrap()).collect()
+/// });
+///
+/// // Shared subexpressions are deduplicated across threads
+/// let ctx = Context::from(shared);
+/// assert_eq!(ctx.len(), 14);
+/// for (i, n) in nodes.iter().enumerate() {
+///     let v = ctx.eval_xyz(*n, 3.0, 4.0, 0.0).unwrap();
+///     assert_eq!(v, 5.0 - i as f64);
+/// }
+/// ```
+pub struct SharedContext {
+    /// Map from operations to nodes, sharded by the operation's hash
+    shards: Box<[Mutex<HashMap<Op, Node>>]>,
+    /// Hasher used to pick a shard
+    hasher: RandomState,
+    /// Number of nodes allocated so far
+    len: AtomicUsize,
+    /// Names and metadata for imported variables
+    vars: Mutex<VarRegistry>,
+}
+
+impl Default for SharedContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SharedContext {
+    /// Builds a new empty context
+    pub fn new() -> Self {
+        Self {
+            shards: (0..SHARD_COUNT).map(|_| Mutex::default()).collect(),
+            hasher: RandomState::new(),
+            len: AtomicUsize::new(0),
+            vars: Mutex::default(),
+        }
+    }
+
+    /// Returns the number of nodes in the context
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+
+    /// Checks whether the context is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Imports the given tree, deduplicating and returning the root
+    ///
+    /// The tree is first imported into a temporary [`Context`] (with the same
+    /// simplifications as [`Context::import`]), then merged into the shared
+    /// graph; only the merge step takes locks.
+    pub fn import(&self, tree: &Tree) -> Node {
+        let mut ctx = Context::new();
+        let root = ctx.import(tree);
+        self.import_from(&ctx, root).unwrap()
+    }
+
+    /// Copies a node (and its children) from a [`Context`] into this one
+    ///
+    /// The copied nodes are deduplicated against existing nodes, and metadata
+    /// for copied variables is added to this context's [`VarRegistry`].
+    /// Returns the imported root, or [`Error::DuplicateVarName`] (without
+    /// modifying the context) if a copied name is used by a different
+    /// variable.
+    pub fn import_from(
+        &self,
+        other: &Context,
+        node: Node,
+    ) -> Result<Node, Error> {
+        let nodes = other.reachable_nodes(&[node])?;
+        {
+            // Check and insert every name under a single lock, so that a
+            // conflict leaves both the registry and the graph unchanged
+            let mut vars = self.vars.lock().unwrap();
+            for (v, info) in other.named_inputs(&nodes, &vars)? {
+                vars.insert(v, info.clone())?;
+            }
+        }
+        let mut remap = HashMap::new();
+        for n in nodes {
+            let op = other.get_op(n).unwrap().remap(|c| remap[&c]);
+            remap.insert(n, self.insert(op));
+        }
+        Ok(remap[&node])
+    }
+
+    /// Finds or creates a node for the given operation
+    ///
+    /// The operation's children must already be present in this context, and
+    /// the operation must already be simplified (e.g. constant-folded).
+    fn insert(&self, op: Op) -> Node {
+        // Sort arguments to commutative operations, so that graphs imported
+        // from different contexts are deduplicated.
+        let op = match op {
+            Op::Binary(
+                op @ (BinaryOpcode::Add
+                | BinaryOpcode::Mul
+                | BinaryOpcode::Min
+                | BinaryOpcode::Max),
+                a,
+                b,
+            ) => Op::Binary(op, a.min(b), a.max(b)),
+            Op::Ternary(TernaryOpcode::SmoothMin, a, b, k) => {
+                Op::Ternary(TernaryOpcode::SmoothMin, a.min(b), a.max(b), k)
+            }
+            op => op,
+        };
+        let shard = self.hasher.hash_one(op) as usize % SHARD_COUNT;
+        let mut shard = self.shards[shard].lock().unwrap();
+
+        // The node's index is allocated while its shard is locked, so an index
+        // is only visible to other threads once its op is recorded.  Children
+        // are always inserted before their parents, so they have lower
+        // indices (as in a `Context`).
+        *shard
+            .entry(op)
+            .or_insert_with(|| Node(self.len.fetch_add(1, Ordering::AcqRel)))
+    }
+}
+
+impl From<SharedContext> for Context {
+    fn from(shared: SharedContext) -> Self {
+        let mut ops: Vec<(Node, Op)> = shared
+            .shards
+            .into_vec()
+            .into_iter()
+            .flat_map(|s| s.into_inner().unwrap())
+            .map(|(op, n)| (n, op))
+            .collect();
+        ops.sort_unstable_by_key(|(n, _op)| *n);
+
+        let mut ctx = Context::new();
+        for (n, op) in ops {
+            let m = ctx.ops.insert(op);
+            assert_eq!(n, m, "nodes must be contiguous");
+        }
+        ctx.vars = shared.vars.into_inner().unwrap();
+        ctx
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::var::Var;
+
+    fn model(i: usize) -> Tree {
+        let (x, y, z) = Tree::axes();
+        let r = (x.square() + y.square() + z.square()).sqrt();
+        let mut out = r.clone() - 1.0;
+        for j in 0..=i % 8 {
+            let s = (x.clone() * f64::from(j as u32)).sin() + y.clone().cos();
+            out = out.min(s.max(r.clone() - 2.0));
+        }
+        out
+    }
+
+    #[test]
+    fn test_shared_import() {
+        let shared = SharedContext::new();
+        let nodes: Vec<(usize, Node)> = std::thread::scope(|s| {
+            let handles: Vec<_> = (0..8)
+                .map(|t| {
+                    let shared = &shared;
+                    s.spawn(move || {
+                        (0..32)
+                            .map(|i| {
+                                let i = (i * 7 + t) % 32;
+                                (i, shared.import(&model(i)))
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap())
+                .collect()
+        });
+        let ctx = Context::from(shared);
+
+        // Every thread gets the same handle for the same model
+        let mut seen = HashMap::new();
+        for &(i, n) in &nodes {
+            assert_eq!(*seen.entry(i).or_insert(n), n);
+        }
+
+        // The shared graph is as compact as one built serially
+        let mut serial = Context::new();
+        let roots: Vec<_> = (0..32).map(|i| serial.import(&model(i))).collect();
+        let nodes = serial.reachable_nodes(&roots).unwrap();
+        assert_eq!(ctx.len(), nodes.len());
+
+        for (i, n) in seen {
+            for (x, y) in [(0.5, 1.0), (-2.0, 0.25)] {
+                assert_eq!(
+                    ctx.eval_xyz(n, x, y, 0.0).unwrap(),
+                    serial.eval_xyz(roots[i], x, y, 0.0).unwrap(),
+                );
+            }
+        }
+
+        // Children have lower indices than their parents
+        for n in ctx.ops.keys() {
+            for c in ctx.get_op(n).unwrap().iter_children() {
+                assert!(c < n);
+            }
+        }
+    }
+
+    #[test]
+    fn test_shared_import_from() {
+        let mut a = Context::new();
+        let r = a.named_var("radius").unwrap();
+        let x = a.x();
+        let sa = a.sub(x, r).unwrap();
+
+        let mut b = Context::new();
+        let y = b.y();
+        let r = b.named_var("radius").unwrap();
+        let sb = b.sub(y, r).unwrap();
+
+        let shared = SharedContext::new();
+        let na = shared.import_from(&a, sa).unwrap();
+        assert_eq!(shared.len(), 3);
+
+        // Names must be consistent, and a failed import adds nothing
+        assert!(shared.import_from(&b, sb).is_err());
+        assert_eq!(shared.len(), 3);
+
+        let ctx = Context::from(shared);
+        let v = ctx.var_registry().find("radius").unwrap();
+        let vars = HashMap::from([(Var::X, 3.0), (v, 1.0)]);
+        assert_eq!(ctx.eval(na, &vars).unwrap(), 2.0);
+    }
+}
@@ -0,0 +1,431 @@
+//! Structural hashing and comparison of [`Tree`] objects
+use super::{tree::TreeOp, BinaryOpcode, TernaryOpcode, Tree, UnaryOpcode};
+use crate::var::Var;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+impl Tree {
+    /// Returns a structural hash of the tree
+    ///
+    /// Trees which are [structurally equal](Self::structurally_eq) have the
+    /// same hash, regardless of how they were built.  The hash is computed with
+    /// a fixed algorithm (rather than [`std::hash::Hash`]), so it is stable
+    /// across runs and platforms for identical [`Var`] values.  Generic
+    /// variables from [`Var::new`] hash by their index, which is random, so
+    /// trees that use them only hash consistently within a single process.
+    ///
+    /// ```
+    /// # use fidget::context::Tree;
+    /// let (x, y, _z) = Tree::axes();
+    /// let a = x.clone() * 2.0 + y.clone();
+    /// let b = y.clone() + x.clone() * 2.0;
+    /// assert_eq!(a.structural_hash(), b.structural_hash());
+    /// assert_ne!(a.structural_hash(), (x - y).structural_hash());
+    /// ```
+    pub fn structural_hash(&self) -> u64 {
+        let mut c = Canonicalizer::default();
+        let id = c.intern(self.arc());
+        c.hashes[id]
+    }
+
+    /// Checks whether two trees are structurally equal
+    ///
+    /// Unlike `==` (which compares pointers), this compares the contents of
+    /// the trees.  Arguments to commutative operations (`add`, `mul`, `min`,
+    /// `max`, and the first two arguments of `smooth_min`) may appear in either
+    /// order; the matrices of affine remappings must be identical.  Constants
+    /// are compared like [`Context`](crate::context::Context) constants, so
+    /// `0.0` equals `-0.0` and `NaN` equals itself.
+    ///
+    /// ```
+    /// # use fidget::context::Tree;
+    /// let (x, y, z) = Tree::axes();
+    /// let a = x.clone().min(y.clone()).remap_xyz(y.clone(), z.clone(), x.clone());
+    /// let b = y.clone().min(x.clone()).remap_xyz(y.clone(), z.clone(), x.clone());
+    /// assert!(a.structurally_eq(&b));
+    /// assert!(a != b); // different pointers
+    /// ```
+    pub fn structurally_eq(&self, other: &Tree) -> bool {
+        let mut c = Canonicalizer::default();
+        c.intern(self.arc()) == c.intern(other.arc())
+    }
+}
+
+/// Canonical form of a single tree node, with children replaced by their ids
+#[derive(Clone, Eq, PartialEq, Hash)]
+enum Key {
+    Input(Var),
+    Const(u64),
+    Unary(UnaryOpcode, usize),
+    Binary(BinaryOpcode, usize, usize),
+    Ternary(TernaryOpcode, usize, usize, usize),
+    RemapAxes([usize; 4]),
+    RemapAffine(usize, [u64; 12]),
+    Substitute(usize, Vec<(Var, usize)>),
+}
+
+/// Assigns ids to trees, such that structurally equal trees get the same id
+#[derive(Default)]
+struct Canonicalizer {
+    /// Map from canonical form to id
+    ids: HashMap<Key, usize>,
+    /// Stable hash of each id
+    hashes: Vec<u64>,
+    /// Ids of already-visited tree nodes
+    seen: HashMap<*const TreeOp, usize>,
+}
+
+impl Canonicalizer {
+    /// Finds the id of a tree
+    fn intern(&mut self, root: &Arc<TreeOp>) -> usize {
+        // Use an explicit stack, because trees may be arbitrarily deep
+        let mut todo = vec![(root, false)];
+        while let Some((t, done)) = todo.pop() {
+            let ptr = Arc::as_ptr(t);
+            if self.seen.contains_key(&ptr) {
+                continue;
+            }
+            if !done {
+                todo.push((t, true));
+                todo.extend(t.children().map(|c| (c, false)));
+                continue;
+            }
+            let id = |c: &Arc<TreeOp>| self.seen[&Arc::as_ptr(c)];
+            let key = match &**t {
+                TreeOp::Input(v) => Key::Input(*v),
+                TreeOp::Const(c) => Key::Const(const_bits(*c)),
+                TreeOp::Unary(op, a) => Key::Unary(*op, id(a)),
+                TreeOp::Binary(op, a, b) => {
+                    let (a, b) = (id(a), id(b));
+                    match op {
+                        BinaryOpcode::Add
+                        | BinaryOpcode::Mul
+                        | BinaryOpcode::Min
+                        | BinaryOpcode::Max => {
+                            Key::Binary(*op, a.min(b), a.max(b))
+                        }
+                        _ => Key::Binary(*op, a, b),
+                    }
+                }
+                TreeOp::Ternary(op, a, b, c) => {
+                    let (a, b, c) = (id(a), id(b), id(c));
+                    match op {
+                        TernaryOpcode::SmoothMin => {
+                            Key::Ternary(*op, a.min(b), a.max(b), c)
+                        }
+                        TernaryOpcode::Select => Key::Ternary(*op, a, b, c),
+                    }
+                }
+                TreeOp::RemapAxes { target, x, y, z } => {
+                    Key::RemapAxes([id(target), id(x), id(y), id(z)])
+                }
+                TreeOp::RemapAffine { target, mat } => {
+                    let mat = mat.matrix();
+                    let mut out = [0; 12];
+                    for i in 0..3 {
+                        for j in 0..4 {
+                            out[i * 4 + j] = const_bits(mat[(i, j)]);
+                        }
+                    }
+                    Key::RemapAffine(id(target), out)
+                }
+                TreeOp::Substitute { target, vars } => {
+                    // Substitution is simultaneous, so the order of variables
+                    // doesn't matter (but later duplicates win).
+                    let mut map = HashMap::new();
+                    for (v, t) in vars {
+                        map.insert(*v, id(t));
+                    }
+                    let mut vars: Vec<_> = map.into_iter().collect();
+                    vars.sort_unstable();
+                    Key::Substitute(id(target), vars)
+                }
+            };
+            let next = self.ids.len();
+            let i = *self.ids.entry(key.clone()).or_insert(next);
+            if i == next {
+                let h = self.hash(&key);
+                self.hashes.push(h);
+            }
+            self.seen.insert(ptr, i);
+        }
+        self.seen[&Arc::as_ptr(root)]
+    }
+
+    /// Computes a stable hash for a newly-interned node
+    ///
+    /// Children are identified by their hashes (rather than their ids, which
+    /// depend on traversal order); arguments to commutative operations are
+    /// sorted by hash.
+    fn hash(&self, key: &Key) -> u64 {
+        let h = |i: &usize| self.hashes[*i];
+        let mut f = Fnv::default();
+        match key {
+            Key::Input(v) => {
+                f.write_u8(0);
+                match v {
+                    Var::X => f.write_u8(0),
+                    Var::Y => f.write_u8(1),
+                    Var::Z => f.write_u8(2),
+                    Var::V(i) => {
+                        f.write_u8(3);
+                        f.write_u64(i.get());
+                    }
+                }
+            }
+            Key::Const(c) => {
+                f.write_u8(1);
+                f.write_u64(*c);
+            }
+            Key::Unary(op, a) => {
+                f.write_u8(2);
+                f.write_u8(unary_tag(*op));
+                f.write_u64(h(a));
+            }
+            Key::Binary(op, a, b) => {
+                f.write_u8(3);
+                f.write_u8(binary_tag(*op));
+                let (mut a, mut b) = (h(a), h(b));
+                if matches!(
+                    op,
+                    BinaryOpcode::Add
+                        | BinaryOpcode::Mul
+                        | BinaryOpcode::Min
+                        | BinaryOpcode::Max
+                ) && a > b
+                {
+                    std::mem::swap(&mut a, &mut b);
+                }
+                f.write_u64(a);
+                f.write_u64(b);
+            }
+            Key::Ternary(op, a, b, c) => {
+                f.write_u8(4);
+                f.write_u8(ternary_tag(*op));
+                let (mut a, mut b) = (h(a), h(b));
+                if *op == TernaryOpcode::SmoothMin && a > b {
+                    std::mem::swap(&mut a, &mut b);
+                }
+                f.write_u64(a);
+                f.write_u64(b);
+                f.write_u64(h(c));
+            }
+            Key::RemapAxes(args) => {
+                f.write_u8(5);
+                for a in args {
+                    f.write_u64(h(a));
+                }
+            }
+            Key::RemapAffine(target, mat) => {
+                f.write_u8(6);
+                f.write_u64(h(target));
+                for m in mat {
+                    f.write_u64(*m);
+                }
+            }
+            Key::Substitute(target, vars) => {
+                f.write_u8(7);
+                f.write_u64(h(target));
+                let mut vars: Vec<_> = vars
+                    .iter()
+                    .map(|(v, t)| (self.hash(&Key::Input(*v)), h(t)))
+                    .collect();
+                vars.sort_unstable();
+                for (v, t) in vars {
+                    f.write_u64(v);
+                    f.write_u64(t);
+                }
+            }
+        }
+        f.0
+    }
+}
+
+/// Returns the hash tag for a unary opcode
+///
+/// Tags are spelled out (rather than using the enum discriminant), so that
+/// reordering or adding opcodes doesn't change existing hashes.
+fn unary_tag(op: UnaryOpcode) -> u8 {
+    match op {
+        UnaryOpcode::Neg => 0,
+        UnaryOpcode::Abs => 1,
+        UnaryOpcode::Recip => 2,
+        UnaryOpcode::Sqrt => 3,
+        UnaryOpcode::Square => 4,
+        UnaryOpcode::Floor => 5,
+        UnaryOpcode::Ceil => 6,
+        UnaryOpcode::Round => 7,
+        UnaryOpcode::Sin => 8,
+        UnaryOpcode::Cos => 9,
+        UnaryOpcode::Tan => 10,
+        UnaryOpcode::Asin => 11,
+        UnaryOpcode::Acos => 12,
+        UnaryOpcode::Atan => 13,
+        UnaryOpcode::Exp => 14,
+        UnaryOpcode::Ln => 15,
+        UnaryOpcode::Sinh => 16,
+        UnaryOpcode::Cosh => 17,
+        UnaryOpcode::Tanh => 18,
+        UnaryOpcode::Exp2 => 19,
+        UnaryOpcode::Log2 => 20,
+        UnaryOpcode::Log10 => 21,
+        UnaryOpcode::Not => 22,
+    }
+}
+
+/// Returns the hash tag for a binary opcode (see [`unary_tag`])
+fn binary_tag(op: BinaryOpcode) -> u8 {
+    match op {
+        BinaryOpcode::Add => 0,
+        BinaryOpcode::Sub => 1,
+        BinaryOpcode::Mul => 2,
+        BinaryOpcode::Div => 3,
+        BinaryOpcode::Atan => 4,
+        BinaryOpcode::Min => 5,
+        BinaryOpcode::Max => 6,
+        BinaryOpcode::Compare => 7,
+        BinaryOpcode::Mod => 8,
+        BinaryOpcode::And => 9,
+        BinaryOpcode::Or => 10,
+        BinaryOpcode::Pow => 11,
+    }
+}
+
+/// Returns the hash tag for a ternary opcode (see [`unary_tag`])
+fn ternary_tag(op: TernaryOpcode) -> u8 {
+    match op {
+        TernaryOpcode::Select => 0,
+        TernaryOpcode::SmoothMin => 1,
+    }
+}
+
+/// Returns the bits of a constant, with all zeros and NaNs normalized
+fn const_bits(c: f64) -> u64 {
+    if c == 0.0 {
+        0
+    } else if c.is_nan() {
+        f64::NAN.to_bits()
+    } else {
+        c.to_bits()
+    }
+}
+
+/// 64-bit FNV-1a hasher, which is stable across runs and platforms
+struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= u64::from(*b);
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+    fn write_u8(&mut self, v: u8) {
+        self.write(&[v]);
+    }
+    fn write_u64(&mut self, v: u64) {
+        self.write(&v.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_structural_eq() {
+        let (x, y, z) = Tree::axes();
+        let eq = |a: &Tree, b: &Tree| {
+            let out = a.structurally_eq(b);
+            if out {
+                assert_eq!(a.structural_hash(), b.structural_hash());
+            }
+            out
+        };
+
+        assert!(eq(&(x.clone() + y.clone()), &(y.clone() + x.clone())));
+        assert!(eq(&(x.clone() * 2.0), &(Tree::constant(2.0) * x.clone())));
+        assert!(!eq(&(x.clone() - y.clone()), &(y.clone() - x.clone())));
+        assert!(!eq(&(x.clone() / 2.0), &(x.clone() * 0.5)));
+        assert!(eq(
+            &x.clone().smooth_min(y.clone(), 0.5),
+            &y.clone().smooth_min(x.clone(), 0.5)
+        ));
+        assert!(!eq(
+            &x.clone().smooth_min(y.clone(), 0.5),
+            &x.clone().smooth_min(0.5, y.clone())
+        ));
+        assert!(eq(
+            &(x.clone() + Tree::constant(0.0)),
+            &(x.clone() + Tree::constant(-0.0))
+        ));
+        assert!(eq(&Tree::constant(f64::NAN), &Tree::constant(-f64::NAN)));
+
+        let v = Var::new();
+        assert!(eq(&Tree::from(v), &Tree::from(v)));
+        assert!(!eq(&Tree::from(v), &Tree::from(Var::new())));
+
+        let s = x.clone().square() + y.clone().square();
+        let a = s.remap_xyz(y.clone(), z.clone(), x.clone());
+        let b = s.remap_xyz(y.clone(), x.clone(), z.clone());
+        assert!(!eq(&a, &b));
+
+        let mat = |k: f64| {
+            let mut m = nalgebra::Matrix4::identity();
+            m[(0, 3)] = k;
+            nalgebra::Affine3::from_matrix_unchecked(m)
+        };
+        assert!(eq(&s.remap_affine(mat(1.0)), &s.remap_affine(mat(1.0))));
+        assert!(!eq(&s.remap_affine(mat(1.0)), &s.remap_affine(mat(2.0))));
+
+        let a = s.substitute(&[(Var::X, y.clone()), (v, z.clone())]);
+        let b = s.substitute(&[(v, z.clone()), (Var::X, y.clone())]);
+        assert!(eq(&a, &b));
+        let c = s.substitute(&[(v, z.clone()), (Var::X, z.clone())]);
+        assert!(!eq(&a, &c));
+    }
+
+    #[test]
+    fn test_structural_hash_stable() {
+        // These values must not change, because hashes may be used as
+        // persistent cache keys
+        let (x, y, _z) = Tree::axes();
+        assert_eq!(x.structural_hash(), 0x08328807b4eb6fed);
+        let t = (x.square() + y.square()).sqrt() - 1.0;
+        assert_eq!(t.structural_hash(), 0x32d860107256aa63);
+
+        // Generic variables are hashed by index
+        let v = Tree::from("V(1234abcd)".parse::<Var>().unwrap());
+        let t = (v + x.clone()).max(y.clone().sin()).smooth_min(x, 0.5);
+        assert_eq!(t.structural_hash(), 0x7b8e242a9e912646);
+    }
+
+    #[test]
+    fn test_structural_deep() {
+        // Deep trees don't overflow the stack
+        let mut a = Tree::x();
+        let mut b = Tree::x();
+        for i in 0..100_000 {
+            a = (a + f64::from(i)).sin();
+            b = (Tree::constant(f64::from(i)) + b).sin();
+        }
+        assert!(a.structurally_eq(&b));
+        assert_eq!(a.structural_hash(), b.structural_hash());
+
+        // Shared subtrees are only visited once
+        let mut a = Tree::x();
+        let mut b = Tree::x();
+        for _ in 0..200 {
+            a = a.clone() * a.clone().sin();
+            b = b.clone().sin() * b.clone();
+        }
+        assert!(a.structurally_eq(&b));
+    }
+}
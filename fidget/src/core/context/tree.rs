@@ -1,13 +1,14 @@
 //! Context-free math trees
-use super::op::{BinaryOpcode, UnaryOpcode};
-use crate::{var::Var, Error};
-use std::sync::Arc;
+use super::op::{BinaryOpcode, TernaryOpcode, UnaryOpcode};
+use crate::{types::Interval, var::Var, Error};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{collections::HashMap, sync::Arc};
 
 /// Opcode type for trees
 ///
 /// This is equivalent to [`Op`](crate::context::Op), but also includes the
-/// [`RemapAxes`](TreeOp::RemapAxes) and [`TreeOp::RemapAffine`] operations for
-/// lazy remapping.
+/// [`RemapAxes`](TreeOp::RemapAxes), [`RemapAffine`](TreeOp::RemapAffine), and
+/// [`Substitute`](TreeOp::Substitute) operations for lazy remapping.
 #[derive(Debug)]
 #[allow(missing_docs)]
 pub enum TreeOp {
@@ -16,6 +17,7 @@ pub enum TreeOp {
     Const(f64),
     Binary(BinaryOpcode, Arc<TreeOp>, Arc<TreeOp>),
     Unary(UnaryOpcode, Arc<TreeOp>),
+    Ternary(TernaryOpcode, Arc<TreeOp>, Arc<TreeOp>, Arc<TreeOp>),
     /// Lazy remapping of trees
     ///
     /// When imported into a `Context`, all `x/y/z` clauses within `target` will
@@ -37,6 +39,15 @@ pub enum TreeOp {
         target: Arc<TreeOp>,
         mat: nalgebra::Affine3<f64>,
     },
+    /// Lazy substitution of arbitrary variables
+    ///
+    /// When imported into a `Context`, each variable in `vars` within `target`
+    /// will be replaced with its paired tree.  Replacement trees are not
+    /// themselves substituted; they're imported in the enclosing scope.
+    Substitute {
+        target: Arc<TreeOp>,
+        vars: Vec<(Var, Arc<TreeOp>)>,
+    },
 }
 
 impl Drop for TreeOp {
@@ -72,6 +83,11 @@ impl TreeOp {
                 matches!(**lhs, TreeOp::Const(..))
                     && matches!(**rhs, TreeOp::Const(..))
             }
+            TreeOp::Ternary(_op, a, b, c) => {
+                matches!(**a, TreeOp::Const(..))
+                    && matches!(**b, TreeOp::Const(..))
+                    && matches!(**c, TreeOp::Const(..))
+            }
             TreeOp::RemapAxes { target, x, y, z } => {
                 matches!(**target, TreeOp::Const(..))
                     && matches!(**x, TreeOp::Const(..))
@@ -81,23 +97,70 @@ impl TreeOp {
             TreeOp::RemapAffine { target, .. } => {
                 matches!(**target, TreeOp::Const(..))
             }
+            TreeOp::Substitute { target, vars } => {
+                matches!(**target, TreeOp::Const(..))
+                    && vars
+                        .iter()
+                        .all(|(_v, t)| matches!(**t, TreeOp::Const(..)))
+            }
         }
     }
 
+    /// Iterates over children
+    pub(super) fn children(&self) -> impl Iterator<Item = &Arc<TreeOp>> {
+        let (fixed, subs): (_, &[(Var, Arc<TreeOp>)]) = match self {
+            TreeOp::Const(..) | TreeOp::Input(..) => {
+                ([None, None, None, None], &[])
+            }
+            TreeOp::Unary(_op, arg) => ([Some(arg), None, None, None], &[]),
+            TreeOp::Binary(_op, lhs, rhs) => {
+                ([Some(lhs), Some(rhs), None, None], &[])
+            }
+            TreeOp::Ternary(_op, a, b, c) => {
+                ([Some(a), Some(b), Some(c), None], &[])
+            }
+            TreeOp::RemapAxes { target, x, y, z } => {
+                ([Some(target), Some(x), Some(y), Some(z)], &[])
+            }
+            TreeOp::RemapAffine { target, .. } => {
+                ([Some(target), None, None, None], &[])
+            }
+            TreeOp::Substitute { target, vars } => {
+                ([Some(target), None, None, None], vars.as_slice())
+            }
+        };
+        fixed
+            .into_iter()
+            .flatten()
+            .chain(subs.iter().map(|(_v, t)| t))
+    }
+
     fn iter_children(&mut self) -> impl Iterator<Item = &mut Arc<TreeOp>> {
-        match self {
-            TreeOp::Const(..) | TreeOp::Input(..) => [None, None, None, None],
-            TreeOp::Unary(_op, arg) => [Some(arg), None, None, None],
-            TreeOp::Binary(_op, lhs, rhs) => [Some(lhs), Some(rhs), None, None],
+        let (fixed, subs): (_, &mut [(Var, Arc<TreeOp>)]) = match self {
+            TreeOp::Const(..) | TreeOp::Input(..) => {
+                ([None, None, None, None], &mut [])
+            }
+            TreeOp::Unary(_op, arg) => ([Some(arg), None, None, None], &mut []),
+            TreeOp::Binary(_op, lhs, rhs) => {
+                ([Some(lhs), Some(rhs), None, None], &mut [])
+            }
+            TreeOp::Ternary(_op, a, b, c) => {
+                ([Some(a), Some(b), Some(c), None], &mut [])
+            }
             TreeOp::RemapAxes { target, x, y, z } => {
-                [Some(target), Some(x), Some(y), Some(z)]
+                ([Some(target), Some(x), Some(y), Some(z)], &mut [])
             }
             TreeOp::RemapAffine { target, .. } => {
-                [Some(target), None, None, None]
+                ([Some(target), None, None, None], &mut [])
             }
-        }
-        .into_iter()
-        .flatten()
+            TreeOp::Substitute { target, vars } => {
+                ([Some(target), None, None, None], vars.as_mut_slice())
+            }
+        };
+        fixed
+            .into_iter()
+            .flatten()
+            .chain(subs.iter_mut().map(|(_v, t)| t))
     }
 }
 
@@ -132,6 +195,11 @@ impl From<TreeOp> for Tree {
 }
 
 /// Owned handle for a standalone math tree
+///
+/// Trees can be serialized and deserialized with `serde`.  The serialized form
+/// is a flat table of nodes, so shared subtrees are only written once (and
+/// remain shared after deserialization), and arbitrarily deep trees can be
+/// deserialized without overflowing the stack.
 #[derive(Clone, Debug)]
 pub struct Tree(Arc<TreeOp>);
 
@@ -206,6 +274,107 @@ impl Tree {
         Self(out.into())
     }
 
+    /// Replaces variables in the given tree
+    ///
+    /// Each `(var, tree)` pair replaces every instance of `var` in `self` with
+    /// `tree`.  Substitutions are simultaneous, so a replacement tree which
+    /// refers to another substituted variable is not itself rewritten.  Any
+    /// [`Var`] may be substituted, including the `X`, `Y`, and `Z` axes.
+    ///
+    /// Like [`remap_xyz`](Self::remap_xyz), the substitution is lazy; it is not
+    /// evaluated until the tree is imported into a `Context`.  Chains of
+    /// substitutions are flattened into a single substitution.
+    ///
+    /// If the same variable appears more than once in `vars`, the last
+    /// replacement wins.
+    pub fn substitute(&self, vars: &[(Var, Tree)]) -> Tree {
+        let vars = vars.iter().map(|(v, t)| (*v, t.0.clone())).collect();
+        Self(Self::substitute_arc(&self.0, vars))
+    }
+
+    /// Builds a [`TreeOp::Substitute`], flattening if possible
+    fn substitute_arc(
+        target: &Arc<TreeOp>,
+        vars: Vec<(Var, Arc<TreeOp>)>,
+    ) -> Arc<TreeOp> {
+        let out = match &**target {
+            _ if vars.is_empty() => return target.clone(),
+            // Constants are unaffected by substitution
+            TreeOp::Const(..) => return target.clone(),
+            // Flatten nested substitutions: the inner replacements are wrapped
+            // by the outer substitution, and the outer replacements are kept
+            // for any variables not already replaced by the inner one.
+            TreeOp::Substitute {
+                target,
+                vars: inner,
+            } => {
+                let mut merged: Vec<_> = inner
+                    .iter()
+                    .map(|(v, t)| {
+                        let t = if matches!(**t, TreeOp::Const(..)) {
+                            t.clone()
+                        } else {
+                            Arc::new(TreeOp::Substitute {
+                                target: t.clone(),
+                                vars: vars.clone(),
+                            })
+                        };
+                        (*v, t)
+                    })
+                    .collect();
+                for (v, t) in vars {
+                    if !inner.iter().any(|(u, _)| *u == v) {
+                        merged.push((v, t));
+                    }
+                }
+                TreeOp::Substitute {
+                    target: target.clone(),
+                    vars: merged,
+                }
+            }
+            _ => TreeOp::Substitute {
+                target: target.clone(),
+                vars,
+            },
+        };
+        Arc::new(out)
+    }
+
+    /// Specializes the tree for a particular region
+    ///
+    /// `region` gives ranges for the X, Y, and Z axes, and `vars` gives ranges
+    /// for any other variables in the tree.  Branches which are decided over
+    /// the whole region are pruned (see
+    /// [`Context::specialize`](crate::Context::specialize)), so the
+    /// resulting tree is equal to `self` within the region but may be much
+    /// smaller.
+    ///
+    /// Returns [`Error::MissingVar`] if a variable in the tree has no range.
+    ///
+    /// ```
+    /// # use fidget::{context::Tree, types::Interval};
+    /// let t = Tree::x().min(Tree::y());
+    /// let region = [
+    ///     Interval::new(-2.0, -1.0),
+    ///     Interval::new(0.0, 1.0),
+    ///     Interval::new(0.0, 1.0),
+    /// ];
+    /// let s = t.specialize(region, &Default::default()).unwrap();
+    /// assert_eq!(s.var(), Some(fidget::var::Var::X));
+    /// ```
+    pub fn specialize(
+        &self,
+        region: [Interval; 3],
+        vars: &HashMap<Var, Interval>,
+    ) -> Result<Tree, Error> {
+        let mut ctx = crate::Context::new();
+        let node = ctx.import(self);
+        let mut vars = vars.clone();
+        vars.extend([Var::X, Var::Y, Var::Z].into_iter().zip(region));
+        let node = ctx.specialize(node, &vars)?;
+        ctx.export(node)
+    }
+
     /// Returns the inner [`Var`] if this is an input tree, or `None`
     pub fn var(&self) -> Option<Var> {
         if let TreeOp::Input(v) = &*self.0 {
@@ -230,6 +399,170 @@ impl TryFrom<Tree> for Var {
     }
 }
 
+/// Flattened [`TreeOp`], used for serialization and pretty-printing
+///
+/// Children are stored as indexes into the node table, and must refer to
+/// earlier nodes in the table.
+#[derive(Serialize, Deserialize)]
+pub(super) enum TableOp {
+    Input(Var),
+    Const(f64),
+    Binary(BinaryOpcode, usize, usize),
+    Unary(UnaryOpcode, usize),
+    Ternary(TernaryOpcode, usize, usize, usize),
+    RemapAxes {
+        target: usize,
+        x: usize,
+        y: usize,
+        z: usize,
+    },
+    RemapAffine {
+        target: usize,
+        mat: nalgebra::Affine3<f64>,
+    },
+    Substitute {
+        target: usize,
+        vars: Vec<(Var, usize)>,
+    },
+}
+
+/// Serialized form of a [`Tree`]
+///
+/// Each unique `TreeOp` is stored once, with children before their parents;
+/// the root of the tree is the last node in the table.
+#[derive(Serialize, Deserialize)]
+pub(super) struct TreeTable(pub(super) Vec<TableOp>);
+
+impl From<&Tree> for TreeTable {
+    fn from(tree: &Tree) -> Self {
+        // Do recursion on the heap to avoid stack overflows for deep trees
+        enum Action<'a> {
+            /// Pushes `Up(t)` followed by `Down(c)` for each child
+            Down(&'a Arc<TreeOp>),
+            /// Writes the node to the table (its children are already present)
+            Up(&'a Arc<TreeOp>),
+        }
+        let mut todo = vec![Action::Down(&tree.0)];
+        let mut seen: HashMap<*const TreeOp, usize> = HashMap::new();
+        let mut out = vec![];
+        while let Some(a) = todo.pop() {
+            match a {
+                Action::Down(t) => {
+                    if !seen.contains_key(&Arc::as_ptr(t)) {
+                        todo.push(Action::Up(t));
+                        todo.extend(t.children().map(Action::Down));
+                    }
+                }
+                Action::Up(t) => {
+                    // The same node may be pushed multiple times before it's
+                    // written, so check again here.
+                    if seen.contains_key(&Arc::as_ptr(t)) {
+                        continue;
+                    }
+                    let i = |c: &Arc<TreeOp>| seen[&Arc::as_ptr(c)];
+                    let op = match &**t {
+                        TreeOp::Input(v) => TableOp::Input(*v),
+                        TreeOp::Const(c) => TableOp::Const(*c),
+                        TreeOp::Unary(op, arg) => TableOp::Unary(*op, i(arg)),
+                        TreeOp::Binary(op, lhs, rhs) => {
+                            TableOp::Binary(*op, i(lhs), i(rhs))
+                        }
+                        TreeOp::Ternary(op, a, b, c) => {
+                            TableOp::Ternary(*op, i(a), i(b), i(c))
+                        }
+                        TreeOp::RemapAxes { target, x, y, z } => {
+                            TableOp::RemapAxes {
+                                target: i(target),
+                                x: i(x),
+                                y: i(y),
+                                z: i(z),
+                            }
+                        }
+                        TreeOp::RemapAffine { target, mat } => {
+                            TableOp::RemapAffine {
+                                target: i(target),
+                                mat: *mat,
+                            }
+                        }
+                        TreeOp::Substitute { target, vars } => {
+                            TableOp::Substitute {
+                                target: i(target),
+                                vars: vars
+                                    .iter()
+                                    .map(|(v, t)| (*v, i(t)))
+                                    .collect(),
+                            }
+                        }
+                    };
+                    seen.insert(Arc::as_ptr(t), out.len());
+                    out.push(op);
+                }
+            }
+        }
+        Self(out)
+    }
+}
+
+impl TryFrom<TreeTable> for Tree {
+    type Error = String;
+    fn try_from(table: TreeTable) -> Result<Self, Self::Error> {
+        let mut nodes: Vec<Arc<TreeOp>> = Vec::with_capacity(table.0.len());
+        for op in table.0 {
+            let i = |c: usize| {
+                nodes.get(c).cloned().ok_or_else(|| {
+                    format!("invalid child {c} for node {}", nodes.len())
+                })
+            };
+            let t = match op {
+                TableOp::Input(v) => TreeOp::Input(v),
+                TableOp::Const(c) => TreeOp::Const(c),
+                TableOp::Unary(op, arg) => TreeOp::Unary(op, i(arg)?),
+                TableOp::Binary(op, lhs, rhs) => {
+                    TreeOp::Binary(op, i(lhs)?, i(rhs)?)
+                }
+                TableOp::Ternary(op, a, b, c) => {
+                    TreeOp::Ternary(op, i(a)?, i(b)?, i(c)?)
+                }
+                TableOp::RemapAxes { target, x, y, z } => TreeOp::RemapAxes {
+                    target: i(target)?,
+                    x: i(x)?,
+                    y: i(y)?,
+                    z: i(z)?,
+                },
+                TableOp::RemapAffine { target, mat } => TreeOp::RemapAffine {
+                    target: i(target)?,
+                    mat,
+                },
+                TableOp::Substitute { target, vars } => TreeOp::Substitute {
+                    target: i(target)?,
+                    vars: vars
+                        .into_iter()
+                        .map(|(v, t)| Ok((v, i(t)?)))
+                        .collect::<Result<_, String>>()?,
+                },
+            };
+            nodes.push(Arc::new(t));
+        }
+        nodes
+            .pop()
+            .map(Tree)
+            .ok_or_else(|| "tree must have at least one node".to_owned())
+    }
+}
+
+impl Serialize for Tree {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        TreeTable::from(self).serialize(s)
+    }
+}
+
+impl<'de> Deserialize<'de> for Tree {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let table = TreeTable::deserialize(d)?;
+        Tree::try_from(table).map_err(serde::de::Error::custom)
+    }
+}
+
 /// See [`Context`](crate::Context) for documentation of these functions
 #[allow(missing_docs)]
 impl Tree {
@@ -251,6 +584,9 @@ impl Tree {
     fn op_binary(a: Tree, b: Tree, op: BinaryOpcode) -> Self {
         Tree(Arc::new(TreeOp::Binary(op, a.0, b.0)))
     }
+    fn op_ternary(a: Tree, b: Tree, c: Tree, op: TernaryOpcode) -> Self {
+        Tree(Arc::new(TreeOp::Ternary(op, a.0, b.0, c.0)))
+    }
     pub fn square(&self) -> Self {
         Self::op_unary(self.clone(), UnaryOpcode::Square)
     }
@@ -287,9 +623,46 @@ impl Tree {
     pub fn atan2<T: Into<Tree>>(&self, other: T) -> Self {
         Self::op_binary(self.clone(), other.into(), BinaryOpcode::Atan)
     }
+    pub fn pow<T: Into<Tree>>(&self, other: T) -> Self {
+        Self::op_binary(self.clone(), other.into(), BinaryOpcode::Pow)
+    }
+    pub fn if_nonzero_else<A: Into<Tree>, B: Into<Tree>>(
+        &self,
+        a: A,
+        b: B,
+    ) -> Self {
+        Self::op_ternary(
+            self.clone(),
+            a.into(),
+            b.into(),
+            TernaryOpcode::Select,
+        )
+    }
+    pub fn smooth_min<B: Into<Tree>, K: Into<Tree>>(
+        &self,
+        other: B,
+        k: K,
+    ) -> Self {
+        Self::op_ternary(
+            self.clone(),
+            other.into(),
+            k.into(),
+            TernaryOpcode::SmoothMin,
+        )
+    }
+    pub fn smooth_max<B: Into<Tree>, K: Into<Tree>>(
+        &self,
+        other: B,
+        k: K,
+    ) -> Self {
+        self.neg().smooth_min(other.into().neg(), k).neg()
+    }
     pub fn neg(&self) -> Self {
         Self::op_unary(self.clone(), UnaryOpcode::Neg)
     }
+    pub fn recip(&self) -> Self {
+        Self::op_unary(self.clone(), UnaryOpcode::Recip)
+    }
     pub fn sin(&self) -> Self {
         Self::op_unary(self.clone(), UnaryOpcode::Sin)
     }
@@ -314,6 +687,24 @@ impl Tree {
     pub fn ln(&self) -> Self {
         Self::op_unary(self.clone(), UnaryOpcode::Ln)
     }
+    pub fn sinh(&self) -> Self {
+        Self::op_unary(self.clone(), UnaryOpcode::Sinh)
+    }
+    pub fn cosh(&self) -> Self {
+        Self::op_unary(self.clone(), UnaryOpcode::Cosh)
+    }
+    pub fn tanh(&self) -> Self {
+        Self::op_unary(self.clone(), UnaryOpcode::Tanh)
+    }
+    pub fn exp2(&self) -> Self {
+        Self::op_unary(self.clone(), UnaryOpcode::Exp2)
+    }
+    pub fn log2(&self) -> Self {
+        Self::op_unary(self.clone(), UnaryOpcode::Log2)
+    }
+    pub fn log10(&self) -> Self {
+        Self::op_unary(self.clone(), UnaryOpcode::Log10)
+    }
     pub fn not(&self) -> Self {
         Self::op_unary(self.clone(), UnaryOpcode::Not)
     }
@@ -636,4 +1027,210 @@ mod test {
         };
         assert_eq!(v, 0.0);
     }
+
+    #[test]
+    fn tree_substitute() {
+        let a = Var::new();
+        let b = Var::new();
+        let t = Tree::from(a) * 2.0 + Tree::from(b);
+
+        // Simultaneous substitution: `b -> a` isn't rewritten by `a -> x`
+        let s = t.substitute(&[(a, Tree::x()), (b, Tree::from(a))]);
+        let mut ctx = Context::new();
+        let root = ctx.import(&s);
+        let vars = [(Var::X, 3.0), (a, 5.0)].into_iter().collect();
+        assert_eq!(ctx.eval(root, &vars).unwrap(), 11.0);
+
+        // Axes can also be substituted
+        let s = (Tree::x() - Tree::y()).substitute(&[(Var::X, Tree::z())]);
+        let root = ctx.import(&s);
+        assert_eq!(ctx.eval_xyz(root, 1.0, 2.0, 7.0).unwrap(), 5.0);
+
+        // Substitution applies within remapped trees
+        let s = Tree::from(a) + Tree::x();
+        let s = s.remap_xyz(Tree::y(), Tree::x(), Tree::z());
+        let s = s.substitute(&[(a, Tree::constant(10.0))]);
+        let root = ctx.import(&s);
+        assert_eq!(ctx.eval_xyz(root, 1.0, 2.0, 3.0).unwrap(), 12.0);
+
+        // Unsubstituted variables are left alone
+        let s = t.substitute(&[(a, Tree::constant(1.0))]);
+        let root = ctx.import(&s);
+        let vars = [(b, 4.0)].into_iter().collect();
+        assert_eq!(ctx.eval(root, &vars).unwrap(), 6.0);
+
+        // Substitutions survive a serde round-trip
+        let data = bincode::serialize(&s).unwrap();
+        let u: Tree = bincode::deserialize(&data).unwrap();
+        assert_eq!(ctx.import(&u), root);
+    }
+
+    #[test]
+    fn tree_substitute_chain() {
+        let a = Var::new();
+        let b = Var::new();
+        let c = Var::new();
+        let t = Tree::from(a) + Tree::from(b) * 10.0;
+
+        // a -> b, then b -> c, then c -> x
+        let s = t.substitute(&[(a, Tree::from(b))]);
+        let s = s.substitute(&[(b, Tree::from(c))]);
+        let s = s.substitute(&[(c, Tree::x())]);
+
+        // Confirm that we didn't stack up Substitute nodes
+        let TreeOp::Substitute { target, vars } = &*s else {
+            panic!("invalid shape");
+        };
+        assert!(matches!(&**target, TreeOp::Binary(..)));
+        assert_eq!(vars.len(), 3);
+
+        let mut ctx = Context::new();
+        let root = ctx.import(&s);
+        assert_eq!(ctx.eval_xyz(root, 2.0, 0.0, 0.0).unwrap(), 22.0);
+
+        // Manually nested substitutions are collapsed in `Context::import`
+        let manual: Tree = TreeOp::Substitute {
+            target: Arc::new(TreeOp::Substitute {
+                target: t.arc().clone(),
+                vars: vec![(a, Tree::from(b).arc().clone())],
+            }),
+            vars: vec![(b, Tree::y().arc().clone())],
+        }
+        .into();
+        let root = ctx.import(&manual);
+        assert_eq!(ctx.eval_xyz(root, 0.0, 3.0, 0.0).unwrap(), 33.0);
+
+        // Long chains don't blow up the stack
+        let mut s = Tree::from(a);
+        for _ in 0..10_000 {
+            s = (s + 1.0).substitute(&[(a, Tree::from(a) * 1.0)]);
+        }
+        let s = s.substitute(&[(a, Tree::constant(1.0))]);
+        let root = ctx.import(&s);
+        assert_eq!(ctx.get_const(root).unwrap(), 10_001.0);
+        drop(s);
+    }
+
+    #[test]
+    fn tree_specialize() {
+        let (x, y, z) = Tree::axes();
+        let r = Var::new();
+        let sphere = (x.square() + y.square() + z.square()).sqrt() - r;
+        let dx = x.clone() - 10.0;
+        let cube = dx.abs().max(y.abs()).max(z.abs()) - 1.0;
+        let t = sphere.min(cube.clone());
+        let vars = [(r, Interval::new(0.5, 1.0))].into_iter().collect();
+
+        // Near the origin, only the sphere remains
+        let region = [Interval::new(-1.0, 1.0); 3];
+        let s = t.specialize(region, &vars).unwrap();
+        let mut ctx = Context::new();
+        let a = ctx.import(&s);
+        let b = ctx.import(&sphere);
+        let count = |ctx: &Context, n| ctx.reachable_nodes(&[n]).unwrap().len();
+        assert_eq!(count(&ctx, a), count(&ctx, b));
+        for (x, y, z, r_) in [(0.5, 0.25, -1.0, 0.5), (-1.0, 1.0, 0.0, 0.75)] {
+            let vars = [(Var::X, x), (Var::Y, y), (Var::Z, z), (r, r_)].into();
+            assert_eq!(
+                ctx.eval(a, &vars).unwrap(),
+                ctx.eval(b, &vars).unwrap()
+            );
+        }
+
+        // Far along the X axis, only one face of the cube remains
+        let region = [
+            Interval::new(12.0, 13.0),
+            Interval::new(-1.0, 1.0),
+            Interval::new(-1.0, 1.0),
+        ];
+        let s = cube.specialize(region, &vars).unwrap();
+        let a = ctx.import(&s);
+        let b = ctx.import(&(dx.abs() - 1.0));
+        assert_eq!(count(&ctx, a), count(&ctx, b));
+        assert_eq!(
+            ctx.eval_xyz(a, 12.5, 0.0, 0.0).unwrap(),
+            ctx.eval_xyz(b, 12.5, 0.0, 0.0).unwrap()
+        );
+
+        // Missing variables are an error
+        assert!(matches!(
+            t.specialize(region, &HashMap::new()),
+            Err(Error::MissingVar(v)) if v == r
+        ));
+    }
+
+    #[test]
+    fn tree_serde() {
+        let (x, y, z) = Tree::axes();
+        let shared = (x.square() + y.square()).sqrt();
+        let t = shared.max(z.clone()) - shared.smooth_min(z.clone(), 0.5);
+        let t = t.remap_xyz(y.clone(), x.clone() * 2.0, z + 1.0);
+        let t = t.remap_affine(nalgebra::convert(nalgebra::Translation3::new(
+            1.0, 2.0, 3.0,
+        )));
+        let t = t + Tree::from(Var::new()) * f64::NAN;
+
+        let data = bincode::serialize(&t).unwrap();
+        let u: Tree = bincode::deserialize(&data).unwrap();
+        let mut ctx = Context::new();
+        assert_eq!(ctx.import(&t), ctx.import(&u));
+
+        // The shared subtree should only be stored once
+        let table = TreeTable::from(&t);
+        let sqrt_count = table
+            .0
+            .iter()
+            .filter(|op| matches!(op, TableOp::Unary(UnaryOpcode::Sqrt, ..)))
+            .count();
+        assert_eq!(sqrt_count, 1);
+
+        // ... and should still be shared after deserialization
+        let u: Tree = bincode::deserialize(&data).unwrap();
+        let TreeOp::Binary(BinaryOpcode::Add, lhs, _) = &*u else {
+            panic!("invalid tree {u:?}");
+        };
+        let TreeOp::RemapAffine { target, .. } = &**lhs else {
+            panic!("invalid tree {lhs:?}");
+        };
+        let TreeOp::RemapAxes { target, .. } = &**target else {
+            panic!("invalid tree {target:?}");
+        };
+        let TreeOp::Binary(BinaryOpcode::Sub, a, b) = &**target else {
+            panic!("invalid tree {target:?}");
+        };
+        let (TreeOp::Binary(_, a, _), TreeOp::Ternary(_, b, _, _)) =
+            (&**a, &**b)
+        else {
+            panic!("invalid tree {target:?}");
+        };
+        assert!(Arc::ptr_eq(a, b));
+    }
+
+    #[test]
+    fn tree_serde_invalid() {
+        // Forward references are not allowed
+        let table = TreeTable(vec![
+            TableOp::Unary(UnaryOpcode::Neg, 1),
+            TableOp::Input(Var::X),
+        ]);
+        let data = bincode::serialize(&table).unwrap();
+        assert!(bincode::deserialize::<Tree>(&data).is_err());
+
+        let table = TreeTable(vec![]);
+        let data = bincode::serialize(&table).unwrap();
+        assert!(bincode::deserialize::<Tree>(&data).is_err());
+    }
+
+    #[test]
+    fn deep_recursion_serde() {
+        let mut x = Tree::x();
+        for _ in 0..1_000_000 {
+            x += 1.0;
+        }
+        let data = bincode::serialize(&x).unwrap();
+        drop(x);
+        let x: Tree = bincode::deserialize(&data).unwrap();
+        drop(x);
+        // we should not panic here!
+    }
 }
@@ -1,7 +1,7 @@
 //! Traits and data structures for function evaluation
 use crate::{
     context::{Context, Node},
-    types::{Grad, Interval},
+    types::{Grad, GradN, Interval},
     var::VarMap,
     Error,
 };
@@ -200,3 +200,31 @@ pub trait MathFunction: Function {
     where
         Self: Sized;
 }
+
+/// A [`Function`] which can evaluate `N`-dimensional gradients
+///
+/// Unlike [`Function::GradSliceEval`], partial derivatives are not tied to
+/// `x`, `y`, and `z`; the caller seeds inputs (e.g. with [`GradN::input`]) to
+/// pick which variables are differentiated.
+///
+/// Only the VM backend implements this trait, evaluating all `N` partial
+/// derivatives in a single pass; the JIT backend does not support [`GradN`]
+/// evaluation.
+pub trait GradNFunction: Function {
+    /// Associated type for evaluating many `N`-dimensional gradients in one
+    /// call
+    type GradNSliceEval<const N: usize>: BulkEvaluator<Data = GradN<N>, TapeStorage = Self::TapeStorage>
+        + Send
+        + Sync;
+
+    /// Builds a new `N`-dimensional gradient slice evaluator
+    fn new_grad_n_slice_eval<const N: usize>() -> Self::GradNSliceEval<N> {
+        Self::GradNSliceEval::<N>::new()
+    }
+
+    /// Returns an evaluation tape for an `N`-dimensional gradient evaluator
+    fn grad_n_slice_tape<const N: usize>(
+        &self,
+        storage: Self::TapeStorage,
+    ) -> <Self::GradNSliceEval<N> as BulkEvaluator>::Tape;
+}
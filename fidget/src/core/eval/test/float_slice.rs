@@ -108,6 +108,82 @@ impl<F: Function + MathFunction> TestFloatSlice<F> {
         );
     }
 
+    pub fn test_f_powi() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let args = [-2.5f32, -1.0, -0.5, 0.25, 1.5, 3.0, 0.0, 2.0, -3.0];
+        for n in [-4, -3, -2, 3, 4, 5, 16] {
+            let node = ctx.pow(x, n as f32).unwrap();
+            let shape = F::new(&ctx, &[node]).unwrap();
+            let mut eval = F::new_float_slice_eval();
+            let tape = shape.float_slice_tape(Default::default());
+            let out = eval.eval(&tape, &[args.as_slice()]).unwrap();
+            for (v, o) in args.iter().zip(out[0].iter()) {
+                let expected = v.powi(n);
+                assert!(
+                    o == &expected
+                        || (o - expected).abs() <= expected.abs() * 1e-6,
+                    "pow({v}, {n}) = {o} != {expected}"
+                );
+            }
+        }
+    }
+
+    pub fn test_f_select() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let s = ctx.if_nonzero_else(x, y, z).unwrap();
+
+        let x = [0.0, 1.0, -1.0, f32::NAN, 0.0, 2.0, -0.0, 0.0, 3.0];
+        let y = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0];
+        let z = [-1.0, -2.0, -3.0, -4.0, -5.0, -6.0, -7.0, -8.0, -9.0];
+
+        let shape = F::new(&ctx, &[s]).unwrap();
+        let mut eval = F::new_float_slice_eval();
+        let tape = shape.float_slice_tape(Default::default());
+        let vs = bind_xyz::<_, &[f32], &[f32]>(&tape);
+        let out = eval.eval(&tape, &vs(&x, &y, &z)).unwrap();
+        assert_eq!(out[0], [-1.0, 2.0, 3.0, 4.0, -5.0, 6.0, -7.0, -8.0, 9.0]);
+    }
+
+    pub fn test_f_smooth_min() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let s = ctx.smooth_min(x, y, z).unwrap();
+
+        let x = [0.0, 2.0, 0.0, 0.0, 0.0, f32::NAN, 0.0, 1.0, -1.0];
+        let y = [2.0, 0.0, 0.5, 0.5, 0.0, 0.0, 0.0, 1.0, 1.0];
+        let z = [1.0, 1.0, 1.0, 0.0, 0.5, 1.0, f32::NAN, -1.0, 4.0];
+
+        let shape = F::new(&ctx, &[s]).unwrap();
+        let mut eval = F::new_float_slice_eval();
+        let tape = shape.float_slice_tape(Default::default());
+        let vs = bind_xyz::<_, &[f32], &[f32]>(&tape);
+        let out = eval.eval(&tape, &vs(&x, &y, &z)).unwrap();
+        let expected = [
+            0.0,
+            0.0,
+            -0.0625,
+            0.0,
+            -0.125,
+            f32::NAN,
+            f32::NAN,
+            1.0,
+            -1.25,
+        ];
+        for (a, b) in out[0].iter().zip(expected) {
+            if b.is_nan() {
+                assert!(a.is_nan());
+            } else {
+                assert_eq!(*a, b);
+            }
+        }
+    }
+
     pub fn test_f_shape_var() {
         let v = Var::new();
         let mut ctx = Context::new();
@@ -381,6 +457,9 @@ macro_rules! float_slice_tests {
         $crate::float_slice_test!(test_give_take, $t);
         $crate::float_slice_test!(test_vectorized, $t);
         $crate::float_slice_test!(test_f_sin, $t);
+        $crate::float_slice_test!(test_f_powi, $t);
+        $crate::float_slice_test!(test_f_select, $t);
+        $crate::float_slice_test!(test_f_smooth_min, $t);
         $crate::float_slice_test!(test_f_shape_var, $t);
         $crate::float_slice_test!(test_f_stress, $t);
 
@@ -0,0 +1,266 @@
+//! Test suite for `N`-dimensional partial derivative evaluation
+//!
+//! If the `eval-tests` feature is set, then this exposes a standard test suite
+//! for `N`-dimensional gradient evaluators; otherwise, the module has no public
+//! exports.
+//!
+//! Most tests compare against the function's three-partial gradient evaluator,
+//! which is itself tested against numerical derivatives.
+use super::{build_stress_fn, test_args, CanonicalBinaryOp, CanonicalUnaryOp};
+use crate::{
+    context::{Context, Node},
+    eval::{BulkEvaluator, GradNFunction, MathFunction, Tape},
+    types::{Grad, GradN},
+    var::Var,
+};
+
+/// Number of partial derivatives used in tests
+const N: usize = 5;
+
+/// Helper struct to put constrains on our `Shape` object
+pub struct TestGradNSlice<F>(std::marker::PhantomData<*const F>);
+
+impl<F: GradNFunction + MathFunction> TestGradNSlice<F> {
+    /// Evaluates `N`-dimensional gradients, with the given inputs
+    fn eval_grad_n<const M: usize>(
+        f: &F,
+        inputs: &[(Var, Vec<GradN<M>>)],
+    ) -> Vec<GradN<M>> {
+        let tape = f.grad_n_slice_tape::<M>(Default::default());
+        let size = inputs.first().map(|(_, v)| v.len()).unwrap_or(0);
+        let mut vars = vec![vec![GradN::from(0.0); size]; tape.vars().len()];
+        for (v, data) in inputs {
+            if let Some(i) = tape.vars().get(v) {
+                vars[i].clone_from(data);
+            }
+        }
+        let mut eval = F::new_grad_n_slice_eval::<M>();
+        eval.eval(&tape, &vars).unwrap()[0].to_owned()
+    }
+
+    /// Evaluates three-partial gradients, with the given inputs
+    fn eval_grad(f: &F, inputs: &[(Var, Vec<Grad>)]) -> Vec<Grad> {
+        let tape = f.grad_slice_tape(Default::default());
+        let size = inputs.first().map(|(_, v)| v.len()).unwrap_or(0);
+        let mut vars = vec![vec![Grad::from(0.0); size]; tape.vars().len()];
+        for (v, data) in inputs {
+            if let Some(i) = tape.vars().get(v) {
+                vars[i].clone_from(data);
+            }
+        }
+        let mut eval = F::new_grad_slice_eval();
+        eval.eval(&tape, &vars).unwrap()[0].to_owned()
+    }
+
+    /// Compares `N`-dimensional and three-partial gradients of a function
+    ///
+    /// Input `k` is seeded with partial derivatives `k` and `k + 3` (if
+    /// present), so the extra partials should duplicate the first two.
+    fn compare(
+        ctx: &Context,
+        node: Node,
+        inputs: &[(Var, &[f32])],
+        name: &str,
+    ) {
+        assert!(inputs.len() <= 3);
+        let f = F::new(ctx, &[node]).unwrap();
+
+        let grad_n = inputs
+            .iter()
+            .enumerate()
+            .map(|(k, (v, data))| {
+                let data = data
+                    .iter()
+                    .map(|&x| {
+                        let mut g = GradN::<N>::input(x, k);
+                        if let Some(d) = g.d.get_mut(k + 3) {
+                            *d = 1.0;
+                        }
+                        g
+                    })
+                    .collect();
+                (*v, data)
+            })
+            .collect::<Vec<_>>();
+        let grad = inputs
+            .iter()
+            .enumerate()
+            .map(|(k, (v, data))| {
+                let data = data
+                    .iter()
+                    .map(|&x| {
+                        let mut d = [0.0; 3];
+                        d[k] = 1.0;
+                        Grad::new(x, d[0], d[1], d[2])
+                    })
+                    .collect();
+                (*v, data)
+            })
+            .collect::<Vec<_>>();
+
+        let out_n = Self::eval_grad_n(&f, &grad_n);
+        let out = Self::eval_grad(&f, &grad);
+        assert_eq!(out_n.len(), out.len());
+
+        let same = |a: f32, b: f32| {
+            a == b
+                || (a.is_nan() && b.is_nan())
+                || (a - b).abs() <= 1e-6 * a.abs().max(b.abs()).max(1.0)
+        };
+        for (i, (a, b)) in out_n.iter().zip(&out).enumerate() {
+            let args = inputs.iter().map(|(_, d)| d[i]).collect::<Vec<_>>();
+            assert!(
+                same(a.v, b.v)
+                    && same(a.d[0], b.dx)
+                    && same(a.d[1], b.dy)
+                    && same(a.d[2], b.dz)
+                    && same(a.d[3], b.dx)
+                    && same(a.d[4], b.dy),
+                "mismatch in '{name}' at {args:?}: {a} != {b}"
+            );
+        }
+    }
+
+    pub fn test_gn_stress() {
+        for depth in [1, 4, 16, 32] {
+            let (ctx, node) = build_stress_fn(depth);
+            let args = (0..32).map(|i| i as f32 / 32f32).collect::<Vec<f32>>();
+            let y: Vec<f32> =
+                args[1..].iter().chain(&args[0..1]).cloned().collect();
+            let z: Vec<f32> =
+                args[2..].iter().chain(&args[0..2]).cloned().collect();
+            Self::compare(
+                &ctx,
+                node,
+                &[(Var::X, &args), (Var::Y, &y), (Var::Z, &z)],
+                "stress",
+            );
+        }
+    }
+
+    pub fn test_gn_params() {
+        // Partial derivatives with respect to three axes and four parameters,
+        // computed in a single evaluation
+        let mut ctx = Context::new();
+        let params = [Var::new(), Var::new(), Var::new(), Var::new()];
+        let axes = [Var::X, Var::Y, Var::Z];
+        let mut sum = ctx.var(params[3]);
+        for (a, p) in axes.iter().zip(&params) {
+            let a = ctx.var(*a);
+            let p = ctx.var(*p);
+            let t = ctx.mul(a, p).unwrap();
+            sum = ctx.add(sum, t).unwrap();
+        }
+        let f = F::new(&ctx, &[sum]).unwrap();
+
+        let values = [2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0];
+        let inputs = axes
+            .iter()
+            .chain(&params)
+            .zip(values)
+            .enumerate()
+            .map(|(i, (v, x))| (*v, vec![GradN::<7>::input(x, i)]))
+            .collect::<Vec<_>>();
+        let out = Self::eval_grad_n(&f, &inputs);
+        assert_eq!(
+            out[0],
+            GradN::new(
+                2.0 * 5.0 + 3.0 * 6.0 + 4.0 * 7.0 + 8.0,
+                [5.0, 6.0, 7.0, 2.0, 3.0, 4.0, 1.0]
+            )
+        );
+
+        // Unseeded inputs contribute no partial derivatives
+        let inputs = axes
+            .iter()
+            .chain(&params)
+            .zip(values)
+            .map(|(v, x)| (*v, vec![GradN::<2>::from(x)]))
+            .collect::<Vec<_>>();
+        let out = Self::eval_grad_n(&f, &inputs);
+        assert_eq!(out[0], GradN::new(64.0, [0.0, 0.0]));
+    }
+
+    pub fn test_gn_zero() {
+        // Zero-dimensional gradients still compute values
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let s = ctx.sin(x).unwrap();
+        let f = F::new(&ctx, &[s]).unwrap();
+        let out =
+            Self::eval_grad_n(&f, &[(Var::X, vec![GradN::<0>::from(2.0)])]);
+        assert_eq!(out[0].v, 2f32.sin());
+    }
+
+    pub fn test_unary<C: CanonicalUnaryOp>() {
+        let args = test_args();
+
+        let mut ctx = Context::new();
+        let v = Var::new();
+        let node = ctx.var(v);
+        let node = C::build(&mut ctx, node);
+        Self::compare(&ctx, node, &[(v, &args)], C::NAME);
+    }
+
+    pub fn test_binary<C: CanonicalBinaryOp>() {
+        let args = test_args();
+        let mut ctx = Context::new();
+        let va = Var::new();
+        let vb = Var::new();
+        let a = ctx.var(va);
+        let b = ctx.var(vb);
+
+        let name = format!("{}(reg, reg)", C::NAME);
+        for rot in 0..args.len() {
+            let mut rgsa = args.clone();
+            rgsa.rotate_left(rot);
+            let node = C::build(&mut ctx, a, b);
+            Self::compare(&ctx, node, &[(va, &args), (vb, &rgsa)], &name);
+        }
+        let node = C::build(&mut ctx, a, a);
+        Self::compare(&ctx, node, &[(va, &args)], &name);
+
+        for &imm in &args {
+            let node = C::build(&mut ctx, a, imm);
+            let name = format!("{}(reg, imm)", C::NAME);
+            Self::compare(&ctx, node, &[(va, &args)], &name);
+
+            let node = C::build(&mut ctx, imm, a);
+            let name = format!("{}(imm, reg)", C::NAME);
+            Self::compare(&ctx, node, &[(va, &args)], &name);
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! grad_n_test {
+    ($i:ident, $t:ty) => {
+        #[test]
+        fn $i() {
+            $crate::eval::test::grad_n_slice::TestGradNSlice::<$t>::$i()
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! grad_n_slice_tests {
+    ($t:ty) => {
+        $crate::grad_n_test!(test_gn_stress, $t);
+        $crate::grad_n_test!(test_gn_params, $t);
+        $crate::grad_n_test!(test_gn_zero, $t);
+
+        mod gn_unary {
+            use super::*;
+            $crate::all_unary_tests!(
+                $crate::eval::test::grad_n_slice::TestGradNSlice::<$t>
+            );
+        }
+
+        mod gn_binary {
+            use super::*;
+            $crate::all_binary_tests!(
+                $crate::eval::test::grad_n_slice::TestGradNSlice::<$t>
+            );
+        }
+    };
+}
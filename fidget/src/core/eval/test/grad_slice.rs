@@ -171,6 +171,32 @@ impl<F: Function + MathFunction> TestGradSlice<F> {
         v[2].compare_eq(Grad::new(6f32.sin(), 0.0, 2.0 * 6f32.cos(), 0.0));
     }
 
+    pub fn test_g_powi() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let s = ctx.add(x, y).unwrap();
+        for n in [-4, -3, -2, 3, 4, 5, 16] {
+            let node = ctx.pow(s, n as f32).unwrap();
+            let shape = F::new(&ctx, &[node]).unwrap();
+            let tape = shape.grad_slice_tape(Default::default());
+            let xs = [-2.5, -1.0, -0.5, 0.25, 1.5, 3.0];
+            let v = Self::eval_xyz(&tape, &xs, &[0.0; 6], &[0.0; 6]);
+            for (x, v) in xs.iter().zip(v) {
+                let d = n as f32 * x.powi(n - 1);
+                let expected = Grad::new(x.powi(n), d, d, 0.0);
+                let scale = expected.v.abs().max(d.abs());
+                assert!(
+                    (v.v - expected.v).abs() <= scale * 1e-6
+                        && (v.dx - expected.dx).abs() <= scale * 1e-6
+                        && (v.dy - expected.dy).abs() <= scale * 1e-6
+                        && v.dz == 0.0,
+                    "pow({x}, {n}) = {v} != {expected}"
+                );
+            }
+        }
+    }
+
     pub fn test_g_mul() {
         let mut ctx = Context::new();
         let x = ctx.x();
@@ -287,6 +313,82 @@ impl<F: Function + MathFunction> TestGradSlice<F> {
         );
     }
 
+    pub fn test_g_select() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let s = ctx.if_nonzero_else(x, y, z).unwrap();
+        let shape = F::new(&ctx, &[s]).unwrap();
+
+        let tape = shape.grad_slice_tape(Default::default());
+        assert_eq!(
+            Self::eval_xyz(&tape, &[1.0], &[3.0], &[4.0])[0],
+            Grad::new(3.0, 0.0, 1.0, 0.0)
+        );
+        assert_eq!(
+            Self::eval_xyz(&tape, &[0.0], &[3.0], &[4.0])[0],
+            Grad::new(4.0, 0.0, 0.0, 1.0)
+        );
+
+        let s = ctx.if_nonzero_else(x, 2.0, z).unwrap();
+        let shape = F::new(&ctx, &[s]).unwrap();
+        let tape = shape.grad_slice_tape(Default::default());
+        assert_eq!(
+            Self::eval_xyz(&tape, &[-1.0], &[0.0], &[4.0])[0],
+            Grad::new(2.0, 0.0, 0.0, 0.0)
+        );
+        assert_eq!(
+            Self::eval_xyz(&tape, &[0.0], &[0.0], &[4.0])[0],
+            Grad::new(4.0, 0.0, 0.0, 1.0)
+        );
+    }
+
+    pub fn test_g_smooth_min() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let s = ctx.smooth_min(x, y, z).unwrap();
+        let shape = F::new(&ctx, &[s]).unwrap();
+
+        let tape = shape.grad_slice_tape(Default::default());
+        assert_eq!(
+            Self::eval_xyz(&tape, &[0.0], &[2.0], &[1.0])[0],
+            Grad::new(0.0, 1.0, 0.0, 0.0)
+        );
+        assert_eq!(
+            Self::eval_xyz(&tape, &[2.0], &[0.0], &[1.0])[0],
+            Grad::new(0.0, 0.0, 1.0, 0.0)
+        );
+        assert_eq!(
+            Self::eval_xyz(&tape, &[0.0], &[0.5], &[1.0])[0],
+            Grad::new(-0.0625, 0.75, 0.25, -0.1875)
+        );
+        assert_eq!(
+            Self::eval_xyz(&tape, &[0.5], &[0.0], &[1.0])[0],
+            Grad::new(-0.0625, 0.25, 0.75, -0.1875)
+        );
+        assert!(Self::eval_xyz(&tape, &[f32::NAN], &[0.0], &[1.0])[0]
+            .v
+            .is_nan());
+        assert!(Self::eval_xyz(&tape, &[0.0], &[0.0], &[f32::NAN])[0]
+            .v
+            .is_nan());
+
+        let s = ctx.smooth_min(x, 1.0, 0.5).unwrap();
+        let shape = F::new(&ctx, &[s]).unwrap();
+        let tape = shape.grad_slice_tape(Default::default());
+        assert_eq!(
+            Self::eval_xyz(&tape, &[0.75], &[0.0], &[0.0])[0],
+            Grad::new(0.71875, 0.75, 0.0, 0.0)
+        );
+        assert_eq!(
+            Self::eval_xyz(&tape, &[2.0], &[0.0], &[0.0])[0],
+            Grad::new(1.0, 0.0, 0.0, 0.0)
+        );
+    }
+
     pub fn test_g_not() {
         let mut ctx = Context::new();
         let x = ctx.x();
@@ -703,10 +805,13 @@ macro_rules! grad_slice_tests {
         $crate::grad_test!(test_g_square, $t);
         $crate::grad_test!(test_g_sqrt, $t);
         $crate::grad_test!(test_g_sin, $t);
+        $crate::grad_test!(test_g_powi, $t);
         $crate::grad_test!(test_g_mul, $t);
         $crate::grad_test!(test_g_min, $t);
         $crate::grad_test!(test_g_max, $t);
         $crate::grad_test!(test_g_min_max, $t);
+        $crate::grad_test!(test_g_select, $t);
+        $crate::grad_test!(test_g_smooth_min, $t);
         $crate::grad_test!(test_g_not, $t);
         $crate::grad_test!(test_g_div, $t);
         $crate::grad_test!(test_g_recip, $t);
@@ -726,6 +726,102 @@ where
         assert_eq!(out[0], Interval::new(0.25, 0.5));
     }
 
+    pub fn test_i_pow() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let out = ctx.pow(x, y).unwrap();
+
+        let shape = F::new(&ctx, &[out]).unwrap();
+        let tape = shape.interval_tape(Default::default());
+        let vs = bind_xy(&tape);
+        let mut eval = F::new_interval_eval();
+
+        let (out, _trace) =
+            eval.eval(&tape, &vs([2.0, 3.0], [1.0, 2.0])).unwrap();
+        assert_eq!(out[0], Interval::new(2.0, 9.0));
+
+        let (out, _trace) =
+            eval.eval(&tape, &vs([0.25, 4.0], [0.5, 0.5])).unwrap();
+        assert_eq!(out[0], Interval::new(0.5, 2.0));
+
+        // Integer exponents are valid for negative bases
+        let (out, _trace) =
+            eval.eval(&tape, &vs([-2.0, 1.0], [4.0, 4.0])).unwrap();
+        assert_eq!(out[0], Interval::new(0.0, 16.0));
+
+        let (out, _trace) =
+            eval.eval(&tape, &vs([-2.0, 1.0], [3.0, 3.0])).unwrap();
+        assert_eq!(out[0], Interval::new(-8.0, 1.0));
+
+        let (out, _trace) =
+            eval.eval(&tape, &vs([-4.0, -2.0], [-2.0, -2.0])).unwrap();
+        assert_eq!(out[0], Interval::new(0.0625, 0.25));
+
+        // Fractional exponents of negative bases are NaN
+        let (out, _trace) =
+            eval.eval(&tape, &vs([-2.0, 1.0], [0.5, 0.5])).unwrap();
+        assert!(out[0].has_nan());
+
+        // Negative exponents of intervals that contain zero are NaN
+        let (out, _trace) =
+            eval.eval(&tape, &vs([-1.0, 1.0], [-3.0, -3.0])).unwrap();
+        assert!(out[0].has_nan());
+    }
+
+    pub fn test_i_cosh() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let cosh_x = ctx.cosh(x).unwrap();
+
+        let shape = F::new(&ctx, &[cosh_x]).unwrap();
+        let tape = shape.interval_tape(Default::default());
+        let mut eval = F::new_interval_eval();
+        let mut check = |i: [f32; 2], lower: f32, upper: f32| {
+            let out = eval.eval(&tape, &[i.into()]).unwrap().0[0];
+            assert!(
+                (out.lower() - lower).abs() < 1e-6
+                    && (out.upper() - upper).abs() < 1e-6,
+                "cosh({i:?}) = {out:?}, expected [{lower}, {upper}]"
+            );
+        };
+        check([1.0, 2.0], 1.0f32.cosh(), 2.0f32.cosh());
+        check([-2.0, -1.0], 1.0f32.cosh(), 2.0f32.cosh());
+
+        // The minimum is at zero
+        check([-1.0, 3.0], 1.0, 3.0f32.cosh());
+        check([-3.0, 1.0], 1.0, 3.0f32.cosh());
+    }
+
+    pub fn test_i_powi() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let cases = [
+            (3, [-2.0, 1.0], Interval::new(-8.0, 1.0)),
+            (3, [-3.0, -2.0], Interval::new(-27.0, -8.0)),
+            (4, [-2.0, 1.0], Interval::new(0.0, 16.0)),
+            (4, [1.0, 2.0], Interval::new(1.0, 16.0)),
+            (-2, [1.0, 2.0], Interval::new(0.25, 1.0)),
+            (-3, [-2.0, -1.0], Interval::new(-1.0, -0.125)),
+        ];
+        for (n, i, expected) in cases {
+            let node = ctx.pow(x, n as f32).unwrap();
+            let shape = F::new(&ctx, &[node]).unwrap();
+            let tape = shape.interval_tape(Default::default());
+            let mut eval = F::new_interval_eval();
+            let (out, _trace) = eval.eval(&tape, &[i.into()]).unwrap();
+            assert_eq!(out[0], expected, "pow({i:?}, {n})");
+        }
+
+        // Negative powers of intervals containing zero are NaN
+        let node = ctx.pow(x, -3.0).unwrap();
+        let shape = F::new(&ctx, &[node]).unwrap();
+        let tape = shape.interval_tape(Default::default());
+        let mut eval = F::new_interval_eval();
+        let (out, _trace) = eval.eval(&tape, &[[-1.0, 1.0].into()]).unwrap();
+        assert!(out[0].has_nan());
+    }
+
     pub fn test_i_simplify() {
         let mut ctx = Context::new();
         let x = ctx.x();
@@ -779,9 +875,8 @@ where
             .eval(&tape, &vs([-1.0, 2.0], [1.0, 2.0], [3.0, 4.0]))
             .unwrap();
 
-        // Alas, we lose the information that the conditional is correlated, so
-        // the interval naively must include 0
-        assert_eq!(out[0], [0.0, 4.0].into());
+        // The condition is ambiguous, so the result covers both branches
+        assert_eq!(out[0], [1.0, 4.0].into());
         assert!(data.is_none());
 
         // Confirm that simplification of the right side works
@@ -827,6 +922,183 @@ where
         assert_eq!(s_y.size(), s_z.size())
     }
 
+    pub fn test_i_select() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+
+        let if_else = ctx.if_nonzero_else(x, y, z).unwrap();
+        let shape = F::new(&ctx, &[if_else]).unwrap();
+        let tape = shape.interval_tape(Default::default());
+        let vs = bind_xyz(&tape);
+        let mut eval = F::new_interval_eval();
+
+        let (out, data) = eval
+            .eval(&tape, &vs([1.0, 2.0], [1.0, 2.0], [3.0, 4.0]))
+            .unwrap();
+        assert_eq!(out[0], [1.0, 2.0].into());
+        assert_eq!(data.unwrap().as_ref(), &[Choice::Left]);
+
+        let (out, data) = eval
+            .eval(&tape, &vs([-2.0, -1.0], [1.0, 2.0], [3.0, 4.0]))
+            .unwrap();
+        assert_eq!(out[0], [1.0, 2.0].into());
+        assert_eq!(data.unwrap().as_ref(), &[Choice::Left]);
+
+        let (out, data) = eval
+            .eval(&tape, &vs([0.0, 0.0], [1.0, 2.0], [3.0, 4.0]))
+            .unwrap();
+        assert_eq!(out[0], [3.0, 4.0].into());
+        assert_eq!(data.unwrap().as_ref(), &[Choice::Right]);
+
+        let (out, data) = eval
+            .eval(&tape, &vs([0.0, 1.0], [1.0, 2.0], [3.0, 4.0]))
+            .unwrap();
+        assert_eq!(out[0], [1.0, 4.0].into());
+        assert!(data.is_none());
+
+        let (out, data) = eval
+            .eval(&tape, &vs([f32::NAN; 2], [1.0, 2.0], [3.0, 4.0]))
+            .unwrap();
+        assert!(out[0].has_nan());
+        assert!(data.is_none());
+
+        let (out, data) = eval
+            .eval(&tape, &vs([0.0, 1.0], [f32::NAN; 2], [3.0, 4.0]))
+            .unwrap();
+        assert!(out[0].has_nan());
+        assert!(data.is_none());
+
+        // Immediate branches
+        let if_else = ctx.if_nonzero_else(x, 1.0, -1.0).unwrap();
+        let shape = F::new(&ctx, &[if_else]).unwrap();
+        let tape = shape.interval_tape(Default::default());
+        let mut eval = F::new_interval_eval();
+        let (out, data) = eval.eval(&tape, &[[2.0, 3.0].into()]).unwrap();
+        assert_eq!(out[0], [1.0, 1.0].into());
+        assert_eq!(data.unwrap().as_ref(), &[Choice::Left]);
+
+        let (out, data) = eval.eval(&tape, &[[0.0, 0.0].into()]).unwrap();
+        assert_eq!(out[0], [-1.0, -1.0].into());
+        assert_eq!(data.unwrap().as_ref(), &[Choice::Right]);
+
+        let (out, data) = eval.eval(&tape, &[[-1.0, 1.0].into()]).unwrap();
+        assert_eq!(out[0], [-1.0, 1.0].into());
+        assert!(data.is_none());
+
+        let (_out, data) = eval.eval(&tape, &[[2.0, 3.0].into()]).unwrap();
+        let next = shape
+            .simplify(
+                data.expect("must have trace"),
+                Default::default(),
+                &mut Default::default(),
+            )
+            .unwrap();
+        assert!(next.size() < shape.size());
+        let tape = next.interval_tape(Default::default());
+        let (out, data) = eval.eval(&tape, &[[-1.0, 1.0].into()]).unwrap();
+        assert_eq!(out[0], [1.0, 1.0].into());
+        assert!(data.is_none());
+    }
+
+    pub fn test_i_smooth_min() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+
+        let m = ctx.smooth_min(x, y, z).unwrap();
+        let shape = F::new(&ctx, &[m]).unwrap();
+        let tape = shape.interval_tape(Default::default());
+        let vs = bind_xyz(&tape);
+        let mut eval = F::new_interval_eval();
+
+        let (out, data) = eval
+            .eval(&tape, &vs([0.0, 1.0], [2.0, 3.0], [0.5, 0.5]))
+            .unwrap();
+        assert_eq!(out[0], [0.0, 1.0].into());
+        assert_eq!(data.unwrap().as_ref(), &[Choice::Left]);
+
+        let (out, data) = eval
+            .eval(&tape, &vs([2.0, 3.0], [0.0, 1.0], [0.5, 0.5]))
+            .unwrap();
+        assert_eq!(out[0], [0.0, 1.0].into());
+        assert_eq!(data.unwrap().as_ref(), &[Choice::Right]);
+
+        // The blend radius keeps the ranges from being separable
+        let (out, data) = eval
+            .eval(&tape, &vs([0.0, 1.0], [2.0, 3.0], [0.5, 1.5]))
+            .unwrap();
+        assert_eq!(out[0], [-0.375, 1.0].into());
+        assert!(data.is_none());
+
+        let (out, data) = eval
+            .eval(&tape, &vs([0.0, 1.0], [0.5, 2.0], [1.0, 1.0]))
+            .unwrap();
+        assert_eq!(out[0], [-0.25, 1.0].into());
+        assert!(data.is_none());
+
+        let (out, data) = eval
+            .eval(&tape, &vs([0.0, 1.0], [2.0, 3.0], [f32::NAN; 2]))
+            .unwrap();
+        assert!(out[0].has_nan());
+        assert!(data.is_none());
+
+        let (out, data) = eval
+            .eval(&tape, &vs([f32::NAN; 2], [2.0, 3.0], [0.5, 0.5]))
+            .unwrap();
+        assert!(out[0].has_nan());
+        assert!(data.is_none());
+
+        // Simplify to the left branch, then check that the blend is gone
+        let (_out, data) = eval
+            .eval(&tape, &vs([0.0, 1.0], [2.0, 3.0], [0.5, 0.5]))
+            .unwrap();
+        let next = shape
+            .simplify(
+                data.expect("must have trace"),
+                Default::default(),
+                &mut Default::default(),
+            )
+            .unwrap();
+        assert!(next.size() < shape.size());
+        let tape = next.interval_tape(Default::default());
+        let (out, data) = eval
+            .eval(&tape, &vs([5.0, 6.0], [2.0, 3.0], [0.5, 0.5]))
+            .unwrap();
+        assert_eq!(out[0], [5.0, 6.0].into());
+        assert!(data.is_none());
+
+        // Immediate arguments
+        let m = ctx.smooth_min(x, 1.0, 0.5).unwrap();
+        let shape = F::new(&ctx, &[m]).unwrap();
+        let tape = shape.interval_tape(Default::default());
+        let mut eval = F::new_interval_eval();
+        let (out, data) = eval.eval(&tape, &[[-1.0, 0.0].into()]).unwrap();
+        assert_eq!(out[0], [-1.0, 0.0].into());
+        assert!(data.is_some());
+
+        let (out, data) = eval.eval(&tape, &[[0.0, 2.0].into()]).unwrap();
+        assert_eq!(out[0], [-0.125, 1.0].into());
+        assert!(data.is_none());
+
+        let (out, data) = eval.eval(&tape, &[[2.0, 3.0].into()]).unwrap();
+        assert_eq!(out[0], [1.0, 1.0].into());
+        let next = shape
+            .simplify(
+                data.expect("must have trace"),
+                Default::default(),
+                &mut Default::default(),
+            )
+            .unwrap();
+        assert!(next.size() < shape.size());
+        let tape = next.interval_tape(Default::default());
+        let (out, data) = eval.eval(&tape, &[[-1.0, 0.0].into()]).unwrap();
+        assert_eq!(out[0], [1.0, 1.0].into());
+        assert!(data.is_none());
+    }
+
     pub fn test_i_max_imm() {
         let mut ctx = Context::new();
         let x = ctx.x();
@@ -1193,8 +1465,13 @@ macro_rules! interval_tests {
         $crate::interval_test!(test_i_or, $t);
         $crate::interval_test!(test_i_compare, $t);
         $crate::interval_test!(test_i_modulo, $t);
+        $crate::interval_test!(test_i_pow, $t);
+        $crate::interval_test!(test_i_powi, $t);
+        $crate::interval_test!(test_i_cosh, $t);
         $crate::interval_test!(test_i_simplify, $t);
         $crate::interval_test!(test_i_simplify_conditional, $t);
+        $crate::interval_test!(test_i_select, $t);
+        $crate::interval_test!(test_i_smooth_min, $t);
         $crate::interval_test!(test_i_stress, $t);
 
         mod i_unary {
@@ -1,5 +1,6 @@
 //! Test suites for each evaluator type
 pub mod float_slice;
+pub mod grad_n_slice;
 pub mod grad_slice;
 pub mod interval;
 pub mod point;
@@ -206,6 +207,7 @@ macro_rules! declare_canonical_binary_full {
      |$lhs_reg_reg:ident, $rhs_reg_reg:ident| $f_reg_reg:expr,
      |$lhs_reg_imm:ident, $rhs_reg_imm:ident| $f_reg_imm:expr,
      |$lhs_imm_reg:ident, $rhs_imm_reg:ident| $f_imm_reg:expr,
+     |$lhs_d:ident, $rhs_d:ident| $d:expr,
      ) => {
         pub struct $i;
         impl CanonicalBinaryOp for $i {
@@ -237,8 +239,24 @@ macro_rules! declare_canonical_binary_full {
             fn eval_imm_reg_f64($lhs_imm_reg: f64, $rhs_imm_reg: f64) -> f64 {
                 $f_imm_reg
             }
+            fn discontinuous_at($lhs_d: f32, $rhs_d: f32) -> bool {
+                $d
+            }
         }
     };
+    (Context::$i:ident,
+     |$lhs_reg_reg:ident, $rhs_reg_reg:ident| $f_reg_reg:expr,
+     |$lhs_reg_imm:ident, $rhs_reg_imm:ident| $f_reg_imm:expr,
+     |$lhs_imm_reg:ident, $rhs_imm_reg:ident| $f_imm_reg:expr,
+     ) => {
+        declare_canonical_binary_full!(
+            Context::$i,
+            |$lhs_reg_reg, $rhs_reg_reg| $f_reg_reg,
+            |$lhs_reg_imm, $rhs_reg_imm| $f_reg_imm,
+            |$lhs_imm_reg, $rhs_imm_reg| $f_imm_reg,
+            |_a, _b| false,
+        );
+    };
 }
 
 #[allow(non_camel_case_types, clippy::useless_conversion)]
@@ -256,6 +274,12 @@ pub mod canonical {
     declare_canonical_unary!(Context::atan, |a| a.atan());
     declare_canonical_unary!(Context::exp, |a| a.exp());
     declare_canonical_unary!(Context::ln, |a| a.ln());
+    declare_canonical_unary!(Context::sinh, |a| a.sinh());
+    declare_canonical_unary!(Context::cosh, |a| a.cosh());
+    declare_canonical_unary!(Context::tanh, |a| a.tanh());
+    declare_canonical_unary!(Context::exp2, |a| a.exp2());
+    declare_canonical_unary!(Context::log2, |a| a.log2());
+    declare_canonical_unary!(Context::log10, |a| a.log10());
     declare_canonical_unary!(Context::square, |a| a * a);
     declare_canonical_unary!(Context::sqrt, |a| a.sqrt());
     declare_canonical_unary!(Context::floor, |a| a.floor());
@@ -320,6 +344,18 @@ pub mod canonical {
         |a, _b| a == 0.0 // discontinuity, because either side snaps to a
     );
     declare_canonical_binary!(Context::atan2, |y, x| y.atan2(x));
+    declare_canonical_binary_full!(
+        Context::pow,
+        |a, b| a.powf(b),
+        // Small integer immediates are evaluated with repeated multiplication
+        |a, imm| if imm.fract() == 0.0 && imm != 0.0 && imm.abs() <= 16.0 {
+            a.powi(imm as i32)
+        } else {
+            a.powf(imm)
+        },
+        |imm, b| imm.powf(b),
+        |a, _b| a == 0.0, // edge of the domain for fractional exponents
+    );
 }
 
 #[macro_export]
@@ -356,6 +392,12 @@ macro_rules! all_unary_tests {
         $crate::one_unary_test!($tester, atan);
         $crate::one_unary_test!($tester, exp);
         $crate::one_unary_test!($tester, ln);
+        $crate::one_unary_test!($tester, sinh);
+        $crate::one_unary_test!($tester, cosh);
+        $crate::one_unary_test!($tester, tanh);
+        $crate::one_unary_test!($tester, exp2);
+        $crate::one_unary_test!($tester, log2);
+        $crate::one_unary_test!($tester, log10);
         $crate::one_unary_test!($tester, not);
         $crate::one_unary_test!($tester, square);
         $crate::one_unary_test!($tester, floor);
@@ -379,5 +421,6 @@ macro_rules! all_binary_tests {
         $crate::one_binary_test!($tester, modulo);
         $crate::one_binary_test!($tester, and);
         $crate::one_binary_test!($tester, or);
+        $crate::one_binary_test!($tester, pow);
     };
 }
@@ -207,6 +207,157 @@ where
         assert_eq!(trace.unwrap().as_ref(), &[Choice::Left]);
     }
 
+    pub fn test_p_select() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let v = ctx.if_nonzero_else(x, y, z).unwrap();
+
+        let shape = F::new(&ctx, &[v]).unwrap();
+        let tape = shape.point_tape(Default::default());
+        let vs = bind_xyz(&tape);
+        let mut eval = F::new_point_eval();
+
+        let (r, trace) = eval.eval(&tape, &vs(1.0, 2.0, 3.0)).unwrap();
+        assert_eq!(r[0], 2.0);
+        assert_eq!(trace.unwrap().as_ref(), &[Choice::Left]);
+
+        let (r, trace) = eval.eval(&tape, &vs(-0.5, 2.0, 3.0)).unwrap();
+        assert_eq!(r[0], 2.0);
+        assert_eq!(trace.unwrap().as_ref(), &[Choice::Left]);
+
+        let (r, trace) = eval.eval(&tape, &vs(f32::NAN, 2.0, 3.0)).unwrap();
+        assert_eq!(r[0], 2.0);
+        assert_eq!(trace.unwrap().as_ref(), &[Choice::Left]);
+
+        let (r, trace) = eval.eval(&tape, &vs(0.0, 2.0, 3.0)).unwrap();
+        assert_eq!(r[0], 3.0);
+        assert_eq!(trace.unwrap().as_ref(), &[Choice::Right]);
+
+        let (r, trace) = eval.eval(&tape, &vs(-0.0, 2.0, 3.0)).unwrap();
+        assert_eq!(r[0], 3.0);
+        assert_eq!(trace.unwrap().as_ref(), &[Choice::Right]);
+
+        // Simplify to the left branch, then check that the condition is gone
+        let (_r, trace) = eval.eval(&tape, &vs(1.0, 2.0, 3.0)).unwrap();
+        let next = shape
+            .simplify(
+                trace.unwrap(),
+                Default::default(),
+                &mut Default::default(),
+            )
+            .unwrap();
+        assert!(next.size() < shape.size());
+        let tape = next.point_tape(Default::default());
+        let (r, trace) = eval.eval(&tape, &vs(0.0, 2.0, 3.0)).unwrap();
+        assert_eq!(r[0], 2.0);
+        assert!(trace.is_none());
+
+        // Immediate branches
+        let v = ctx.if_nonzero_else(x, 1.0, y).unwrap();
+        let shape = F::new(&ctx, &[v]).unwrap();
+        let tape = shape.point_tape(Default::default());
+        let vs = bind_xy(&tape);
+        let mut eval = F::new_point_eval();
+
+        let (r, trace) = eval.eval(&tape, &vs(1.0, 2.0)).unwrap();
+        assert_eq!(r[0], 1.0);
+        assert_eq!(trace.unwrap().as_ref(), &[Choice::Left]);
+
+        let (r, trace) = eval.eval(&tape, &vs(0.0, 2.0)).unwrap();
+        assert_eq!(r[0], 2.0);
+        assert_eq!(trace.unwrap().as_ref(), &[Choice::Right]);
+
+        let next = shape
+            .simplify(
+                trace.unwrap(),
+                Default::default(),
+                &mut Default::default(),
+            )
+            .unwrap();
+        let tape = next.point_tape(Default::default());
+        let (r, trace) = eval.eval(&tape, &vs(1.0, 5.0)).unwrap();
+        assert_eq!(r[0], 5.0);
+        assert!(trace.is_none());
+    }
+
+    pub fn test_p_smooth_min() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let z = ctx.z();
+        let v = ctx.smooth_min(x, y, z).unwrap();
+
+        let shape = F::new(&ctx, &[v]).unwrap();
+        let tape = shape.point_tape(Default::default());
+        let vs = bind_xyz(&tape);
+        let mut eval = F::new_point_eval();
+
+        let (r, trace) = eval.eval(&tape, &vs(0.0, 2.0, 1.0)).unwrap();
+        assert_eq!(r[0], 0.0);
+        assert_eq!(trace.unwrap().as_ref(), &[Choice::Left]);
+
+        let (r, trace) = eval.eval(&tape, &vs(2.0, 0.0, 1.0)).unwrap();
+        assert_eq!(r[0], 0.0);
+        assert_eq!(trace.unwrap().as_ref(), &[Choice::Right]);
+
+        let (r, trace) = eval.eval(&tape, &vs(0.0, 0.5, 1.0)).unwrap();
+        assert_eq!(r[0], -0.0625);
+        assert!(trace.is_none());
+
+        // A zero-width blend is a plain minimum
+        let (r, trace) = eval.eval(&tape, &vs(0.0, 0.5, 0.0)).unwrap();
+        assert_eq!(r[0], 0.0);
+        assert_eq!(trace.unwrap().as_ref(), &[Choice::Left]);
+
+        let (r, trace) = eval.eval(&tape, &vs(f32::NAN, 0.0, 1.0)).unwrap();
+        assert!(r[0].is_nan());
+        assert!(trace.is_none());
+
+        let (r, trace) = eval.eval(&tape, &vs(0.0, 0.0, f32::NAN)).unwrap();
+        assert!(r[0].is_nan());
+        assert!(trace.is_none());
+
+        // Simplify to the left branch, then check that the blend is gone
+        let (_r, trace) = eval.eval(&tape, &vs(0.0, 2.0, 1.0)).unwrap();
+        let next = shape
+            .simplify(
+                trace.unwrap(),
+                Default::default(),
+                &mut Default::default(),
+            )
+            .unwrap();
+        assert!(next.size() < shape.size());
+        let tape = next.point_tape(Default::default());
+        let (r, trace) = eval.eval(&tape, &vs(2.0, 0.0, 1.0)).unwrap();
+        assert_eq!(r[0], 2.0);
+        assert!(trace.is_none());
+
+        // Immediate arguments
+        let v = ctx.smooth_min(x, 1.0, 0.5).unwrap();
+        let shape = F::new(&ctx, &[v]).unwrap();
+        let tape = shape.point_tape(Default::default());
+        let mut eval = F::new_point_eval();
+        let (r, _trace) = eval.eval(&tape, &[0.0]).unwrap();
+        assert_eq!(r[0], 0.0);
+        let (r, _trace) = eval.eval(&tape, &[1.0]).unwrap();
+        assert_eq!(r[0], 0.875);
+        let (r, _trace) = eval.eval(&tape, &[3.0]).unwrap();
+        assert_eq!(r[0], 1.0);
+
+        let v = ctx.smooth_min(x, y, 0.5).unwrap();
+        let shape = F::new(&ctx, &[v]).unwrap();
+        let tape = shape.point_tape(Default::default());
+        let vs = bind_xy(&tape);
+        let (r, trace) = eval.eval(&tape, &vs(0.0, 0.0)).unwrap();
+        assert_eq!(r[0], -0.125);
+        assert!(trace.is_none());
+        let (r, trace) = eval.eval(&tape, &vs(0.0, 1.0)).unwrap();
+        assert_eq!(r[0], 0.0);
+        assert_eq!(trace.unwrap().as_ref(), &[Choice::Left]);
+    }
+
     pub fn test_p_sin() {
         let mut ctx = Context::new();
         let x = ctx.x();
@@ -258,6 +409,26 @@ where
         assert_eq!(eval.eval(&tape, &vs(3.0, 3.5)).unwrap().0, [3.5]);
     }
 
+    pub fn test_p_powi() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        for n in [-4, -3, -2, 3, 4, 5, 16] {
+            let node = ctx.pow(x, n as f32).unwrap();
+            let shape = F::new(&ctx, &[node]).unwrap();
+            let tape = shape.point_tape(Default::default());
+            let mut eval = F::new_point_eval();
+            for v in [-2.5f32, -1.0, -0.5, 0.25, 1.5, 3.0] {
+                let (out, _trace) = eval.eval(&tape, &[v]).unwrap();
+                let expected = v.powi(n);
+                assert!(
+                    (out[0] - expected).abs() <= expected.abs() * 1e-6,
+                    "pow({v}, {n}) = {} != {expected}",
+                    out[0]
+                );
+            }
+        }
+    }
+
     pub fn test_push() {
         let mut ctx = Context::new();
         let x = ctx.x();
@@ -642,8 +813,11 @@ macro_rules! point_tests {
         $crate::point_test!(test_p_max, $t);
         $crate::point_test!(test_p_min, $t);
         $crate::point_test!(test_p_sin, $t);
+        $crate::point_test!(test_p_powi, $t);
         $crate::point_test!(test_p_and, $t);
         $crate::point_test!(test_p_or, $t);
+        $crate::point_test!(test_p_select, $t);
+        $crate::point_test!(test_p_smooth_min, $t);
         $crate::point_test!(basic_interpreter, $t);
         $crate::point_test!(test_push, $t);
         $crate::point_test!(test_basic, $t);
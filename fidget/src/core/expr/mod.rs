@@ -0,0 +1,639 @@
+//! Parser for infix math expressions
+//!
+//! This is a small, dependency-free text front end, for use when the
+//! [`rhai`](https://docs.rs/fidget/latest/fidget/rhai/) bindings are not
+//! available.  It parses the format printed by
+//! [`Tree::to_infix`](crate::context::Tree::to_infix):
+//!
+//! ```
+//! # use fidget::context::Context;
+//! let tree = fidget::expr::parse("let r = x^2 + y^2; sqrt(r) - 1")?;
+//! let mut ctx = Context::new();
+//! let node = ctx.import(&tree);
+//! assert_eq!(ctx.eval_xyz(node, 3.0, 4.0, 0.0)?, 4.0);
+//! # Ok::<(), fidget::Error>(())
+//! ```
+//!
+//! A script is a sequence of `let name = expr;` bindings, followed by a single
+//! expression.  Expressions are built from
+//!
+//! - Numbers (e.g. `1`, `-2.5`, `1e-3`, `inf`, `NaN`)
+//! - The axes `x`, `y`, and `z`
+//! - Infix operators `+`, `-`, `*`, `/`, `%` (modulo), and `^` (power), with
+//!   the usual precedence.  `^` is right-associative, and binds more tightly
+//!   than unary negation (so `-x^2` is `-(x^2)`).
+//! - Function calls, using the opcode names from [`Tree`] (e.g. `sqrt(x)`,
+//!   `min(x, y)`, `atan2(y, x)`, `smooth_min(a, b, k)`, or
+//!   `if_nonzero_else(cond, a, b)`)
+//! - `remap_xyz(shape, x, y, z)` and `substitute(shape, name = value, ...)`
+//!
+//! Any other identifier is a named variable.  Names are looked up in (or added
+//! to) a [`VarRegistry`] when parsing with [`parse_with_vars`]; names in the
+//! form printed by [`Var`]'s `Display` implementation (e.g. `v_3`) refer to
+//! that exact variable.  Comments begin with `#` and continue to the end of the
+//! line.
+//!
+//! Errors are reported as [`Error::ParseError`], with 1-indexed line and column
+//! numbers.
+use crate::{
+    context::Tree,
+    var::{Var, VarInfo, VarRegistry},
+    Error,
+};
+use std::collections::HashMap;
+
+/// Maximum nesting depth of parenthesized expressions and function calls
+const MAX_DEPTH: usize = 256;
+
+/// Parses an infix expression into a [`Tree`]
+///
+/// Each named variable is assigned a new [`Var`]; use [`parse_with_vars`] to
+/// recover their names.
+pub fn parse(s: &str) -> Result<Tree, Error> {
+    parse_with_vars(s, &mut VarRegistry::new())
+}
+
+/// Parses an infix expression into a [`Tree`], using a registry of names
+///
+/// Named variables which are already present in `vars` keep their identity;
+/// new names are registered with default metadata.
+///
+/// ```
+/// # use fidget::var::VarRegistry;
+/// let mut vars = VarRegistry::new();
+/// let a = fidget::expr::parse_with_vars("x - radius", &mut vars)?;
+/// let r = vars.find("radius").unwrap();
+/// let b = fidget::expr::parse_with_vars("radius * 2", &mut vars)?;
+/// assert_eq!(vars.len(), 1);
+/// assert_eq!(vars.find("radius"), Some(r));
+/// # Ok::<(), fidget::Error>(())
+/// ```
+pub fn parse_with_vars(s: &str, vars: &mut VarRegistry) -> Result<Tree, Error> {
+    let tokens = lex(s)?;
+    let mut p = Parser {
+        tokens,
+        index: 0,
+        lets: HashMap::new(),
+        vars,
+        depth: 0,
+    };
+    p.script()
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Position of a token in the input text (1-indexed)
+#[derive(Copy, Clone, Debug)]
+struct Pos {
+    line: usize,
+    column: usize,
+}
+
+impl Pos {
+    fn error(&self, reason: String) -> Error {
+        Error::ParseError {
+            line: self.line,
+            column: self.column,
+            reason,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Token<'a> {
+    Num(f64),
+    Ident(&'a str),
+    Sym(char),
+    End,
+}
+
+impl std::fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Token::Num(v) => write!(f, "number `{v}`"),
+            Token::Ident(s) => write!(f, "`{s}`"),
+            Token::Sym(c) => write!(f, "`{c}`"),
+            Token::End => write!(f, "end of input"),
+        }
+    }
+}
+
+/// Splits the input text into tokens, ending with [`Token::End`]
+fn lex(s: &str) -> Result<Vec<(Pos, Token<'_>)>, Error> {
+    let mut out = vec![];
+    let mut pos = Pos { line: 1, column: 1 };
+    let mut iter = s.char_indices().peekable();
+
+    // Consumes characters while the predicate holds, returning the end index
+    let take = |iter: &mut std::iter::Peekable<std::str::CharIndices>,
+                pos: &mut Pos,
+                f: &dyn Fn(char) -> bool| {
+        while let Some((i, c)) = iter.peek().cloned() {
+            if !f(c) {
+                return i;
+            }
+            pos.column += 1;
+            iter.next();
+        }
+        s.len()
+    };
+
+    while let Some((start, c)) = iter.peek().cloned() {
+        let here = pos;
+        if c == '\n' {
+            pos.line += 1;
+            pos.column = 1;
+            iter.next();
+        } else if c.is_whitespace() {
+            pos.column += 1;
+            iter.next();
+        } else if c == '#' {
+            take(&mut iter, &mut pos, &|c| c != '\n');
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut end =
+                take(&mut iter, &mut pos, &|c| c.is_ascii_digit() || c == '.');
+            if matches!(iter.peek(), Some((_, 'e' | 'E'))) {
+                pos.column += 1;
+                iter.next();
+                if matches!(iter.peek(), Some((_, '+' | '-'))) {
+                    pos.column += 1;
+                    iter.next();
+                }
+                end = take(&mut iter, &mut pos, &|c| c.is_ascii_digit());
+            }
+            let text = &s[start..end];
+            let v = text
+                .parse()
+                .map_err(|_| here.error(format!("invalid number `{text}`")))?;
+            out.push((here, Token::Num(v)));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut end =
+                take(&mut iter, &mut pos, &|c| c.is_alphanumeric() || c == '_');
+            // Variables with large indices are printed as `V(hex)`
+            if &s[start..end] == "V" && s[end..].starts_with('(') {
+                let rest = &s[end + 1..];
+                let n = rest.find(|c: char| !c.is_ascii_hexdigit());
+                if let Some(n) = n.filter(|n| rest[*n..].starts_with(')')) {
+                    for _ in 0..n + 2 {
+                        iter.next();
+                    }
+                    pos.column += n + 2;
+                    end += n + 2;
+                }
+            }
+            out.push((here, Token::Ident(&s[start..end])));
+        } else if "+-*/%^(),=;".contains(c) {
+            pos.column += 1;
+            iter.next();
+            out.push((here, Token::Sym(c)));
+        } else {
+            return Err(here.error(format!("unexpected character `{c}`")));
+        }
+    }
+    out.push((pos, Token::End));
+    Ok(out)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+struct Parser<'a, 'v> {
+    tokens: Vec<(Pos, Token<'a>)>,
+    index: usize,
+    /// Values bound with `let` statements
+    lets: HashMap<&'a str, Tree>,
+    vars: &'v mut VarRegistry,
+    /// Current nesting depth
+    depth: usize,
+}
+
+impl<'a> Parser<'a, '_> {
+    fn peek(&self) -> Token<'a> {
+        self.tokens[self.index].1
+    }
+
+    fn pos(&self) -> Pos {
+        self.tokens[self.index].0
+    }
+
+    fn next(&mut self) -> (Pos, Token<'a>) {
+        let out = self.tokens[self.index];
+        if out.1 != Token::End {
+            self.index += 1;
+        }
+        out
+    }
+
+    /// Consumes the given symbol, if it's next in the input
+    fn accept(&mut self, c: char) -> bool {
+        if self.peek() == Token::Sym(c) {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), Error> {
+        if self.accept(c) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("`{c}`")))
+        }
+    }
+
+    fn unexpected(&self, what: &str) -> Error {
+        self.pos()
+            .error(format!("expected {what}, found {}", self.peek()))
+    }
+
+    fn script(&mut self) -> Result<Tree, Error> {
+        while self.peek() == Token::Ident("let") {
+            self.next();
+            let name = match self.next() {
+                (_, Token::Ident(name)) => name,
+                (pos, t) => {
+                    return Err(pos.error(format!("expected name, found {t}")))
+                }
+            };
+            self.expect('=')?;
+            let value = self.expr()?;
+            self.expect(';')?;
+            self.lets.insert(name, value);
+        }
+        let out = self.expr()?;
+        self.accept(';');
+        match self.peek() {
+            Token::End => Ok(out),
+            _ => Err(self.unexpected("end of input")),
+        }
+    }
+
+    /// Parses a sum or difference
+    fn expr(&mut self) -> Result<Tree, Error> {
+        let pos = self.pos();
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(pos.error("expression is nested too deeply".to_owned()));
+        }
+        let mut out = self.product()?;
+        loop {
+            if self.accept('+') {
+                out += self.product()?;
+            } else if self.accept('-') {
+                out -= self.product()?;
+            } else {
+                break;
+            }
+        }
+        self.depth -= 1;
+        Ok(out)
+    }
+
+    fn product(&mut self) -> Result<Tree, Error> {
+        let mut out = self.power()?;
+        loop {
+            if self.accept('*') {
+                out *= self.power()?;
+            } else if self.accept('/') {
+                out /= self.power()?;
+            } else if self.accept('%') {
+                out = out.modulo(self.power()?);
+            } else {
+                break;
+            }
+        }
+        Ok(out)
+    }
+
+    /// Parses a chain of exponents, each of which may be negated
+    ///
+    /// Negation binds more loosely than exponentiation, so `-x^-y^2` is
+    /// `-(x^(-(y^2)))`.
+    fn power(&mut self) -> Result<Tree, Error> {
+        let mut terms = vec![self.unary()?];
+        while self.accept('^') {
+            terms.push(self.unary()?);
+        }
+        // Exponentiation is right-associative; each negation applies to the
+        // rest of the chain, starting from its own term
+        let (mut negate, mut out) = terms.pop().unwrap();
+        while let Some((n, t)) = terms.pop() {
+            if negate {
+                out = out.neg();
+            }
+            out = t.pow(out);
+            negate = n;
+        }
+        Ok(if negate { out.neg() } else { out })
+    }
+
+    /// Parses an atom with optional leading `-` signs
+    ///
+    /// Returns the atom and whether it should be negated.
+    fn unary(&mut self) -> Result<(bool, Tree), Error> {
+        let mut negate = false;
+        while self.accept('-') {
+            negate = !negate;
+        }
+        // Negative literals are parsed as constants, unless they're the base of
+        // an exponent (in which case `-2^2` is `-(2^2)`)
+        if let Token::Num(v) = self.peek() {
+            if negate && self.tokens[self.index + 1].1 != Token::Sym('^') {
+                self.next();
+                return Ok((false, Tree::constant(-v)));
+            }
+        }
+        Ok((negate, self.atom()?))
+    }
+
+    fn atom(&mut self) -> Result<Tree, Error> {
+        let (pos, t) = self.next();
+        match t {
+            Token::Sym('(') => {
+                let out = self.expr()?;
+                self.expect(')')?;
+                Ok(out)
+            }
+            Token::Ident(name) if self.peek() == Token::Sym('(') => {
+                self.call(pos, name)
+            }
+            Token::Ident(name) => self.ident(pos, name),
+            Token::Num(v) => Ok(Tree::constant(v)),
+            t => Err(pos.error(format!("expected expression, found {t}"))),
+        }
+    }
+
+    /// Resolves an identifier, which isn't a function name
+    fn ident(&mut self, pos: Pos, name: &str) -> Result<Tree, Error> {
+        if let Some(t) = self.lets.get(name) {
+            return Ok(t.clone());
+        }
+        Ok(match name {
+            "inf" => Tree::constant(f64::INFINITY),
+            "nan" | "NaN" => Tree::constant(f64::NAN),
+            name => Tree::from(self.var(pos, name)?),
+        })
+    }
+
+    /// Finds or declares a variable
+    fn var(&mut self, pos: Pos, name: &str) -> Result<Var, Error> {
+        match name {
+            "x" => return Ok(Var::X),
+            "y" => return Ok(Var::Y),
+            "z" => return Ok(Var::Z),
+            _ => (),
+        }
+        if let Ok(v) = name.parse::<Var>() {
+            return Ok(v);
+        }
+        if let Some(v) = self.vars.find(name) {
+            return Ok(v);
+        }
+        let v = Var::new();
+        self.vars
+            .insert(v, VarInfo::new(name))
+            .map_err(|e| pos.error(e.to_string()))?;
+        Ok(v)
+    }
+
+    /// Parses a comma-separated argument list, after the opening parenthesis
+    fn args(&mut self) -> Result<Vec<Tree>, Error> {
+        let mut out = vec![];
+        if !self.accept(')') {
+            loop {
+                out.push(self.expr()?);
+                if self.accept(')') {
+                    break;
+                }
+                self.expect(',')?;
+            }
+        }
+        Ok(out)
+    }
+
+    fn call(&mut self, pos: Pos, name: &str) -> Result<Tree, Error> {
+        self.expect('(')?;
+        if name == "substitute" {
+            return self.substitute();
+        }
+        let args = self.args()?;
+        let count = |n: usize| {
+            if args.len() == n {
+                Ok(())
+            } else {
+                let s = if n == 1 { "" } else { "s" };
+                Err(pos.error(format!(
+                    "`{name}` expects {n} argument{s}, got {}",
+                    args.len()
+                )))
+            }
+        };
+        let out = match name {
+            "neg" | "abs" | "recip" | "sqrt" | "square" | "floor" | "ceil"
+            | "round" | "sin" | "cos" | "tan" | "asin" | "acos" | "atan"
+            | "exp" | "ln" | "sinh" | "cosh" | "tanh" | "exp2" | "log2"
+            | "log10" | "not" => {
+                count(1)?;
+                let a = &args[0];
+                match name {
+                    "neg" => a.neg(),
+                    "abs" => a.abs(),
+                    "recip" => a.recip(),
+                    "sqrt" => a.sqrt(),
+                    "square" => a.square(),
+                    "floor" => a.floor(),
+                    "ceil" => a.ceil(),
+                    "round" => a.round(),
+                    "sin" => a.sin(),
+                    "cos" => a.cos(),
+                    "tan" => a.tan(),
+                    "asin" => a.asin(),
+                    "acos" => a.acos(),
+                    "atan" => a.atan(),
+                    "exp" => a.exp(),
+                    "ln" => a.ln(),
+                    "sinh" => a.sinh(),
+                    "cosh" => a.cosh(),
+                    "tanh" => a.tanh(),
+                    "exp2" => a.exp2(),
+                    "log2" => a.log2(),
+                    "log10" => a.log10(),
+                    "not" => a.not(),
+                    _ => unreachable!(),
+                }
+            }
+            "min" | "max" | "compare" | "and" | "or" | "atan2" | "pow"
+            | "mod" => {
+                count(2)?;
+                let (a, b) = (&args[0], args[1].clone());
+                match name {
+                    "min" => a.min(b),
+                    "max" => a.max(b),
+                    "compare" => a.compare(b),
+                    "and" => a.and(b),
+                    "or" => a.or(b),
+                    "atan2" => a.atan2(b),
+                    "pow" => a.pow(b),
+                    "mod" => a.modulo(b),
+                    _ => unreachable!(),
+                }
+            }
+            "if_nonzero_else" | "smooth_min" | "smooth_max" => {
+                count(3)?;
+                let (a, b, c) = (&args[0], args[1].clone(), args[2].clone());
+                match name {
+                    "if_nonzero_else" => a.if_nonzero_else(b, c),
+                    "smooth_min" => a.smooth_min(b, c),
+                    "smooth_max" => a.smooth_max(b, c),
+                    _ => unreachable!(),
+                }
+            }
+            "remap_xyz" => {
+                count(4)?;
+                let [t, x, y, z] = <[Tree; 4]>::try_from(args).unwrap();
+                t.remap_xyz(x, y, z)
+            }
+            _ => return Err(pos.error(format!("unknown function `{name}`"))),
+        };
+        Ok(out)
+    }
+
+    /// Parses the arguments to `substitute(shape, name = value, ...)`
+    fn substitute(&mut self) -> Result<Tree, Error> {
+        let target = self.expr()?;
+        let mut vars = vec![];
+        while self.accept(',') {
+            let var = match self.next() {
+                (pos, Token::Ident(name)) => self.var(pos, name)?,
+                (pos, t) => {
+                    let e = format!("expected variable name, found {t}");
+                    return Err(pos.error(e));
+                }
+            };
+            self.expect('=')?;
+            vars.push((var, self.expr()?));
+        }
+        self.expect(')')?;
+        Ok(target.substitute(&vars))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Context;
+
+    fn eval(s: &str, x: f64, y: f64) -> f64 {
+        let t = parse(s).unwrap();
+        let mut ctx = Context::new();
+        let n = ctx.import(&t);
+        ctx.eval_xyz(n, x, y, 0.0).unwrap()
+    }
+
+    fn err(s: &str) -> (usize, usize, String) {
+        match parse(s) {
+            Err(Error::ParseError {
+                line,
+                column,
+                reason,
+            }) => (line, column, reason),
+            r => panic!("expected parse error, got {r:?}"),
+        }
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(eval("1 + 2 * 3", 0.0, 0.0), 7.0);
+        assert_eq!(eval("(1 + 2) * 3", 0.0, 0.0), 9.0);
+        assert_eq!(eval("x - y - 1", 5.0, 1.0), 3.0);
+        assert_eq!(eval("x / y / 2", 8.0, 2.0), 2.0);
+        assert_eq!(eval("2 ^ 3 ^ 2", 0.0, 0.0), 512.0);
+        assert_eq!(eval("-x ^ 2", 3.0, 0.0), -9.0);
+        assert_eq!(eval("-x^2", 2.0, 0.0), -4.0);
+        assert_eq!(eval("(-x) ^ 2", 3.0, 0.0), 9.0);
+        assert_eq!(eval("-2 ^ 2", 0.0, 0.0), -4.0);
+        assert_eq!(eval("2 ^ -x ^ 2", 1.0, 0.0), 0.5);
+        assert_eq!(eval("x * -2 ^ 2", 1.0, 0.0), -4.0);
+        assert_eq!(eval("x % 3", 7.0, 0.0), 1.0);
+        assert_eq!(eval("x * -y", 2.0, 3.0), -6.0);
+        assert_eq!(eval("--x", 2.0, 0.0), 2.0);
+        assert_eq!(eval("1.5e1 + .5", 0.0, 0.0), 15.5);
+        assert_eq!(eval("max(x, y) + -inf", 0.0, 0.0), -f64::INFINITY);
+    }
+
+    #[test]
+    fn test_script() {
+        let s = "
+            # distance to a circle
+            let r = sqrt(x^2 + y^2);
+            let r = r - radius;   # rebinding is allowed
+            r * 2;
+        ";
+        let mut vars = VarRegistry::new();
+        let t = parse_with_vars(s, &mut vars).unwrap();
+        let radius = vars.find("radius").unwrap();
+        let mut ctx = Context::new();
+        let n = ctx.import(&t);
+        let v = ctx.eval(
+            n,
+            &HashMap::from([(Var::X, 3.0), (Var::Y, 4.0), (radius, 1.0)]),
+        );
+        assert_eq!(v.unwrap(), 8.0);
+    }
+
+    #[test]
+    fn test_functions() {
+        assert_eq!(eval("if_nonzero_else(x, 1, 2)", 0.0, 0.0), 2.0);
+        assert_eq!(eval("smooth_min(x, 1, 0.5)", -2.0, 0.0), -2.0);
+        assert_eq!(eval("atan2(y, x)", 1.0, 0.0), 0.0);
+        assert_eq!(eval("remap_xyz(x - y, y, x, z)", 1.0, 3.0), 2.0);
+        assert_eq!(eval("substitute(x * y, y = x + 1)", 2.0, 0.0), 6.0);
+        assert_eq!(eval("recip(square(x))", 2.0, 0.0), 0.25);
+        assert_eq!(eval("mod(x, 3) + neg(y)", 4.0, 1.0), 0.0);
+    }
+
+    #[test]
+    fn test_vars() {
+        let v = Var::new();
+        let t = parse(&format!("{v} + X + v_3")).unwrap();
+        let mut ctx = Context::new();
+        let n = ctx.import(&t);
+        let w: Var = "v_3".parse().unwrap();
+        let vars = HashMap::from([(v, 1.0), (Var::X, 2.0), (w, 4.0)]);
+        assert_eq!(ctx.eval(n, &vars).unwrap(), 7.0);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            err("x +"),
+            (1, 4, "expected expression, found end of input".to_owned())
+        );
+        assert_eq!(
+            err("let a = x;\n  sin(a, y)"),
+            (2, 3, "`sin` expects 1 argument, got 2".to_owned())
+        );
+        assert_eq!(err("foo(x)"), (1, 1, "unknown function `foo`".to_owned()));
+        assert_eq!(
+            err("(x + y"),
+            (1, 7, "expected `)`, found end of input".to_owned())
+        );
+        assert_eq!(
+            err("x + y z"),
+            (1, 7, "expected end of input, found `z`".to_owned())
+        );
+        assert_eq!(err("x $ y"), (1, 3, "unexpected character `$`".to_owned()));
+        assert_eq!(err("1.2.3"), (1, 1, "invalid number `1.2.3`".to_owned()));
+
+        let deep = format!("{}x{}", "(".repeat(1000), ")".repeat(1000));
+        assert!(parse(&deep).is_err());
+    }
+
+    #[test]
+    fn test_infix_round_trip() {
+        // Deep trees are split up with `let` bindings
+        crate::context::check_round_trip(Tree::to_infix, parse, 1000);
+    }
+}
@@ -56,8 +56,10 @@
 pub mod context;
 pub use context::Context;
 
+pub mod codegen;
 pub mod compiler;
 pub mod eval;
+pub mod expr;
 pub mod shape;
 pub mod types;
 pub mod var;
@@ -29,7 +29,7 @@
 use crate::{
     context::{Context, Node, Tree},
     eval::{BulkEvaluator, Function, MathFunction, Tape, TracingEvaluator},
-    types::{Grad, Interval},
+    types::{Grad, Hessian, Interval},
     var::{Var, VarIndex, VarMap},
     Error,
 };
@@ -668,6 +668,58 @@ where
     }
 }
 
+impl<E: BulkEvaluator<Data = Hessian>> ShapeBulkEval<E> {
+    /// Evaluates mean curvature at many points, without any variables
+    ///
+    /// Curvature is computed for the isosurface passing through each point
+    /// (see [`Hessian::mean_curvature`]); it's only meaningful where the
+    /// gradient is non-zero.
+    ///
+    /// Before evaluation, the tape's transform matrix is applied (if present).
+    pub fn mean_curvature(
+        &mut self,
+        tape: &ShapeTape<E::Tape>,
+        x: &[f32],
+        y: &[f32],
+        z: &[f32],
+    ) -> Result<Vec<f32>, Error> {
+        self.eval_curvature(tape, x, y, z, Hessian::mean_curvature)
+    }
+
+    /// Evaluates Gaussian curvature at many points, without any variables
+    ///
+    /// Curvature is computed for the isosurface passing through each point
+    /// (see [`Hessian::gaussian_curvature`]); it's only meaningful where the
+    /// gradient is non-zero.
+    ///
+    /// Before evaluation, the tape's transform matrix is applied (if present).
+    pub fn gaussian_curvature(
+        &mut self,
+        tape: &ShapeTape<E::Tape>,
+        x: &[f32],
+        y: &[f32],
+        z: &[f32],
+    ) -> Result<Vec<f32>, Error> {
+        self.eval_curvature(tape, x, y, z, Hessian::gaussian_curvature)
+    }
+
+    /// Helper function to evaluate a curvature function at many points
+    fn eval_curvature(
+        &mut self,
+        tape: &ShapeTape<E::Tape>,
+        x: &[f32],
+        y: &[f32],
+        z: &[f32],
+        f: fn(&Hessian) -> f32,
+    ) -> Result<Vec<f32>, Error> {
+        let seed = |v: &[f32], i| {
+            v.iter().map(|&v| Hessian::input(v, i)).collect::<Vec<_>>()
+        };
+        let out = self.eval(tape, &seed(x, 0), &seed(y, 1), &seed(z, 2))?;
+        Ok(out.iter().map(f).collect())
+    }
+}
+
 /// Trait for types that can be transformed by a 4x4 homogeneous transform matrix
 pub trait Transformable {
     /// Apply the given transform to an `(x, y, z)` position
@@ -720,6 +772,22 @@ impl Transformable for Grad {
     }
 }
 
+impl Transformable for Hessian {
+    fn transform(
+        x: Hessian,
+        y: Hessian,
+        z: Hessian,
+        mat: Matrix4<f32>,
+    ) -> (Hessian, Hessian, Hessian) {
+        let out = [0, 1, 2, 3].map(|i| {
+            let row = mat.row(i);
+            x * row[0] + y * row[1] + z * row[2] + Hessian::from(row[3])
+        });
+
+        (out[0] / out[3], out[1] / out[3], out[2] / out[3])
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -0,0 +1,625 @@
+use crate::{types::Interval, vm::Choice};
+
+/// Number of noise symbols stored in an affine form
+const TERMS: usize = 3;
+
+/// Relative rounding error allowed for in each operation
+const ROUNDING: f32 = 4.0 * f32::EPSILON;
+
+/// Stores a value in [affine arithmetic](https://en.wikipedia.org/wiki/Affine_arithmetic)
+///
+/// An affine form is `center + Σ terms[i] · εᵢ ± err`, where each noise symbol
+/// `εᵢ` is an unknown value in `[-1, 1]`.  Noise symbols are shared between
+/// every form that depends on them, so correlated quantities cancel: `x - x` is
+/// zero up to rounding error (rather than `[-w, w]`, as with an [`Interval`]),
+/// and rotations of a box stay tight.
+///
+/// There are noise symbols for up to [`Affine::TERMS`] inputs (typically `x`,
+/// `y`, and `z`); other uncertainty (including approximation error in
+/// nonlinear operations) is accumulated into an uncorrelated error term.
+///
+/// Each form also carries an [`Interval`] bound, which is computed with
+/// interval arithmetic alongside the affine form.  The form's range is the
+/// intersection of the two, so it's never looser than interval arithmetic.
+///
+/// # Warning
+/// Like [`Interval`], this implementation does not set rounding modes, so it
+/// may not be _perfect_.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Affine {
+    center: f32,
+    terms: [f32; TERMS],
+    err: f32,
+    range: Interval,
+}
+
+impl Affine {
+    /// Number of noise symbols stored in an affine form
+    pub const TERMS: usize = TERMS;
+
+    /// Builds a new affine form, narrowing `range` with the form's own range
+    ///
+    /// The error term is widened slightly to account for rounding.  If the form
+    /// is not finite, then only `range` is used.
+    fn new(
+        center: f32,
+        terms: [f32; TERMS],
+        err: f32,
+        range: Interval,
+    ) -> Self {
+        let mut out = Self {
+            center,
+            terms,
+            err,
+            range,
+        };
+        out.err += out.magnitude() * ROUNDING;
+        let r = out.radius();
+        let range = if range.has_nan() || !center.is_finite() || !r.is_finite()
+        {
+            range
+        } else {
+            let lower = range.lower().max(center - r);
+            let upper = range.upper().min(center + r);
+            if lower <= upper {
+                Interval::new(lower, upper)
+            } else {
+                // This can only happen due to rounding error
+                range
+            }
+        };
+        Self { range, ..out }
+    }
+
+    /// Builds an affine form for an input, assigning it the given noise symbol
+    ///
+    /// If `symbol` is not below [`Affine::TERMS`], the input's uncertainty is
+    /// stored in the (uncorrelated) error term instead.
+    pub fn input(i: Interval, symbol: usize) -> Self {
+        let mut out = Self::from(i);
+        if symbol < TERMS {
+            out.terms[symbol] = out.err;
+            out.err = 0.0;
+        }
+        out
+    }
+
+    /// Returns the center of the affine form
+    pub fn center(&self) -> f32 {
+        self.center
+    }
+
+    /// Returns the range of values which the form may take
+    pub fn interval(&self) -> Interval {
+        self.range
+    }
+
+    /// Returns `true` if either bound of the form's range is `NaN`
+    pub fn has_nan(&self) -> bool {
+        self.range.has_nan()
+    }
+
+    /// Returns the total deviation from the center
+    fn radius(&self) -> f32 {
+        self.terms.iter().map(|t| t.abs()).sum::<f32>() + self.err
+    }
+
+    /// Returns the largest magnitude which the form may take
+    fn magnitude(&self) -> f32 {
+        self.center.abs() + self.radius()
+    }
+
+    /// Computes `alpha * self + zeta ± delta`, bounded by `range`
+    fn affine(
+        self,
+        alpha: f32,
+        zeta: f32,
+        delta: f32,
+        range: Interval,
+    ) -> Self {
+        Self::new(
+            alpha * self.center + zeta,
+            self.terms.map(|t| alpha * t),
+            alpha.abs() * self.err + delta,
+            range,
+        )
+    }
+
+    /// Approximates a unary function with the min-range linear approximation
+    ///
+    /// `slope` must be the derivative of the function with the smallest
+    /// magnitude within the form's range, and the derivative must have the
+    /// same sign throughout; then `f(x) - slope · x` is monotonic, so its
+    /// values at the endpoints bound the approximation error.
+    ///
+    /// `range` is the function's output, computed with interval arithmetic.
+    fn min_range(self, f: fn(f32) -> f32, slope: f32, range: Interval) -> Self {
+        if range.has_nan() || !slope.is_finite() {
+            return range.into();
+        }
+        let (lower, upper) = (self.range.lower(), self.range.upper());
+        let a = f(lower) - slope * lower;
+        let b = f(upper) - slope * upper;
+        let (lo, hi) = (a.min(b), a.max(b));
+        self.affine(slope, (lo + hi) / 2.0, (hi - lo) / 2.0, range)
+    }
+
+    /// Returns the endpoint of the range which is closest to zero, or zero
+    fn closest_to_zero(&self) -> f32 {
+        if self.range.contains(0.0) {
+            0.0
+        } else if self.range.lower() > 0.0 {
+            self.range.lower()
+        } else {
+            self.range.upper()
+        }
+    }
+
+    /// Returns the endpoint of the range which is farthest from zero
+    fn farthest_from_zero(&self) -> f32 {
+        if self.range.lower().abs() > self.range.upper().abs() {
+            self.range.lower()
+        } else {
+            self.range.upper()
+        }
+    }
+
+    /// Calculates the absolute value of the form
+    ///
+    /// This is exact if the form's range doesn't contain zero.
+    pub fn abs(self) -> Self {
+        if self.range.lower() >= 0.0 {
+            self
+        } else if self.range.upper() <= 0.0 {
+            -self
+        } else {
+            self.range.abs().into()
+        }
+    }
+
+    /// Squares the form
+    pub fn square(self) -> Self {
+        // The square of the deviation is somewhere in [0, r²]
+        let r = self.radius();
+        let h = r * r / 2.0;
+        Self::new(
+            self.center * self.center + h,
+            self.terms.map(|t| 2.0 * self.center * t),
+            2.0 * self.center.abs() * self.err + h,
+            self.range.square(),
+        )
+    }
+
+    /// Calculates the square root of the form
+    ///
+    /// If the range contains values below 0, returns a `NAN` form.
+    pub fn sqrt(self) -> Self {
+        let slope = 0.5 / self.range.upper().sqrt();
+        self.min_range(f32::sqrt, slope, self.range.sqrt())
+    }
+
+    /// Calculates the reciprocal of the form
+    ///
+    /// If the range includes 0, returns a `NAN` form.
+    pub fn recip(self) -> Self {
+        let m = self.farthest_from_zero();
+        self.min_range(f32::recip, -1.0 / (m * m), self.range.recip())
+    }
+
+    /// Computes the sine of the form
+    pub fn sin(self) -> Self {
+        self.range.sin().into()
+    }
+
+    /// Computes the cosine of the form
+    pub fn cos(self) -> Self {
+        self.range.cos().into()
+    }
+
+    /// Computes the tangent of the form
+    pub fn tan(self) -> Self {
+        self.range.tan().into()
+    }
+
+    /// Computes the arcsine of the form
+    pub fn asin(self) -> Self {
+        let m = self.closest_to_zero();
+        let slope = 1.0 / (1.0 - m * m).sqrt();
+        self.min_range(f32::asin, slope, self.range.asin())
+    }
+
+    /// Computes the arccosine of the form
+    pub fn acos(self) -> Self {
+        let m = self.closest_to_zero();
+        let slope = -1.0 / (1.0 - m * m).sqrt();
+        self.min_range(f32::acos, slope, self.range.acos())
+    }
+
+    /// Computes the arctangent of the form
+    pub fn atan(self) -> Self {
+        let m = self.farthest_from_zero();
+        let slope = 1.0 / (1.0 + m * m);
+        self.min_range(f32::atan, slope, self.range.atan())
+    }
+
+    /// Computes the exponent function applied to the form
+    pub fn exp(self) -> Self {
+        let slope = self.range.lower().exp();
+        self.min_range(f32::exp, slope, self.range.exp())
+    }
+
+    /// Computes the natural log of the form
+    pub fn ln(self) -> Self {
+        let slope = 1.0 / self.range.upper();
+        self.min_range(f32::ln, slope, self.range.ln())
+    }
+
+    /// Computes the hyperbolic sine of the form
+    pub fn sinh(self) -> Self {
+        let slope = self.closest_to_zero().cosh();
+        self.min_range(f32::sinh, slope, self.range.sinh())
+    }
+
+    /// Computes the hyperbolic cosine of the form
+    pub fn cosh(self) -> Self {
+        self.range.cosh().into()
+    }
+
+    /// Computes the hyperbolic tangent of the form
+    pub fn tanh(self) -> Self {
+        let t = self.farthest_from_zero().tanh();
+        self.min_range(f32::tanh, 1.0 - t * t, self.range.tanh())
+    }
+
+    /// Computes the base-2 exponent function applied to the form
+    pub fn exp2(self) -> Self {
+        let slope = self.range.lower().exp2() * std::f32::consts::LN_2;
+        self.min_range(f32::exp2, slope, self.range.exp2())
+    }
+
+    /// Computes the base-2 log of the form
+    pub fn log2(self) -> Self {
+        let slope = 1.0 / (self.range.upper() * std::f32::consts::LN_2);
+        self.min_range(f32::log2, slope, self.range.log2())
+    }
+
+    /// Computes the base-10 log of the form
+    pub fn log10(self) -> Self {
+        let slope = 1.0 / (self.range.upper() * std::f32::consts::LN_10);
+        self.min_range(f32::log10, slope, self.range.log10())
+    }
+
+    /// Largest value that is less-than-or-equal to this value
+    pub fn floor(&self) -> Self {
+        self.range.floor().into()
+    }
+
+    /// Smallest value that is greater-than-or-equal to this value
+    pub fn ceil(&self) -> Self {
+        self.range.ceil().into()
+    }
+
+    /// Rounded value
+    pub fn round(&self) -> Self {
+        self.range.round().into()
+    }
+
+    /// Least non-negative remainder
+    pub fn rem_euclid(&self, other: Self) -> Self {
+        self.range.rem_euclid(other.range).into()
+    }
+
+    /// Four-quadrant arctangent
+    pub fn atan2(self, x: Self) -> Self {
+        self.range.atan2(x.range).into()
+    }
+
+    /// Raises the form to the power of another form
+    pub fn pow(self, rhs: Self) -> Self {
+        self.range.pow(rhs.range).into()
+    }
+
+    /// Raises the form to an integer power (see [`Interval::powi`])
+    pub fn powi(self, n: i32) -> Self {
+        self.range.powi(n).into()
+    }
+
+    /// Three-way comparison, returning -1, 0, or 1
+    ///
+    /// The comparison is made on the difference between the two forms, so
+    /// correlated values can be compared unambiguously.
+    pub fn compare(self, rhs: Self) -> Self {
+        let d = (self - rhs).range;
+        if d.has_nan() {
+            f32::NAN.into()
+        } else if d.upper() < 0.0 {
+            (-1.0).into()
+        } else if d.lower() > 0.0 {
+            1.0.into()
+        } else {
+            Interval::new(-1.0, 1.0).into()
+        }
+    }
+
+    /// Calculates the minimum of two forms
+    ///
+    /// Returns both the result and a [`Choice`] indicating whether one side is
+    /// always less than the other, which is decided using the difference
+    /// between the two forms.
+    ///
+    /// If either side is `NAN`, returns the `NAN` form and `Choice::Both`.
+    pub fn min_choice(self, rhs: Self) -> (Self, Choice) {
+        if self.has_nan() || rhs.has_nan() {
+            return (f32::NAN.into(), Choice::Both);
+        }
+        let d = (self - rhs).range;
+        if d.upper() < 0.0 {
+            (self, Choice::Left)
+        } else if d.lower() > 0.0 {
+            (rhs, Choice::Right)
+        } else {
+            (self.range.min_choice(rhs.range).0.into(), Choice::Both)
+        }
+    }
+
+    /// Calculates the maximum of two forms
+    ///
+    /// Returns both the result and a [`Choice`] indicating whether one side is
+    /// always greater than the other, which is decided using the difference
+    /// between the two forms.
+    ///
+    /// If either side is `NAN`, returns the `NAN` form and `Choice::Both`.
+    pub fn max_choice(self, rhs: Self) -> (Self, Choice) {
+        if self.has_nan() || rhs.has_nan() {
+            return (f32::NAN.into(), Choice::Both);
+        }
+        let d = (self - rhs).range;
+        if d.lower() > 0.0 {
+            (self, Choice::Left)
+        } else if d.upper() < 0.0 {
+            (rhs, Choice::Right)
+        } else {
+            (self.range.max_choice(rhs.range).0.into(), Choice::Both)
+        }
+    }
+
+    /// Calculates the polynomial smooth minimum of two forms, blending within
+    /// a radius `k`
+    ///
+    /// See [`Interval::smooth_min_choice`] for details; the choice is decided
+    /// using the difference between the two forms.
+    pub fn smooth_min_choice(self, rhs: Self, k: Self) -> (Self, Choice) {
+        if self.has_nan() || rhs.has_nan() || k.has_nan() {
+            return (f32::NAN.into(), Choice::Both);
+        }
+        let d = (self - rhs).range;
+        let kk = k.range.upper().max(0.0);
+        if d.upper() + kk < 0.0 {
+            (self, Choice::Left)
+        } else if d.lower() - kk > 0.0 {
+            (rhs, Choice::Right)
+        } else {
+            let (v, _) = self.range.smooth_min_choice(rhs.range, k.range);
+            (v.into(), Choice::Both)
+        }
+    }
+
+    /// Calculates the short-circuiting `AND` of two forms
+    ///
+    /// See [`Interval::and_choice`] for details.
+    pub fn and_choice(self, rhs: Self) -> (Self, Choice) {
+        let (v, choice) = self.range.and_choice(rhs.range);
+        match choice {
+            Choice::Left => (0.0.into(), choice),
+            Choice::Right => (rhs, choice),
+            _ => (v.into(), choice),
+        }
+    }
+
+    /// Calculates the short-circuiting `OR` of two forms
+    ///
+    /// See [`Interval::or_choice`] for details.
+    pub fn or_choice(self, rhs: Self) -> (Self, Choice) {
+        let (v, choice) = self.range.or_choice(rhs.range);
+        match choice {
+            Choice::Left => (self, choice),
+            Choice::Right => (rhs, choice),
+            _ => (v.into(), choice),
+        }
+    }
+
+    /// Uses this form as a condition to pick between two other forms
+    ///
+    /// See [`Interval::select_choice`] for details.
+    pub fn select_choice(self, a: Self, b: Self) -> (Self, Choice) {
+        let (v, choice) = self.range.select_choice(a.range, b.range);
+        match choice {
+            Choice::Left => (a, choice),
+            Choice::Right => (b, choice),
+            _ => (v.into(), choice),
+        }
+    }
+}
+
+impl std::fmt::Display for Affine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.center)?;
+        for (i, t) in self.terms.iter().enumerate() {
+            write!(f, " + {t}ε{i}")?;
+        }
+        write!(f, " ± {} in {}", self.err, self.range)
+    }
+}
+
+impl From<Interval> for Affine {
+    fn from(i: Interval) -> Affine {
+        let center = i.midpoint();
+        let err = (i.upper() - center).max(center - i.lower());
+        Affine::new(center, [0.0; TERMS], err, i)
+    }
+}
+
+impl From<f32> for Affine {
+    fn from(f: f32) -> Self {
+        Interval::from(f).into()
+    }
+}
+
+impl std::ops::Add<Affine> for Affine {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        let mut terms = self.terms;
+        for (t, r) in terms.iter_mut().zip(rhs.terms) {
+            *t += r;
+        }
+        Affine::new(
+            self.center + rhs.center,
+            terms,
+            self.err
+                + rhs.err
+                + (self.magnitude() + rhs.magnitude()) * ROUNDING,
+            self.range + rhs.range,
+        )
+    }
+}
+
+impl std::ops::Sub<Affine> for Affine {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        let mut terms = self.terms;
+        for (t, r) in terms.iter_mut().zip(rhs.terms) {
+            *t -= r;
+        }
+        Affine::new(
+            self.center - rhs.center,
+            terms,
+            self.err
+                + rhs.err
+                + (self.magnitude() + rhs.magnitude()) * ROUNDING,
+            self.range - rhs.range,
+        )
+    }
+}
+
+impl std::ops::Mul<Affine> for Affine {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        // The product of the two deviations is bounded by the product of
+        // their radii, and is added to the error term.
+        let mut terms = [0.0; TERMS];
+        for (i, t) in terms.iter_mut().enumerate() {
+            *t = self.center * rhs.terms[i] + rhs.center * self.terms[i];
+        }
+        Affine::new(
+            self.center * rhs.center,
+            terms,
+            self.center.abs() * rhs.err
+                + rhs.center.abs() * self.err
+                + self.radius() * rhs.radius()
+                + self.magnitude() * rhs.magnitude() * ROUNDING,
+            self.range * rhs.range,
+        )
+    }
+}
+
+impl std::ops::Mul<f32> for Affine {
+    type Output = Self;
+    fn mul(self, rhs: f32) -> Self {
+        self.affine(rhs, 0.0, 0.0, self.range * rhs)
+    }
+}
+
+impl std::ops::Div<Affine> for Affine {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self {
+        let range = self.range / rhs.range;
+        if range.has_nan() {
+            range.into()
+        } else {
+            let q = self * rhs.recip();
+            Affine::new(q.center, q.terms, q.err, range)
+        }
+    }
+}
+
+impl std::ops::Neg for Affine {
+    type Output = Self;
+    fn neg(self) -> Self {
+        self.affine(-1.0, 0.0, 0.0, -self.range)
+    }
+}
+
+/// Logical not: 1 if the value is zero, 0 otherwise
+impl std::ops::Not for Affine {
+    type Output = Self;
+    fn not(self) -> Self {
+        if !self.range.contains(0.0) && !self.range.has_nan() {
+            0.0.into()
+        } else if self.range.lower() == 0.0 && self.range.upper() == 0.0 {
+            1.0.into()
+        } else {
+            Interval::new(0.0, 1.0).into()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_affine_cancellation() {
+        let x = Affine::input(Interval::new(0.0, 2.0), 0);
+        let y = Affine::input(Interval::new(-1.0, 1.0), 1);
+        let r = (x - x).interval();
+        assert!(r.lower() <= 0.0 && r.upper() >= 0.0, "{r}");
+        assert!(r.width() < 1e-5, "{r}");
+        let r = (x + y - x).interval();
+        assert!(r.lower() <= -1.0 && r.upper() >= 1.0, "{r}");
+        assert!(r.width() < 2.0 + 1e-4, "{r}");
+
+        // A box rotated by 45°, which has a radius of √2 in interval
+        // arithmetic but is tight in affine arithmetic
+        let c = std::f32::consts::FRAC_1_SQRT_2;
+        let x = Affine::input(Interval::new(-1.0, 1.0), 0);
+        let y = Affine::input(Interval::new(-1.0, 1.0), 1);
+        let u = x * c + y * c;
+        let v = x * c - y * c;
+        let r = (u * c + v * c).interval();
+        assert!((r.lower() + 1.0).abs() < 1e-4, "{r}");
+        assert!((r.upper() - 1.0).abs() < 1e-4, "{r}");
+
+        let (_, choice) = x.min_choice(x + 1.0.into());
+        assert_eq!(choice, Choice::Left);
+        let (_, choice) = (x * 2.0).max_choice(x + x);
+        assert_eq!(choice, Choice::Both);
+    }
+
+    #[test]
+    fn test_affine_nonlinear() {
+        let x = Affine::input(Interval::new(1.0, 4.0), 0);
+        for (name, a, f) in [
+            ("sqrt", x.sqrt(), f32::sqrt as fn(f32) -> f32),
+            ("exp", x.exp(), f32::exp),
+            ("ln", x.ln(), f32::ln),
+            ("recip", x.recip(), f32::recip),
+            ("atan", x.atan(), f32::atan),
+            ("tanh", x.tanh(), f32::tanh),
+            ("sinh", x.sinh(), f32::sinh),
+        ] {
+            let i = a.interval();
+            for j in 0..=16 {
+                let v = 1.0 + 3.0 * j as f32 / 16.0;
+                let out = f(v);
+                assert!(
+                    out >= i.lower() - 1e-5 && out <= i.upper() + 1e-5,
+                    "{name}({v}) = {out} not in {i}"
+                );
+            }
+        }
+        let y = Affine::input(Interval::new(-0.5, 0.5), 1);
+        let a = y.asin().interval();
+        assert!(a.lower() <= (-0.5f32).asin() && a.upper() >= 0.5f32.asin());
+        assert!(Affine::from(Interval::new(-1.0, 1.0)).sqrt().has_nan());
+    }
+}
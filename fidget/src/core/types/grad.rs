@@ -141,6 +141,68 @@ impl Grad {
             dz: self.dz / self.v,
         }
     }
+    /// Hyperbolic sine
+    pub fn sinh(self) -> Self {
+        let c = self.v.cosh();
+        Grad {
+            v: self.v.sinh(),
+            dx: self.dx * c,
+            dy: self.dy * c,
+            dz: self.dz * c,
+        }
+    }
+    /// Hyperbolic cosine
+    pub fn cosh(self) -> Self {
+        let s = self.v.sinh();
+        Grad {
+            v: self.v.cosh(),
+            dx: self.dx * s,
+            dy: self.dy * s,
+            dz: self.dz * s,
+        }
+    }
+    /// Hyperbolic tangent
+    pub fn tanh(self) -> Self {
+        let v = self.v.tanh();
+        let d = 1.0 - v.powi(2);
+        Grad {
+            v,
+            dx: self.dx * d,
+            dy: self.dy * d,
+            dz: self.dz * d,
+        }
+    }
+    /// Base-2 exponential function
+    pub fn exp2(self) -> Self {
+        let v = self.v.exp2();
+        let d = v * std::f32::consts::LN_2;
+        Grad {
+            v,
+            dx: d * self.dx,
+            dy: d * self.dy,
+            dz: d * self.dz,
+        }
+    }
+    /// Base-2 log
+    pub fn log2(self) -> Self {
+        let d = self.v * std::f32::consts::LN_2;
+        Grad {
+            v: self.v.log2(),
+            dx: self.dx / d,
+            dy: self.dy / d,
+            dz: self.dz / d,
+        }
+    }
+    /// Base-10 log
+    pub fn log10(self) -> Self {
+        let d = self.v * std::f32::consts::LN_10;
+        Grad {
+            v: self.v.log10(),
+            dx: self.dx / d,
+            dy: self.dy / d,
+            dz: self.dz / d,
+        }
+    }
 
     /// Reciprocal
     pub fn recip(self) -> Self {
@@ -171,6 +233,17 @@ impl Grad {
         }
     }
 
+    /// Polynomial smooth minimum of two values, blending within a radius `k`
+    pub fn smooth_min(self, rhs: Self, k: Self) -> Self {
+        let m = self.min(rhs);
+        let h = k - (self - rhs).abs();
+        if h.v > 0.0 {
+            m - h * h / (k * 4.0)
+        } else {
+            m
+        }
+    }
+
     /// Least non-negative remainder
     pub fn rem_euclid(&self, rhs: Grad) -> Self {
         let e = self.v.div_euclid(rhs.v);
@@ -225,6 +298,48 @@ impl Grad {
         }
     }
 
+    /// Raises this value to the power of another value
+    ///
+    /// Partial derivatives are only accumulated from arguments with a non-zero
+    /// derivative, so a constant exponent with a negative base (where
+    /// `ln(base)` would be `NaN`) still produces a valid gradient.
+    pub fn pow(self, rhs: Self) -> Self {
+        let v = self.v.powf(rhs.v);
+        let da = rhs.v * self.v.powf(rhs.v - 1.0);
+        let db = v * self.v.ln();
+        let d = |a: f32, b: f32| {
+            let mut out = 0.0;
+            if a != 0.0 {
+                out += da * a;
+            }
+            if b != 0.0 {
+                out += db * b;
+            }
+            out
+        };
+        Grad {
+            v,
+            dx: d(self.dx, rhs.dx),
+            dy: d(self.dy, rhs.dy),
+            dz: d(self.dz, rhs.dz),
+        }
+    }
+
+    /// Raises this value to an integer power
+    ///
+    /// Like [`Grad::pow`], partial derivatives are only accumulated from
+    /// non-zero derivatives of the base.
+    pub fn powi(self, n: i32) -> Self {
+        let da = n as f32 * self.v.powi(n - 1);
+        let d = |a: f32| if a != 0.0 { da * a } else { 0.0 };
+        Grad {
+            v: self.v.powi(n),
+            dx: d(self.dx),
+            dy: d(self.dy),
+            dz: d(self.dz),
+        }
+    }
+
     /// Checks that the two values are roughly equal, panicking otherwise
     #[cfg(test)]
     pub(crate) fn compare_eq(&self, other: Self) {
@@ -0,0 +1,440 @@
+use crate::types::Grad;
+
+/// A point in space with associated partial derivatives, in double precision
+///
+/// This is the `f64` equivalent of [`Grad`], used by the double-precision
+/// evaluators in [`vm`](crate::vm).
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[repr(C)]
+pub struct Grad64 {
+    /// Value of the distance field at this point
+    pub v: f64,
+    /// Partial derivative with respect to `x`
+    pub dx: f64,
+    /// Partial derivative with respect to `y`
+    pub dy: f64,
+    /// Partial derivative with respect to `z`
+    pub dz: f64,
+}
+
+impl std::fmt::Display for Grad64 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {}, {}, {})", self.v, self.dx, self.dy, self.dz)
+    }
+}
+
+impl Grad64 {
+    /// Constructs a new gradient
+    pub fn new(v: f64, dx: f64, dy: f64, dz: f64) -> Self {
+        Self { v, dx, dy, dz }
+    }
+
+    /// Looks up a gradient by index (0 = x, 1 = y, 2 = z)
+    ///
+    /// # Panics
+    /// If the index is not in the 0-2 range
+    pub fn d(&self, i: usize) -> f64 {
+        match i {
+            0 => self.dx,
+            1 => self.dy,
+            2 => self.dz,
+            _ => panic!("invalid index {i}"),
+        }
+    }
+
+    /// Absolute value
+    pub fn abs(self) -> Self {
+        if self.v < 0.0 {
+            Grad64 {
+                v: -self.v,
+                dx: -self.dx,
+                dy: -self.dy,
+                dz: -self.dz,
+            }
+        } else {
+            self
+        }
+    }
+
+    /// Square root
+    pub fn sqrt(self) -> Self {
+        let v = self.v.sqrt();
+        Grad64 {
+            v,
+            dx: self.dx / (2.0 * v),
+            dy: self.dy / (2.0 * v),
+            dz: self.dz / (2.0 * v),
+        }
+    }
+
+    /// Sine
+    pub fn sin(self) -> Self {
+        let c = self.v.cos();
+        Grad64 {
+            v: self.v.sin(),
+            dx: self.dx * c,
+            dy: self.dy * c,
+            dz: self.dz * c,
+        }
+    }
+    /// Cosine
+    pub fn cos(self) -> Self {
+        let s = -self.v.sin();
+        Grad64 {
+            v: self.v.cos(),
+            dx: self.dx * s,
+            dy: self.dy * s,
+            dz: self.dz * s,
+        }
+    }
+    /// Tangent
+    pub fn tan(self) -> Self {
+        let c = self.v.cos().powi(2);
+        Grad64 {
+            v: self.v.tan(),
+            dx: self.dx / c,
+            dy: self.dy / c,
+            dz: self.dz / c,
+        }
+    }
+    /// Arcsin
+    pub fn asin(self) -> Self {
+        let r = (1.0 - self.v.powi(2)).sqrt();
+        Grad64 {
+            v: self.v.asin(),
+            dx: self.dx / r,
+            dy: self.dy / r,
+            dz: self.dz / r,
+        }
+    }
+    /// Arccos
+    pub fn acos(self) -> Self {
+        let r = (1.0 - self.v.powi(2)).sqrt();
+        Grad64 {
+            v: self.v.acos(),
+            dx: -self.dx / r,
+            dy: -self.dy / r,
+            dz: -self.dz / r,
+        }
+    }
+    /// Arctangent
+    pub fn atan(self) -> Self {
+        let r = self.v.powi(2) + 1.0;
+        Grad64 {
+            v: self.v.atan(),
+            dx: self.dx / r,
+            dy: self.dy / r,
+            dz: self.dz / r,
+        }
+    }
+    /// Exponential function
+    pub fn exp(self) -> Self {
+        let v = self.v.exp();
+        Grad64 {
+            v,
+            dx: v * self.dx,
+            dy: v * self.dy,
+            dz: v * self.dz,
+        }
+    }
+    /// Natural log
+    pub fn ln(self) -> Self {
+        Grad64 {
+            v: self.v.ln(),
+            dx: self.dx / self.v,
+            dy: self.dy / self.v,
+            dz: self.dz / self.v,
+        }
+    }
+    /// Hyperbolic sine
+    pub fn sinh(self) -> Self {
+        let c = self.v.cosh();
+        Grad64 {
+            v: self.v.sinh(),
+            dx: self.dx * c,
+            dy: self.dy * c,
+            dz: self.dz * c,
+        }
+    }
+    /// Hyperbolic cosine
+    pub fn cosh(self) -> Self {
+        let s = self.v.sinh();
+        Grad64 {
+            v: self.v.cosh(),
+            dx: self.dx * s,
+            dy: self.dy * s,
+            dz: self.dz * s,
+        }
+    }
+    /// Hyperbolic tangent
+    pub fn tanh(self) -> Self {
+        let v = self.v.tanh();
+        let d = 1.0 - v.powi(2);
+        Grad64 {
+            v,
+            dx: self.dx * d,
+            dy: self.dy * d,
+            dz: self.dz * d,
+        }
+    }
+    /// Base-2 exponential function
+    pub fn exp2(self) -> Self {
+        let v = self.v.exp2();
+        let d = v * std::f64::consts::LN_2;
+        Grad64 {
+            v,
+            dx: d * self.dx,
+            dy: d * self.dy,
+            dz: d * self.dz,
+        }
+    }
+    /// Base-2 log
+    pub fn log2(self) -> Self {
+        let d = self.v * std::f64::consts::LN_2;
+        Grad64 {
+            v: self.v.log2(),
+            dx: self.dx / d,
+            dy: self.dy / d,
+            dz: self.dz / d,
+        }
+    }
+    /// Base-10 log
+    pub fn log10(self) -> Self {
+        let d = self.v * std::f64::consts::LN_10;
+        Grad64 {
+            v: self.v.log10(),
+            dx: self.dx / d,
+            dy: self.dy / d,
+            dz: self.dz / d,
+        }
+    }
+
+    /// Reciprocal
+    pub fn recip(self) -> Self {
+        let v2 = -self.v.powi(2);
+        Grad64 {
+            v: 1.0 / self.v,
+            dx: self.dx / v2,
+            dy: self.dy / v2,
+            dz: self.dz / v2,
+        }
+    }
+
+    /// Minimum of two values
+    pub fn min(self, rhs: Self) -> Self {
+        if self.v < rhs.v {
+            self
+        } else {
+            rhs
+        }
+    }
+
+    /// Maximum of two values
+    pub fn max(self, rhs: Self) -> Self {
+        if self.v > rhs.v {
+            self
+        } else {
+            rhs
+        }
+    }
+
+    /// Polynomial smooth minimum of two values, blending within a radius `k`
+    pub fn smooth_min(self, rhs: Self, k: Self) -> Self {
+        let m = self.min(rhs);
+        let h = k - (self - rhs).abs();
+        if h.v > 0.0 {
+            m - h * h / (k * 4.0)
+        } else {
+            m
+        }
+    }
+
+    /// Least non-negative remainder
+    pub fn rem_euclid(&self, rhs: Grad64) -> Self {
+        let e = self.v.div_euclid(rhs.v);
+        Grad64 {
+            v: self.v.rem_euclid(rhs.v),
+            dx: self.dx - rhs.dx * e,
+            dy: self.dy - rhs.dy * e,
+            dz: self.dz - rhs.dz * e,
+        }
+    }
+
+    /// Snap to the largest less-than-or-equal value
+    pub fn floor(&self) -> Self {
+        Grad64 {
+            v: self.v.floor(),
+            dx: 0.0,
+            dy: 0.0,
+            dz: 0.0,
+        }
+    }
+
+    /// Snap to the smallest greater-than-or-equal value
+    pub fn ceil(&self) -> Self {
+        Grad64 {
+            v: self.v.ceil(),
+            dx: 0.0,
+            dy: 0.0,
+            dz: 0.0,
+        }
+    }
+
+    /// Rounds to the nearest integer
+    pub fn round(&self) -> Self {
+        Grad64 {
+            v: self.v.round(),
+            dx: 0.0,
+            dy: 0.0,
+            dz: 0.0,
+        }
+    }
+
+    /// Four-quadrant arctangent
+    pub fn atan2(self, x: Self) -> Self {
+        let y = self;
+
+        let d = x.v.powi(2) + y.v.powi(2);
+        Grad64 {
+            v: y.v.atan2(x.v),
+            dx: (x.v * y.dx - y.v * x.dx) / d,
+            dy: (x.v * y.dy - y.v * x.dy) / d,
+            dz: (x.v * y.dz - y.v * x.dz) / d,
+        }
+    }
+
+    /// Raises this value to the power of another value
+    ///
+    /// Partial derivatives are only accumulated from arguments with a non-zero
+    /// derivative, so a constant exponent with a negative base (where
+    /// `ln(base)` would be `NaN`) still produces a valid gradient.
+    pub fn pow(self, rhs: Self) -> Self {
+        let v = self.v.powf(rhs.v);
+        let da = rhs.v * self.v.powf(rhs.v - 1.0);
+        let db = v * self.v.ln();
+        let d = |a: f64, b: f64| {
+            let mut out = 0.0;
+            if a != 0.0 {
+                out += da * a;
+            }
+            if b != 0.0 {
+                out += db * b;
+            }
+            out
+        };
+        Grad64 {
+            v,
+            dx: d(self.dx, rhs.dx),
+            dy: d(self.dy, rhs.dy),
+            dz: d(self.dz, rhs.dz),
+        }
+    }
+}
+
+impl From<f64> for Grad64 {
+    fn from(v: f64) -> Self {
+        Grad64 {
+            v,
+            dx: 0.0,
+            dy: 0.0,
+            dz: 0.0,
+        }
+    }
+}
+
+impl From<f32> for Grad64 {
+    fn from(v: f32) -> Self {
+        f64::from(v).into()
+    }
+}
+
+impl From<Grad> for Grad64 {
+    fn from(g: Grad) -> Self {
+        Grad64::new(g.v.into(), g.dx.into(), g.dy.into(), g.dz.into())
+    }
+}
+
+impl From<Grad64> for Grad {
+    fn from(g: Grad64) -> Self {
+        Grad::new(g.v as f32, g.dx as f32, g.dy as f32, g.dz as f32)
+    }
+}
+
+impl From<Grad64> for nalgebra::Vector4<f64> {
+    fn from(g: Grad64) -> Self {
+        nalgebra::Vector4::new(g.dx, g.dy, g.dz, g.v)
+    }
+}
+
+impl std::ops::Add<Grad64> for Grad64 {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Grad64 {
+            v: self.v + rhs.v,
+            dx: self.dx + rhs.dx,
+            dy: self.dy + rhs.dy,
+            dz: self.dz + rhs.dz,
+        }
+    }
+}
+
+impl std::ops::Mul<Grad64> for Grad64 {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        Self {
+            v: self.v * rhs.v,
+            dx: self.v * rhs.dx + rhs.v * self.dx,
+            dy: self.v * rhs.dy + rhs.v * self.dy,
+            dz: self.v * rhs.dz + rhs.v * self.dz,
+        }
+    }
+}
+
+impl std::ops::Mul<f64> for Grad64 {
+    type Output = Self;
+    fn mul(self, rhs: f64) -> Self {
+        Self {
+            v: self.v * rhs,
+            dx: self.dx * rhs,
+            dy: self.dy * rhs,
+            dz: self.dz * rhs,
+        }
+    }
+}
+
+impl std::ops::Div<Grad64> for Grad64 {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self {
+        let d = rhs.v.powi(2);
+        Self {
+            v: self.v / rhs.v,
+            dx: (rhs.v * self.dx - self.v * rhs.dx) / d,
+            dy: (rhs.v * self.dy - self.v * rhs.dy) / d,
+            dz: (rhs.v * self.dz - self.v * rhs.dz) / d,
+        }
+    }
+}
+
+impl std::ops::Sub<Grad64> for Grad64 {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Self {
+            v: self.v - rhs.v,
+            dx: self.dx - rhs.dx,
+            dy: self.dy - rhs.dy,
+            dz: self.dz - rhs.dz,
+        }
+    }
+}
+
+impl std::ops::Neg for Grad64 {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self {
+            v: -self.v,
+            dx: -self.dx,
+            dy: -self.dy,
+            dz: -self.dz,
+        }
+    }
+}
@@ -0,0 +1,367 @@
+/// A value with partial derivatives with respect to `N` arbitrary inputs
+///
+/// This is a generalization of [`Grad`](crate::types::Grad), which always
+/// stores partial derivatives with respect to `x`, `y`, and `z`.  With a
+/// `GradN`, the caller picks which variables are differentiated (by seeding
+/// their inputs with [`GradN::input`]), so derivatives with respect to any set
+/// of [`Var`](crate::var::Var) values can be computed in a single pass.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GradN<const N: usize> {
+    /// Value of the function at this point
+    pub v: f32,
+    /// Partial derivatives with respect to each seeded input
+    pub d: [f32; N],
+}
+
+impl<const N: usize> Default for GradN<N> {
+    fn default() -> Self {
+        Self::from(0.0)
+    }
+}
+
+impl<const N: usize> std::fmt::Display for GradN<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}", self.v)?;
+        for d in &self.d {
+            write!(f, ", {d}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl<const N: usize> GradN<N> {
+    /// Constructs a new value with the given partial derivatives
+    pub fn new(v: f32, d: [f32; N]) -> Self {
+        Self { v, d }
+    }
+
+    /// Constructs an input value, with a unit derivative for partial `i`
+    ///
+    /// # Panics
+    /// If `i` is not less than `N`
+    pub fn input(v: f32, i: usize) -> Self {
+        assert!(i < N, "invalid index {i}");
+        let mut d = [0.0; N];
+        d[i] = 1.0;
+        Self { v, d }
+    }
+
+    /// Applies the chain rule, mapping each partial derivative with `f`
+    fn chain(self, v: f32, f: impl Fn(f32) -> f32) -> Self {
+        Self {
+            v,
+            d: self.d.map(f),
+        }
+    }
+
+    /// Combines partial derivatives from two values
+    fn zip(self, rhs: Self, f: impl Fn(f32, f32) -> f32) -> [f32; N] {
+        std::array::from_fn(|i| f(self.d[i], rhs.d[i]))
+    }
+
+    /// Absolute value
+    pub fn abs(self) -> Self {
+        if self.v < 0.0 {
+            -self
+        } else {
+            self
+        }
+    }
+
+    /// Square root
+    pub fn sqrt(self) -> Self {
+        let v = self.v.sqrt();
+        self.chain(v, |d| d / (2.0 * v))
+    }
+
+    /// Sine
+    pub fn sin(self) -> Self {
+        let c = self.v.cos();
+        self.chain(self.v.sin(), |d| d * c)
+    }
+    /// Cosine
+    pub fn cos(self) -> Self {
+        let s = -self.v.sin();
+        self.chain(self.v.cos(), |d| d * s)
+    }
+    /// Tangent
+    pub fn tan(self) -> Self {
+        let c = self.v.cos().powi(2);
+        self.chain(self.v.tan(), |d| d / c)
+    }
+    /// Arcsin
+    pub fn asin(self) -> Self {
+        let r = (1.0 - self.v.powi(2)).sqrt();
+        self.chain(self.v.asin(), |d| d / r)
+    }
+    /// Arccos
+    pub fn acos(self) -> Self {
+        let r = (1.0 - self.v.powi(2)).sqrt();
+        self.chain(self.v.acos(), |d| -d / r)
+    }
+    /// Arctangent
+    pub fn atan(self) -> Self {
+        let r = self.v.powi(2) + 1.0;
+        self.chain(self.v.atan(), |d| d / r)
+    }
+    /// Exponential function
+    pub fn exp(self) -> Self {
+        let v = self.v.exp();
+        self.chain(v, |d| v * d)
+    }
+    /// Natural log
+    pub fn ln(self) -> Self {
+        self.chain(self.v.ln(), |d| d / self.v)
+    }
+    /// Hyperbolic sine
+    pub fn sinh(self) -> Self {
+        let c = self.v.cosh();
+        self.chain(self.v.sinh(), |d| d * c)
+    }
+    /// Hyperbolic cosine
+    pub fn cosh(self) -> Self {
+        let s = self.v.sinh();
+        self.chain(self.v.cosh(), |d| d * s)
+    }
+    /// Hyperbolic tangent
+    pub fn tanh(self) -> Self {
+        let v = self.v.tanh();
+        let g = 1.0 - v.powi(2);
+        self.chain(v, |d| d * g)
+    }
+    /// Base-2 exponential function
+    pub fn exp2(self) -> Self {
+        let v = self.v.exp2();
+        let g = v * std::f32::consts::LN_2;
+        self.chain(v, |d| g * d)
+    }
+    /// Base-2 log
+    pub fn log2(self) -> Self {
+        let g = self.v * std::f32::consts::LN_2;
+        self.chain(self.v.log2(), |d| d / g)
+    }
+    /// Base-10 log
+    pub fn log10(self) -> Self {
+        let g = self.v * std::f32::consts::LN_10;
+        self.chain(self.v.log10(), |d| d / g)
+    }
+
+    /// Reciprocal
+    pub fn recip(self) -> Self {
+        let v2 = -self.v.powi(2);
+        self.chain(1.0 / self.v, |d| d / v2)
+    }
+
+    /// Minimum of two values
+    pub fn min(self, rhs: Self) -> Self {
+        if self.v < rhs.v {
+            self
+        } else {
+            rhs
+        }
+    }
+
+    /// Maximum of two values
+    pub fn max(self, rhs: Self) -> Self {
+        if self.v > rhs.v {
+            self
+        } else {
+            rhs
+        }
+    }
+
+    /// Polynomial smooth minimum of two values, blending within a radius `k`
+    pub fn smooth_min(self, rhs: Self, k: Self) -> Self {
+        let m = self.min(rhs);
+        let h = k - (self - rhs).abs();
+        if h.v > 0.0 {
+            m - h * h / (k * 4.0)
+        } else {
+            m
+        }
+    }
+
+    /// Least non-negative remainder
+    pub fn rem_euclid(&self, rhs: Self) -> Self {
+        let e = self.v.div_euclid(rhs.v);
+        Self {
+            v: self.v.rem_euclid(rhs.v),
+            d: self.zip(rhs, |a, b| a - b * e),
+        }
+    }
+
+    /// Snap to the largest less-than-or-equal value
+    pub fn floor(&self) -> Self {
+        self.v.floor().into()
+    }
+
+    /// Snap to the smallest greater-than-or-equal value
+    pub fn ceil(&self) -> Self {
+        self.v.ceil().into()
+    }
+
+    /// Rounds to the nearest integer
+    pub fn round(&self) -> Self {
+        self.v.round().into()
+    }
+
+    /// Four-quadrant arctangent
+    pub fn atan2(self, x: Self) -> Self {
+        let y = self;
+
+        let d = x.v.powi(2) + y.v.powi(2);
+        Self {
+            v: y.v.atan2(x.v),
+            d: y.zip(x, |dy, dx| (x.v * dy - y.v * dx) / d),
+        }
+    }
+
+    /// Raises this value to the power of another value
+    ///
+    /// As with [`Grad::pow`](crate::types::Grad::pow), partial derivatives are
+    /// only accumulated from arguments with a non-zero derivative.
+    pub fn pow(self, rhs: Self) -> Self {
+        let v = self.v.powf(rhs.v);
+        let da = rhs.v * self.v.powf(rhs.v - 1.0);
+        let db = v * self.v.ln();
+        Self {
+            v,
+            d: self.zip(rhs, |a, b| {
+                let mut out = 0.0;
+                if a != 0.0 {
+                    out += da * a;
+                }
+                if b != 0.0 {
+                    out += db * b;
+                }
+                out
+            }),
+        }
+    }
+}
+
+impl<const N: usize> From<f32> for GradN<N> {
+    fn from(v: f32) -> Self {
+        Self { v, d: [0.0; N] }
+    }
+}
+
+impl From<crate::types::Grad> for GradN<3> {
+    fn from(g: crate::types::Grad) -> Self {
+        Self {
+            v: g.v,
+            d: [g.dx, g.dy, g.dz],
+        }
+    }
+}
+
+impl From<GradN<3>> for crate::types::Grad {
+    fn from(g: GradN<3>) -> Self {
+        Self::new(g.v, g.d[0], g.d[1], g.d[2])
+    }
+}
+
+impl<const N: usize> std::ops::Add<GradN<N>> for GradN<N> {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            v: self.v + rhs.v,
+            d: self.zip(rhs, |a, b| a + b),
+        }
+    }
+}
+
+impl<const N: usize> std::ops::Mul<GradN<N>> for GradN<N> {
+    type Output = Self;
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn mul(self, rhs: Self) -> Self {
+        Self {
+            v: self.v * rhs.v,
+            d: self.zip(rhs, |a, b| self.v * b + rhs.v * a),
+        }
+    }
+}
+
+impl<const N: usize> std::ops::Mul<f32> for GradN<N> {
+    type Output = Self;
+    fn mul(self, rhs: f32) -> Self {
+        self.chain(self.v * rhs, |d| d * rhs)
+    }
+}
+
+impl<const N: usize> std::ops::Div<GradN<N>> for GradN<N> {
+    type Output = Self;
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn div(self, rhs: Self) -> Self {
+        let d = rhs.v.powi(2);
+        Self {
+            v: self.v / rhs.v,
+            d: self.zip(rhs, |a, b| (rhs.v * a - self.v * b) / d),
+        }
+    }
+}
+
+impl<const N: usize> std::ops::Sub<GradN<N>> for GradN<N> {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Self {
+            v: self.v - rhs.v,
+            d: self.zip(rhs, |a, b| a - b),
+        }
+    }
+}
+
+impl<const N: usize> std::ops::Neg for GradN<N> {
+    type Output = Self;
+    fn neg(self) -> Self {
+        self.chain(-self.v, |d| -d)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::Grad;
+
+    #[test]
+    fn test_grad_n_matches_grad() {
+        let a = Grad::new(0.7, 1.0, 0.0, 0.5);
+        let b = Grad::new(1.3, 0.0, 1.0, -2.0);
+        let (an, bn) = (GradN::<3>::from(a), GradN::<3>::from(b));
+        for (name, g, n) in [
+            ("add", a + b, an + bn),
+            ("sub", a - b, an - bn),
+            ("mul", a * b, an * bn),
+            ("div", a / b, an / bn),
+            ("atan2", a.atan2(b), an.atan2(bn)),
+            ("pow", a.pow(b), an.pow(bn)),
+            ("rem", b.rem_euclid(a), bn.rem_euclid(an)),
+            (
+                "smin",
+                a.smooth_min(b, 1.0.into()),
+                an.smooth_min(bn, 1.0.into()),
+            ),
+            ("sqrt", a.sqrt(), an.sqrt()),
+            ("sin", a.sin(), an.sin()),
+            ("asin", a.asin(), an.asin()),
+            ("acos", a.acos(), an.acos()),
+            ("exp2", a.exp2(), an.exp2()),
+            ("log10", a.log10(), an.log10()),
+            ("recip", a.recip(), an.recip()),
+            ("neg", -a, -an),
+        ] {
+            assert_eq!(g, Grad::from(n), "mismatch in {name}");
+        }
+    }
+
+    #[test]
+    fn test_grad_n_input() {
+        let x = GradN::<5>::input(2.0, 0);
+        let p = GradN::<5>::input(3.0, 4);
+        let out = (x * p).sin();
+        assert_eq!(out.v, 6f32.sin());
+        assert_eq!(out.d[0], 3.0 * 6f32.cos());
+        assert_eq!(out.d[1..4], [0.0; 3]);
+        assert_eq!(out.d[4], 2.0 * 6f32.cos());
+    }
+}
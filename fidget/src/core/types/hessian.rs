@@ -0,0 +1,503 @@
+/// A point in space with first and second partial derivatives
+///
+/// This is a forward-mode hyper-dual number: in addition to the value and
+/// gradient (as in [`Grad`](crate::types::Grad)), it carries the symmetric 3×3
+/// Hessian matrix of second partial derivatives with respect to `x`, `y`, and
+/// `z`.  It's used to compute surface curvature (see
+/// [`mean_curvature`](Self::mean_curvature) and
+/// [`gaussian_curvature`](Self::gaussian_curvature)).
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Hessian {
+    /// Value of the distance field at this point
+    pub v: f32,
+    /// Partial derivatives with respect to `x`, `y`, and `z`
+    pub d: [f32; 3],
+    /// Second partial derivatives, where `h[i][j]` is `∂²f / ∂i ∂j`
+    ///
+    /// This matrix is symmetric.
+    pub h: [[f32; 3]; 3],
+}
+
+impl std::fmt::Display for Hessian {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {:?}, {:?})", self.v, self.d, self.h)
+    }
+}
+
+impl Hessian {
+    /// Constructs a new value with the given derivatives
+    pub fn new(v: f32, d: [f32; 3], h: [[f32; 3]; 3]) -> Self {
+        Self { v, d, h }
+    }
+
+    /// Constructs an input value along the given axis (0 = x, 1 = y, 2 = z)
+    ///
+    /// # Panics
+    /// If the index is not in the 0-2 range
+    pub fn input(v: f32, i: usize) -> Self {
+        assert!(i < 3, "invalid index {i}");
+        let mut d = [0.0; 3];
+        d[i] = 1.0;
+        Self {
+            v,
+            d,
+            h: [[0.0; 3]; 3],
+        }
+    }
+
+    /// Mean curvature of the isosurface passing through this point
+    ///
+    /// This is half the divergence of the unit normal, so it's positive for
+    /// convex shapes that are negative inside (e.g. `1 / r` for a sphere of
+    /// radius `r`).
+    pub fn mean_curvature(&self) -> f32 {
+        let g = self.d;
+        let n2 = dot(g, g);
+        let ghg = dot(g, mat_vec(self.h, g));
+        let trace = self.h[0][0] + self.h[1][1] + self.h[2][2];
+        (n2 * trace - ghg) / (2.0 * n2.powf(1.5))
+    }
+
+    /// Gaussian curvature of the isosurface passing through this point
+    ///
+    /// This is the product of the two principal curvatures (e.g. `1 / r²` for
+    /// a sphere of radius `r`).
+    pub fn gaussian_curvature(&self) -> f32 {
+        let g = self.d;
+        let h = self.h;
+        let adj = [
+            [
+                h[1][1] * h[2][2] - h[1][2] * h[2][1],
+                h[0][2] * h[2][1] - h[0][1] * h[2][2],
+                h[0][1] * h[1][2] - h[0][2] * h[1][1],
+            ],
+            [
+                h[1][2] * h[2][0] - h[1][0] * h[2][2],
+                h[0][0] * h[2][2] - h[0][2] * h[2][0],
+                h[0][2] * h[1][0] - h[0][0] * h[1][2],
+            ],
+            [
+                h[1][0] * h[2][1] - h[1][1] * h[2][0],
+                h[0][1] * h[2][0] - h[0][0] * h[2][1],
+                h[0][0] * h[1][1] - h[0][1] * h[1][0],
+            ],
+        ];
+        dot(g, mat_vec(adj, g)) / dot(g, g).powi(2)
+    }
+
+    /// Applies the chain rule for a unary function `f`
+    ///
+    /// `d1` and `d2` are the first and second derivatives of `f` at this value.
+    fn chain(self, v: f32, d1: f32, d2: f32) -> Self {
+        Self {
+            v,
+            d: self.d.map(|d| d * d1),
+            h: std::array::from_fn(|i| {
+                std::array::from_fn(|j| {
+                    self.h[i][j] * d1 + self.d[i] * self.d[j] * d2
+                })
+            }),
+        }
+    }
+
+    /// Absolute value
+    pub fn abs(self) -> Self {
+        if self.v < 0.0 {
+            -self
+        } else {
+            self
+        }
+    }
+
+    /// Square root
+    pub fn sqrt(self) -> Self {
+        let v = self.v.sqrt();
+        self.chain(v, 1.0 / (2.0 * v), -1.0 / (4.0 * v.powi(3)))
+    }
+
+    /// Sine
+    pub fn sin(self) -> Self {
+        let s = self.v.sin();
+        self.chain(s, self.v.cos(), -s)
+    }
+    /// Cosine
+    pub fn cos(self) -> Self {
+        let c = self.v.cos();
+        self.chain(c, -self.v.sin(), -c)
+    }
+    /// Tangent
+    pub fn tan(self) -> Self {
+        let t = self.v.tan();
+        let s = 1.0 / self.v.cos().powi(2);
+        self.chain(t, s, 2.0 * t * s)
+    }
+    /// Arcsin
+    pub fn asin(self) -> Self {
+        let r = 1.0 - self.v.powi(2);
+        self.chain(self.v.asin(), 1.0 / r.sqrt(), self.v / r.powf(1.5))
+    }
+    /// Arccos
+    pub fn acos(self) -> Self {
+        let r = 1.0 - self.v.powi(2);
+        self.chain(self.v.acos(), -1.0 / r.sqrt(), -self.v / r.powf(1.5))
+    }
+    /// Arctangent
+    pub fn atan(self) -> Self {
+        let r = self.v.powi(2) + 1.0;
+        self.chain(self.v.atan(), 1.0 / r, -2.0 * self.v / r.powi(2))
+    }
+    /// Exponential function
+    pub fn exp(self) -> Self {
+        let v = self.v.exp();
+        self.chain(v, v, v)
+    }
+    /// Natural log
+    pub fn ln(self) -> Self {
+        self.chain(self.v.ln(), 1.0 / self.v, -1.0 / self.v.powi(2))
+    }
+    /// Hyperbolic sine
+    pub fn sinh(self) -> Self {
+        let s = self.v.sinh();
+        self.chain(s, self.v.cosh(), s)
+    }
+    /// Hyperbolic cosine
+    pub fn cosh(self) -> Self {
+        let c = self.v.cosh();
+        self.chain(c, self.v.sinh(), c)
+    }
+    /// Hyperbolic tangent
+    pub fn tanh(self) -> Self {
+        let t = self.v.tanh();
+        let g = 1.0 - t.powi(2);
+        self.chain(t, g, -2.0 * t * g)
+    }
+    /// Base-2 exponential function
+    pub fn exp2(self) -> Self {
+        let v = self.v.exp2();
+        let g = v * std::f32::consts::LN_2;
+        self.chain(v, g, g * std::f32::consts::LN_2)
+    }
+    /// Base-2 log
+    pub fn log2(self) -> Self {
+        let g = self.v * std::f32::consts::LN_2;
+        self.chain(self.v.log2(), 1.0 / g, -1.0 / (g * self.v))
+    }
+    /// Base-10 log
+    pub fn log10(self) -> Self {
+        let g = self.v * std::f32::consts::LN_10;
+        self.chain(self.v.log10(), 1.0 / g, -1.0 / (g * self.v))
+    }
+
+    /// Reciprocal
+    pub fn recip(self) -> Self {
+        let v2 = self.v.powi(2);
+        self.chain(1.0 / self.v, -1.0 / v2, 2.0 / (v2 * self.v))
+    }
+
+    /// Minimum of two values
+    pub fn min(self, rhs: Self) -> Self {
+        if self.v < rhs.v {
+            self
+        } else {
+            rhs
+        }
+    }
+
+    /// Maximum of two values
+    pub fn max(self, rhs: Self) -> Self {
+        if self.v > rhs.v {
+            self
+        } else {
+            rhs
+        }
+    }
+
+    /// Polynomial smooth minimum of two values, blending within a radius `k`
+    pub fn smooth_min(self, rhs: Self, k: Self) -> Self {
+        let m = self.min(rhs);
+        let h = k - (self - rhs).abs();
+        if h.v > 0.0 {
+            m - h * h / (k * 4.0)
+        } else {
+            m
+        }
+    }
+
+    /// Least non-negative remainder
+    pub fn rem_euclid(&self, rhs: Self) -> Self {
+        let e = self.v.div_euclid(rhs.v);
+        Self {
+            v: self.v.rem_euclid(rhs.v),
+            d: std::array::from_fn(|i| self.d[i] - rhs.d[i] * e),
+            h: std::array::from_fn(|i| {
+                std::array::from_fn(|j| self.h[i][j] - rhs.h[i][j] * e)
+            }),
+        }
+    }
+
+    /// Snap to the largest less-than-or-equal value
+    pub fn floor(&self) -> Self {
+        self.v.floor().into()
+    }
+
+    /// Snap to the smallest greater-than-or-equal value
+    pub fn ceil(&self) -> Self {
+        self.v.ceil().into()
+    }
+
+    /// Rounds to the nearest integer
+    pub fn round(&self) -> Self {
+        self.v.round().into()
+    }
+
+    /// Four-quadrant arctangent
+    pub fn atan2(self, x: Self) -> Self {
+        let y = self;
+
+        let r = x.v.powi(2) + y.v.powi(2);
+        let r2 = r.powi(2);
+        let xy = 2.0 * x.v * y.v;
+        Self {
+            v: y.v.atan2(x.v),
+            d: std::array::from_fn(|i| (x.v * y.d[i] - y.v * x.d[i]) / r),
+            h: std::array::from_fn(|i| {
+                std::array::from_fn(|j| {
+                    (x.v * y.h[i][j] - y.v * x.h[i][j]) / r
+                        + (-xy * y.d[i] * y.d[j]
+                            + (y.v.powi(2) - x.v.powi(2))
+                                * (y.d[i] * x.d[j] + x.d[i] * y.d[j])
+                            + xy * x.d[i] * x.d[j])
+                            / r2
+                })
+            }),
+        }
+    }
+
+    /// Raises this value to the power of another value
+    ///
+    /// As with [`Grad::pow`](crate::types::Grad::pow), terms are only
+    /// accumulated from derivatives which are non-zero (so that, for example,
+    /// `x^2` has a well-defined Hessian at negative `x`, even though
+    /// `ln(x)` is `NaN`).
+    pub fn pow(self, rhs: Self) -> Self {
+        let (a, b) = (self.v, rhs.v);
+        let v = a.powf(b);
+        let ln = a.ln();
+        let da = b * a.powf(b - 1.0);
+        let db = v * ln;
+        let daa = b * (b - 1.0) * a.powf(b - 2.0);
+        let dab = a.powf(b - 1.0) * (1.0 + b * ln);
+        let dbb = db * ln;
+
+        let term = |c: f32, x: f32| if x != 0.0 { c * x } else { 0.0 };
+        Self {
+            v,
+            d: std::array::from_fn(|i| {
+                term(da, self.d[i]) + term(db, rhs.d[i])
+            }),
+            h: std::array::from_fn(|i| {
+                std::array::from_fn(|j| {
+                    term(da, self.h[i][j])
+                        + term(db, rhs.h[i][j])
+                        + term(daa, self.d[i] * self.d[j])
+                        + term(dab, self.d[i] * rhs.d[j] + rhs.d[i] * self.d[j])
+                        + term(dbb, rhs.d[i] * rhs.d[j])
+                })
+            }),
+        }
+    }
+}
+
+/// Dot product of two vectors
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// Product of a matrix and a vector
+fn mat_vec(m: [[f32; 3]; 3], v: [f32; 3]) -> [f32; 3] {
+    m.map(|row| dot(row, v))
+}
+
+impl From<f32> for Hessian {
+    fn from(v: f32) -> Self {
+        Self {
+            v,
+            d: [0.0; 3],
+            h: [[0.0; 3]; 3],
+        }
+    }
+}
+
+impl From<Hessian> for crate::types::Grad {
+    fn from(h: Hessian) -> Self {
+        Self::new(h.v, h.d[0], h.d[1], h.d[2])
+    }
+}
+
+impl std::ops::Add<Hessian> for Hessian {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            v: self.v + rhs.v,
+            d: std::array::from_fn(|i| self.d[i] + rhs.d[i]),
+            h: std::array::from_fn(|i| {
+                std::array::from_fn(|j| self.h[i][j] + rhs.h[i][j])
+            }),
+        }
+    }
+}
+
+impl std::ops::Mul<Hessian> for Hessian {
+    type Output = Self;
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn mul(self, rhs: Self) -> Self {
+        Self {
+            v: self.v * rhs.v,
+            d: std::array::from_fn(|i| self.v * rhs.d[i] + rhs.v * self.d[i]),
+            h: std::array::from_fn(|i| {
+                std::array::from_fn(|j| {
+                    self.v * rhs.h[i][j]
+                        + rhs.v * self.h[i][j]
+                        + self.d[i] * rhs.d[j]
+                        + rhs.d[i] * self.d[j]
+                })
+            }),
+        }
+    }
+}
+
+impl std::ops::Mul<f32> for Hessian {
+    type Output = Self;
+    fn mul(self, rhs: f32) -> Self {
+        Self {
+            v: self.v * rhs,
+            d: self.d.map(|d| d * rhs),
+            h: self.h.map(|row| row.map(|h| h * rhs)),
+        }
+    }
+}
+
+impl std::ops::Div<Hessian> for Hessian {
+    type Output = Self;
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn div(self, rhs: Self) -> Self {
+        let (a, b) = (self.v, rhs.v);
+        let b2 = b.powi(2);
+        let b3 = b2 * b;
+        Self {
+            v: a / b,
+            d: std::array::from_fn(|i| (b * self.d[i] - a * rhs.d[i]) / b2),
+            h: std::array::from_fn(|i| {
+                std::array::from_fn(|j| {
+                    self.h[i][j] / b
+                        - a * rhs.h[i][j] / b2
+                        - (self.d[i] * rhs.d[j] + rhs.d[i] * self.d[j]) / b2
+                        + 2.0 * a * rhs.d[i] * rhs.d[j] / b3
+                })
+            }),
+        }
+    }
+}
+
+impl std::ops::Sub<Hessian> for Hessian {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Self {
+            v: self.v - rhs.v,
+            d: std::array::from_fn(|i| self.d[i] - rhs.d[i]),
+            h: std::array::from_fn(|i| {
+                std::array::from_fn(|j| self.h[i][j] - rhs.h[i][j])
+            }),
+        }
+    }
+}
+
+impl std::ops::Neg for Hessian {
+    type Output = Self;
+    fn neg(self) -> Self {
+        self * -1.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::Grad;
+
+    #[test]
+    fn test_hessian_matches_grad() {
+        let a = Grad::new(0.7, 1.0, 0.0, 0.5);
+        let b = Grad::new(1.3, 0.0, 1.0, -2.0);
+        let ha = Hessian::new(0.7, [1.0, 0.0, 0.5], [[0.0; 3]; 3]);
+        let hb = Hessian::new(1.3, [0.0, 1.0, -2.0], [[0.0; 3]; 3]);
+        for (name, g, h) in [
+            ("add", a + b, ha + hb),
+            ("sub", a - b, ha - hb),
+            ("mul", a * b, ha * hb),
+            ("div", a / b, ha / hb),
+            ("atan2", a.atan2(b), ha.atan2(hb)),
+            ("pow", a.pow(b), ha.pow(hb)),
+            ("rem", b.rem_euclid(a), hb.rem_euclid(ha)),
+            ("sqrt", a.sqrt(), ha.sqrt()),
+            ("sin", a.sin(), ha.sin()),
+            ("exp2", a.exp2(), ha.exp2()),
+            ("neg", -a, -ha),
+        ] {
+            let h = Grad::from(h);
+            for (x, y) in [(g.v, h.v), (g.dx, h.dx), (g.dy, h.dy), (g.dz, h.dz)]
+            {
+                assert!((x - y).abs() < 1e-6, "mismatch in {name}: {g} != {h}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_hessian_product() {
+        // f = x² y, so ∂²f/∂x² = 2y, ∂²f/∂x∂y = 2x, ∂²f/∂y² = 0
+        let x = Hessian::input(3.0, 0);
+        let y = Hessian::input(5.0, 1);
+        let f = x * x * y;
+        assert_eq!(f.v, 45.0);
+        assert_eq!(f.d, [30.0, 9.0, 0.0]);
+        assert_eq!(f.h, [[10.0, 6.0, 0.0], [6.0, 0.0, 0.0], [0.0; 3]]);
+
+        // Division should give the same result
+        let g = (x * x * y * y) / y;
+        assert_eq!(g.v, 45.0);
+        for i in 0..3 {
+            assert!((f.d[i] - g.d[i]).abs() < 1e-5);
+            for j in 0..3 {
+                assert!((f.h[i][j] - g.h[i][j]).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_hessian_curvature() {
+        // Sphere with radius 2, at a point on its surface
+        let (x, y, z) = (
+            Hessian::input(1.2, 0),
+            Hessian::input(-1.6, 1),
+            Hessian::input(0.0, 2),
+        );
+        let s = (x * x + y * y + z * z).sqrt() - 2.0.into();
+        assert!(s.v.abs() < 1e-6);
+        assert!((s.mean_curvature() - 0.5).abs() < 1e-6);
+        assert!((s.gaussian_curvature() - 0.25).abs() < 1e-6);
+
+        // Curvature doesn't depend on scale
+        let s = s * 3.0;
+        assert!((s.mean_curvature() - 0.5).abs() < 1e-6);
+        assert!((s.gaussian_curvature() - 0.25).abs() < 1e-6);
+
+        // Cylinder with radius 2 about the Z axis
+        let c = (x * x + y * y).sqrt() - 2.0.into();
+        assert!((c.mean_curvature() - 0.25).abs() < 1e-6);
+        assert!(c.gaussian_curvature().abs() < 1e-6);
+
+        // A plane has no curvature
+        let p = x + y * 2.0;
+        assert_eq!(p.mean_curvature(), 0.0);
+        assert_eq!(p.gaussian_curvature(), 0.0);
+    }
+}
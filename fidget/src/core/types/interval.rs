@@ -164,6 +164,52 @@ impl Interval {
             Interval::new(self.lower.ln(), self.upper.ln())
         }
     }
+    /// Computes the hyperbolic sine of the interval
+    pub fn sinh(self) -> Self {
+        Interval::new(self.lower.sinh(), self.upper.sinh())
+    }
+    /// Computes the hyperbolic cosine of the interval
+    ///
+    /// The lower bound is 1 if the interval contains zero
+    pub fn cosh(self) -> Self {
+        if self.has_nan() {
+            f32::NAN.into()
+        } else if self.lower > 0.0 {
+            Interval::new(self.lower.cosh(), self.upper.cosh())
+        } else if self.upper < 0.0 {
+            Interval::new(self.upper.cosh(), self.lower.cosh())
+        } else {
+            Interval::new(1.0, self.lower.cosh().max(self.upper.cosh()))
+        }
+    }
+    /// Computes the hyperbolic tangent of the interval
+    pub fn tanh(self) -> Self {
+        Interval::new(self.lower.tanh(), self.upper.tanh())
+    }
+    /// Computes the base-2 exponent function applied to the interval
+    pub fn exp2(self) -> Self {
+        Interval::new(self.lower.exp2(), self.upper.exp2())
+    }
+    /// Computes the base-2 log of the input interval
+    ///
+    /// Returns the `NAN` interval if the input contains zero
+    pub fn log2(self) -> Self {
+        if self.lower <= 0.0 {
+            f32::NAN.into()
+        } else {
+            Interval::new(self.lower.log2(), self.upper.log2())
+        }
+    }
+    /// Computes the base-10 log of the input interval
+    ///
+    /// Returns the `NAN` interval if the input contains zero
+    pub fn log10(self) -> Self {
+        if self.lower <= 0.0 {
+            f32::NAN.into()
+        } else {
+            Interval::new(self.lower.log10(), self.upper.log10())
+        }
+    }
     /// Calculates the square root of the interval
     ///
     /// If the interval contains values below 0, returns a `NAN` interval.
@@ -206,6 +252,35 @@ impl Interval {
             choice,
         )
     }
+    /// Calculates the polynomial smooth minimum of two intervals, blending
+    /// within a radius `k`
+    ///
+    /// Returns both the result and a [`Choice`] indicating whether one side is
+    /// always less than the other by at least `k` (in which case the result is
+    /// exactly that side).
+    ///
+    /// If any argument is `NAN`, returns the `NAN` interval and `Choice::Both`.
+    pub fn smooth_min_choice(self, rhs: Self, k: Self) -> (Self, Choice) {
+        if self.has_nan() || rhs.has_nan() || k.has_nan() {
+            return (f32::NAN.into(), Choice::Both);
+        }
+        let k = k.upper.max(0.0);
+        if self.upper + k < rhs.lower {
+            (self, Choice::Left)
+        } else if rhs.upper + k < self.lower {
+            (rhs, Choice::Right)
+        } else {
+            // The blend pulls the minimum down by at most k / 4
+            (
+                Interval::new(
+                    self.lower.min(rhs.lower) - k / 4.0,
+                    self.upper.min(rhs.upper),
+                ),
+                Choice::Both,
+            )
+        }
+    }
+
     /// Calculates the maximum of two intervals
     ///
     /// Returns both the result and a [`Choice`] indicating whether one side is
@@ -275,6 +350,29 @@ impl Interval {
         }
     }
 
+    /// Uses this interval as a condition to pick between two other intervals
+    ///
+    /// Returns both the result and a [`Choice`] indicating whether one branch
+    /// is always selected.  A condition which excludes 0 selects `a`
+    /// ([`Choice::Left`]); an unambiguous 0 selects `b` ([`Choice::Right`]).
+    pub fn select_choice(self, a: Self, b: Self) -> (Self, Choice) {
+        if self.has_nan() {
+            (f32::NAN.into(), Choice::Both)
+        } else if !self.contains(0.0) {
+            (a, Choice::Left)
+        } else if self.lower == 0.0 && self.upper == 0.0 {
+            (b, Choice::Right)
+        } else if a.has_nan() || b.has_nan() {
+            (f32::NAN.into(), Choice::Both)
+        } else {
+            // The output could be anywhere in either interval
+            (
+                Interval::new(a.lower.min(b.lower), a.upper.max(b.upper)),
+                Choice::Both,
+            )
+        }
+    }
+
     /// Returns the midpoint of the interval
     pub fn midpoint(self) -> f32 {
         (self.lower + self.upper) / 2.0
@@ -379,6 +477,65 @@ impl Interval {
             Interval::new(-std::f32::consts::PI, std::f32::consts::PI)
         }
     }
+
+    /// Raises the interval to the power of another interval
+    ///
+    /// If the exponent is a single integer, then negative bases are allowed
+    /// (and even powers are clamped at zero, like [`Interval::square`]);
+    /// otherwise, returns the `NAN` interval if the base includes negative
+    /// values.  Also returns the `NAN` interval if the base contains zero and
+    /// the exponent may be negative.
+    pub fn pow(self, rhs: Self) -> Self {
+        if self.has_nan() || rhs.has_nan() {
+            return f32::NAN.into();
+        }
+        let integer = rhs.lower == rhs.upper && rhs.lower.fract() == 0.0;
+        if (self.lower < 0.0 && !integer)
+            || (rhs.lower < 0.0 && self.contains(0.0))
+        {
+            return f32::NAN.into();
+        }
+
+        // `pow` is monotonic in each argument (within the valid domain), so
+        // the bounds are found at the corners of the input box.
+        let corners = [
+            self.lower.powf(rhs.lower),
+            self.lower.powf(rhs.upper),
+            self.upper.powf(rhs.lower),
+            self.upper.powf(rhs.upper),
+        ];
+        let mut lower = corners.into_iter().fold(f32::INFINITY, f32::min);
+        let upper = corners.into_iter().fold(f32::NEG_INFINITY, f32::max);
+
+        // Even integer powers of an interval that straddles zero have their
+        // minimum at zero, rather than at a corner.
+        if integer
+            && rhs.lower > 0.0
+            && rhs.lower % 2.0 == 0.0
+            && self.lower < 0.0
+            && self.upper > 0.0
+        {
+            lower = 0.0;
+        }
+        Interval::new(lower, upper)
+    }
+
+    /// Raises the interval to an integer power
+    ///
+    /// This matches [`Interval::pow`] with a single integer exponent, but uses
+    /// [`f32::powi`] to evaluate the bounds.
+    pub fn powi(self, n: i32) -> Self {
+        if self.has_nan() || (n < 0 && self.contains(0.0)) {
+            return f32::NAN.into();
+        }
+        let a = self.lower.powi(n);
+        let b = self.upper.powi(n);
+        if n > 0 && n % 2 == 0 && self.lower < 0.0 && self.upper > 0.0 {
+            Interval::new(0.0, a.max(b))
+        } else {
+            Interval::new(a.min(b), a.max(b))
+        }
+    }
 }
 
 impl std::fmt::Display for Interval {
@@ -0,0 +1,665 @@
+use crate::{types::Interval, vm::Choice};
+
+/// Stores a double-precision range, with conservative calculations to
+/// guarantee that it always contains the actual value.
+///
+/// This is the `f64` equivalent of [`Interval`], used by the double-precision
+/// evaluators in [`vm`](crate::vm).
+///
+/// # Warning
+/// This implementation does not set rounding modes, so it may not be _perfect_.
+#[derive(Copy, Clone, PartialEq)]
+#[repr(C)]
+pub struct Interval64 {
+    lower: f64,
+    upper: f64,
+}
+
+impl std::fmt::Debug for Interval64 {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> Result<(), std::fmt::Error> {
+        f.debug_tuple("")
+            .field(&self.lower)
+            .field(&self.upper)
+            .finish()
+    }
+}
+
+impl Interval64 {
+    /// Builds a new interval
+    ///
+    /// There are two kinds of valid interval:
+    /// - `[lower, upper]` where `lower <= upper`
+    /// - `[NaN, NaN]`
+    ///
+    /// # Panics
+    /// Panics if the resulting interval would be invalid
+    #[inline]
+    pub fn new(lower: f64, upper: f64) -> Self {
+        assert!(
+            upper >= lower || (lower.is_nan() && upper.is_nan()),
+            "invalid interval [{lower}, {upper}]"
+        );
+        Self { lower, upper }
+    }
+    /// Returns the lower bound of the interval
+    #[inline]
+    pub fn lower(&self) -> f64 {
+        self.lower
+    }
+    /// Returns the upper bound of the interval
+    #[inline]
+    pub fn upper(&self) -> f64 {
+        self.upper
+    }
+    /// Checks whether the given value is (strictly) contained in the interval
+    #[inline]
+    pub fn contains(&self, v: f64) -> bool {
+        v >= self.lower && v <= self.upper
+    }
+    /// Returns `true` if either bound of the interval is `NaN`
+    pub fn has_nan(&self) -> bool {
+        self.lower.is_nan() || self.upper.is_nan()
+    }
+    /// Calculates the absolute value of the interval
+    pub fn abs(self) -> Self {
+        if self.lower < 0.0 {
+            if self.upper > 0.0 {
+                Interval64::new(0.0, self.upper.max(-self.lower))
+            } else {
+                Interval64::new(-self.upper, -self.lower)
+            }
+        } else {
+            self
+        }
+    }
+    /// Squares the interval
+    ///
+    /// Note that this has tighter bounds than multiplication, because we know
+    /// that both sides of the multiplication are the same value.
+    pub fn square(self) -> Self {
+        if self.upper < 0.0 {
+            Interval64::new(self.upper.powi(2), self.lower.powi(2))
+        } else if self.lower > 0.0 {
+            Interval64::new(self.lower.powi(2), self.upper.powi(2))
+        } else if self.has_nan() {
+            f64::NAN.into()
+        } else {
+            Interval64::new(0.0, self.lower.abs().max(self.upper.abs()).powi(2))
+        }
+    }
+    /// Computes the sine of the interval
+    ///
+    /// Right now, this always returns the maximum range of `[-1, 1]`
+    pub fn sin(self) -> Self {
+        if self.has_nan() {
+            f64::NAN.into()
+        } else {
+            // TODO: make this smarter
+            Interval64::new(-1.0, 1.0)
+        }
+    }
+    /// Computes the cosine of the interval
+    ///
+    /// Right now, this always returns the maximum range of `[-1, 1]`
+    pub fn cos(self) -> Self {
+        if self.has_nan() {
+            f64::NAN.into()
+        } else {
+            // TODO: make this smarter
+            Interval64::new(-1.0, 1.0)
+        }
+    }
+    /// Computes the tangent of the interval
+    ///
+    /// Returns the `NAN` interval if the result contains a undefined point
+    pub fn tan(self) -> Self {
+        let size = self.upper - self.lower;
+        if size >= std::f64::consts::PI {
+            f64::NAN.into()
+        } else {
+            let lower = self.lower.tan();
+            let upper = self.upper.tan();
+            if upper >= lower {
+                Interval64::new(lower, upper)
+            } else {
+                f64::NAN.into()
+            }
+        }
+    }
+    /// Computes the arcsine of the interval
+    ///
+    /// Returns the `NAN` interval if the input is invalid
+    pub fn asin(self) -> Self {
+        if self.lower < -1.0 || self.upper > 1.0 {
+            f64::NAN.into()
+        } else {
+            Interval64::new(self.lower.asin(), self.upper.asin())
+        }
+    }
+    /// Computes the arccosine of the interval
+    ///
+    /// Returns the `NAN` interval if the input is invalid
+    pub fn acos(self) -> Self {
+        if self.lower < -1.0 || self.upper > 1.0 {
+            f64::NAN.into()
+        } else {
+            Interval64::new(self.upper.acos(), self.lower.acos())
+        }
+    }
+    /// Computes the arctangent of the interval
+    pub fn atan(self) -> Self {
+        Interval64::new(self.lower.atan(), self.upper.atan())
+    }
+    /// Computes the exponent function applied to the interval
+    pub fn exp(self) -> Self {
+        Interval64::new(self.lower.exp(), self.upper.exp())
+    }
+    /// Computes the natural log of the input interval
+    ///
+    /// Returns the `NAN` interval if the input contains zero
+    pub fn ln(self) -> Self {
+        if self.lower <= 0.0 {
+            f64::NAN.into()
+        } else {
+            Interval64::new(self.lower.ln(), self.upper.ln())
+        }
+    }
+    /// Computes the hyperbolic sine of the interval
+    pub fn sinh(self) -> Self {
+        Interval64::new(self.lower.sinh(), self.upper.sinh())
+    }
+    /// Computes the hyperbolic cosine of the interval
+    ///
+    /// The lower bound is 1 if the interval contains zero
+    pub fn cosh(self) -> Self {
+        if self.has_nan() {
+            f64::NAN.into()
+        } else if self.lower > 0.0 {
+            Interval64::new(self.lower.cosh(), self.upper.cosh())
+        } else if self.upper < 0.0 {
+            Interval64::new(self.upper.cosh(), self.lower.cosh())
+        } else {
+            Interval64::new(1.0, self.lower.cosh().max(self.upper.cosh()))
+        }
+    }
+    /// Computes the hyperbolic tangent of the interval
+    pub fn tanh(self) -> Self {
+        Interval64::new(self.lower.tanh(), self.upper.tanh())
+    }
+    /// Computes the base-2 exponent function applied to the interval
+    pub fn exp2(self) -> Self {
+        Interval64::new(self.lower.exp2(), self.upper.exp2())
+    }
+    /// Computes the base-2 log of the input interval
+    ///
+    /// Returns the `NAN` interval if the input contains zero
+    pub fn log2(self) -> Self {
+        if self.lower <= 0.0 {
+            f64::NAN.into()
+        } else {
+            Interval64::new(self.lower.log2(), self.upper.log2())
+        }
+    }
+    /// Computes the base-10 log of the input interval
+    ///
+    /// Returns the `NAN` interval if the input contains zero
+    pub fn log10(self) -> Self {
+        if self.lower <= 0.0 {
+            f64::NAN.into()
+        } else {
+            Interval64::new(self.lower.log10(), self.upper.log10())
+        }
+    }
+    /// Calculates the square root of the interval
+    ///
+    /// If the interval contains values below 0, returns a `NAN` interval.
+    pub fn sqrt(self) -> Self {
+        if self.lower < 0.0 {
+            f64::NAN.into()
+        } else {
+            Interval64::new(self.lower.sqrt(), self.upper.sqrt())
+        }
+    }
+    /// Calculates the reciprocal of the interval
+    ///
+    /// If the interval includes 0, returns the `NAN` interval
+    pub fn recip(self) -> Self {
+        if self.lower > 0.0 || self.upper < 0.0 {
+            Interval64::new(1.0 / self.upper, 1.0 / self.lower)
+        } else {
+            f64::NAN.into()
+        }
+    }
+    /// Calculates the minimum of two intervals
+    ///
+    /// Returns both the result and a [`Choice`] indicating whether one side is
+    /// always less than the other.
+    ///
+    /// If either side is `NAN`, returns the `NAN` interval and `Choice::Both`.
+    pub fn min_choice(self, rhs: Self) -> (Self, Choice) {
+        if self.has_nan() || rhs.has_nan() {
+            return (f64::NAN.into(), Choice::Both);
+        }
+        let choice = if self.upper < rhs.lower {
+            Choice::Left
+        } else if rhs.upper < self.lower {
+            Choice::Right
+        } else {
+            Choice::Both
+        };
+        (
+            Interval64::new(
+                self.lower.min(rhs.lower),
+                self.upper.min(rhs.upper),
+            ),
+            choice,
+        )
+    }
+    /// Calculates the polynomial smooth minimum of two intervals, blending
+    /// within a radius `k`
+    ///
+    /// Returns both the result and a [`Choice`] indicating whether one side is
+    /// always less than the other by at least `k` (in which case the result is
+    /// exactly that side).
+    ///
+    /// If any argument is `NAN`, returns the `NAN` interval and `Choice::Both`.
+    pub fn smooth_min_choice(self, rhs: Self, k: Self) -> (Self, Choice) {
+        if self.has_nan() || rhs.has_nan() || k.has_nan() {
+            return (f64::NAN.into(), Choice::Both);
+        }
+        let k = k.upper.max(0.0);
+        if self.upper + k < rhs.lower {
+            (self, Choice::Left)
+        } else if rhs.upper + k < self.lower {
+            (rhs, Choice::Right)
+        } else {
+            // The blend pulls the minimum down by at most k / 4
+            (
+                Interval64::new(
+                    self.lower.min(rhs.lower) - k / 4.0,
+                    self.upper.min(rhs.upper),
+                ),
+                Choice::Both,
+            )
+        }
+    }
+
+    /// Calculates the maximum of two intervals
+    ///
+    /// Returns both the result and a [`Choice`] indicating whether one side is
+    /// always greater than the other.
+    ///
+    /// If either side is `NAN`, returns the `NAN` interval and `Choice::Both`.
+    pub fn max_choice(self, rhs: Self) -> (Self, Choice) {
+        if self.has_nan() || rhs.has_nan() {
+            return (f64::NAN.into(), Choice::Both);
+        }
+        let choice = if self.lower > rhs.upper {
+            Choice::Left
+        } else if rhs.lower > self.upper {
+            Choice::Right
+        } else {
+            Choice::Both
+        };
+        (
+            Interval64::new(
+                self.lower.max(rhs.lower),
+                self.upper.max(rhs.upper),
+            ),
+            choice,
+        )
+    }
+
+    /// Calculates the short-circuiting `AND` of two intervals
+    ///
+    /// Returns both the result and a [`Choice`] indicating whether one side is
+    /// always selected.  An unambiguous 0 in `self` selects itself; an
+    /// unambiguous 1 selects the opposite branch.
+    pub fn and_choice(self, rhs: Self) -> (Self, Choice) {
+        if self.has_nan() || rhs.has_nan() {
+            (f64::NAN.into(), Choice::Both)
+        } else if self.lower == 0.0 && self.upper == 0.0 {
+            (0.0.into(), Choice::Left)
+        } else if !self.contains(0.0) {
+            (rhs, Choice::Right)
+        } else {
+            // The output will either be the RHS or zero, so extend the interval
+            // to include zero in it.
+            (
+                Interval64::new(rhs.lower.min(0.0), rhs.upper.max(0.0)),
+                Choice::Both,
+            )
+        }
+    }
+
+    /// Calculates the short-circuiting `OR` of two intervals
+    ///
+    /// Returns both the result and a [`Choice`] indicating whether one side is
+    /// always selected.  An unambiguous 0 in `self` selects the opposite
+    /// branch; an unambiguous 1 selects itself.
+    pub fn or_choice(self, rhs: Self) -> (Self, Choice) {
+        if self.has_nan() || rhs.has_nan() {
+            (f64::NAN.into(), Choice::Both)
+        } else if !self.contains(0.0) {
+            (self, Choice::Left)
+        } else if self.lower == 0.0 && self.upper == 0.0 {
+            (rhs, Choice::Right)
+        } else {
+            // The output could be anywhere in either interval
+            (
+                Interval64::new(
+                    self.lower.min(rhs.lower),
+                    self.upper.max(rhs.upper),
+                ),
+                Choice::Both,
+            )
+        }
+    }
+
+    /// Uses this interval as a condition to pick between two other intervals
+    ///
+    /// Returns both the result and a [`Choice`] indicating whether one branch
+    /// is always selected.  A condition which excludes 0 selects `a`
+    /// ([`Choice::Left`]); an unambiguous 0 selects `b` ([`Choice::Right`]).
+    pub fn select_choice(self, a: Self, b: Self) -> (Self, Choice) {
+        if self.has_nan() {
+            (f64::NAN.into(), Choice::Both)
+        } else if !self.contains(0.0) {
+            (a, Choice::Left)
+        } else if self.lower == 0.0 && self.upper == 0.0 {
+            (b, Choice::Right)
+        } else if a.has_nan() || b.has_nan() {
+            (f64::NAN.into(), Choice::Both)
+        } else {
+            // The output could be anywhere in either interval
+            (
+                Interval64::new(a.lower.min(b.lower), a.upper.max(b.upper)),
+                Choice::Both,
+            )
+        }
+    }
+
+    /// Returns the midpoint of the interval
+    pub fn midpoint(self) -> f64 {
+        (self.lower + self.upper) / 2.0
+    }
+
+    /// Splits the interval at the midpoint
+    ///
+    /// ```
+    /// # use fidget::types::Interval64;
+    /// let a = Interval64::new(0.0, 1.0);
+    /// let (lo, hi) = a.split();
+    /// assert_eq!(lo, Interval64::new(0.0, 0.5));
+    /// assert_eq!(hi, Interval64::new(0.5, 1.0));
+    /// ```
+    pub fn split(self) -> (Self, Self) {
+        let mid = self.midpoint();
+        (
+            Interval64::new(self.lower, mid),
+            Interval64::new(mid, self.upper),
+        )
+    }
+
+    /// Linear interpolation from `lower` to `upper`
+    ///
+    /// ```
+    /// # use fidget::types::Interval64;
+    /// let a = Interval64::new(0.0, 2.0);
+    /// assert_eq!(a.lerp(0.5), 1.0);
+    /// assert_eq!(a.lerp(0.75), 1.5);
+    /// assert_eq!(a.lerp(2.0), 4.0);
+    /// ```
+    pub fn lerp(self, frac: f64) -> f64 {
+        self.lower * (1.0 - frac) + self.upper * frac
+    }
+
+    /// Calculates the width of the interval
+    ///
+    /// ```
+    /// # use fidget::types::Interval64;
+    /// let a = Interval64::new(2.0, 3.0);
+    /// assert_eq!(a.width(), 1.0);
+    /// let b = Interval64::new(2.0, 5.0);
+    /// assert_eq!(b.width(), 3.0);
+    /// ```
+    pub fn width(self) -> f64 {
+        self.upper - self.lower
+    }
+
+    /// Least non-negative remainder
+    pub fn rem_euclid(&self, other: Interval64) -> Self {
+        // TODO optimize this more?
+        if self.has_nan() || other.has_nan() || other.contains(0.0) {
+            f64::NAN.into()
+        } else if other.lower == other.upper && other.lower > 0.0 {
+            let a = self.lower / other.lower;
+            let b = self.upper / other.lower;
+            if a != a.floor() && a.floor() == b.floor() {
+                Interval64::new(
+                    self.lower.rem_euclid(other.lower),
+                    self.upper.rem_euclid(other.lower),
+                )
+            } else {
+                Interval64::new(0.0, other.abs().upper())
+            }
+        } else {
+            Interval64::new(0.0, other.abs().upper())
+        }
+    }
+
+    /// Largest value that is less-than-or-equal to this value
+    pub fn floor(&self) -> Self {
+        Interval64::new(self.lower.floor(), self.upper.floor())
+    }
+
+    /// Smallest value that is greater-than-or-equal to this value
+    pub fn ceil(&self) -> Self {
+        Interval64::new(self.lower.ceil(), self.upper.ceil())
+    }
+
+    /// Rounded value
+    pub fn round(&self) -> Self {
+        Interval64::new(self.lower.round(), self.upper.round())
+    }
+
+    /// Four-quadrant arctangent
+    pub fn atan2(self, x: Self) -> Self {
+        if self.has_nan() || x.has_nan() {
+            f64::NAN.into()
+        } else {
+            // TODO optimize this further
+            Interval64::new(-std::f64::consts::PI, std::f64::consts::PI)
+        }
+    }
+
+    /// Raises the interval to the power of another interval
+    ///
+    /// If the exponent is a single integer, then negative bases are allowed
+    /// (and even powers are clamped at zero, like [`Interval64::square`]);
+    /// otherwise, returns the `NAN` interval if the base includes negative
+    /// values.  Also returns the `NAN` interval if the base contains zero and
+    /// the exponent may be negative.
+    pub fn pow(self, rhs: Self) -> Self {
+        if self.has_nan() || rhs.has_nan() {
+            return f64::NAN.into();
+        }
+        let integer = rhs.lower == rhs.upper && rhs.lower.fract() == 0.0;
+        if (self.lower < 0.0 && !integer)
+            || (rhs.lower < 0.0 && self.contains(0.0))
+        {
+            return f64::NAN.into();
+        }
+
+        // `pow` is monotonic in each argument (within the valid domain), so
+        // the bounds are found at the corners of the input box.
+        let corners = [
+            self.lower.powf(rhs.lower),
+            self.lower.powf(rhs.upper),
+            self.upper.powf(rhs.lower),
+            self.upper.powf(rhs.upper),
+        ];
+        let mut lower = corners.into_iter().fold(f64::INFINITY, f64::min);
+        let upper = corners.into_iter().fold(f64::NEG_INFINITY, f64::max);
+
+        // Even integer powers of an interval that straddles zero have their
+        // minimum at zero, rather than at a corner.
+        if integer
+            && rhs.lower > 0.0
+            && rhs.lower % 2.0 == 0.0
+            && self.lower < 0.0
+            && self.upper > 0.0
+        {
+            lower = 0.0;
+        }
+        Interval64::new(lower, upper)
+    }
+}
+
+impl std::fmt::Display for Interval64 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.lower, self.upper)
+    }
+}
+
+impl From<[f64; 2]> for Interval64 {
+    fn from(i: [f64; 2]) -> Interval64 {
+        Interval64::new(i[0], i[1])
+    }
+}
+
+impl From<f64> for Interval64 {
+    fn from(f: f64) -> Self {
+        Interval64::new(f, f)
+    }
+}
+
+impl From<f32> for Interval64 {
+    fn from(f: f32) -> Self {
+        f64::from(f).into()
+    }
+}
+
+impl From<Interval> for Interval64 {
+    fn from(i: Interval) -> Self {
+        Interval64::new(i.lower().into(), i.upper().into())
+    }
+}
+
+/// Rounds outwards to single precision, so the result contains the original
+impl From<Interval64> for Interval {
+    fn from(i: Interval64) -> Self {
+        let mut lower = i.lower as f32;
+        if f64::from(lower) > i.lower {
+            lower = lower.next_down();
+        }
+        let mut upper = i.upper as f32;
+        if f64::from(upper) < i.upper {
+            upper = upper.next_up();
+        }
+        Interval::new(lower, upper)
+    }
+}
+
+impl std::ops::Add<Interval64> for Interval64 {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Interval64::new(self.lower + rhs.lower, self.upper + rhs.upper)
+    }
+}
+
+impl std::ops::Mul<Interval64> for Interval64 {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        if self.has_nan() || rhs.has_nan() {
+            return f64::NAN.into();
+        }
+        let mut out = [0.0; 4];
+        let mut k = 0;
+        for i in [self.lower, self.upper] {
+            for j in [rhs.lower, rhs.upper] {
+                out[k] = i * j;
+                k += 1;
+            }
+        }
+        let mut lower = out[0];
+        let mut upper = out[0];
+        for &v in &out[1..] {
+            lower = lower.min(v);
+            upper = upper.max(v);
+        }
+        Interval64::new(lower, upper)
+    }
+}
+
+impl std::ops::Mul<f64> for Interval64 {
+    type Output = Self;
+    fn mul(self, rhs: f64) -> Self {
+        if self.has_nan() || rhs.is_nan() {
+            f64::NAN.into()
+        } else if rhs < 0.0 {
+            Interval64::new(self.upper * rhs, self.lower * rhs)
+        } else {
+            Interval64::new(self.lower * rhs, self.upper * rhs)
+        }
+    }
+}
+
+impl std::ops::Div<Interval64> for Interval64 {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self {
+        if self.has_nan() {
+            return f64::NAN.into();
+        }
+        if rhs.lower > 0.0 || rhs.upper < 0.0 {
+            let mut out = [0.0; 4];
+            let mut k = 0;
+            for i in [self.lower, self.upper] {
+                for j in [rhs.lower, rhs.upper] {
+                    out[k] = i / j;
+                    k += 1;
+                }
+            }
+            let mut lower = out[0];
+            let mut upper = out[0];
+            for &v in &out[1..] {
+                lower = lower.min(v);
+                upper = upper.max(v);
+            }
+            Interval64::new(lower, upper)
+        } else {
+            f64::NAN.into()
+        }
+    }
+}
+
+impl std::ops::Sub<Interval64> for Interval64 {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Interval64::new(self.lower - rhs.upper, self.upper - rhs.lower)
+    }
+}
+
+impl std::ops::Neg for Interval64 {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Interval64::new(-self.upper, -self.lower)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_interval64() {
+        let a = Interval64::new(0.0, 1.0);
+        let b = Interval64::new(0.5, 1.5);
+        let (v, c) = a.min_choice(b);
+        assert_eq!(v, [0.0, 1.0].into());
+        assert_eq!(c, Choice::Both);
+    }
+}
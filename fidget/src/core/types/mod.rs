@@ -1,6 +1,16 @@
 //! Custom types used during evaluation
 
+mod affine;
 mod grad;
+mod grad64;
+mod grad_n;
+mod hessian;
 mod interval;
+mod interval64;
+pub use affine::Affine;
 pub use grad::Grad;
+pub use grad64::Grad64;
+pub use grad_n::GradN;
+pub use hessian::Hessian;
 pub use interval::Interval;
+pub use interval64::Interval64;
@@ -8,7 +8,7 @@
 use crate::context::{Context, IntoNode, Node};
 use crate::Error;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 /// The [`Var`] type is an input to a math expression
 ///
@@ -57,6 +57,13 @@ pub enum Var {
 #[serde(transparent)]
 pub struct VarIndex(u64);
 
+impl VarIndex {
+    /// Returns the raw 64-bit index
+    pub(crate) fn get(&self) -> u64 {
+        self.0
+    }
+}
+
 impl Var {
     /// Returns a new variable, with a random 64-bit index
     ///
@@ -91,6 +98,192 @@ impl std::fmt::Display for Var {
     }
 }
 
+/// Parses the output of [`Var`]'s `Display` implementation
+///
+/// ```
+/// # use fidget::var::Var;
+/// let v = Var::new();
+/// assert_eq!(v.to_string().parse::<Var>().unwrap(), v);
+/// assert_eq!("X".parse::<Var>().unwrap(), Var::X);
+/// ```
+impl std::str::FromStr for Var {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let index = if let Some(v) = s.strip_prefix("v_") {
+            v.parse().ok()
+        } else if let Some(v) =
+            s.strip_prefix("V(").and_then(|s| s.strip_suffix(')'))
+        {
+            u64::from_str_radix(v, 16).ok()
+        } else {
+            None
+        };
+        match (s, index) {
+            ("X", _) => Ok(Var::X),
+            ("Y", _) => Ok(Var::Y),
+            ("Z", _) => Ok(Var::Z),
+            (_, Some(i)) => Ok(Var::V(VarIndex(i))),
+            (_, None) => Err(Error::UnknownVariable(s.to_owned())),
+        }
+    }
+}
+
+/// Metadata attached to a [`Var`] in a [`VarRegistry`]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct VarInfo {
+    /// Human-readable name, unique within a registry
+    pub name: String,
+    /// Default value, e.g. for initializing a UI slider
+    pub default: f64,
+    /// Optional range of valid values, as `(min, max)`
+    pub range: Option<(f64, f64)>,
+    /// Free-form description
+    pub description: String,
+}
+
+impl VarInfo {
+    /// Builds a new `VarInfo` with the given name and default metadata
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Registry of names and metadata for generic variables
+///
+/// Names are unique within a registry, and must be valid tokens in the text
+/// format used by [`Context::from_text`]: they must be non-empty, must not
+/// contain whitespace or `#`, and must not parse as a [`Var`] (e.g. `X` or
+/// `v_3`).
+///
+/// ```
+/// # use fidget::var::{Var, VarInfo, VarRegistry};
+/// let mut reg = VarRegistry::new();
+/// let r = Var::new();
+/// reg.insert(r, VarInfo {
+///     default: 1.0,
+///     range: Some((0.0, 10.0)),
+///     ..VarInfo::new("radius")
+/// })
+/// .unwrap();
+/// assert_eq!(reg.find("radius"), Some(r));
+/// assert_eq!(reg.name(&r), Some("radius"));
+/// assert!(reg.insert(Var::new(), VarInfo::new("radius")).is_err());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "Vec<(Var, VarInfo)>", into = "Vec<(Var, VarInfo)>")]
+pub struct VarRegistry {
+    info: BTreeMap<Var, VarInfo>,
+    names: HashMap<String, Var>,
+}
+
+impl VarRegistry {
+    /// Builds a new empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of registered variables
+    pub fn len(&self) -> usize {
+        self.info.len()
+    }
+
+    /// Checks whether the registry is empty
+    pub fn is_empty(&self) -> bool {
+        self.info.is_empty()
+    }
+
+    /// Clears the registry
+    pub fn clear(&mut self) {
+        self.info.clear();
+        self.names.clear();
+    }
+
+    /// Attaches metadata to a variable, replacing any previous metadata
+    ///
+    /// Returns [`Error::BadVarName`] if the name is invalid, or
+    /// [`Error::DuplicateVarName`] if it's already used by a different
+    /// variable.
+    pub fn insert(&mut self, var: Var, info: VarInfo) -> Result<(), Error> {
+        let name = info.name.as_str();
+        if name.is_empty()
+            || name.contains(|c: char| c.is_whitespace() || c == '#')
+            || name.parse::<Var>().is_ok()
+        {
+            return Err(Error::BadVarName(info.name));
+        }
+        match self.names.get(name) {
+            Some(v) if *v != var => {
+                return Err(Error::DuplicateVarName(info.name));
+            }
+            _ => (),
+        }
+        if let Some(prev) = self.info.remove(&var) {
+            self.names.remove(&prev.name);
+        }
+        self.names.insert(info.name.clone(), var);
+        self.info.insert(var, info);
+        Ok(())
+    }
+
+    /// Removes a variable's metadata, returning it if present
+    pub fn remove(&mut self, var: &Var) -> Option<VarInfo> {
+        let prev = self.info.remove(var)?;
+        self.names.remove(&prev.name);
+        Some(prev)
+    }
+
+    /// Looks up metadata for the given variable
+    pub fn get(&self, var: &Var) -> Option<&VarInfo> {
+        self.info.get(var)
+    }
+
+    /// Looks up the name of the given variable
+    pub fn name(&self, var: &Var) -> Option<&str> {
+        self.info.get(var).map(|i| i.name.as_str())
+    }
+
+    /// Finds a variable by name
+    pub fn find(&self, name: &str) -> Option<Var> {
+        self.names.get(name).cloned()
+    }
+
+    /// Iterates over registered variables, sorted by [`Var`]
+    pub fn iter(&self) -> impl Iterator<Item = (Var, &VarInfo)> + '_ {
+        self.info.iter().map(|(v, i)| (*v, i))
+    }
+
+    /// Copies every entry from another registry into this one
+    ///
+    /// Returns an error (and leaves the registry partially updated) if names
+    /// conflict.
+    pub fn merge(&mut self, other: &VarRegistry) -> Result<(), Error> {
+        for (v, info) in other.iter() {
+            self.insert(v, info.clone())?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<Vec<(Var, VarInfo)>> for VarRegistry {
+    type Error = Error;
+    fn try_from(vs: Vec<(Var, VarInfo)>) -> Result<Self, Error> {
+        let mut out = Self::new();
+        for (v, info) in vs {
+            out.insert(v, info)?;
+        }
+        Ok(out)
+    }
+}
+
+impl From<VarRegistry> for Vec<(Var, VarInfo)> {
+    fn from(r: VarRegistry) -> Self {
+        r.info.into_iter().collect()
+    }
+}
+
 impl IntoNode for Var {
     fn into_node(self, ctx: &mut Context) -> Result<Node, Error> {
         Ok(ctx.var(self))
@@ -106,12 +299,17 @@ impl IntoNode for Var {
 /// values from `0..vars.len()`.
 ///
 /// For efficiency, this type does not allocate heap memory for `Var::X/Y/Z`.
+///
+/// The map also carries [`VarInfo`] metadata for its variables (copied from the
+/// [`Context`]'s registry when building a tape), so that names survive
+/// serialization.
 #[derive(Default, Serialize, Deserialize)]
 pub struct VarMap {
     x: Option<usize>,
     y: Option<usize>,
     z: Option<usize>,
     v: HashMap<VarIndex, usize>,
+    info: VarRegistry,
 }
 
 #[allow(missing_docs)]
@@ -151,6 +349,21 @@ impl VarMap {
             Var::V(v) => self.v.entry(v).or_insert(next),
         };
     }
+    /// Returns metadata for the given variable, if present
+    pub fn info(&self, v: &Var) -> Option<&VarInfo> {
+        self.info.get(v)
+    }
+    /// Returns the registry of variable metadata
+    pub fn registry(&self) -> &VarRegistry {
+        &self.info
+    }
+    /// Attaches metadata to a variable which is already in the map
+    pub(crate) fn set_info(&mut self, v: Var, info: VarInfo) {
+        debug_assert!(self.get(&v).is_some());
+        // Names are already unique and valid, because they came from another
+        // registry
+        self.info.insert(v, info).unwrap();
+    }
 
     pub(crate) fn check_tracing_arguments<T>(
         &self,
@@ -209,6 +422,66 @@ mod test {
         assert_ne!(v1, v2);
     }
 
+    #[test]
+    fn var_from_str() {
+        for v in [Var::X, Var::Y, Var::Z, Var::V(VarIndex(3)), Var::new()] {
+            assert_eq!(v.to_string().parse::<Var>().unwrap(), v);
+        }
+        assert_eq!("V(ff)".parse::<Var>().unwrap(), Var::V(VarIndex(255)));
+        for s in ["x", "v_", "v_-1", "V(12", "V(zz)", "radius"] {
+            assert!(s.parse::<Var>().is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn var_registry() {
+        let mut reg = VarRegistry::new();
+        let a = Var::new();
+        let b = Var::new();
+        reg.insert(a, VarInfo::new("a")).unwrap();
+        reg.insert(b, VarInfo::new("b")).unwrap();
+        assert_eq!(reg.len(), 2);
+
+        // Renaming frees up the old name
+        reg.insert(a, VarInfo::new("c")).unwrap();
+        assert_eq!(reg.find("a"), None);
+        assert_eq!(reg.find("c"), Some(a));
+        reg.insert(b, VarInfo::new("a")).unwrap();
+        assert!(matches!(
+            reg.insert(b, VarInfo::new("c")),
+            Err(Error::DuplicateVarName(..))
+        ));
+
+        for name in ["", "two words", "a#b", "X", "v_3", "V(ff)"] {
+            assert!(
+                matches!(
+                    reg.insert(a, VarInfo::new(name)),
+                    Err(Error::BadVarName(..))
+                ),
+                "{name}"
+            );
+        }
+
+        let info = reg.remove(&a).unwrap();
+        assert_eq!(info.name, "c");
+        assert_eq!(reg.find("c"), None);
+        assert_eq!(reg.len(), 1);
+
+        reg.insert(
+            a,
+            VarInfo {
+                default: 0.5,
+                range: Some((-1.0, 1.0)),
+                description: "the letter a".to_owned(),
+                ..VarInfo::new("c")
+            },
+        )
+        .unwrap();
+        let bytes = bincode::serialize(&reg).unwrap();
+        let out: VarRegistry = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(out, reg);
+    }
+
     #[test]
     fn var_map() {
         let v = Var::new();
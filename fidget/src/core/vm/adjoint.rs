@@ -0,0 +1,647 @@
+//! Reverse-mode (adjoint) gradient evaluation with the VM backend
+//!
+//! Forward-mode gradients (e.g. [`Grad`](crate::types::Grad) and
+//! [`GradN`](crate::types::GradN)) carry one partial derivative per input, so
+//! their cost scales with the number of inputs.  A [`VmAdjointEval`] instead
+//! records the forward pass, then walks the tape backwards, propagating
+//! adjoints from each output to every input; its cost scales with the number
+//! of outputs, which makes it a better fit for functions with many parameters.
+//!
+//! ```
+//! use fidget::{
+//!     context::{Context, Tree},
+//!     eval::{MathFunction, Tape},
+//!     var::Var,
+//!     vm::{VmAdjointEval, VmFunction},
+//! };
+//!
+//! // A polynomial with 100 coefficients
+//! let params: Vec<Var> = (0..100).map(|_| Var::new()).collect();
+//! let mut t = Tree::constant(0.0);
+//! for (i, p) in params.iter().enumerate() {
+//!     t += Tree::from(*p) * Tree::x().pow(i as f64);
+//! }
+//! let mut ctx = Context::new();
+//! let root = ctx.import(&t);
+//! let f = VmFunction::new(&ctx, &[root])?;
+//! let tape = f.tape();
+//!
+//! let mut vars = vec![1.0; tape.vars().len()];
+//! vars[tape.vars()[&Var::X]] = 0.5;
+//! let mut eval = VmAdjointEval::new();
+//! let (_value, grad) = eval.eval(&tape, &vars)?;
+//!
+//! // The partial derivative with respect to each coefficient is `x^i`
+//! for (i, p) in params.iter().enumerate().take(10) {
+//!     let d = grad[0][tape.vars()[p]];
+//!     assert!((d - 0.5f32.powi(i as i32)).abs() < 1e-6);
+//! }
+//! # Ok::<(), fidget::Error>(())
+//! ```
+use super::{point_step, Choice, GenericVmTape, SlotArray, TracingVmEval};
+use crate::{compiler::RegOp, eval::Tape, Error};
+
+/// VM-based evaluator for reverse-mode (adjoint) gradients at a single point
+///
+/// Evaluation records the value of each operation and its local partial
+/// derivatives, along with a [`Choice`] for each `min`, `max`, and other
+/// branching operation (in the same format as a [`VmTrace`](super::VmTrace)).
+/// Adjoints are then back-propagated from each output, following the recorded
+/// choices; at ties, the adjoint follows the argument that produced the value
+/// (matching [`VmGradSliceEval`](super::VmGradSliceEval)).
+///
+/// As in forward-mode evaluation, zero partial derivatives don't propagate, so
+/// (for example) a `NaN` gradient in an unused branch doesn't leak into the
+/// result.
+pub struct VmAdjointEval<const N: usize> {
+    /// Values and choices from the forward pass
+    eval: TracingVmEval<f32>,
+    /// Local partial derivatives for each operation, in evaluation order
+    partials: Vec<[f32; 3]>,
+    /// Adjoint of each slot during the reverse pass
+    adjoints: Vec<f32>,
+    /// Gradient with respect to each input, for each output
+    grad: Vec<Vec<f32>>,
+}
+
+impl<const N: usize> Default for VmAdjointEval<N> {
+    fn default() -> Self {
+        Self {
+            eval: TracingVmEval::default(),
+            partials: vec![],
+            adjoints: vec![],
+            grad: vec![],
+        }
+    }
+}
+
+impl<const N: usize> VmAdjointEval<N> {
+    /// Builds a new evaluator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Evaluates the tape at a single point, computing full gradients
+    ///
+    /// Returns a tuple of output values and gradients; `grad[i][j]` is the
+    /// partial derivative of output `i` with respect to input `j` (using
+    /// indices from [`Tape::vars`]).
+    pub fn eval(
+        &mut self,
+        tape: &GenericVmTape<N>,
+        vars: &[f32],
+    ) -> Result<(&[f32], &[Vec<f32>]), Error> {
+        tape.vars().check_tracing_arguments(vars)?;
+        let data = tape.data();
+        self.eval.resize_slots(data);
+        self.forward(tape, vars);
+
+        self.adjoints.resize(data.slot_count(), 0.0);
+        self.grad.resize_with(data.output_count(), Vec::new);
+        for i in 0..data.output_count() {
+            self.reverse(tape, i as u32, vars.len());
+        }
+        Ok((&self.eval.out, &self.grad))
+    }
+
+    /// Evaluates the tape, recording values, partial derivatives, and choices
+    ///
+    /// Values and choices come from the point evaluator; partial derivatives
+    /// are computed from each operation's arguments before it runs.
+    fn forward(&mut self, tape: &GenericVmTape<N>, vars: &[f32]) {
+        self.partials.clear();
+        let mut choices = self.eval.choices.as_mut_slice().iter_mut();
+        let mut v = SlotArray(&mut self.eval.slots);
+        for op in tape.data().iter_asm() {
+            self.partials.push(partials(op, &v));
+            if let Some(c) = point_step(op, &mut v, vars, &mut self.eval.out) {
+                *choices.next().unwrap() = c;
+            }
+        }
+    }
+
+    /// Back-propagates adjoints from the given output
+    fn reverse(&mut self, tape: &GenericVmTape<N>, output: u32, n: usize) {
+        self.adjoints.fill(0.0);
+        let grad = &mut self.grad[output as usize];
+        grad.clear();
+        grad.resize(n, 0.0);
+
+        let mut choices = self.eval.choices.as_slice().iter().rev();
+        let mut adj = SlotArray(&mut self.adjoints);
+        let ops = tape.data().iter_asm().rev().zip(self.partials.iter().rev());
+        for (op, &[d0, d1, d2]) in ops {
+            match op {
+                RegOp::Output(arg, i) => {
+                    if i == output {
+                        adj[arg] += 1.0;
+                    }
+                }
+                RegOp::Input(out, i) => {
+                    grad[i as usize] += take(&mut adj[out]);
+                }
+                RegOp::CopyImm(out, _imm) => {
+                    adj[out] = 0.0;
+                }
+                RegOp::Load(out, mem) => {
+                    let g = take(&mut adj[out]);
+                    adj[mem] += g;
+                }
+                RegOp::Store(out, mem) => {
+                    let g = take(&mut adj[mem]);
+                    adj[out] += g;
+                }
+
+                // Unary operations, and binary operations with an immediate
+                RegOp::NegReg(out, arg)
+                | RegOp::AbsReg(out, arg)
+                | RegOp::RecipReg(out, arg)
+                | RegOp::SqrtReg(out, arg)
+                | RegOp::SquareReg(out, arg)
+                | RegOp::FloorReg(out, arg)
+                | RegOp::CeilReg(out, arg)
+                | RegOp::RoundReg(out, arg)
+                | RegOp::SinReg(out, arg)
+                | RegOp::CosReg(out, arg)
+                | RegOp::TanReg(out, arg)
+                | RegOp::AsinReg(out, arg)
+                | RegOp::AcosReg(out, arg)
+                | RegOp::AtanReg(out, arg)
+                | RegOp::ExpReg(out, arg)
+                | RegOp::LnReg(out, arg)
+                | RegOp::SinhReg(out, arg)
+                | RegOp::CoshReg(out, arg)
+                | RegOp::TanhReg(out, arg)
+                | RegOp::Exp2Reg(out, arg)
+                | RegOp::Log2Reg(out, arg)
+                | RegOp::Log10Reg(out, arg)
+                | RegOp::NotReg(out, arg)
+                | RegOp::CopyReg(out, arg)
+                | RegOp::AddRegImm(out, arg, _)
+                | RegOp::MulRegImm(out, arg, _)
+                | RegOp::DivRegImm(out, arg, _)
+                | RegOp::DivImmReg(out, arg, _)
+                | RegOp::SubImmReg(out, arg, _)
+                | RegOp::SubRegImm(out, arg, _)
+                | RegOp::AtanRegImm(out, arg, _)
+                | RegOp::AtanImmReg(out, arg, _)
+                | RegOp::PowRegImm(out, arg, _)
+                | RegOp::PowImmReg(out, arg, _)
+                | RegOp::ModRegImm(out, arg, _)
+                | RegOp::ModImmReg(out, arg, _)
+                | RegOp::CompareRegImm(out, arg, _)
+                | RegOp::CompareImmReg(out, arg, _) => {
+                    let g = take(&mut adj[out]);
+                    accumulate(&mut adj[arg], g, d0);
+                }
+
+                // Binary operations with two registers
+                RegOp::AddRegReg(out, lhs, rhs)
+                | RegOp::SubRegReg(out, lhs, rhs)
+                | RegOp::MulRegReg(out, lhs, rhs)
+                | RegOp::DivRegReg(out, lhs, rhs)
+                | RegOp::AtanRegReg(out, lhs, rhs)
+                | RegOp::PowRegReg(out, lhs, rhs)
+                | RegOp::ModRegReg(out, lhs, rhs)
+                | RegOp::CompareRegReg(out, lhs, rhs) => {
+                    let g = take(&mut adj[out]);
+                    accumulate(&mut adj[lhs], g, d0);
+                    accumulate(&mut adj[rhs], g, d1);
+                }
+
+                // Operations with choices, which only propagate to the branch
+                // that was taken during the forward pass
+                RegOp::MinRegImm(out, arg, _)
+                | RegOp::MaxRegImm(out, arg, _)
+                | RegOp::AndRegImm(out, arg, _)
+                | RegOp::OrRegImm(out, arg, _) => {
+                    let g = take(&mut adj[out]);
+                    if *choices.next().unwrap() == Choice::Left {
+                        adj[arg] += g;
+                    }
+                }
+                RegOp::MinRegReg(out, lhs, rhs)
+                | RegOp::MaxRegReg(out, lhs, rhs)
+                | RegOp::AndRegReg(out, lhs, rhs)
+                | RegOp::OrRegReg(out, lhs, rhs) => {
+                    let g = take(&mut adj[out]);
+                    match choices.next().unwrap() {
+                        Choice::Left => adj[lhs] += g,
+                        Choice::Right => adj[rhs] += g,
+                        _ => {
+                            accumulate(&mut adj[lhs], g, d0);
+                            accumulate(&mut adj[rhs], g, d1);
+                        }
+                    }
+                }
+                RegOp::SelectRegReg(out, _cond, arg) => {
+                    // If the condition was false, `out` is unchanged
+                    if *choices.next().unwrap() == Choice::Left {
+                        let g = take(&mut adj[out]);
+                        adj[arg] += g;
+                    }
+                }
+                RegOp::SelectRegImm(out, _cond, _imm) => {
+                    if *choices.next().unwrap() == Choice::Left {
+                        adj[out] = 0.0;
+                    }
+                }
+                RegOp::SmoothMinRegReg(out, lhs, rhs) => {
+                    // The blending radius is read from `out`
+                    let g = take(&mut adj[out]);
+                    match choices.next().unwrap() {
+                        Choice::Left => adj[lhs] += g,
+                        Choice::Right => adj[rhs] += g,
+                        _ => {
+                            accumulate(&mut adj[lhs], g, d0);
+                            accumulate(&mut adj[rhs], g, d1);
+                            accumulate(&mut adj[out], g, d2);
+                        }
+                    }
+                }
+                RegOp::SmoothMinRegImm(out, arg, _imm) => {
+                    let g = take(&mut adj[out]);
+                    match choices.next().unwrap() {
+                        Choice::Left => adj[arg] += g,
+                        Choice::Right => (),
+                        _ => {
+                            accumulate(&mut adj[arg], g, d0);
+                            accumulate(&mut adj[out], g, d2);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Resets an adjoint to zero, returning its previous value
+fn take(a: &mut f32) -> f32 {
+    std::mem::replace(a, 0.0)
+}
+
+/// Accumulates `g * d` into an adjoint, skipping zero terms
+///
+/// Skipping zeros matches forward-mode evaluation, where an operation with a
+/// zero partial derivative (or an unused argument) doesn't propagate `NaN` or
+/// infinite values.
+fn accumulate(a: &mut f32, g: f32, d: f32) {
+    if g != 0.0 && d != 0.0 {
+        *a += g * d;
+    }
+}
+
+/// Returns the local partial derivatives of an operation
+///
+/// `v` holds slot values before the operation runs.  Partials are with respect
+/// to the operation's register arguments, in order; for smooth minimums, the
+/// third partial is with respect to the blending radius (read from `out`).
+/// Operations with choices only use their partials when both branches are
+/// taken (i.e. [`Choice::Both`]).
+fn partials(op: RegOp, v: &SlotArray<'_, f32>) -> [f32; 3] {
+    let unary = |d| [d, 0.0, 0.0];
+    match op {
+        RegOp::Output(..)
+        | RegOp::Input(..)
+        | RegOp::CopyImm(..)
+        | RegOp::Load(..)
+        | RegOp::Store(..)
+        | RegOp::FloorReg(..)
+        | RegOp::CeilReg(..)
+        | RegOp::RoundReg(..)
+        | RegOp::NotReg(..)
+        | RegOp::CompareRegReg(..)
+        | RegOp::CompareRegImm(..)
+        | RegOp::CompareImmReg(..)
+        | RegOp::MinRegImm(..)
+        | RegOp::MaxRegImm(..)
+        | RegOp::AndRegImm(..)
+        | RegOp::OrRegImm(..)
+        | RegOp::AndRegReg(..)
+        | RegOp::OrRegReg(..)
+        | RegOp::SelectRegReg(..)
+        | RegOp::SelectRegImm(..) => [0.0; 3],
+
+        RegOp::NegReg(..) | RegOp::SubImmReg(..) => unary(-1.0),
+        RegOp::CopyReg(..)
+        | RegOp::AddRegImm(..)
+        | RegOp::SubRegImm(..)
+        | RegOp::ModRegImm(..) => unary(1.0),
+        RegOp::AbsReg(_, arg) => unary(if v[arg] < 0.0 { -1.0 } else { 1.0 }),
+        RegOp::RecipReg(_, arg) => unary(1.0 / -v[arg].powi(2)),
+        RegOp::SqrtReg(_, arg) => unary(1.0 / (2.0 * v[arg].sqrt())),
+        RegOp::SquareReg(_, arg) => unary(2.0 * v[arg]),
+        RegOp::SinReg(_, arg) => unary(v[arg].cos()),
+        RegOp::CosReg(_, arg) => unary(-v[arg].sin()),
+        RegOp::TanReg(_, arg) => unary(1.0 / v[arg].cos().powi(2)),
+        RegOp::AsinReg(_, arg) => unary(1.0 / (1.0 - v[arg].powi(2)).sqrt()),
+        RegOp::AcosReg(_, arg) => unary(-1.0 / (1.0 - v[arg].powi(2)).sqrt()),
+        RegOp::AtanReg(_, arg) => unary(1.0 / (v[arg].powi(2) + 1.0)),
+        RegOp::ExpReg(_, arg) => unary(v[arg].exp()),
+        RegOp::LnReg(_, arg) => unary(1.0 / v[arg]),
+        RegOp::SinhReg(_, arg) => unary(v[arg].cosh()),
+        RegOp::CoshReg(_, arg) => unary(v[arg].sinh()),
+        RegOp::TanhReg(_, arg) => unary(1.0 - v[arg].tanh().powi(2)),
+        RegOp::Exp2Reg(_, arg) => unary(v[arg].exp2() * std::f32::consts::LN_2),
+        RegOp::Log2Reg(_, arg) => {
+            unary(1.0 / (v[arg] * std::f32::consts::LN_2))
+        }
+        RegOp::Log10Reg(_, arg) => {
+            unary(1.0 / (v[arg] * std::f32::consts::LN_10))
+        }
+        RegOp::MulRegImm(_, _, imm) => unary(imm),
+        RegOp::DivRegImm(_, _, imm) => unary(1.0 / imm),
+        RegOp::DivImmReg(_, arg, imm) => unary(-imm / v[arg].powi(2)),
+        RegOp::AtanRegImm(_, arg, imm) => unary(atan2_partials(v[arg], imm).0),
+        RegOp::AtanImmReg(_, arg, imm) => unary(atan2_partials(imm, v[arg]).1),
+        RegOp::PowRegImm(_, arg, imm) => unary(pow_partials(v[arg], imm).0),
+        RegOp::PowImmReg(_, arg, imm) => unary(pow_partials(imm, v[arg]).1),
+        RegOp::ModImmReg(_, arg, imm) => unary(-imm.div_euclid(v[arg])),
+
+        RegOp::AddRegReg(..) => [1.0, 1.0, 0.0],
+        RegOp::SubRegReg(..) => [1.0, -1.0, 0.0],
+        RegOp::MulRegReg(_, lhs, rhs) => [v[rhs], v[lhs], 0.0],
+        RegOp::DivRegReg(_, lhs, rhs) => {
+            let (a, b) = (v[lhs], v[rhs]);
+            [1.0 / b, -a / b.powi(2), 0.0]
+        }
+        RegOp::AtanRegReg(_, lhs, rhs) => {
+            let (dy, dx) = atan2_partials(v[lhs], v[rhs]);
+            [dy, dx, 0.0]
+        }
+        RegOp::PowRegReg(_, lhs, rhs) => {
+            let (da, db) = pow_partials(v[lhs], v[rhs]);
+            [da, db, 0.0]
+        }
+        RegOp::ModRegReg(_, lhs, rhs) => [1.0, -v[lhs].div_euclid(v[rhs]), 0.0],
+
+        // At ties, the adjoint follows the right-hand argument, which is the
+        // one that produced the value
+        RegOp::MinRegReg(_, lhs, rhs) | RegOp::MaxRegReg(_, lhs, rhs) => {
+            if v[lhs].is_nan() || v[rhs].is_nan() {
+                [0.0; 3]
+            } else {
+                [0.0, 1.0, 0.0]
+            }
+        }
+        RegOp::SmoothMinRegReg(out, lhs, rhs) => {
+            smooth_min_partials(v[lhs], v[rhs], v[out])
+        }
+        RegOp::SmoothMinRegImm(out, arg, imm) => {
+            smooth_min_partials(v[arg], imm, v[out])
+        }
+    }
+}
+
+/// Returns partial derivatives of `atan2(y, x)` with respect to `y` and `x`
+fn atan2_partials(y: f32, x: f32) -> (f32, f32) {
+    let d = x.powi(2) + y.powi(2);
+    (x / d, -y / d)
+}
+
+/// Returns partial derivatives of `a^b` with respect to `a` and `b`
+fn pow_partials(a: f32, b: f32) -> (f32, f32) {
+    (b * a.powf(b - 1.0), a.powf(b) * a.ln())
+}
+
+/// Returns partial derivatives of a polynomial smooth minimum
+///
+/// Partials are with respect to `a`, `b`, and the blending radius `k`; they
+/// are only used when both sides are blended (i.e. [`Choice::Both`]).
+fn smooth_min_partials(a: f32, b: f32, k: f32) -> [f32; 3] {
+    if a.is_nan() || b.is_nan() || k.is_nan() {
+        return [0.0; 3];
+    }
+    let (da, db) = if a < b { (1.0, 0.0) } else { (0.0, 1.0) };
+    let h = k - (a - b).abs();
+    if h > 0.0 {
+        let s = if a - b < 0.0 { -1.0 } else { 1.0 };
+        let t = h * s / (2.0 * k);
+        [
+            da + t,
+            db - t,
+            h.powi(2) / (4.0 * k.powi(2)) - h / (2.0 * k),
+        ]
+    } else {
+        [da, db, 0.0]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        context::{Context, Tree},
+        eval::{
+            test::{build_stress_fn, CanonicalBinaryOp, CanonicalUnaryOp},
+            BulkEvaluator, GradNFunction, MathFunction,
+        },
+        types::GradN,
+        var::Var,
+        vm::VmFunction,
+    };
+
+    /// Arguments used when testing individual operations
+    fn test_args() -> Vec<f32> {
+        let mut args: Vec<f32> = (-16..=16).map(|i| i as f32 * 0.37).collect();
+        args.extend([0.5, 1.0, 2.0, std::f32::consts::PI, f32::NAN]);
+        args
+    }
+
+    /// Compares adjoint gradients with forward-mode gradients
+    fn compare(ctx: &Context, root: crate::context::Node, points: &[Vec<f32>]) {
+        let f = VmFunction::new(ctx, &[root]).unwrap();
+        let tape = f.tape();
+        let n = tape.vars().len();
+        assert!(n <= 4);
+
+        let mut fwd = VmFunction::new_grad_n_slice_eval::<4>();
+        let mut rev = VmAdjointEval::new();
+        for p in points {
+            let inputs: Vec<_> = p
+                .iter()
+                .enumerate()
+                .map(|(i, v)| vec![GradN::<4>::input(*v, i)])
+                .collect();
+            let expected = fwd.eval(&tape, &inputs).unwrap()[0][0];
+            let (out, grad) = rev.eval(&tape, p).unwrap();
+
+            let same = |a: f32, b: f32| {
+                a == b
+                    || (a.is_nan() && b.is_nan())
+                    || (a - b).abs() <= 1e-5 * a.abs().max(b.abs()).max(1.0)
+            };
+            assert!(
+                same(out[0], expected.v),
+                "value mismatch at {p:?}: {} != {}",
+                out[0],
+                expected.v
+            );
+            // Forward-mode gradients are poisoned by NaN or infinite values
+            // (because `NaN * 0.0` is NaN), and reverse-mode gradients apply
+            // the chain rule in a different order, so only check gradients of
+            // finite values
+            if !out[0].is_finite() {
+                continue;
+            }
+            for i in 0..n {
+                assert!(
+                    same(grad[0][i], expected.d[i]),
+                    "gradient mismatch at {p:?}: {:?} != {}",
+                    grad[0],
+                    expected,
+                );
+            }
+        }
+    }
+
+    struct TestAdjoint;
+    impl TestAdjoint {
+        fn test_unary<C: CanonicalUnaryOp>() {
+            let mut ctx = Context::new();
+            let x = ctx.var(Var::new());
+            let node = C::build(&mut ctx, x);
+            let points: Vec<_> =
+                test_args().into_iter().map(|a| vec![a]).collect();
+            compare(&ctx, node, &points);
+        }
+
+        fn test_binary<C: CanonicalBinaryOp>() {
+            let args = test_args();
+            let mut ctx = Context::new();
+            let a = ctx.var(Var::new());
+            let b = ctx.var(Var::new());
+
+            let mut points = vec![];
+            for &x in &args {
+                for &y in &args {
+                    points.push(vec![x, y]);
+                }
+            }
+            let node = C::build(&mut ctx, a, b);
+            compare(&ctx, node, &points);
+
+            let points: Vec<_> = args.iter().map(|a| vec![*a]).collect();
+            let node = C::build(&mut ctx, a, a);
+            compare(&ctx, node, &points);
+            for &imm in &args {
+                let node = C::build(&mut ctx, a, imm);
+                compare(&ctx, node, &points);
+                let node = C::build(&mut ctx, imm, a);
+                compare(&ctx, node, &points);
+            }
+        }
+    }
+
+    mod unary {
+        use super::*;
+        crate::all_unary_tests!(TestAdjoint);
+    }
+
+    mod binary {
+        use super::*;
+        crate::all_binary_tests!(TestAdjoint);
+    }
+
+    #[test]
+    fn test_adjoint_stress() {
+        for depth in [1, 4, 16, 64] {
+            let (ctx, node) = build_stress_fn(depth);
+            let points: Vec<_> = (0..16)
+                .map(|i| {
+                    let i = i as f32 / 16.0;
+                    vec![i, 1.0 - i, i * 2.0]
+                })
+                .collect();
+            compare(&ctx, node, &points);
+        }
+    }
+
+    #[test]
+    fn test_adjoint_branches() {
+        let (x, y, z) = Tree::axes();
+        let t = (x.clone() * 2.0).max(y.clone()).min(z.clone())
+            + x.clone().smooth_min(y.clone(), z.clone());
+        let mut ctx = Context::new();
+        let root = ctx.import(&t);
+        let mut points = vec![];
+        for &x in &[-1.0, 0.1, 0.5, 2.0] {
+            for &y in &[-0.5, 0.3, 1.5] {
+                for &z in &[0.2, 1.0, 3.0] {
+                    points.push(vec![x, y, z]);
+                }
+            }
+        }
+        compare(&ctx, root, &points);
+    }
+
+    #[test]
+    fn test_adjoint_many_params() {
+        // Sum of p_k * sin(k * x), with 200 parameters
+        let params: Vec<Var> = (0..200).map(|_| Var::new()).collect();
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let mut sum = ctx.constant(0.0);
+        for (i, p) in params.iter().enumerate() {
+            let s = ctx.mul(x, (i + 1) as f32).unwrap();
+            let s = ctx.sin(s).unwrap();
+            let p = ctx.var(*p);
+            let t = ctx.mul(p, s).unwrap();
+            sum = ctx.add(sum, t).unwrap();
+        }
+        let f = VmFunction::new(&ctx, &[sum]).unwrap();
+        let tape = f.tape();
+        let vars = tape.vars();
+
+        let xv = 0.3;
+        let mut inputs = vec![0.0; vars.len()];
+        for (i, p) in params.iter().enumerate() {
+            inputs[vars[p]] = (i + 1) as f32 / 100.0;
+        }
+        inputs[vars[&Var::X]] = xv;
+
+        let mut eval = VmAdjointEval::new();
+        let (out, grad) = eval.eval(&tape, &inputs).unwrap();
+        let mut expected = 0.0;
+        let mut expected_dx = 0.0;
+        for (i, p) in params.iter().enumerate() {
+            let k = (i + 1) as f32;
+            let s = (xv * k).sin();
+            assert!((grad[0][vars[p]] - s).abs() < 1e-5);
+            expected += s * k / 100.0;
+            expected_dx += (xv * k).cos() * k.powi(2) / 100.0;
+        }
+        assert!((out[0] - expected).abs() < 1e-3, "{} != {expected}", out[0]);
+        let dx = grad[0][vars[&Var::X]];
+        assert!(
+            (dx - expected_dx).abs() < 1e-4 * expected_dx.abs(),
+            "{dx} != {expected_dx}"
+        );
+    }
+
+    #[test]
+    fn test_adjoint_outputs() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let y = ctx.y();
+        let a = ctx.mul(x, y).unwrap();
+        let b = ctx.sub(x, y).unwrap();
+        let c = ctx.square(y).unwrap();
+        let f = VmFunction::new(&ctx, &[a, b, c]).unwrap();
+        let tape = f.tape();
+        let (ix, iy) = (tape.vars()[&Var::X], tape.vars()[&Var::Y]);
+
+        let mut inputs = [0.0; 2];
+        inputs[ix] = 3.0;
+        inputs[iy] = 5.0;
+        let mut eval = VmAdjointEval::new();
+        let (out, grad) = eval.eval(&tape, &inputs).unwrap();
+        assert_eq!(out, [15.0, -2.0, 25.0]);
+        assert_eq!(grad.len(), 3);
+        assert_eq!((grad[0][ix], grad[0][iy]), (5.0, 3.0));
+        assert_eq!((grad[1][ix], grad[1][iy]), (1.0, -1.0));
+        assert_eq!((grad[2][ix], grad[2][iy]), (0.0, 10.0));
+
+        assert!(eval.eval(&tape, &[1.0]).is_err());
+    }
+}
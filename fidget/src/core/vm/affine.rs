@@ -0,0 +1,356 @@
+//! Affine arithmetic evaluation with the VM backend
+use super::{
+    forward_interval_value, EmptyTapeStorage, GenericVmFunction, GenericVmTape,
+    IntervalValue, TracingVmEval, VmData, VmFloatSliceEval, VmGradNSliceEval,
+    VmGradSliceEval, VmPointEval, VmTrace, VmWorkspace,
+};
+use crate::{
+    context::Node,
+    eval::{Function, GradNFunction, MathFunction, TracingEvaluator},
+    render::{RenderHints, TileSizes},
+    shape::Shape,
+    types::{Affine, Interval},
+    var::VarMap,
+    vm::Choice,
+    Context, Error,
+};
+
+/// Function which uses the VM backend, with affine arithmetic for interval
+/// evaluation
+///
+/// This is identical to a [`VmFunction`](super::VmFunction), except that its
+/// [`IntervalEval`](Function::IntervalEval) is a [`VmAffineEval`]: intervals
+/// are tighter (so tapes are simplified more aggressively and more tiles are
+/// pruned), at the cost of slower interval evaluation.
+pub type VmAffineFunction = GenericVmAffineFunction<{ u8::MAX as usize }>;
+
+/// Shape that uses the [`VmAffineFunction`] backend for evaluation
+pub type VmAffineShape = Shape<VmAffineFunction>;
+
+/// VM-backed function with affine interval evaluation and a configurable
+/// number of registers
+///
+/// You are unlikely to use this directly; [`VmAffineShape`] should be used
+/// for affine-arithmetic VM-based evaluation.
+#[derive(Clone)]
+pub struct GenericVmAffineFunction<const N: usize>(GenericVmFunction<N>);
+
+impl<const N: usize> From<VmData<N>> for GenericVmAffineFunction<N> {
+    fn from(d: VmData<N>) -> Self {
+        Self(d.into())
+    }
+}
+
+impl<const N: usize> GenericVmAffineFunction<N> {
+    /// Borrows the inner [`GenericVmFunction`]
+    pub fn inner(&self) -> &GenericVmFunction<N> {
+        &self.0
+    }
+}
+
+impl<const N: usize> Function for GenericVmAffineFunction<N> {
+    type Storage = VmData<N>;
+    type Workspace = VmWorkspace<N>;
+
+    type TapeStorage = EmptyTapeStorage;
+
+    type FloatSliceEval = VmFloatSliceEval<N>;
+    fn float_slice_tape(&self, _storage: EmptyTapeStorage) -> GenericVmTape<N> {
+        self.0.tape()
+    }
+    type GradSliceEval = VmGradSliceEval<N>;
+    fn grad_slice_tape(&self, _storage: EmptyTapeStorage) -> GenericVmTape<N> {
+        self.0.tape()
+    }
+    type PointEval = VmPointEval<N>;
+    fn point_tape(&self, _storage: EmptyTapeStorage) -> GenericVmTape<N> {
+        self.0.tape()
+    }
+    type IntervalEval = VmAffineEval<N>;
+    fn interval_tape(&self, _storage: EmptyTapeStorage) -> GenericVmTape<N> {
+        self.0.tape()
+    }
+    type Trace = VmTrace;
+    fn simplify(
+        &self,
+        trace: &Self::Trace,
+        storage: Self::Storage,
+        workspace: &mut Self::Workspace,
+    ) -> Result<Self, Error> {
+        self.0.simplify_with(trace, storage, workspace).map(Self)
+    }
+
+    fn recycle(self) -> Option<Self::Storage> {
+        self.0.recycle()
+    }
+
+    fn size(&self) -> usize {
+        self.0.size()
+    }
+
+    fn vars(&self) -> &VarMap {
+        self.0.vars()
+    }
+}
+
+impl<const N: usize> RenderHints for GenericVmAffineFunction<N> {
+    fn tile_sizes_3d() -> TileSizes {
+        GenericVmFunction::<N>::tile_sizes_3d()
+    }
+
+    fn tile_sizes_2d() -> TileSizes {
+        GenericVmFunction::<N>::tile_sizes_2d()
+    }
+}
+
+impl<const N: usize> MathFunction for GenericVmAffineFunction<N> {
+    fn new(ctx: &Context, nodes: &[Node]) -> Result<Self, Error> {
+        GenericVmFunction::new(ctx, nodes).map(Self)
+    }
+}
+
+impl<const N: usize> GradNFunction for GenericVmAffineFunction<N> {
+    type GradNSliceEval<const M: usize> = VmGradNSliceEval<N, M>;
+    fn grad_n_slice_tape<const M: usize>(
+        &self,
+        _storage: EmptyTapeStorage,
+    ) -> GenericVmTape<N> {
+        self.0.tape()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// VM-based tracing evaluator using affine arithmetic
+///
+/// This evaluator takes and returns [`Interval`] values, so it's a drop-in
+/// replacement for a [`VmIntervalEval`](super::VmIntervalEval) and produces a
+/// [`VmTrace`] in the same format.  Internally, each input is converted to an
+/// [`Affine`] form with its own noise symbol (for the first
+/// [`Affine::TERMS`] inputs), which lets correlated subexpressions cancel:
+/// `x - x` evaluates to (nearly) zero, and `min` / `max` choices are decided
+/// using the difference between their arguments.
+#[derive(Default)]
+pub struct VmAffineEval<const N: usize>(TracingVmEval<Affine>, Vec<Interval>);
+impl<const N: usize> TracingEvaluator for VmAffineEval<N> {
+    type Data = Interval;
+    type Tape = GenericVmTape<N>;
+    type Trace = VmTrace;
+    type TapeStorage = EmptyTapeStorage;
+
+    fn eval(
+        &mut self,
+        tape: &Self::Tape,
+        vars: &[Interval],
+    ) -> Result<(&[Interval], Option<&VmTrace>), Error> {
+        let simplify = self.0.eval_interval(tape, vars)?;
+        self.1.clear();
+        self.1.extend(self.0.out.iter().map(Affine::interval));
+        Ok((
+            &self.1,
+            if simplify {
+                Some(&self.0.choices)
+            } else {
+                None
+            },
+        ))
+    }
+}
+
+impl IntervalValue for Affine {
+    fn input(v: Interval, i: usize) -> Self {
+        Affine::input(v, i)
+    }
+    fn not(self) -> Self {
+        !self
+    }
+    fn compare(self, rhs: Self) -> Self {
+        Affine::compare(self, rhs)
+    }
+    forward_interval_value!(Affine);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        context::{Op, Tree},
+        eval::{
+            test::{canonical, interval::TestInterval, CanonicalBinaryOp},
+            Tape,
+        },
+        shape::EzShape,
+        var::Var,
+    };
+
+    crate::grad_slice_tests!(VmAffineFunction);
+    crate::float_slice_tests!(VmAffineFunction);
+    crate::point_tests!(VmAffineFunction);
+
+    // The shared interval tests are used individually, because some of them
+    // assume that results are exactly as loose as interval arithmetic (e.g.
+    // `x - x` is not zero, and stress tests compare against `VmIntervalEval`)
+    crate::interval_test!(test_interval, VmAffineFunction);
+    crate::interval_test!(test_i_abs, VmAffineFunction);
+    crate::interval_test!(test_i_add_abs, VmAffineFunction);
+    crate::interval_test!(test_i_sqrt, VmAffineFunction);
+    crate::interval_test!(test_i_square, VmAffineFunction);
+    crate::interval_test!(test_i_sin, VmAffineFunction);
+    crate::interval_test!(test_i_neg, VmAffineFunction);
+    crate::interval_test!(test_i_not, VmAffineFunction);
+    crate::interval_test!(test_i_mul, VmAffineFunction);
+    crate::interval_test!(test_i_mul_imm, VmAffineFunction);
+    crate::interval_test!(test_i_sub, VmAffineFunction);
+    crate::interval_test!(test_i_sub_imm, VmAffineFunction);
+    crate::interval_test!(test_i_recip, VmAffineFunction);
+    crate::interval_test!(test_i_div, VmAffineFunction);
+    crate::interval_test!(test_i_min, VmAffineFunction);
+    crate::interval_test!(test_i_min_imm, VmAffineFunction);
+    crate::interval_test!(test_i_max, VmAffineFunction);
+    crate::interval_test!(test_i_max_imm, VmAffineFunction);
+    crate::interval_test!(test_i_and, VmAffineFunction);
+    crate::interval_test!(test_i_or, VmAffineFunction);
+    crate::interval_test!(test_i_compare, VmAffineFunction);
+    crate::interval_test!(test_i_modulo, VmAffineFunction);
+    crate::interval_test!(test_i_pow, VmAffineFunction);
+    crate::interval_test!(test_i_cosh, VmAffineFunction);
+    crate::interval_test!(test_i_simplify, VmAffineFunction);
+    crate::interval_test!(test_i_simplify_conditional, VmAffineFunction);
+    crate::interval_test!(test_i_select, VmAffineFunction);
+    crate::interval_test!(test_i_smooth_min, VmAffineFunction);
+
+    mod i_unary {
+        use super::*;
+        crate::all_unary_tests!(TestInterval::<VmAffineFunction>);
+    }
+
+    /// Checks binary operations against a grid of sample points
+    ///
+    /// This is like [`TestInterval::test_binary`], but only samples matching
+    /// values when both arguments are the same variable.
+    fn test_binary<C: CanonicalBinaryOp>() {
+        type T = TestInterval<VmAffineFunction>;
+        T::test_binary_reg_imm::<C>();
+        T::test_binary_imm_reg::<C>();
+
+        let args = T::interval_test_args();
+        let mut ctx = Context::new();
+        let va = Var::new();
+        let vb = Var::new();
+        let a = ctx.var(va);
+        let b = ctx.var(vb);
+        let name = format!("{}(reg, reg)", C::NAME);
+        let mut eval = VmAffineFunction::new_interval_eval();
+
+        let node = C::build(&mut ctx, a, b);
+        let shape = VmAffineFunction::new(&ctx, &[node]).unwrap();
+        let tape = shape.interval_tape(Default::default());
+        let (ia, ib) = (tape.vars()[&va], tape.vars()[&vb]);
+        for &lhs in args.iter() {
+            for &rhs in args.iter() {
+                let mut vs = [Interval::from(0.0); 2];
+                vs[ia] = lhs;
+                vs[ib] = rhs;
+                let (out, _trace) = eval.eval(&tape, &vs).unwrap();
+                T::compare_interval_results(
+                    lhs,
+                    rhs,
+                    out[0],
+                    C::eval_reg_reg_f32,
+                    &name,
+                );
+            }
+        }
+
+        let node = C::build(&mut ctx, a, a);
+        if matches!(ctx.get_op(node).unwrap(), Op::Unary(..)) {
+            return;
+        }
+        let shape = VmAffineFunction::new(&ctx, &[node]).unwrap();
+        let tape = shape.interval_tape(Default::default());
+        for &lhs in args.iter() {
+            let (out, _trace) = eval.eval(&tape, &[lhs]).unwrap();
+            let out = out[0];
+            for i in 0..8 {
+                let i = i as f32 / 7.0;
+                let v = (lhs.lower() * i + lhs.upper() * (1.0 - i))
+                    .max(lhs.lower())
+                    .min(lhs.upper());
+                let inside_value = C::eval_reg_reg_f32(v, v);
+                if inside_value.is_nan() || inside_value.is_infinite() {
+                    assert!(
+                        out.has_nan(),
+                        "interval failure in '{name}': {v} in {lhs} => \
+                         {inside_value} not in {out} (should be [NaN, NaN])"
+                    );
+                } else if !out.has_nan() {
+                    assert!(
+                        inside_value >= out.lower()
+                            && inside_value <= out.upper(),
+                        "interval failure in '{name}': {v} in {lhs} => \
+                         {inside_value} not in {out}"
+                    );
+                }
+            }
+        }
+    }
+
+    mod i_binary {
+        use super::*;
+        macro_rules! affine_binary_test {
+            ($i:ident) => {
+                #[test]
+                fn $i() {
+                    test_binary::<canonical::$i>()
+                }
+            };
+        }
+        affine_binary_test!(add);
+        affine_binary_test!(sub);
+        affine_binary_test!(mul);
+        affine_binary_test!(div);
+        affine_binary_test!(atan2);
+        affine_binary_test!(min);
+        affine_binary_test!(max);
+        affine_binary_test!(compare);
+        affine_binary_test!(modulo);
+        affine_binary_test!(and);
+        affine_binary_test!(or);
+        affine_binary_test!(pow);
+    }
+
+    #[test]
+    fn test_affine_tighter() {
+        let (x, y, _z) = Tree::axes();
+        let c = std::f64::consts::FRAC_1_SQRT_2;
+
+        // x - x is (very nearly) zero
+        let shape = VmAffineShape::from(x.clone() - x.clone());
+        let tape = shape.ez_interval_tape();
+        let mut eval = VmAffineShape::new_interval_eval();
+        let (out, _) = eval
+            .eval(&tape, [-1.0, 1.0], [-1.0, 1.0], [0.0, 0.0])
+            .unwrap();
+        assert!(out.width() < 1e-5, "{out}");
+
+        // Rotating by 45° and back, then evaluating in a tile that's entirely
+        // inside the shape; interval arithmetic can't tell.
+        let u = (x.clone() + y.clone()) * c;
+        let v = (x.clone() - y.clone()) * c;
+        let f = (u + v) * c - 1.5;
+        let (lo, hi) = ([0.9, 1.1], [-1.0, 1.0]);
+
+        let shape = VmAffineShape::from(f.clone());
+        let tape = shape.ez_interval_tape();
+        let mut eval = VmAffineShape::new_interval_eval();
+        let (out, trace) = eval.eval(&tape, lo, hi, [0.0, 0.0]).unwrap();
+        assert!(out.upper() < 0.0, "{out}");
+        assert!(trace.is_none());
+
+        let shape = crate::vm::VmShape::from(f);
+        let tape = shape.ez_interval_tape();
+        let mut eval = crate::vm::VmShape::new_interval_eval();
+        let (out, _) = eval.eval(&tape, lo, hi, [0.0, 0.0]).unwrap();
+        assert!(out.upper() >= 0.0, "{out}");
+    }
+}
@@ -6,9 +6,16 @@ use crate::{
     vm::Choice,
     Error,
 };
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::sync::Arc;
 
+/// Version of the serialized [`VmData`] format
+///
+/// This must be incremented whenever the serialized layout changes (e.g. when
+/// opcodes are added or [`VarMap`] gains fields), so that stale data is
+/// rejected instead of being misinterpreted.
+const VM_DATA_VERSION: u32 = 1;
+
 /// A flattened math expression, ready for evaluation or further compilation.
 ///
 /// Under the hood, [`VmData`] stores two different representations:
@@ -62,7 +69,11 @@ use std::sync::Arc;
 /// Despite this peek at its internals, users are unlikely to touch `VmData`
 /// directly; a [`VmShape`](crate::vm::VmShape) wraps the `VmData` and
 /// implements our common traits.
-#[derive(Default, Serialize, Deserialize)]
+///
+/// `VmData` may be serialized with [`serde`]; the serialized form begins with
+/// a format version, and data written by a different version of the format is
+/// rejected when deserializing.
+#[derive(Default)]
 pub struct VmData<const N: usize = { u8::MAX as usize }> {
     ssa: SsaTape,
     asm: RegTape,
@@ -74,10 +85,70 @@ pub struct VmData<const N: usize = { u8::MAX as usize }> {
     pub vars: Arc<VarMap>,
 }
 
+/// Serialized form of a [`VmData`], borrowing from the original
+#[derive(Serialize)]
+struct VmDataRef<'a> {
+    version: u32,
+    ssa: &'a SsaTape,
+    asm: &'a RegTape,
+    vars: &'a VarMap,
+}
+
+/// Serialized form of a [`VmData`]
+///
+/// The version must be the first field, so that it can be checked before the
+/// rest of the data is interpreted.
+#[derive(Deserialize)]
+struct VmDataOwned {
+    version: u32,
+    ssa: SsaTape,
+    asm: RegTape,
+    vars: VarMap,
+}
+
+impl<const N: usize> Serialize for VmData<N> {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        VmDataRef {
+            version: VM_DATA_VERSION,
+            ssa: &self.ssa,
+            asm: &self.asm,
+            vars: &self.vars,
+        }
+        .serialize(s)
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for VmData<N> {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let data = VmDataOwned::deserialize(d)?;
+        if data.version != VM_DATA_VERSION {
+            return Err(serde::de::Error::custom(format!(
+                "unsupported VM data version {} (expected {VM_DATA_VERSION})",
+                data.version
+            )));
+        }
+        Ok(Self {
+            ssa: data.ssa,
+            asm: data.asm,
+            vars: data.vars.into(),
+        })
+    }
+}
+
 impl<const N: usize> VmData<N> {
     /// Builds a new tape for the given node
     pub fn new(context: &Context, nodes: &[Node]) -> Result<Self, Error> {
-        let (ssa, vars) = SsaTape::new(context, nodes)?;
+        Self::new_with_immediates(context, nodes, |c| c as f32)
+    }
+
+    /// Builds a new tape, using a custom conversion from constants to
+    /// immediates (see [`SsaTape::new_with_immediates`])
+    pub(crate) fn new_with_immediates<F: FnMut(f64) -> f32>(
+        context: &Context,
+        nodes: &[Node],
+        to_imm: F,
+    ) -> Result<Self, Error> {
+        let (ssa, vars) = SsaTape::new_with_immediates(context, nodes, to_imm)?;
         let asm = RegTape::new::<N>(&ssa);
         Ok(Self {
             ssa,
@@ -141,6 +212,10 @@ impl<const N: usize> VmData<N> {
         let mut choice_count = 0;
         let mut output_count = 0;
 
+        // In-place operations which are kept produce two operations for a
+        // single binding (a copy into the output slot, then the operation)
+        let mut in_place_count = 0;
+
         // Other iterators to consume various arrays in order
         let mut choice_iter = choices.iter().rev();
 
@@ -191,6 +266,12 @@ impl<const N: usize> VmData<N> {
                 | SsaOp::AtanReg(index, arg)
                 | SsaOp::ExpReg(index, arg)
                 | SsaOp::LnReg(index, arg)
+                | SsaOp::SinhReg(index, arg)
+                | SsaOp::CoshReg(index, arg)
+                | SsaOp::TanhReg(index, arg)
+                | SsaOp::Exp2Reg(index, arg)
+                | SsaOp::Log2Reg(index, arg)
+                | SsaOp::Log10Reg(index, arg)
                 | SsaOp::NotReg(index, arg) => {
                     *index = new_index;
                     *arg = workspace.get_or_insert_active(*arg);
@@ -269,11 +350,119 @@ impl<const N: usize> VmData<N> {
                         Choice::Unknown => panic!("oh no"),
                     }
                 }
+                SsaOp::SelectRegReg(index, cond, arg) => {
+                    match choice_iter.next().unwrap() {
+                        Choice::Left => {
+                            // The earlier copy into this slot is now dead
+                            workspace.clear_active(*index);
+                            match workspace.active(*arg) {
+                                Some(new_arg) => {
+                                    op = SsaOp::CopyReg(new_index, new_arg);
+                                }
+                                None => {
+                                    workspace.set_active(*arg, new_index);
+                                    continue;
+                                }
+                            }
+                        }
+                        // The earlier copy into this slot is all that we need
+                        Choice::Right => continue,
+                        Choice::Both => {
+                            choice_count += 1;
+                            in_place_count += 1;
+                            *index = new_index;
+                            *cond = workspace.get_or_insert_active(*cond);
+                            *arg = workspace.get_or_insert_active(*arg);
+                        }
+                        Choice::Unknown => panic!("oh no"),
+                    }
+                }
+                SsaOp::SelectRegImm(index, cond, imm) => {
+                    match choice_iter.next().unwrap() {
+                        Choice::Left => {
+                            workspace.clear_active(*index);
+                            op = SsaOp::CopyImm(new_index, *imm);
+                        }
+                        Choice::Right => continue,
+                        Choice::Both => {
+                            choice_count += 1;
+                            in_place_count += 1;
+                            *index = new_index;
+                            *cond = workspace.get_or_insert_active(*cond);
+                        }
+                        Choice::Unknown => panic!("oh no"),
+                    }
+                }
+                SsaOp::SmoothMinRegReg(index, lhs, rhs) => {
+                    match choice_iter.next().unwrap() {
+                        Choice::Left => {
+                            // The earlier copy of the blend radius is now dead
+                            workspace.clear_active(*index);
+                            match workspace.active(*lhs) {
+                                Some(new_lhs) => {
+                                    op = SsaOp::CopyReg(new_index, new_lhs);
+                                }
+                                None => {
+                                    workspace.set_active(*lhs, new_index);
+                                    continue;
+                                }
+                            }
+                        }
+                        Choice::Right => {
+                            workspace.clear_active(*index);
+                            match workspace.active(*rhs) {
+                                Some(new_rhs) => {
+                                    op = SsaOp::CopyReg(new_index, new_rhs);
+                                }
+                                None => {
+                                    workspace.set_active(*rhs, new_index);
+                                    continue;
+                                }
+                            }
+                        }
+                        Choice::Both => {
+                            choice_count += 1;
+                            in_place_count += 1;
+                            *index = new_index;
+                            *lhs = workspace.get_or_insert_active(*lhs);
+                            *rhs = workspace.get_or_insert_active(*rhs);
+                        }
+                        Choice::Unknown => panic!("oh no"),
+                    }
+                }
+                SsaOp::SmoothMinRegImm(index, arg, imm) => {
+                    match choice_iter.next().unwrap() {
+                        Choice::Left => {
+                            workspace.clear_active(*index);
+                            match workspace.active(*arg) {
+                                Some(new_arg) => {
+                                    op = SsaOp::CopyReg(new_index, new_arg);
+                                }
+                                None => {
+                                    workspace.set_active(*arg, new_index);
+                                    continue;
+                                }
+                            }
+                        }
+                        Choice::Right => {
+                            workspace.clear_active(*index);
+                            op = SsaOp::CopyImm(new_index, *imm);
+                        }
+                        Choice::Both => {
+                            choice_count += 1;
+                            in_place_count += 1;
+                            *index = new_index;
+                            *arg = workspace.get_or_insert_active(*arg);
+                        }
+                        Choice::Unknown => panic!("oh no"),
+                    }
+                }
                 SsaOp::AddRegReg(index, lhs, rhs)
                 | SsaOp::MulRegReg(index, lhs, rhs)
                 | SsaOp::SubRegReg(index, lhs, rhs)
                 | SsaOp::DivRegReg(index, lhs, rhs)
                 | SsaOp::AtanRegReg(index, lhs, rhs)
+                | SsaOp::PowRegReg(index, lhs, rhs)
                 | SsaOp::CompareRegReg(index, lhs, rhs)
                 | SsaOp::ModRegReg(index, lhs, rhs) => {
                     *index = new_index;
@@ -288,6 +477,8 @@ impl<const N: usize> VmData<N> {
                 | SsaOp::DivImmReg(index, arg, _imm)
                 | SsaOp::AtanImmReg(index, arg, _imm)
                 | SsaOp::AtanRegImm(index, arg, _imm)
+                | SsaOp::PowImmReg(index, arg, _imm)
+                | SsaOp::PowRegImm(index, arg, _imm)
                 | SsaOp::CompareRegImm(index, arg, _imm)
                 | SsaOp::CompareImmReg(index, arg, _imm)
                 | SsaOp::ModRegImm(index, arg, _imm)
@@ -300,7 +491,10 @@ impl<const N: usize> VmData<N> {
             ops_out.push(op);
         }
 
-        assert_eq!(workspace.count as usize + 1, ops_out.len());
+        assert_eq!(
+            workspace.count as usize + 1 + in_place_count,
+            ops_out.len()
+        );
         let asm_tape = workspace.alloc.finalize();
 
         Ok(VmData {
@@ -315,10 +509,22 @@ impl<const N: usize> VmData<N> {
     }
 
     /// Produces an iterator that visits [`RegOp`] values in evaluation order
-    pub fn iter_asm(&self) -> impl Iterator<Item = RegOp> + '_ {
+    ///
+    /// The iterator can be reversed to visit operations in reverse order (e.g.
+    /// for back-propagation).
+    pub fn iter_asm(&self) -> impl DoubleEndedIterator<Item = RegOp> + '_ {
         self.asm.iter().cloned().rev()
     }
 
+    /// Generates standalone C or Rust source for the register-allocated tape
+    ///
+    /// The generated function takes inputs in the order given by
+    /// [`self.vars`](Self::vars); see [`codegen::source`](crate::codegen::source)
+    /// for details.
+    pub fn to_source(&self, opts: &crate::codegen::SourceOptions) -> String {
+        crate::codegen::source(&self.asm, opts)
+    }
+
     /// Pretty-prints the inner SSA tape
     pub fn pretty_print(&self) {
         self.ssa.pretty_print();
@@ -378,6 +584,10 @@ impl<const N: usize> VmWorkspace<N> {
         self.bind[i as usize] = bind;
     }
 
+    fn clear_active(&mut self, i: u32) {
+        self.bind[i as usize] = u32::MAX;
+    }
+
     /// Resets the workspace, preserving allocations and claiming the given
     /// [`RegTape`].
     pub fn reset(&mut self, tape_len: usize, tape: RegTape) {
@@ -392,6 +602,37 @@ impl<const N: usize> VmWorkspace<N> {
 mod test {
     use super::*;
 
+    #[test]
+    fn var_names_round_trip() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let r = ctx.named_var("radius").unwrap();
+        let out = ctx.sub(x, r).unwrap();
+        let r = ctx.get_var(r).unwrap();
+        let data = VmData::<255>::new(&ctx, &[out]).unwrap();
+        assert_eq!(data.vars.info(&r).unwrap().name, "radius");
+        assert!(data.vars.info(&crate::var::Var::X).is_none());
+
+        let bytes = bincode::serialize(&data).unwrap();
+        let data: VmData<255> = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(data.vars.registry().find("radius"), Some(r));
+    }
+
+    #[test]
+    fn version_mismatch() {
+        let mut ctx = Context::new();
+        let x = ctx.x();
+        let data = VmData::<255>::new(&ctx, &[x]).unwrap();
+        let mut bytes = bincode::serialize(&data).unwrap();
+        assert_eq!(bytes[..4], VM_DATA_VERSION.to_le_bytes());
+        bytes[..4].copy_from_slice(&(VM_DATA_VERSION + 1).to_le_bytes());
+        let err = bincode::deserialize::<VmData<255>>(&bytes)
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.contains("unsupported VM data version"), "{err}");
+    }
+
     #[test]
     fn simplify_reg_count_change() {
         let mut ctx = Context::new();
@@ -0,0 +1,2124 @@
+//! Double-precision evaluation with the VM backend
+//!
+//! Tapes store immediates as `f32`, which isn't enough for models with large
+//! coordinates or finely-spaced constants.  A [`VmFunction64`] evaluates the
+//! same register-allocated tape in `f64`; each constant that can't be exactly
+//! represented as an `f32` is assigned a unique `f32` key when the tape is
+//! built, and the function owns a separate stream of `f64` immediates (one per
+//! operation) which is used during evaluation.
+//!
+//! `VmFunction64` implements [`Function`], so it can be used in a
+//! [`Shape`] (as a [`VmShape64`]) for rendering and meshing.  Its evaluators
+//! take and return single-precision values through the [`TracingEvaluator`]
+//! and [`BulkEvaluator`] traits, but compute every intermediate value in
+//! double precision; each evaluator also has an `eval_f64` method which takes
+//! and returns `f64`-based data types ([`f64`], [`Interval64`], and
+//! [`Grad64`]).  The JIT backend does not support double-precision
+//! evaluation.
+//!
+//! ```
+//! use fidget::{
+//!     context::{Context, Tree},
+//!     eval::{MathFunction, TracingEvaluator},
+//!     vm::{VmFunction64, VmPointEval64},
+//! };
+//!
+//! // A sphere with a 1 km radius, 1 µm away from the surface
+//! let (x, y, z) = Tree::axes();
+//! let r = (x.square() + y.square() + z.square()).sqrt() - 1e3;
+//! let mut ctx = Context::new();
+//! let root = ctx.import(&r);
+//!
+//! let f = VmFunction64::new(&ctx, &[root])?;
+//! let tape = f.tape();
+//! let mut eval = VmPointEval64::new();
+//! let (out, _trace) = eval.eval_f64(&tape, &[1e3 + 1e-6, 0.0, 0.0])?;
+//! assert!((out[0] - 1e-6).abs() < 1e-12);
+//! # Ok::<(), fidget::Error>(())
+//! ```
+use super::{
+    BulkVmEval, Choice, EmptyTapeStorage, SlotArray, TracingVmEval, VmData,
+    VmTrace, VmWorkspace,
+};
+use crate::{
+    compiler::RegOp,
+    context::{Context, Node, Op},
+    eval::{
+        BulkEvaluator, BulkOutput, Function, MathFunction, Tape,
+        TracingEvaluator,
+    },
+    render::{RenderHints, TileSizes},
+    shape::Shape,
+    types::{Grad, Grad64, Interval, Interval64},
+    var::VarMap,
+    Error,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+/// Function which uses the VM backend for double-precision evaluation
+pub type VmFunction64 = GenericVmFunction64<{ u8::MAX as usize }>;
+
+/// Shape that uses the [`VmFunction64`] backend for evaluation
+pub type VmShape64 = Shape<VmFunction64>;
+
+/// VM-backed double-precision function with a configurable number of registers
+///
+/// You are unlikely to use this directly; [`VmFunction64`] should be used for
+/// double-precision VM-based evaluation.
+#[derive(Clone)]
+pub struct GenericVmFunction64<const N: usize> {
+    data: Arc<VmData<N>>,
+
+    /// Map from `f32` immediate keys (as bits) to their original values
+    ///
+    /// Only constants which aren't exactly representable as `f32` are stored
+    /// here; other immediates are simply widened.
+    consts: Arc<HashMap<u32, f64>>,
+
+    /// Double-precision immediate for each operation in the tape
+    imms: Arc<[f64]>,
+}
+
+impl<const N: usize> GenericVmFunction64<N> {
+    /// Builds the function, resolving double-precision immediates
+    fn from_data(data: VmData<N>, consts: Arc<HashMap<u32, f64>>) -> Self {
+        let imms = data
+            .iter_asm()
+            .map(|op| match immediate(op) {
+                Some(imm) => {
+                    consts.get(&imm.to_bits()).cloned().unwrap_or(imm.into())
+                }
+                None => f64::NAN,
+            })
+            .collect();
+        Self {
+            data: data.into(),
+            consts,
+            imms,
+        }
+    }
+
+    /// Returns a characteristic size (the length of the inner assembly tape)
+    pub fn size(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Reclaim the inner `VmData` if there's only a single reference
+    pub fn recycle(self) -> Option<VmData<N>> {
+        Arc::try_unwrap(self.data).ok()
+    }
+
+    /// Borrows the inner [`VmData`]
+    ///
+    /// Note that immediates in the inner tape may be keys, rather than the
+    /// original constant values.
+    pub fn data(&self) -> &VmData<N> {
+        self.data.as_ref()
+    }
+
+    /// Returns a [`GenericVmTape64`] for the given function
+    pub fn tape(&self) -> GenericVmTape64<N> {
+        GenericVmTape64 {
+            data: self.data.clone(),
+            imms: self.imms.clone(),
+        }
+    }
+
+    /// Returns the number of choices (i.e. `min` and `max` nodes) in the tape
+    pub fn choice_count(&self) -> usize {
+        self.data.choice_count()
+    }
+
+    /// Returns the number of outputs in the tape
+    pub fn output_count(&self) -> usize {
+        self.data.output_count()
+    }
+
+    /// Simplifies the function with the given trace and a new register count
+    pub fn simplify_with<const M: usize>(
+        &self,
+        trace: &VmTrace,
+        storage: VmData<M>,
+        workspace: &mut VmWorkspace<M>,
+    ) -> Result<GenericVmFunction64<M>, Error> {
+        let d =
+            self.data
+                .simplify::<M>(trace.as_slice(), workspace, storage)?;
+        Ok(GenericVmFunction64::from_data(d, self.consts.clone()))
+    }
+}
+
+impl<const N: usize> Function for GenericVmFunction64<N> {
+    type Storage = VmData<N>;
+    type Workspace = VmWorkspace<N>;
+
+    type TapeStorage = EmptyTapeStorage;
+
+    type FloatSliceEval = VmFloatSliceEval64<N>;
+    fn float_slice_tape(
+        &self,
+        _storage: EmptyTapeStorage,
+    ) -> GenericVmTape64<N> {
+        self.tape()
+    }
+    type GradSliceEval = VmGradSliceEval64<N>;
+    fn grad_slice_tape(
+        &self,
+        _storage: EmptyTapeStorage,
+    ) -> GenericVmTape64<N> {
+        self.tape()
+    }
+    type PointEval = VmPointEval64<N>;
+    fn point_tape(&self, _storage: EmptyTapeStorage) -> GenericVmTape64<N> {
+        self.tape()
+    }
+    type IntervalEval = VmIntervalEval64<N>;
+    fn interval_tape(&self, _storage: EmptyTapeStorage) -> GenericVmTape64<N> {
+        self.tape()
+    }
+    type Trace = VmTrace;
+    fn simplify(
+        &self,
+        trace: &Self::Trace,
+        storage: Self::Storage,
+        workspace: &mut Self::Workspace,
+    ) -> Result<Self, Error> {
+        self.simplify_with(trace, storage, workspace)
+    }
+
+    fn recycle(self) -> Option<Self::Storage> {
+        GenericVmFunction64::recycle(self)
+    }
+
+    fn size(&self) -> usize {
+        GenericVmFunction64::size(self)
+    }
+
+    fn vars(&self) -> &VarMap {
+        &self.data.vars
+    }
+}
+
+impl<const N: usize> RenderHints for GenericVmFunction64<N> {
+    fn tile_sizes_3d() -> TileSizes {
+        TileSizes::new(&[128, 64, 32, 16, 8]).unwrap()
+    }
+
+    fn tile_sizes_2d() -> TileSizes {
+        TileSizes::new(&[128, 32, 8]).unwrap()
+    }
+}
+
+impl<const N: usize> MathFunction for GenericVmFunction64<N> {
+    fn new(ctx: &Context, nodes: &[Node]) -> Result<Self, Error> {
+        // Find every constant reachable from the roots
+        let mut seen = HashSet::new();
+        let mut todo = nodes.to_vec();
+        let mut values = vec![];
+        while let Some(node) = todo.pop() {
+            if !seen.insert(node) {
+                continue;
+            }
+            let op = ctx.get_op(node).ok_or(Error::BadNode)?;
+            if let Op::Const(c) = op {
+                values.push(c.0);
+            }
+            todo.extend(op.iter_children());
+        }
+
+        // Constants which are exact in `f32` are used directly, so their keys
+        // are reserved; every other constant is assigned a unique nearby key.
+        let is_exact = |c: f64| c.is_nan() || f64::from(c as f32) == c;
+        let mut used: HashSet<u32> = values
+            .iter()
+            .filter(|c| is_exact(**c))
+            .map(|c| (*c as f32).to_bits())
+            .collect();
+        let mut keys: HashMap<u64, f32> = HashMap::new();
+        let mut consts = HashMap::new();
+        for c in values.into_iter().filter(|c| !is_exact(*c)) {
+            if keys.contains_key(&c.to_bits()) {
+                continue;
+            }
+            let mut k = (c as f32).clamp(-f32::MAX, f32::MAX);
+            let step = if k > 0.0 {
+                f32::next_down
+            } else {
+                f32::next_up
+            };
+            while !used.insert(k.to_bits()) {
+                k = step(k);
+            }
+            keys.insert(c.to_bits(), k);
+            consts.insert(k.to_bits(), c);
+        }
+
+        let data = VmData::new_with_immediates(ctx, nodes, |c| {
+            keys.get(&c.to_bits()).cloned().unwrap_or(c as f32)
+        })?;
+        Ok(Self::from_data(data, consts.into()))
+    }
+}
+
+/// Returns the immediate stored in an operation, if present
+fn immediate(op: RegOp) -> Option<f32> {
+    match op {
+        RegOp::CopyImm(_, imm)
+        | RegOp::AddRegImm(_, _, imm)
+        | RegOp::MulRegImm(_, _, imm)
+        | RegOp::DivRegImm(_, _, imm)
+        | RegOp::DivImmReg(_, _, imm)
+        | RegOp::SubImmReg(_, _, imm)
+        | RegOp::SubRegImm(_, _, imm)
+        | RegOp::AtanRegImm(_, _, imm)
+        | RegOp::AtanImmReg(_, _, imm)
+        | RegOp::PowRegImm(_, _, imm)
+        | RegOp::PowImmReg(_, _, imm)
+        | RegOp::MinRegImm(_, _, imm)
+        | RegOp::MaxRegImm(_, _, imm)
+        | RegOp::CompareRegImm(_, _, imm)
+        | RegOp::CompareImmReg(_, _, imm)
+        | RegOp::ModRegImm(_, _, imm)
+        | RegOp::ModImmReg(_, _, imm)
+        | RegOp::AndRegImm(_, _, imm)
+        | RegOp::OrRegImm(_, _, imm)
+        | RegOp::SelectRegImm(_, _, imm)
+        | RegOp::SmoothMinRegImm(_, _, imm) => Some(imm),
+        _ => None,
+    }
+}
+
+/// Tape which uses the VM backend for double-precision evaluation
+#[derive(Clone)]
+pub struct GenericVmTape64<const N: usize> {
+    data: Arc<VmData<N>>,
+    imms: Arc<[f64]>,
+}
+
+impl<const N: usize> GenericVmTape64<N> {
+    /// Returns a handle to the inner [`VmData`] used by the tape
+    pub fn data(&self) -> &VmData<N> {
+        &self.data
+    }
+}
+
+impl<const N: usize> Tape for GenericVmTape64<N> {
+    type Storage = EmptyTapeStorage;
+    fn recycle(self) -> Option<Self::Storage> {
+        Some(EmptyTapeStorage)
+    }
+
+    fn vars(&self) -> &VarMap {
+        &self.data.vars
+    }
+
+    fn output_count(&self) -> usize {
+        self.data.output_count()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Polynomial smooth minimum of two values, blending within a radius `k`
+///
+/// This is the `f64` equivalent of [`super::smooth_min_choice`].
+fn smooth_min_choice(a: f64, b: f64, k: f64) -> (Choice, f64) {
+    if a.is_nan() || b.is_nan() || k.is_nan() {
+        return (Choice::Both, f64::NAN);
+    }
+    let kk = k.max(0.0);
+    if a + kk < b {
+        (Choice::Left, a)
+    } else if b + kk < a {
+        (Choice::Right, b)
+    } else {
+        let h = k - (a - b).abs();
+        let m = a.min(b);
+        (
+            Choice::Both,
+            if h > 0.0 { m - h * h / (4.0 * k) } else { m },
+        )
+    }
+}
+
+/// VM-based tracing evaluator for double-precision intervals
+///
+/// As a [`TracingEvaluator`], this takes and returns single-precision
+/// [`Interval`] values (rounding outputs outwards, so that they remain
+/// conservative); use [`eval_f64`](Self::eval_f64) for [`Interval64`] values.
+#[derive(Default)]
+pub struct VmIntervalEval64<const N: usize> {
+    eval: TracingVmEval<Interval64>,
+    /// Double-precision inputs, widened from single-precision arguments
+    vars: Vec<Interval64>,
+    /// Single-precision outputs
+    out: Vec<Interval>,
+}
+
+impl<const N: usize> VmIntervalEval64<N> {
+    /// Evaluates the tape with double-precision inputs and outputs
+    pub fn eval_f64(
+        &mut self,
+        tape: &GenericVmTape64<N>,
+        vars: &[Interval64],
+    ) -> Result<(&[Interval64], Option<&VmTrace>), Error> {
+        tape.vars().check_tracing_arguments(vars)?;
+        let imms = tape.imms.iter();
+        let tape = tape.data();
+        self.eval.resize_slots(tape);
+
+        let mut simplify = false;
+        let mut v = SlotArray(&mut self.eval.slots);
+        let mut choices = self.eval.choices.as_mut_slice().iter_mut();
+        for (op, &imm) in tape.iter_asm().zip(imms) {
+            match op {
+                RegOp::Output(arg, i) => {
+                    self.eval.out[i as usize] = v[arg];
+                }
+                RegOp::Input(out, i) => {
+                    v[out] = vars[i as usize];
+                }
+                RegOp::NegReg(out, arg) => {
+                    v[out] = -v[arg];
+                }
+                RegOp::AbsReg(out, arg) => {
+                    v[out] = v[arg].abs();
+                }
+                RegOp::RecipReg(out, arg) => {
+                    v[out] = v[arg].recip();
+                }
+                RegOp::SqrtReg(out, arg) => {
+                    v[out] = v[arg].sqrt();
+                }
+                RegOp::SquareReg(out, arg) => {
+                    v[out] = v[arg].square();
+                }
+                RegOp::FloorReg(out, arg) => {
+                    v[out] = v[arg].floor();
+                }
+                RegOp::CeilReg(out, arg) => {
+                    v[out] = v[arg].ceil();
+                }
+                RegOp::RoundReg(out, arg) => {
+                    v[out] = v[arg].round();
+                }
+                RegOp::SinReg(out, arg) => {
+                    v[out] = v[arg].sin();
+                }
+                RegOp::CosReg(out, arg) => {
+                    v[out] = v[arg].cos();
+                }
+                RegOp::TanReg(out, arg) => {
+                    v[out] = v[arg].tan();
+                }
+                RegOp::AsinReg(out, arg) => {
+                    v[out] = v[arg].asin();
+                }
+                RegOp::AcosReg(out, arg) => {
+                    v[out] = v[arg].acos();
+                }
+                RegOp::AtanReg(out, arg) => {
+                    v[out] = v[arg].atan();
+                }
+                RegOp::ExpReg(out, arg) => {
+                    v[out] = v[arg].exp();
+                }
+                RegOp::LnReg(out, arg) => {
+                    v[out] = v[arg].ln();
+                }
+                RegOp::SinhReg(out, arg) => {
+                    v[out] = v[arg].sinh();
+                }
+                RegOp::CoshReg(out, arg) => {
+                    v[out] = v[arg].cosh();
+                }
+                RegOp::TanhReg(out, arg) => {
+                    v[out] = v[arg].tanh();
+                }
+                RegOp::Exp2Reg(out, arg) => {
+                    v[out] = v[arg].exp2();
+                }
+                RegOp::Log2Reg(out, arg) => {
+                    v[out] = v[arg].log2();
+                }
+                RegOp::Log10Reg(out, arg) => {
+                    v[out] = v[arg].log10();
+                }
+                RegOp::NotReg(out, arg) => {
+                    v[out] = if !v[arg].contains(0.0) && !v[arg].has_nan() {
+                        Interval64::new(0.0, 0.0)
+                    } else if v[arg].lower() == 0.0 && v[arg].upper() == 0.0 {
+                        Interval64::new(1.0, 1.0)
+                    } else {
+                        Interval64::new(0.0, 1.0)
+                    };
+                }
+                RegOp::CopyReg(out, arg) => v[out] = v[arg],
+                RegOp::AddRegImm(out, arg, _) => {
+                    v[out] = v[arg] + imm.into();
+                }
+                RegOp::MulRegImm(out, arg, _) => {
+                    v[out] = v[arg] * imm;
+                }
+                RegOp::DivRegImm(out, arg, _) => {
+                    v[out] = v[arg] / imm.into();
+                }
+                RegOp::DivImmReg(out, arg, _) => {
+                    let imm: Interval64 = imm.into();
+                    v[out] = imm / v[arg];
+                }
+                RegOp::AtanRegImm(out, arg, _) => {
+                    v[out] = v[arg].atan2(imm.into());
+                }
+                RegOp::AtanImmReg(out, arg, _) => {
+                    let imm: Interval64 = imm.into();
+                    v[out] = imm.atan2(v[arg]);
+                }
+                RegOp::AtanRegReg(out, lhs, rhs) => {
+                    v[out] = v[lhs].atan2(v[rhs]);
+                }
+                RegOp::PowRegImm(out, arg, _) => {
+                    v[out] = v[arg].pow(imm.into());
+                }
+                RegOp::PowImmReg(out, arg, _) => {
+                    let imm: Interval64 = imm.into();
+                    v[out] = imm.pow(v[arg]);
+                }
+                RegOp::PowRegReg(out, lhs, rhs) => {
+                    v[out] = v[lhs].pow(v[rhs]);
+                }
+                RegOp::SubImmReg(out, arg, _) => {
+                    v[out] = Interval64::from(imm) - v[arg];
+                }
+                RegOp::SubRegImm(out, arg, _) => {
+                    v[out] = v[arg] - imm.into();
+                }
+                RegOp::MinRegImm(out, arg, _) => {
+                    let (value, choice) = v[arg].min_choice(imm.into());
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
+                RegOp::MaxRegImm(out, arg, _) => {
+                    let (value, choice) = v[arg].max_choice(imm.into());
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
+                RegOp::AndRegReg(out, lhs, rhs) => {
+                    let (value, choice) = v[lhs].and_choice(v[rhs]);
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
+                RegOp::AndRegImm(out, arg, _) => {
+                    let (value, choice) = v[arg].and_choice(imm.into());
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
+                RegOp::OrRegReg(out, lhs, rhs) => {
+                    let (value, choice) = v[lhs].or_choice(v[rhs]);
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
+                RegOp::OrRegImm(out, arg, _) => {
+                    let (value, choice) = v[arg].or_choice(imm.into());
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
+                RegOp::SelectRegReg(out, cond, arg) => {
+                    let (value, choice) = v[cond].select_choice(v[arg], v[out]);
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
+                RegOp::SelectRegImm(out, cond, _) => {
+                    let (value, choice) =
+                        v[cond].select_choice(imm.into(), v[out]);
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
+                RegOp::SmoothMinRegReg(out, lhs, rhs) => {
+                    let (value, choice) =
+                        v[lhs].smooth_min_choice(v[rhs], v[out]);
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
+                RegOp::SmoothMinRegImm(out, arg, _) => {
+                    let (value, choice) =
+                        v[arg].smooth_min_choice(imm.into(), v[out]);
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
+                RegOp::ModRegReg(out, lhs, rhs) => {
+                    v[out] = v[lhs].rem_euclid(v[rhs]);
+                }
+                RegOp::ModRegImm(out, arg, _) => {
+                    v[out] = v[arg].rem_euclid(imm.into());
+                }
+                RegOp::ModImmReg(out, arg, _) => {
+                    v[out] = Interval64::from(imm).rem_euclid(v[arg]);
+                }
+                RegOp::AddRegReg(out, lhs, rhs) => v[out] = v[lhs] + v[rhs],
+                RegOp::MulRegReg(out, lhs, rhs) => v[out] = v[lhs] * v[rhs],
+                RegOp::DivRegReg(out, lhs, rhs) => v[out] = v[lhs] / v[rhs],
+                RegOp::SubRegReg(out, lhs, rhs) => v[out] = v[lhs] - v[rhs],
+                RegOp::CompareRegReg(out, lhs, rhs) => {
+                    v[out] = if v[lhs].has_nan() || v[rhs].has_nan() {
+                        f64::NAN.into()
+                    } else if v[lhs].upper() < v[rhs].lower() {
+                        Interval64::from(-1.0)
+                    } else if v[lhs].lower() > v[rhs].upper() {
+                        Interval64::from(1.0)
+                    } else {
+                        Interval64::new(-1.0, 1.0)
+                    };
+                }
+                RegOp::CompareRegImm(out, arg, _) => {
+                    v[out] = if v[arg].has_nan() || imm.is_nan() {
+                        f64::NAN.into()
+                    } else if v[arg].upper() < imm {
+                        Interval64::from(-1.0)
+                    } else if v[arg].lower() > imm {
+                        Interval64::from(1.0)
+                    } else {
+                        Interval64::new(-1.0, 1.0)
+                    };
+                }
+                RegOp::CompareImmReg(out, arg, _) => {
+                    v[out] = if v[arg].has_nan() || imm.is_nan() {
+                        f64::NAN.into()
+                    } else if imm < v[arg].lower() {
+                        Interval64::from(-1.0)
+                    } else if imm > v[arg].upper() {
+                        Interval64::from(1.0)
+                    } else {
+                        Interval64::new(-1.0, 1.0)
+                    };
+                }
+                RegOp::MinRegReg(out, lhs, rhs) => {
+                    let (value, choice) = v[lhs].min_choice(v[rhs]);
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
+                RegOp::MaxRegReg(out, lhs, rhs) => {
+                    let (value, choice) = v[lhs].max_choice(v[rhs]);
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
+                RegOp::CopyImm(out, _) => {
+                    v[out] = imm.into();
+                }
+                RegOp::Load(out, mem) => {
+                    v[out] = v[mem];
+                }
+                RegOp::Store(out, mem) => {
+                    v[mem] = v[out];
+                }
+            }
+        }
+        Ok((
+            &self.eval.out,
+            if simplify {
+                Some(&self.eval.choices)
+            } else {
+                None
+            },
+        ))
+    }
+}
+
+impl<const N: usize> TracingEvaluator for VmIntervalEval64<N> {
+    type Data = Interval;
+    type Tape = GenericVmTape64<N>;
+    type Trace = VmTrace;
+    type TapeStorage = EmptyTapeStorage;
+
+    fn eval(
+        &mut self,
+        tape: &Self::Tape,
+        vars: &[Interval],
+    ) -> Result<(&[Interval], Option<&VmTrace>), Error> {
+        let mut wide = std::mem::take(&mut self.vars);
+        wide.clear();
+        wide.extend(vars.iter().map(|v| Interval64::from(*v)));
+        let r = self.eval_f64(tape, &wide).map(|(_, t)| t.is_some());
+        self.vars = wide;
+        let simplify = r?;
+
+        self.out.clear();
+        self.out
+            .extend(self.eval.out.iter().map(|v| Interval::from(*v)));
+        Ok((&self.out, simplify.then_some(&self.eval.choices)))
+    }
+}
+
+/// VM-based tracing evaluator for single double-precision points
+///
+/// As a [`TracingEvaluator`], this takes and returns `f32` values; use
+/// [`eval_f64`](Self::eval_f64) for `f64` values.
+#[derive(Default)]
+pub struct VmPointEval64<const N: usize> {
+    eval: TracingVmEval<f64>,
+    /// Double-precision inputs, widened from single-precision arguments
+    vars: Vec<f64>,
+    /// Single-precision outputs
+    out: Vec<f32>,
+}
+
+impl<const N: usize> VmPointEval64<N> {
+    /// Evaluates the tape with double-precision inputs and outputs
+    pub fn eval_f64(
+        &mut self,
+        tape: &GenericVmTape64<N>,
+        vars: &[f64],
+    ) -> Result<(&[f64], Option<&VmTrace>), Error> {
+        tape.vars().check_tracing_arguments(vars)?;
+        let imms = tape.imms.iter();
+        let tape = tape.data();
+        self.eval.resize_slots(tape);
+
+        let mut choices = self.eval.choices.as_mut_slice().iter_mut();
+        let mut simplify = false;
+        let mut v = SlotArray(&mut self.eval.slots);
+        for (op, &imm) in tape.iter_asm().zip(imms) {
+            match op {
+                RegOp::Output(arg, i) => {
+                    self.eval.out[i as usize] = v[arg];
+                }
+                RegOp::Input(out, i) => {
+                    v[out] = vars[i as usize];
+                }
+                RegOp::NegReg(out, arg) => {
+                    v[out] = -v[arg];
+                }
+                RegOp::AbsReg(out, arg) => {
+                    v[out] = v[arg].abs();
+                }
+                RegOp::RecipReg(out, arg) => {
+                    v[out] = 1.0 / v[arg];
+                }
+                RegOp::SqrtReg(out, arg) => {
+                    v[out] = v[arg].sqrt();
+                }
+                RegOp::SquareReg(out, arg) => {
+                    let s = v[arg];
+                    v[out] = s * s;
+                }
+                RegOp::FloorReg(out, arg) => {
+                    v[out] = v[arg].floor();
+                }
+                RegOp::CeilReg(out, arg) => {
+                    v[out] = v[arg].ceil();
+                }
+                RegOp::RoundReg(out, arg) => {
+                    v[out] = v[arg].round();
+                }
+                RegOp::SinReg(out, arg) => {
+                    v[out] = v[arg].sin();
+                }
+                RegOp::CosReg(out, arg) => {
+                    v[out] = v[arg].cos();
+                }
+                RegOp::TanReg(out, arg) => {
+                    v[out] = v[arg].tan();
+                }
+                RegOp::AsinReg(out, arg) => {
+                    v[out] = v[arg].asin();
+                }
+                RegOp::AcosReg(out, arg) => {
+                    v[out] = v[arg].acos();
+                }
+                RegOp::AtanReg(out, arg) => {
+                    v[out] = v[arg].atan();
+                }
+                RegOp::ExpReg(out, arg) => {
+                    v[out] = v[arg].exp();
+                }
+                RegOp::LnReg(out, arg) => {
+                    v[out] = v[arg].ln();
+                }
+                RegOp::SinhReg(out, arg) => {
+                    v[out] = v[arg].sinh();
+                }
+                RegOp::CoshReg(out, arg) => {
+                    v[out] = v[arg].cosh();
+                }
+                RegOp::TanhReg(out, arg) => {
+                    v[out] = v[arg].tanh();
+                }
+                RegOp::Exp2Reg(out, arg) => {
+                    v[out] = v[arg].exp2();
+                }
+                RegOp::Log2Reg(out, arg) => {
+                    v[out] = v[arg].log2();
+                }
+                RegOp::Log10Reg(out, arg) => {
+                    v[out] = v[arg].log10();
+                }
+                RegOp::NotReg(out, arg) => v[out] = (v[arg] == 0.0).into(),
+                RegOp::CopyReg(out, arg) => {
+                    v[out] = v[arg];
+                }
+                RegOp::AddRegImm(out, arg, _) => {
+                    v[out] = v[arg] + imm;
+                }
+                RegOp::MulRegImm(out, arg, _) => {
+                    v[out] = v[arg] * imm;
+                }
+                RegOp::DivRegImm(out, arg, _) => {
+                    v[out] = v[arg] / imm;
+                }
+                RegOp::DivImmReg(out, arg, _) => {
+                    v[out] = imm / v[arg];
+                }
+                RegOp::AtanRegImm(out, arg, _) => {
+                    v[out] = v[arg].atan2(imm);
+                }
+                RegOp::AtanImmReg(out, arg, _) => {
+                    v[out] = imm.atan2(v[arg]);
+                }
+                RegOp::AtanRegReg(out, lhs, rhs) => {
+                    v[out] = v[lhs].atan2(v[rhs]);
+                }
+                RegOp::PowRegImm(out, arg, _) => {
+                    v[out] = v[arg].powf(imm);
+                }
+                RegOp::PowImmReg(out, arg, _) => {
+                    v[out] = imm.powf(v[arg]);
+                }
+                RegOp::PowRegReg(out, lhs, rhs) => {
+                    v[out] = v[lhs].powf(v[rhs]);
+                }
+                RegOp::SubImmReg(out, arg, _) => {
+                    v[out] = imm - v[arg];
+                }
+                RegOp::SubRegImm(out, arg, _) => {
+                    v[out] = v[arg] - imm;
+                }
+                RegOp::MinRegImm(out, arg, _) => {
+                    let a = v[arg];
+                    let (choice, value) = if a < imm {
+                        (Choice::Left, a)
+                    } else if imm < a {
+                        (Choice::Right, imm)
+                    } else {
+                        (
+                            Choice::Both,
+                            if a.is_nan() || imm.is_nan() {
+                                f64::NAN
+                            } else {
+                                imm
+                            },
+                        )
+                    };
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
+                RegOp::MaxRegImm(out, arg, _) => {
+                    let a = v[arg];
+                    let (choice, value) = if a > imm {
+                        (Choice::Left, a)
+                    } else if imm > a {
+                        (Choice::Right, imm)
+                    } else {
+                        (
+                            Choice::Both,
+                            if a.is_nan() || imm.is_nan() {
+                                f64::NAN
+                            } else {
+                                imm
+                            },
+                        )
+                    };
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
+                RegOp::AndRegImm(out, arg, _) => {
+                    let a = v[arg];
+                    let (choice, value) = if a == 0.0 {
+                        (Choice::Left, a)
+                    } else {
+                        (Choice::Right, imm)
+                    };
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
+                RegOp::OrRegImm(out, arg, _) => {
+                    let a = v[arg];
+                    let (choice, value) = if a != 0.0 {
+                        (Choice::Left, a)
+                    } else {
+                        (Choice::Right, imm)
+                    };
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
+                RegOp::SelectRegReg(out, cond, arg) => {
+                    let (choice, value) = if v[cond] != 0.0 {
+                        (Choice::Left, v[arg])
+                    } else {
+                        (Choice::Right, v[out])
+                    };
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
+                RegOp::SelectRegImm(out, cond, _) => {
+                    let (choice, value) = if v[cond] != 0.0 {
+                        (Choice::Left, imm)
+                    } else {
+                        (Choice::Right, v[out])
+                    };
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
+                RegOp::SmoothMinRegReg(out, lhs, rhs) => {
+                    let (choice, value) =
+                        smooth_min_choice(v[lhs], v[rhs], v[out]);
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
+                RegOp::SmoothMinRegImm(out, arg, _) => {
+                    let (choice, value) =
+                        smooth_min_choice(v[arg], imm, v[out]);
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
+                RegOp::ModRegReg(out, lhs, rhs) => {
+                    v[out] = v[lhs].rem_euclid(v[rhs]);
+                }
+                RegOp::ModRegImm(out, arg, _) => {
+                    v[out] = v[arg].rem_euclid(imm);
+                }
+                RegOp::ModImmReg(out, arg, _) => {
+                    v[out] = imm.rem_euclid(v[arg]);
+                }
+                RegOp::AddRegReg(out, lhs, rhs) => {
+                    v[out] = v[lhs] + v[rhs];
+                }
+                RegOp::MulRegReg(out, lhs, rhs) => {
+                    v[out] = v[lhs] * v[rhs];
+                }
+                RegOp::DivRegReg(out, lhs, rhs) => {
+                    v[out] = v[lhs] / v[rhs];
+                }
+                RegOp::CompareRegReg(out, lhs, rhs) => {
+                    v[out] = v[lhs]
+                        .partial_cmp(&v[rhs])
+                        .map(|c| c as i8 as f64)
+                        .unwrap_or(f64::NAN)
+                }
+                RegOp::CompareRegImm(out, arg, _) => {
+                    v[out] = v[arg]
+                        .partial_cmp(&imm)
+                        .map(|c| c as i8 as f64)
+                        .unwrap_or(f64::NAN)
+                }
+                RegOp::CompareImmReg(out, arg, _) => {
+                    v[out] = imm
+                        .partial_cmp(&v[arg])
+                        .map(|c| c as i8 as f64)
+                        .unwrap_or(f64::NAN)
+                }
+                RegOp::SubRegReg(out, lhs, rhs) => {
+                    v[out] = v[lhs] - v[rhs];
+                }
+                RegOp::MinRegReg(out, lhs, rhs) => {
+                    let a = v[lhs];
+                    let b = v[rhs];
+                    let (choice, value) = if a < b {
+                        (Choice::Left, a)
+                    } else if b < a {
+                        (Choice::Right, b)
+                    } else {
+                        (
+                            Choice::Both,
+                            if a.is_nan() || b.is_nan() {
+                                f64::NAN
+                            } else {
+                                b
+                            },
+                        )
+                    };
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
+                RegOp::MaxRegReg(out, lhs, rhs) => {
+                    let a = v[lhs];
+                    let b = v[rhs];
+                    let (choice, value) = if a > b {
+                        (Choice::Left, a)
+                    } else if b > a {
+                        (Choice::Right, b)
+                    } else {
+                        (
+                            Choice::Both,
+                            if a.is_nan() || b.is_nan() {
+                                f64::NAN
+                            } else {
+                                b
+                            },
+                        )
+                    };
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
+                RegOp::AndRegReg(out, lhs, rhs) => {
+                    let a = v[lhs];
+                    let b = v[rhs];
+                    let (choice, value) = if a == 0.0 {
+                        (Choice::Left, a)
+                    } else {
+                        (Choice::Right, b)
+                    };
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
+                RegOp::OrRegReg(out, lhs, rhs) => {
+                    let a = v[lhs];
+                    let b = v[rhs];
+                    let (choice, value) = if a != 0.0 {
+                        (Choice::Left, a)
+                    } else {
+                        (Choice::Right, b)
+                    };
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
+                RegOp::CopyImm(out, _) => {
+                    v[out] = imm;
+                }
+                RegOp::Load(out, mem) => {
+                    v[out] = v[mem];
+                }
+                RegOp::Store(out, mem) => {
+                    v[mem] = v[out];
+                }
+            }
+        }
+        Ok((
+            &self.eval.out,
+            if simplify {
+                Some(&self.eval.choices)
+            } else {
+                None
+            },
+        ))
+    }
+}
+
+impl<const N: usize> TracingEvaluator for VmPointEval64<N> {
+    type Data = f32;
+    type Tape = GenericVmTape64<N>;
+    type Trace = VmTrace;
+    type TapeStorage = EmptyTapeStorage;
+
+    fn eval(
+        &mut self,
+        tape: &Self::Tape,
+        vars: &[f32],
+    ) -> Result<(&[f32], Option<&VmTrace>), Error> {
+        let mut wide = std::mem::take(&mut self.vars);
+        wide.clear();
+        wide.extend(vars.iter().map(|v| f64::from(*v)));
+        let r = self.eval_f64(tape, &wide).map(|(_, t)| t.is_some());
+        self.vars = wide;
+        let simplify = r?;
+
+        self.out.clear();
+        self.out.extend(self.eval.out.iter().map(|v| *v as f32));
+        Ok((&self.out, simplify.then_some(&self.eval.choices)))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// VM-based bulk evaluator for arrays of double-precision points
+///
+/// As a [`BulkEvaluator`], this takes and returns `f32` values; use
+/// [`eval_f64`](Self::eval_f64) for `f64` values.
+#[derive(Default)]
+pub struct VmFloatSliceEval64<const N: usize> {
+    eval: BulkVmEval<f64>,
+    /// Double-precision inputs, widened from single-precision arguments
+    vars: Vec<Vec<f64>>,
+    /// Single-precision outputs
+    out: Vec<Vec<f32>>,
+}
+
+impl<const N: usize> VmFloatSliceEval64<N> {
+    /// Evaluates the tape with double-precision inputs and outputs
+    pub fn eval_f64<V: std::ops::Deref<Target = [f64]>>(
+        &mut self,
+        tape: &GenericVmTape64<N>,
+        vars: &[V],
+    ) -> Result<BulkOutput<'_, f64>, Error> {
+        tape.vars().check_bulk_arguments(vars)?;
+        let imms = tape.imms.iter();
+        let tape = tape.data();
+
+        let size = vars.first().map(|v| v.len()).unwrap_or(0);
+        self.eval.resize_slots(tape, size);
+
+        let mut v = SlotArray(&mut self.eval.slots);
+        for (op, &imm) in tape.iter_asm().zip(imms) {
+            match op {
+                RegOp::Output(arg, i) => {
+                    self.eval.out[i as usize][0..size]
+                        .copy_from_slice(&v[arg][0..size]);
+                }
+                RegOp::Input(out, i) => {
+                    v[out][0..size].copy_from_slice(&vars[i as usize]);
+                }
+                RegOp::NegReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = -v[arg][i];
+                    }
+                }
+                RegOp::AbsReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].abs();
+                    }
+                }
+                RegOp::RecipReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = 1.0 / v[arg][i];
+                    }
+                }
+                RegOp::SqrtReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].sqrt();
+                    }
+                }
+                RegOp::SquareReg(out, arg) => {
+                    for i in 0..size {
+                        let s = v[arg][i];
+                        v[out][i] = s * s;
+                    }
+                }
+                RegOp::FloorReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].floor();
+                    }
+                }
+                RegOp::CeilReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].ceil();
+                    }
+                }
+                RegOp::RoundReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].round();
+                    }
+                }
+                RegOp::SinReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].sin();
+                    }
+                }
+                RegOp::CosReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].cos();
+                    }
+                }
+                RegOp::TanReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].tan();
+                    }
+                }
+                RegOp::AsinReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].asin();
+                    }
+                }
+                RegOp::AcosReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].acos();
+                    }
+                }
+                RegOp::AtanReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].atan();
+                    }
+                }
+                RegOp::ExpReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].exp();
+                    }
+                }
+                RegOp::LnReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].ln();
+                    }
+                }
+                RegOp::SinhReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].sinh();
+                    }
+                }
+                RegOp::CoshReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].cosh();
+                    }
+                }
+                RegOp::TanhReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].tanh();
+                    }
+                }
+                RegOp::Exp2Reg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].exp2();
+                    }
+                }
+                RegOp::Log2Reg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].log2();
+                    }
+                }
+                RegOp::Log10Reg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].log10();
+                    }
+                }
+                RegOp::NotReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = (v[arg][i] == 0.0).into();
+                    }
+                }
+                RegOp::CopyReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i];
+                    }
+                }
+                RegOp::AddRegImm(out, arg, _) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i] + imm;
+                    }
+                }
+                RegOp::MulRegImm(out, arg, _) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i] * imm;
+                    }
+                }
+                RegOp::DivRegImm(out, arg, _) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i] / imm;
+                    }
+                }
+                RegOp::DivImmReg(out, arg, _) => {
+                    for i in 0..size {
+                        v[out][i] = imm / v[arg][i];
+                    }
+                }
+                RegOp::AtanRegImm(out, arg, _) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].atan2(imm);
+                    }
+                }
+                RegOp::AtanImmReg(out, arg, _) => {
+                    for i in 0..size {
+                        v[out][i] = imm.atan2(v[arg][i]);
+                    }
+                }
+                RegOp::AtanRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        v[out][i] = v[lhs][i].atan2(v[rhs][i]);
+                    }
+                }
+                RegOp::PowRegImm(out, arg, _) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].powf(imm);
+                    }
+                }
+                RegOp::PowImmReg(out, arg, _) => {
+                    for i in 0..size {
+                        v[out][i] = imm.powf(v[arg][i]);
+                    }
+                }
+                RegOp::PowRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        v[out][i] = v[lhs][i].powf(v[rhs][i]);
+                    }
+                }
+                RegOp::SubImmReg(out, arg, _) => {
+                    for i in 0..size {
+                        v[out][i] = imm - v[arg][i];
+                    }
+                }
+                RegOp::SubRegImm(out, arg, _) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i] - imm;
+                    }
+                }
+                RegOp::CompareImmReg(out, arg, _) => {
+                    for i in 0..size {
+                        v[out][i] = imm
+                            .partial_cmp(&v[arg][i])
+                            .map(|c| c as i8 as f64)
+                            .unwrap_or(f64::NAN)
+                    }
+                }
+                RegOp::CompareRegImm(out, arg, _) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i]
+                            .partial_cmp(&imm)
+                            .map(|c| c as i8 as f64)
+                            .unwrap_or(f64::NAN)
+                    }
+                }
+                RegOp::MinRegImm(out, arg, _) => {
+                    for i in 0..size {
+                        v[out][i] = if v[arg][i].is_nan() || imm.is_nan() {
+                            f64::NAN
+                        } else {
+                            v[arg][i].min(imm)
+                        };
+                    }
+                }
+                RegOp::MaxRegImm(out, arg, _) => {
+                    for i in 0..size {
+                        v[out][i] = if v[arg][i].is_nan() || imm.is_nan() {
+                            f64::NAN
+                        } else {
+                            v[arg][i].max(imm)
+                        };
+                    }
+                }
+                RegOp::AndRegImm(out, arg, _) => {
+                    for i in 0..size {
+                        v[out][i] =
+                            if v[arg][i] == 0.0 { v[arg][i] } else { imm };
+                    }
+                }
+                RegOp::OrRegImm(out, arg, _) => {
+                    for i in 0..size {
+                        v[out][i] =
+                            if v[arg][i] != 0.0 { v[arg][i] } else { imm };
+                    }
+                }
+                RegOp::SelectRegReg(out, cond, arg) => {
+                    for i in 0..size {
+                        if v[cond][i] != 0.0 {
+                            v[out][i] = v[arg][i];
+                        }
+                    }
+                }
+                RegOp::SelectRegImm(out, cond, _) => {
+                    for i in 0..size {
+                        if v[cond][i] != 0.0 {
+                            v[out][i] = imm;
+                        }
+                    }
+                }
+                RegOp::SmoothMinRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        v[out][i] =
+                            smooth_min_choice(v[lhs][i], v[rhs][i], v[out][i])
+                                .1;
+                    }
+                }
+                RegOp::SmoothMinRegImm(out, arg, _) => {
+                    for i in 0..size {
+                        v[out][i] =
+                            smooth_min_choice(v[arg][i], imm, v[out][i]).1;
+                    }
+                }
+                RegOp::ModRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        v[out][i] = v[lhs][i].rem_euclid(v[rhs][i]);
+                    }
+                }
+                RegOp::ModRegImm(out, arg, _) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].rem_euclid(imm);
+                    }
+                }
+                RegOp::ModImmReg(out, arg, _) => {
+                    for i in 0..size {
+                        v[out][i] = imm.rem_euclid(v[arg][i]);
+                    }
+                }
+                RegOp::AddRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        v[out][i] = v[lhs][i] + v[rhs][i];
+                    }
+                }
+                RegOp::MulRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        v[out][i] = v[lhs][i] * v[rhs][i];
+                    }
+                }
+                RegOp::DivRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        v[out][i] = v[lhs][i] / v[rhs][i];
+                    }
+                }
+                RegOp::SubRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        v[out][i] = v[lhs][i] - v[rhs][i];
+                    }
+                }
+                RegOp::CompareRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        v[out][i] = v[lhs][i]
+                            .partial_cmp(&v[rhs][i])
+                            .map(|c| c as i8 as f64)
+                            .unwrap_or(f64::NAN)
+                    }
+                }
+                RegOp::MinRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        v[out][i] = if v[lhs][i].is_nan() || v[rhs][i].is_nan()
+                        {
+                            f64::NAN
+                        } else {
+                            v[lhs][i].min(v[rhs][i])
+                        };
+                    }
+                }
+                RegOp::MaxRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        v[out][i] = if v[lhs][i].is_nan() || v[rhs][i].is_nan()
+                        {
+                            f64::NAN
+                        } else {
+                            v[lhs][i].max(v[rhs][i])
+                        };
+                    }
+                }
+                RegOp::AndRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        v[out][i] = if v[lhs][i] == 0.0 {
+                            v[lhs][i]
+                        } else {
+                            v[rhs][i]
+                        };
+                    }
+                }
+                RegOp::OrRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        v[out][i] = if v[lhs][i] != 0.0 {
+                            v[lhs][i]
+                        } else {
+                            v[rhs][i]
+                        };
+                    }
+                }
+                RegOp::CopyImm(out, _) => {
+                    for i in 0..size {
+                        v[out][i] = imm;
+                    }
+                }
+                RegOp::Load(out, mem) => {
+                    for i in 0..size {
+                        v[out][i] = v[mem][i];
+                    }
+                }
+                RegOp::Store(out, mem) => {
+                    for i in 0..size {
+                        v[mem][i] = v[out][i];
+                    }
+                }
+            }
+        }
+        Ok(BulkOutput::new(&self.eval.out, size))
+    }
+}
+
+impl<const N: usize> BulkEvaluator for VmFloatSliceEval64<N> {
+    type Data = f32;
+    type Tape = GenericVmTape64<N>;
+    type TapeStorage = EmptyTapeStorage;
+
+    fn eval<V: std::ops::Deref<Target = [Self::Data]>>(
+        &mut self,
+        tape: &Self::Tape,
+        vars: &[V],
+    ) -> Result<BulkOutput<'_, f32>, Error> {
+        let mut wide = std::mem::take(&mut self.vars);
+        wide.resize_with(vars.len(), Vec::new);
+        for (w, v) in wide.iter_mut().zip(vars) {
+            w.clear();
+            w.extend(v.iter().map(|v| f64::from(*v)));
+        }
+        let r = self.eval_f64(tape, &wide).map(|_| ());
+        self.vars = wide;
+        r?;
+
+        let size = vars.first().map(|v| v.len()).unwrap_or(0);
+        self.out.resize_with(self.eval.out.len(), Vec::new);
+        for (o, w) in self.out.iter_mut().zip(&self.eval.out) {
+            o.clear();
+            o.extend(w[..size].iter().map(|v| *v as f32));
+        }
+        Ok(BulkOutput::new(&self.out, size))
+    }
+}
+
+/// VM-based bulk evaluator for arrays of double-precision gradients
+///
+/// As a [`BulkEvaluator`], this takes and returns [`Grad`] values; use
+/// [`eval_f64`](Self::eval_f64) for [`Grad64`] values.
+#[derive(Default)]
+pub struct VmGradSliceEval64<const N: usize> {
+    eval: BulkVmEval<Grad64>,
+    /// Double-precision inputs, widened from single-precision arguments
+    vars: Vec<Vec<Grad64>>,
+    /// Single-precision outputs
+    out: Vec<Vec<Grad>>,
+}
+
+impl<const N: usize> VmGradSliceEval64<N> {
+    /// Evaluates the tape with double-precision inputs and outputs
+    pub fn eval_f64<V: std::ops::Deref<Target = [Grad64]>>(
+        &mut self,
+        tape: &GenericVmTape64<N>,
+        vars: &[V],
+    ) -> Result<BulkOutput<'_, Grad64>, Error> {
+        tape.vars().check_bulk_arguments(vars)?;
+        let imms = tape.imms.iter();
+        let tape = tape.data();
+        let size = vars.first().map(|v| v.len()).unwrap_or(0);
+        self.eval.resize_slots(tape, size);
+
+        let mut v = SlotArray(&mut self.eval.slots);
+        for (op, &imm) in tape.iter_asm().zip(imms) {
+            match op {
+                RegOp::Output(arg, i) => {
+                    self.eval.out[i as usize][0..size]
+                        .copy_from_slice(&v[arg][0..size]);
+                }
+                RegOp::Input(out, i) => {
+                    v[out][0..size].copy_from_slice(&vars[i as usize]);
+                }
+                RegOp::NegReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = -v[arg][i];
+                    }
+                }
+                RegOp::AbsReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].abs();
+                    }
+                }
+                RegOp::RecipReg(out, arg) => {
+                    let one: Grad64 = 1.0.into();
+                    for i in 0..size {
+                        v[out][i] = one / v[arg][i];
+                    }
+                }
+                RegOp::SqrtReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].sqrt();
+                    }
+                }
+                RegOp::SquareReg(out, arg) => {
+                    for i in 0..size {
+                        let s = v[arg][i];
+                        v[out][i] = s * s;
+                    }
+                }
+                RegOp::FloorReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].floor();
+                    }
+                }
+                RegOp::CeilReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].ceil();
+                    }
+                }
+                RegOp::RoundReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].round();
+                    }
+                }
+                RegOp::SinReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].sin();
+                    }
+                }
+                RegOp::CosReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].cos();
+                    }
+                }
+                RegOp::TanReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].tan();
+                    }
+                }
+                RegOp::AsinReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].asin();
+                    }
+                }
+                RegOp::AcosReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].acos();
+                    }
+                }
+                RegOp::AtanReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].atan();
+                    }
+                }
+                RegOp::ExpReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].exp();
+                    }
+                }
+                RegOp::LnReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].ln();
+                    }
+                }
+                RegOp::SinhReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].sinh();
+                    }
+                }
+                RegOp::CoshReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].cosh();
+                    }
+                }
+                RegOp::TanhReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].tanh();
+                    }
+                }
+                RegOp::Exp2Reg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].exp2();
+                    }
+                }
+                RegOp::Log2Reg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].log2();
+                    }
+                }
+                RegOp::Log10Reg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].log10();
+                    }
+                }
+                RegOp::NotReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = f64::from(v[arg][i].v == 0.0).into();
+                    }
+                }
+                RegOp::CopyReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i];
+                    }
+                }
+                RegOp::AddRegImm(out, arg, _) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i] + imm.into();
+                    }
+                }
+                RegOp::MulRegImm(out, arg, _) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i] * imm;
+                    }
+                }
+                RegOp::DivRegImm(out, arg, _) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i] / imm.into();
+                    }
+                }
+                RegOp::DivImmReg(out, arg, _) => {
+                    let imm = Grad64::from(imm);
+                    for i in 0..size {
+                        v[out][i] = imm / v[arg][i];
+                    }
+                }
+                RegOp::AtanRegImm(out, arg, _) => {
+                    let imm = Grad64::from(imm);
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].atan2(imm);
+                    }
+                }
+                RegOp::AtanImmReg(out, arg, _) => {
+                    let imm = Grad64::from(imm);
+                    for i in 0..size {
+                        v[out][i] = imm.atan2(v[arg][i]);
+                    }
+                }
+                RegOp::AtanRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        v[out][i] = v[lhs][i].atan2(v[rhs][i]);
+                    }
+                }
+                RegOp::PowRegImm(out, arg, _) => {
+                    let imm = Grad64::from(imm);
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].pow(imm);
+                    }
+                }
+                RegOp::PowImmReg(out, arg, _) => {
+                    let imm = Grad64::from(imm);
+                    for i in 0..size {
+                        v[out][i] = imm.pow(v[arg][i]);
+                    }
+                }
+                RegOp::PowRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        v[out][i] = v[lhs][i].pow(v[rhs][i]);
+                    }
+                }
+                RegOp::SubImmReg(out, arg, _) => {
+                    let imm: Grad64 = imm.into();
+                    for i in 0..size {
+                        v[out][i] = imm - v[arg][i];
+                    }
+                }
+                RegOp::SubRegImm(out, arg, _) => {
+                    let imm: Grad64 = imm.into();
+                    for i in 0..size {
+                        v[out][i] = v[arg][i] - imm;
+                    }
+                }
+                RegOp::CompareImmReg(out, arg, _) => {
+                    for i in 0..size {
+                        let p = imm
+                            .partial_cmp(&v[arg][i].v)
+                            .map(|c| c as i8 as f64)
+                            .unwrap_or(f64::NAN);
+                        v[out][i] = Grad64::new(p, 0.0, 0.0, 0.0);
+                    }
+                }
+                RegOp::CompareRegImm(out, arg, _) => {
+                    for i in 0..size {
+                        let p = v[arg][i]
+                            .v
+                            .partial_cmp(&imm)
+                            .map(|c| c as i8 as f64)
+                            .unwrap_or(f64::NAN);
+                        v[out][i] = Grad64::new(p, 0.0, 0.0, 0.0);
+                    }
+                }
+                RegOp::MinRegImm(out, arg, _) => {
+                    let imm: Grad64 = imm.into();
+                    for i in 0..size {
+                        v[out][i] = if v[arg][i].v.is_nan() || imm.v.is_nan() {
+                            f64::NAN.into()
+                        } else {
+                            v[arg][i].min(imm)
+                        };
+                    }
+                }
+                RegOp::MaxRegImm(out, arg, _) => {
+                    let imm: Grad64 = imm.into();
+                    for i in 0..size {
+                        v[out][i] = if v[arg][i].v.is_nan() || imm.v.is_nan() {
+                            f64::NAN.into()
+                        } else {
+                            v[arg][i].max(imm)
+                        };
+                    }
+                }
+                RegOp::ModRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        v[out][i] = v[lhs][i].rem_euclid(v[rhs][i]);
+                    }
+                }
+                RegOp::ModRegImm(out, arg, _) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].rem_euclid(imm.into());
+                    }
+                }
+                RegOp::ModImmReg(out, arg, _) => {
+                    for i in 0..size {
+                        v[out][i] = Grad64::from(imm).rem_euclid(v[arg][i]);
+                    }
+                }
+                RegOp::AddRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        v[out][i] = v[lhs][i] + v[rhs][i];
+                    }
+                }
+                RegOp::MulRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        v[out][i] = v[lhs][i] * v[rhs][i];
+                    }
+                }
+                RegOp::AndRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        v[out][i] = if v[lhs][i].v == 0.0 {
+                            v[lhs][i]
+                        } else {
+                            v[rhs][i]
+                        };
+                    }
+                }
+                RegOp::AndRegImm(out, arg, _) => {
+                    for i in 0..size {
+                        v[out][i] = if v[arg][i].v == 0.0 {
+                            v[arg][i]
+                        } else {
+                            imm.into()
+                        };
+                    }
+                }
+                RegOp::OrRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        v[out][i] = if v[lhs][i].v != 0.0 {
+                            v[lhs][i]
+                        } else {
+                            v[rhs][i]
+                        };
+                    }
+                }
+                RegOp::OrRegImm(out, arg, _) => {
+                    for i in 0..size {
+                        v[out][i] = if v[arg][i].v != 0.0 {
+                            v[arg][i]
+                        } else {
+                            imm.into()
+                        };
+                    }
+                }
+                RegOp::SelectRegReg(out, cond, arg) => {
+                    for i in 0..size {
+                        if v[cond][i].v != 0.0 {
+                            v[out][i] = v[arg][i];
+                        }
+                    }
+                }
+                RegOp::SelectRegImm(out, cond, _) => {
+                    for i in 0..size {
+                        if v[cond][i].v != 0.0 {
+                            v[out][i] = imm.into();
+                        }
+                    }
+                }
+                RegOp::SmoothMinRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        let (a, b, k) = (v[lhs][i], v[rhs][i], v[out][i]);
+                        v[out][i] =
+                            if a.v.is_nan() || b.v.is_nan() || k.v.is_nan() {
+                                f64::NAN.into()
+                            } else {
+                                a.smooth_min(b, k)
+                            };
+                    }
+                }
+                RegOp::SmoothMinRegImm(out, arg, _) => {
+                    for i in 0..size {
+                        let (a, k) = (v[arg][i], v[out][i]);
+                        v[out][i] =
+                            if a.v.is_nan() || imm.is_nan() || k.v.is_nan() {
+                                f64::NAN.into()
+                            } else {
+                                a.smooth_min(imm.into(), k)
+                            };
+                    }
+                }
+                RegOp::DivRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        v[out][i] = v[lhs][i] / v[rhs][i];
+                    }
+                }
+                RegOp::SubRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        v[out][i] = v[lhs][i] - v[rhs][i];
+                    }
+                }
+                RegOp::CompareRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        let p = v[lhs][i]
+                            .v
+                            .partial_cmp(&v[rhs][i].v)
+                            .map(|c| c as i8 as f64)
+                            .unwrap_or(f64::NAN);
+                        v[out][i] = Grad64::new(p, 0.0, 0.0, 0.0);
+                    }
+                }
+                RegOp::MinRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        v[out][i] =
+                            if v[lhs][i].v.is_nan() || v[rhs][i].v.is_nan() {
+                                f64::NAN.into()
+                            } else {
+                                v[lhs][i].min(v[rhs][i])
+                            };
+                    }
+                }
+                RegOp::MaxRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        v[out][i] =
+                            if v[lhs][i].v.is_nan() || v[rhs][i].v.is_nan() {
+                                f64::NAN.into()
+                            } else {
+                                v[lhs][i].max(v[rhs][i])
+                            };
+                    }
+                }
+                RegOp::CopyImm(out, _) => {
+                    let imm: Grad64 = imm.into();
+                    for i in 0..size {
+                        v[out][i] = imm;
+                    }
+                }
+                RegOp::Load(out, mem) => {
+                    for i in 0..size {
+                        v[out][i] = v[mem][i];
+                    }
+                }
+                RegOp::Store(out, mem) => {
+                    for i in 0..size {
+                        v[mem][i] = v[out][i];
+                    }
+                }
+            }
+        }
+        Ok(BulkOutput::new(&self.eval.out, size))
+    }
+}
+
+impl<const N: usize> BulkEvaluator for VmGradSliceEval64<N> {
+    type Data = Grad;
+    type Tape = GenericVmTape64<N>;
+    type TapeStorage = EmptyTapeStorage;
+
+    fn eval<V: std::ops::Deref<Target = [Self::Data]>>(
+        &mut self,
+        tape: &Self::Tape,
+        vars: &[V],
+    ) -> Result<BulkOutput<'_, Grad>, Error> {
+        let mut wide = std::mem::take(&mut self.vars);
+        wide.resize_with(vars.len(), Vec::new);
+        for (w, v) in wide.iter_mut().zip(vars) {
+            w.clear();
+            w.extend(v.iter().map(|v| Grad64::from(*v)));
+        }
+        let r = self.eval_f64(tape, &wide).map(|_| ());
+        self.vars = wide;
+        r?;
+
+        let size = vars.first().map(|v| v.len()).unwrap_or(0);
+        self.out.resize_with(self.eval.out.len(), Vec::new);
+        for (o, w) in self.out.iter_mut().zip(&self.eval.out) {
+            o.clear();
+            o.extend(w[..size].iter().map(|v| Grad::from(*v)));
+        }
+        Ok(BulkOutput::new(&self.out, size))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{context::Tree, shape::EzShape, var::Var};
+
+    #[test]
+    fn test_large_coordinates() {
+        // A plane 1 µm past a kilometre-scale offset, which isn't
+        // representable in `f32`
+        let x = Tree::x();
+        let mut ctx = Context::new();
+        let root = ctx.import(&(x - 1000.000001));
+        let f = VmFunction64::new(&ctx, &[root]).unwrap();
+        let tape = f.tape();
+
+        let mut eval = VmPointEval64::new();
+        let (out, _) = eval.eval_f64(&tape, &[1000.000002]).unwrap();
+        assert!((out[0] - 1e-6).abs() < 1e-12, "bad value {}", out[0]);
+        let (out, _) = eval.eval_f64(&tape, &[1000.0]).unwrap();
+        assert!((out[0] + 1e-6).abs() < 1e-12, "bad value {}", out[0]);
+
+        let mut eval = VmFloatSliceEval64::new();
+        let out = eval.eval_f64(&tape, &[vec![1000.0, 1000.000002]]).unwrap();
+        assert!(out[0][0] < 0.0 && out[0][1] > 0.0);
+
+        let mut eval = VmIntervalEval64::new();
+        let (out, _) = eval
+            .eval_f64(&tape, &[[1000.0, 1000.000001].into()])
+            .unwrap();
+        assert!(out[0].upper() <= 0.0 && out[0].lower() < 0.0);
+    }
+
+    #[test]
+    fn test_nearby_constants() {
+        // These constants all round to the same `f32`
+        let x = Tree::x();
+        let t = (x.clone() + 0.1) * 1e9
+            - (x.clone() + 0.100000001) * 1e9
+            - (x + f64::from(0.1f32)) * 1e9;
+        let mut ctx = Context::new();
+        let root = ctx.import(&t);
+        let expected = ctx.eval_xyz(root, 2.0, 0.0, 0.0).unwrap();
+
+        let f = VmFunction64::new(&ctx, &[root]).unwrap();
+        let mut eval = VmPointEval64::new();
+        let (out, _) = eval.eval_f64(&f.tape(), &[2.0]).unwrap();
+        assert!((out[0] - expected).abs() < 1e-3, "{} != {expected}", out[0]);
+    }
+
+    #[test]
+    fn test_against_context() {
+        let (x, y, _z) = Tree::axes();
+        let exprs = [
+            (x.clone() * 0.3 + y.clone() / 0.7).sin() * 1.1,
+            (x.square() + y.square()).sqrt() - 0.6,
+            x.clone().min(y.clone() + 0.2).max(x.clone() * 0.1 - 1.3),
+            (x.clone() - 0.4).abs().exp() + (y.clone() * 0.9).cos(),
+            (x.clone() + 2.1).ln() - (y.clone() + 3.3).sqrt().recip(),
+            x.clone().atan2(y.clone() - 0.3) + (x.clone() * 0.7).tanh(),
+            (x.clone() + 1.7).pow(y.clone() * 0.5 + 1.1)
+                - x.clone().modulo(0.3),
+            x.clone().smooth_min(y.clone() * 1.3, Tree::constant(0.35)),
+            (x.clone() - 0.1).compare(y.clone())
+                + x.clone().and(y.clone() - 0.5)
+                + x.clone().or(y.clone()),
+            Tree::constant(0.1).if_nonzero_else(x.clone(), y.clone()) * 0.7,
+        ];
+        let pts = [(0.11, -0.3), (1.5, 0.25), (-0.9, 0.65), (0.3, 0.3)];
+        for t in exprs {
+            let mut ctx = Context::new();
+            let root = ctx.import(&t);
+            let dx = ctx.deriv(root, Var::X).unwrap();
+            let dy = ctx.deriv(root, Var::Y).unwrap();
+            let f = VmFunction64::new(&ctx, &[root]).unwrap();
+            let tape = f.tape();
+            let ix = f.vars().get(&Var::X);
+            let iy = f.vars().get(&Var::Y);
+            let args = |a: f64, b: f64| {
+                let mut out = vec![0.0; f.vars().len()];
+                if let Some(i) = ix {
+                    out[i] = a;
+                }
+                if let Some(i) = iy {
+                    out[i] = b;
+                }
+                out
+            };
+
+            let mut point = VmPointEval64::new();
+            let mut float = VmFloatSliceEval64::new();
+            let mut grad = VmGradSliceEval64::new();
+            let mut interval = VmIntervalEval64::new();
+            for &(a, b) in &pts {
+                let expected = ctx.eval_xyz(root, a, b, 0.0).unwrap();
+                let (out, _) = point.eval_f64(&tape, &args(a, b)).unwrap();
+                assert!((out[0] - expected).abs() < 1e-12, "{t:?}");
+
+                let v: Vec<_> = args(a, b).iter().map(|v| vec![*v]).collect();
+                let out = float.eval_f64(&tape, &v).unwrap();
+                assert!((out[0][0] - expected).abs() < 1e-12, "{t:?}");
+
+                let mut g = args(a, b)
+                    .into_iter()
+                    .map(|v| vec![Grad64::from(v)])
+                    .collect::<Vec<_>>();
+                if let Some(i) = ix {
+                    g[i][0].dx = 1.0;
+                }
+                if let Some(i) = iy {
+                    g[i][0].dy = 1.0;
+                }
+                let out = grad.eval_f64(&tape, &g).unwrap();
+                let expected_dx = ctx.eval_xyz(dx, a, b, 0.0).unwrap();
+                let expected_dy = ctx.eval_xyz(dy, a, b, 0.0).unwrap();
+                assert!((out[0][0].v - expected).abs() < 1e-12, "{t:?}");
+                assert!((out[0][0].dx - expected_dx).abs() < 1e-9, "{t:?}");
+                assert!((out[0][0].dy - expected_dy).abs() < 1e-9, "{t:?}");
+
+                let i: Vec<Interval64> = args(a, b)
+                    .into_iter()
+                    .map(|v| Interval64::new(v - 1e-3, v + 1e-3))
+                    .collect();
+                let (out, _) = interval.eval_f64(&tape, &i).unwrap();
+                assert!(
+                    out[0].has_nan() || out[0].contains(expected),
+                    "{t:?}: {expected} not in {}",
+                    out[0]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_simplify() {
+        let (x, y, _z) = Tree::axes();
+        let t = (x - 1e4 - 1e-7).min(y + 0.1);
+        let mut ctx = Context::new();
+        let root = ctx.import(&t);
+        let f = VmFunction64::new(&ctx, &[root]).unwrap();
+
+        let mut eval = VmIntervalEval64::new();
+        let (_, trace) = eval
+            .eval_f64(&f.tape(), &[(-1.0).into(), (100.0).into()])
+            .unwrap();
+        let trace = trace.unwrap().clone();
+        let g = f
+            .simplify(&trace, Default::default(), &mut Default::default())
+            .unwrap();
+        assert!(g.size() < f.size());
+
+        let mut eval = VmPointEval64::new();
+        let (out, _) = eval.eval_f64(&g.tape(), &[1e4, 1e4]).unwrap();
+        assert!((out[0] + 1e-7).abs() < 1e-12, "bad value {}", out[0]);
+    }
+
+    #[test]
+    fn test_shape() {
+        // The offset is lost entirely when the constant is rounded to `f32`,
+        // but survives when evaluating through a double-precision shape
+        let x = Tree::x();
+        let shape = VmShape64::from((x - 1000.000001) * 1e6);
+
+        let tape = shape.ez_point_tape();
+        let mut eval = VmShape64::new_point_eval();
+        let (out, _) = eval.eval(&tape, 1000.0, 0.0, 0.0).unwrap();
+        assert!((out + 1.0).abs() < 1e-3, "bad value {out}");
+
+        let tape = shape.ez_interval_tape();
+        let mut eval = VmShape64::new_interval_eval();
+        let (out, _) = eval
+            .eval(&tape, [999.0, 1000.0], [0.0, 0.0], [0.0, 0.0])
+            .unwrap();
+        assert!(out.upper() < 0.0, "bad interval {out}");
+
+        let tape = shape.ez_float_slice_tape();
+        let mut eval = VmShape64::new_float_slice_eval();
+        let out = eval
+            .eval(&tape, &[1000.0, 1000.001], &[0.0; 2], &[0.0; 2])
+            .unwrap();
+        assert!(out[0] < 0.0 && out[1] > 0.0, "bad values {out:?}");
+
+        let tape = shape.ez_grad_slice_tape();
+        let mut eval = VmShape64::new_grad_slice_eval();
+        let x = Grad::new(1000.0, 1.0, 0.0, 0.0);
+        let zero = Grad::from(0.0);
+        let out = eval.eval(&tape, &[x], &[zero], &[zero]).unwrap();
+        assert!((out[0].v + 1.0).abs() < 1e-3, "bad value {}", out[0].v);
+        assert_eq!(out[0].dx, 1e6);
+    }
+}
@@ -0,0 +1,379 @@
+//! Second-order (Hessian) evaluation with the VM backend
+//!
+//! A [`VmHessianSliceEval`] computes values, gradients, and Hessian matrices
+//! using [`Hessian`] hyper-dual numbers; it's used for curvature estimation
+//! (e.g. [`ShapeBulkEval::mean_curvature`]).
+//!
+//! This evaluator isn't part of the [`Function`](crate::eval::Function) trait,
+//! so it's only available with the VM backend.  Hessian tapes are the same as
+//! float and gradient tapes, so they can be built from any VM-backed
+//! [`Shape`]:
+//!
+//! ```
+//! use fidget::{context::Tree, vm::VmShape};
+//!
+//! // Sphere with radius 2
+//! let (x, y, z) = Tree::axes();
+//! let sphere = (x.square() + y.square() + z.square()).sqrt() - 2.0;
+//! let shape = VmShape::from(sphere);
+//!
+//! let tape = shape.hessian_slice_tape(Default::default());
+//! let mut eval = VmShape::new_hessian_slice_eval();
+//! let k = eval.mean_curvature(&tape, &[2.0, 0.0], &[0.0, 0.0], &[0.0, 2.0])?;
+//! assert_eq!(k, [0.5, 0.5]);
+//! # Ok::<(), fidget::Error>(())
+//! ```
+use super::{
+    forward_grad_value, BulkVmEval, EmptyTapeStorage, GenericVmTape, GradValue,
+};
+use crate::{
+    eval::{BulkEvaluator, BulkOutput},
+    shape::{Shape, ShapeBulkEval, ShapeTape},
+    types::Hessian,
+    vm::GenericVmFunction,
+    Error,
+};
+
+/// VM-based bulk evaluator for arrays of points, yielding [`Hessian`] values
+///
+/// Inputs are typically seeded with [`Hessian::input`], so that derivatives are
+/// with respect to the `x`, `y`, and `z` axes.  Evaluation shares its loop
+/// with [`VmGradSliceEval`](super::VmGradSliceEval), so the value and gradient
+/// match a gradient evaluator.
+#[derive(Default)]
+pub struct VmHessianSliceEval<const N: usize>(BulkVmEval<Hessian>);
+impl<const N: usize> BulkEvaluator for VmHessianSliceEval<N> {
+    type Data = Hessian;
+    type Tape = GenericVmTape<N>;
+    type TapeStorage = EmptyTapeStorage;
+
+    fn eval<V: std::ops::Deref<Target = [Self::Data]>>(
+        &mut self,
+        tape: &Self::Tape,
+        vars: &[V],
+    ) -> Result<BulkOutput<'_, Hessian>, Error> {
+        self.0.eval_grad(tape, vars)
+    }
+}
+
+impl GradValue for Hessian {
+    fn value(&self) -> f32 {
+        self.v
+    }
+    fn scale(self, s: f32) -> Self {
+        self * s
+    }
+    forward_grad_value!(Hessian);
+}
+
+impl<const N: usize> Shape<GenericVmFunction<N>> {
+    /// Builds a new Hessian slice evaluator
+    pub fn new_hessian_slice_eval() -> ShapeBulkEval<VmHessianSliceEval<N>> {
+        ShapeBulkEval::default()
+    }
+
+    /// Returns an evaluation tape for a Hessian slice evaluator
+    pub fn hessian_slice_tape(
+        &self,
+        storage: EmptyTapeStorage,
+    ) -> ShapeTape<GenericVmTape<N>> {
+        self.float_slice_tape(storage)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        context::{Context, Node, Tree},
+        eval::{
+            test::{build_stress_fn, CanonicalBinaryOp, CanonicalUnaryOp},
+            Function, MathFunction, Tape,
+        },
+        shape::EzShape,
+        types::Grad,
+        var::Var,
+        vm::{VmFunction, VmShape},
+    };
+
+    /// Step size used for finite differences
+    const EPSILON: f64 = 1e-4;
+
+    /// Arguments used when testing individual operations
+    fn test_args() -> Vec<f32> {
+        let mut args: Vec<f32> = (-16..=16).map(|i| i as f32 * 0.37).collect();
+        args.extend([0.5, 1.0, 2.0, std::f32::consts::PI, f32::NAN]);
+        args
+    }
+
+    /// Estimates a second partial derivative with finite differences
+    ///
+    /// Returns `None` if the function isn't smooth at this point (i.e.
+    /// estimates taken from different sides don't agree).
+    fn estimate(
+        f: impl Fn(f64, f64) -> f64,
+        i: usize,
+        j: usize,
+    ) -> Option<f64> {
+        let h = EPSILON;
+        let step = |s: f64, t: f64| {
+            let mut d = [0.0; 2];
+            d[i] += s * h;
+            d[j] += t * h;
+            f(d[0], d[1])
+        };
+        let mut out = vec![];
+        for (s, t) in [(1.0, 1.0), (-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0)] {
+            if i == j && s != t {
+                continue;
+            }
+            let est = if i == j {
+                (step(2.0 * s, 0.0) - 2.0 * step(s, 0.0) + f(0.0, 0.0))
+                    / h.powi(2)
+            } else {
+                (step(s, t) - step(s, 0.0) - step(0.0, t) + f(0.0, 0.0))
+                    / (s * t * h.powi(2))
+            };
+            out.push(est);
+        }
+        let lo = out.iter().cloned().fold(f64::INFINITY, f64::min);
+        let hi = out.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let mid = out.iter().sum::<f64>() / out.len() as f64;
+        (hi - lo < 1e-2 * mid.abs().max(1.0)).then_some(mid)
+    }
+
+    /// Checks Hessian evaluation of a function of up to two variables
+    ///
+    /// Values and gradients are compared against a [`Grad`] evaluator, and
+    /// second derivatives are compared against finite differences of `f`.
+    fn check(
+        ctx: &Context,
+        node: Node,
+        vars: &[(Var, &[f32])],
+        f: impl Fn(&[f64]) -> f64,
+        name: &str,
+    ) {
+        let func = VmFunction::new(ctx, &[node]).unwrap();
+        let tape = func.tape();
+        let size = vars[0].1.len();
+
+        let mut grads = vec![vec![Grad::from(0.0); size]; tape.vars().len()];
+        let mut hess = vec![vec![Hessian::from(0.0); size]; tape.vars().len()];
+        for (k, (v, data)) in vars.iter().enumerate() {
+            if let Some(i) = tape.vars().get(v) {
+                for (j, &x) in data.iter().enumerate() {
+                    let mut d = [0.0; 3];
+                    d[k] = 1.0;
+                    grads[i][j] = Grad::new(x, d[0], d[1], d[2]);
+                    hess[i][j] = Hessian::input(x, k);
+                }
+            }
+        }
+        let mut grad_eval = VmFunction::new_grad_slice_eval();
+        let expected = grad_eval.eval(&tape, &grads).unwrap()[0].to_owned();
+        let mut eval = VmHessianSliceEval::default();
+        let out = eval.eval(&tape, &hess).unwrap();
+
+        let same = |a: f32, b: f32| {
+            a == b
+                || (a.is_nan() && b.is_nan())
+                || (a - b).abs() <= 1e-6 * a.abs().max(b.abs()).max(1.0)
+        };
+        for (p, (o, g)) in out[0].iter().zip(&expected).enumerate() {
+            let args: Vec<f64> = vars.iter().map(|v| v.1[p] as f64).collect();
+            assert!(
+                same(o.v, g.v) && (0..3).all(|i| same(o.d[i], g.d(i))),
+                "value mismatch in '{name}' at {args:?}: {o} != {g}"
+            );
+            if !o.v.is_finite() {
+                continue;
+            }
+            for i in 0..vars.len() {
+                for j in i..vars.len() {
+                    let h = o.h[i][j];
+                    assert_eq!(h.to_bits(), o.h[j][i].to_bits(), "asymmetric");
+                    if !h.is_finite() || h.abs() > 1e6 {
+                        continue;
+                    }
+                    let Some(est) = estimate(
+                        |di, dj| {
+                            let mut a = args.clone();
+                            a[0] += di;
+                            if let Some(b) = a.get_mut(1) {
+                                *b += dj;
+                            }
+                            f(&a)
+                        },
+                        i,
+                        j,
+                    ) else {
+                        continue;
+                    };
+                    let err = (est as f32 - h).abs();
+                    assert!(
+                        err < 1e-3 * h.abs().max(1.0),
+                        "Hessian mismatch in '{name}' at {args:?} [{i}][{j}]: \
+                         {h} != {est}"
+                    );
+                }
+            }
+        }
+    }
+
+    struct TestHessian;
+    impl TestHessian {
+        fn test_unary<C: CanonicalUnaryOp>() {
+            let args: Vec<f32> = test_args()
+                .into_iter()
+                .filter(|&a| !C::discontinuous_at(a))
+                .collect();
+            let mut ctx = Context::new();
+            let v = Var::new();
+            let node = ctx.var(v);
+            let node = C::build(&mut ctx, node);
+            check(&ctx, node, &[(v, &args)], |a| C::eval_f64(a[0]), C::NAME);
+        }
+
+        fn test_binary<C: CanonicalBinaryOp>() {
+            let args = test_args();
+            let mut ctx = Context::new();
+            let va = Var::new();
+            let vb = Var::new();
+            let a = ctx.var(va);
+            let b = ctx.var(vb);
+
+            let (mut lhs, mut rhs) = (vec![], vec![]);
+            for &x in &args {
+                for &y in &args {
+                    if !C::discontinuous_at(x, y) {
+                        lhs.push(x);
+                        rhs.push(y);
+                    }
+                }
+            }
+            let node = C::build(&mut ctx, a, b);
+            let name = format!("{}(reg, reg)", C::NAME);
+            check(
+                &ctx,
+                node,
+                &[(va, &lhs), (vb, &rhs)],
+                |a| C::eval_reg_reg_f64(a[0], a[1]),
+                &name,
+            );
+
+            for &imm in &args {
+                let args: Vec<f32> = test_args()
+                    .into_iter()
+                    .filter(|&a| !C::discontinuous_at(a, imm))
+                    .collect();
+                let node = C::build(&mut ctx, a, imm);
+                let name = format!("{}(reg, {imm})", C::NAME);
+                check(
+                    &ctx,
+                    node,
+                    &[(va, &args)],
+                    |a| C::eval_reg_imm_f64(a[0], imm as f64),
+                    &name,
+                );
+
+                let args: Vec<f32> = test_args()
+                    .into_iter()
+                    .filter(|&a| !C::discontinuous_at(imm, a))
+                    .collect();
+                let node = C::build(&mut ctx, imm, a);
+                let name = format!("{}({imm}, reg)", C::NAME);
+                check(
+                    &ctx,
+                    node,
+                    &[(va, &args)],
+                    |a| C::eval_imm_reg_f64(imm as f64, a[0]),
+                    &name,
+                );
+            }
+        }
+    }
+
+    mod unary {
+        use super::*;
+        crate::all_unary_tests!(TestHessian);
+    }
+
+    mod binary {
+        use super::*;
+        crate::all_binary_tests!(TestHessian);
+    }
+
+    #[test]
+    fn test_hessian_stress() {
+        for depth in [1, 4, 16, 64] {
+            let (ctx, node) = build_stress_fn(depth);
+            let f = VmFunction::new(&ctx, &[node]).unwrap();
+            let tape = f.tape();
+            let mut grads = vec![vec![]; tape.vars().len()];
+            let mut hess = vec![vec![]; tape.vars().len()];
+            for (k, v) in [Var::X, Var::Y, Var::Z].iter().enumerate() {
+                let Some(i) = tape.vars().get(v) else {
+                    continue;
+                };
+                for j in 0..32 {
+                    let a = j as f32 / 32.0 + k as f32;
+                    let mut d = [0.0; 3];
+                    d[k] = 1.0;
+                    grads[i].push(Grad::new(a, d[0], d[1], d[2]));
+                    hess[i].push(Hessian::input(a, k));
+                }
+            }
+
+            let mut grad_eval = VmFunction::new_grad_slice_eval();
+            let expected = grad_eval.eval(&tape, &grads).unwrap()[0].to_owned();
+            let mut eval = VmHessianSliceEval::default();
+            let out = eval.eval(&tape, &hess).unwrap();
+            for (o, g) in out[0].iter().zip(&expected) {
+                assert_eq!(Grad::from(*o), *g);
+                for i in 0..3 {
+                    for j in 0..3 {
+                        assert_eq!(o.h[i][j].to_bits(), o.h[j][i].to_bits());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_curvature() {
+        // Torus in the XY plane, with major radius 2 and minor radius 0.5
+        let (x, y, z) = Tree::axes();
+        let q = (x.square() + y.square()).sqrt() - 2.0;
+        let torus = (q.square() + z.square()).sqrt() - 0.5;
+        let shape = VmShape::from(torus);
+        let tape = shape.hessian_slice_tape(Default::default());
+        let mut eval = VmShape::new_hessian_slice_eval();
+
+        // Outer and inner equator, then the top of the tube
+        let xs = [2.5, 1.5, 2.0];
+        let ys = [0.0, 0.0, 0.0];
+        let zs = [0.0, 0.0, 0.5];
+        let k = eval.gaussian_curvature(&tape, &xs, &ys, &zs).unwrap();
+        assert!((k[0] - 1.0 / (0.5 * 2.5)).abs() < 1e-5, "{k:?}");
+        assert!((k[1] + 1.0 / (0.5 * 1.5)).abs() < 1e-5, "{k:?}");
+        assert!(k[2].abs() < 1e-5, "{k:?}");
+
+        let h = eval.mean_curvature(&tape, &xs, &ys, &zs).unwrap();
+        assert!((h[0] - (1.0 / 0.5 + 1.0 / 2.5) / 2.0).abs() < 1e-5, "{h:?}");
+        assert!((h[1] - (1.0 / 0.5 - 1.0 / 1.5) / 2.0).abs() < 1e-5, "{h:?}");
+        assert!((h[2] - 1.0).abs() < 1e-5, "{h:?}");
+
+        // Scaling the model should scale curvature
+        let shape = shape.apply_transform(nalgebra::Matrix4::new_scaling(2.0));
+        let tape = shape.hessian_slice_tape(Default::default());
+        let k = eval.gaussian_curvature(&tape, &[1.25], &[0.0], &[0.0]);
+        assert!((k.unwrap()[0] - 4.0 / (0.5 * 2.5)).abs() < 1e-4);
+
+        // Curvature evaluation uses the same tape as float evaluation
+        let tape = shape.ez_float_slice_tape();
+        let h = eval.mean_curvature(&tape, &[1.25], &[0.0], &[0.0]).unwrap();
+        assert!((h[0] - (1.0 / 0.5 + 1.0 / 2.5)).abs() < 1e-4, "{h:?}");
+
+        assert!(eval.mean_curvature(&tape, &[1.0], &[], &[]).is_err());
+    }
+}
@@ -1,24 +1,37 @@
 //! Simple virtual machine for shape evaluation
 use crate::{
-    compiler::RegOp,
+    compiler::{integer_exponent, RegOp},
     context::Node,
     eval::{
-        BulkEvaluator, BulkOutput, Function, MathFunction, Tape, Trace,
-        TracingEvaluator,
+        BulkEvaluator, BulkOutput, Function, GradNFunction, MathFunction, Tape,
+        Trace, TracingEvaluator,
     },
     render::{RenderHints, TileSizes},
     shape::Shape,
-    types::{Grad, Interval},
+    types::{Grad, GradN, Interval},
     var::VarMap,
     Context, Error,
 };
 use std::sync::Arc;
 
+mod adjoint;
+mod affine;
 mod choice;
 mod data;
+mod double;
+mod hessian;
 
+pub use adjoint::VmAdjointEval;
+pub use affine::{
+    GenericVmAffineFunction, VmAffineEval, VmAffineFunction, VmAffineShape,
+};
 pub use choice::Choice;
 pub use data::{VmData, VmWorkspace};
+pub use double::{
+    GenericVmFunction64, GenericVmTape64, VmFloatSliceEval64, VmFunction64,
+    VmGradSliceEval64, VmIntervalEval64, VmPointEval64, VmShape64,
+};
+pub use hessian::VmHessianSliceEval;
 
 ////////////////////////////////////////////////////////////////////////////////
 
@@ -235,6 +248,16 @@ impl<const N: usize> MathFunction for GenericVmFunction<N> {
     }
 }
 
+impl<const N: usize> GradNFunction for GenericVmFunction<N> {
+    type GradNSliceEval<const M: usize> = VmGradNSliceEval<N, M>;
+    fn grad_n_slice_tape<const M: usize>(
+        &self,
+        _storage: EmptyTapeStorage,
+    ) -> GenericVmTape<N> {
+        self.tape()
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 
 /// Helper struct to reduce boilerplate conversions
@@ -290,34 +313,161 @@ impl<T: From<f32> + Clone> TracingVmEval<T> {
     }
 }
 
-/// VM-based tracing evaluator for intervals
-#[derive(Default)]
-pub struct VmIntervalEval<const N: usize>(TracingVmEval<Interval>);
-impl<const N: usize> TracingEvaluator for VmIntervalEval<N> {
-    type Data = Interval;
-    type Tape = GenericVmTape<N>;
-    type Trace = VmTrace;
-    type TapeStorage = EmptyTapeStorage;
+/// Value type for interval-style tracing evaluation
+///
+/// This is implemented by [`Interval`] (for [`VmIntervalEval`]) and
+/// [`Affine`](crate::types::Affine) (for [`VmAffineEval`]), which share a
+/// single evaluation loop.  Math functions forward to the type's inherent
+/// methods, so they have the same semantics as calling them directly.
+trait IntervalValue:
+    Copy
+    + From<f32>
+    + std::ops::Add<Output = Self>
+    + std::ops::Sub<Output = Self>
+    + std::ops::Mul<Output = Self>
+    + std::ops::Mul<f32, Output = Self>
+    + std::ops::Div<Output = Self>
+    + std::ops::Neg<Output = Self>
+{
+    /// Builds the value for input `i`, given its interval
+    fn input(v: Interval, i: usize) -> Self;
+    /// Logical not: 1 if the value is zero, 0 if it's nonzero
+    fn not(self) -> Self;
+    /// Returns -1, 0, or 1 depending on whether `self` is less than, equal
+    /// to, or greater than `rhs`
+    fn compare(self, rhs: Self) -> Self;
 
-    fn eval(
+    fn abs(self) -> Self;
+    fn recip(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn square(self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn tan(self) -> Self;
+    fn asin(self) -> Self;
+    fn acos(self) -> Self;
+    fn atan(self) -> Self;
+    fn exp(self) -> Self;
+    fn ln(self) -> Self;
+    fn sinh(self) -> Self;
+    fn cosh(self) -> Self;
+    fn tanh(self) -> Self;
+    fn exp2(self) -> Self;
+    fn log2(self) -> Self;
+    fn log10(self) -> Self;
+    fn floor(self) -> Self;
+    fn ceil(self) -> Self;
+    fn round(self) -> Self;
+    fn atan2(self, x: Self) -> Self;
+    fn pow(self, rhs: Self) -> Self;
+    fn powi(self, n: i32) -> Self;
+    fn rem_euclid(self, rhs: Self) -> Self;
+    fn min_choice(self, rhs: Self) -> (Self, Choice);
+    fn max_choice(self, rhs: Self) -> (Self, Choice);
+    fn and_choice(self, rhs: Self) -> (Self, Choice);
+    fn or_choice(self, rhs: Self) -> (Self, Choice);
+    fn select_choice(self, a: Self, b: Self) -> (Self, Choice);
+    fn smooth_min_choice(self, rhs: Self, k: Self) -> (Self, Choice);
+}
+
+/// Implements the math functions of [`IntervalValue`] by forwarding to
+/// inherent methods of the given type
+macro_rules! forward_interval_value {
+    ($ty:ty) => {
+        forward_interval_value!(@self $ty; abs, recip, sqrt, square, sin, cos,
+            tan, asin, acos, atan, exp, ln, sinh, cosh, tanh, exp2, log2,
+            log10);
+        forward_interval_value!(@ref $ty; floor, ceil, round);
+        forward_interval_value!(@choice $ty; min_choice, max_choice,
+            and_choice, or_choice);
+        fn atan2(self, x: Self) -> Self {
+            <$ty>::atan2(self, x)
+        }
+        fn pow(self, rhs: Self) -> Self {
+            <$ty>::pow(self, rhs)
+        }
+        fn powi(self, n: i32) -> Self {
+            <$ty>::powi(self, n)
+        }
+        fn rem_euclid(self, rhs: Self) -> Self {
+            <$ty>::rem_euclid(&self, rhs)
+        }
+        fn select_choice(self, a: Self, b: Self) -> (Self, Choice) {
+            <$ty>::select_choice(self, a, b)
+        }
+        fn smooth_min_choice(self, rhs: Self, k: Self) -> (Self, Choice) {
+            <$ty>::smooth_min_choice(self, rhs, k)
+        }
+    };
+    (@self $ty:ty; $($f:ident),*) => {
+        $(fn $f(self) -> Self {
+            <$ty>::$f(self)
+        })*
+    };
+    (@ref $ty:ty; $($f:ident),*) => {
+        $(fn $f(self) -> Self {
+            <$ty>::$f(&self)
+        })*
+    };
+    (@choice $ty:ty; $($f:ident),*) => {
+        $(fn $f(self, rhs: Self) -> (Self, Choice) {
+            <$ty>::$f(self, rhs)
+        })*
+    };
+}
+use forward_interval_value;
+
+impl IntervalValue for Interval {
+    fn input(v: Interval, _i: usize) -> Self {
+        v
+    }
+    fn not(self) -> Self {
+        if !self.contains(0.0) && !self.has_nan() {
+            Interval::new(0.0, 0.0)
+        } else if self.lower() == 0.0 && self.upper() == 0.0 {
+            Interval::new(1.0, 1.0)
+        } else {
+            Interval::new(0.0, 1.0)
+        }
+    }
+    fn compare(self, rhs: Self) -> Self {
+        if self.has_nan() || rhs.has_nan() {
+            f32::NAN.into()
+        } else if self.upper() < rhs.lower() {
+            Interval::from(-1.0)
+        } else if self.lower() > rhs.upper() {
+            Interval::from(1.0)
+        } else {
+            Interval::new(-1.0, 1.0)
+        }
+    }
+    forward_interval_value!(Interval);
+}
+
+impl<T: IntervalValue> TracingVmEval<T> {
+    /// Evaluates a tape with interval-style values, writing to `self.out`
+    ///
+    /// Returns `true` if any branching operation picked a single side, i.e.
+    /// if the resulting trace can be used to simplify the tape.
+    fn eval_interval<const N: usize>(
         &mut self,
-        tape: &Self::Tape,
+        tape: &GenericVmTape<N>,
         vars: &[Interval],
-    ) -> Result<(&[Interval], Option<&VmTrace>), Error> {
+    ) -> Result<bool, Error> {
         tape.vars().check_tracing_arguments(vars)?;
         let tape = tape.data();
-        self.0.resize_slots(tape);
+        self.resize_slots(tape);
 
         let mut simplify = false;
-        let mut v = SlotArray(&mut self.0.slots);
-        let mut choices = self.0.choices.as_mut_slice().iter_mut();
+        let mut v = SlotArray(&mut self.slots);
+        let mut choices = self.choices.as_mut_slice().iter_mut();
         for op in tape.iter_asm() {
             match op {
                 RegOp::Output(arg, i) => {
-                    self.0.out[i as usize] = v[arg];
+                    self.out[i as usize] = v[arg];
                 }
                 RegOp::Input(out, i) => {
-                    v[out] = vars[i as usize];
+                    v[out] = T::input(vars[i as usize], i as usize);
                 }
                 RegOp::NegReg(out, arg) => {
                     v[out] = -v[arg];
@@ -367,15 +517,25 @@ impl<const N: usize> TracingEvaluator for VmIntervalEval<N> {
                 RegOp::LnReg(out, arg) => {
                     v[out] = v[arg].ln();
                 }
-                RegOp::NotReg(out, arg) => {
-                    v[out] = if !v[arg].contains(0.0) && !v[arg].has_nan() {
-                        Interval::new(0.0, 0.0)
-                    } else if v[arg].lower() == 0.0 && v[arg].upper() == 0.0 {
-                        Interval::new(1.0, 1.0)
-                    } else {
-                        Interval::new(0.0, 1.0)
-                    };
+                RegOp::SinhReg(out, arg) => {
+                    v[out] = v[arg].sinh();
+                }
+                RegOp::CoshReg(out, arg) => {
+                    v[out] = v[arg].cosh();
+                }
+                RegOp::TanhReg(out, arg) => {
+                    v[out] = v[arg].tanh();
+                }
+                RegOp::Exp2Reg(out, arg) => {
+                    v[out] = v[arg].exp2();
+                }
+                RegOp::Log2Reg(out, arg) => {
+                    v[out] = v[arg].log2();
+                }
+                RegOp::Log10Reg(out, arg) => {
+                    v[out] = v[arg].log10();
                 }
+                RegOp::NotReg(out, arg) => v[out] = v[arg].not(),
                 RegOp::CopyReg(out, arg) => v[out] = v[arg],
                 RegOp::AddRegImm(out, arg, imm) => {
                     v[out] = v[arg] + imm.into();
@@ -387,21 +547,34 @@ impl<const N: usize> TracingEvaluator for VmIntervalEval<N> {
                     v[out] = v[arg] / imm.into();
                 }
                 RegOp::DivImmReg(out, arg, imm) => {
-                    let imm: Interval = imm.into();
+                    let imm: T = imm.into();
                     v[out] = imm / v[arg];
                 }
                 RegOp::AtanRegImm(out, arg, imm) => {
                     v[out] = v[arg].atan2(imm.into());
                 }
                 RegOp::AtanImmReg(out, arg, imm) => {
-                    let imm: Interval = imm.into();
+                    let imm: T = imm.into();
                     v[out] = imm.atan2(v[arg]);
                 }
                 RegOp::AtanRegReg(out, lhs, rhs) => {
                     v[out] = v[lhs].atan2(v[rhs]);
                 }
+                RegOp::PowRegImm(out, arg, imm) => {
+                    v[out] = match integer_exponent(imm) {
+                        Some(n) => v[arg].powi(n),
+                        None => v[arg].pow(imm.into()),
+                    };
+                }
+                RegOp::PowImmReg(out, arg, imm) => {
+                    let imm: T = imm.into();
+                    v[out] = imm.pow(v[arg]);
+                }
+                RegOp::PowRegReg(out, lhs, rhs) => {
+                    v[out] = v[lhs].pow(v[rhs]);
+                }
                 RegOp::SubImmReg(out, arg, imm) => {
-                    v[out] = Interval::from(imm) - v[arg];
+                    v[out] = T::from(imm) - v[arg];
                 }
                 RegOp::SubRegImm(out, arg, imm) => {
                     v[out] = v[arg] - imm.into();
@@ -442,6 +615,33 @@ impl<const N: usize> TracingEvaluator for VmIntervalEval<N> {
                     *choices.next().unwrap() |= choice;
                     simplify |= choice != Choice::Both;
                 }
+                RegOp::SelectRegReg(out, cond, arg) => {
+                    let (value, choice) = v[cond].select_choice(v[arg], v[out]);
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
+                RegOp::SelectRegImm(out, cond, imm) => {
+                    let (value, choice) =
+                        v[cond].select_choice(imm.into(), v[out]);
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
+                RegOp::SmoothMinRegReg(out, lhs, rhs) => {
+                    let (value, choice) =
+                        v[lhs].smooth_min_choice(v[rhs], v[out]);
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
+                RegOp::SmoothMinRegImm(out, arg, imm) => {
+                    let (value, choice) =
+                        v[arg].smooth_min_choice(imm.into(), v[out]);
+                    v[out] = value;
+                    *choices.next().unwrap() |= choice;
+                    simplify |= choice != Choice::Both;
+                }
                 RegOp::ModRegReg(out, lhs, rhs) => {
                     v[out] = v[lhs].rem_euclid(v[rhs]);
                 }
@@ -449,44 +649,20 @@ impl<const N: usize> TracingEvaluator for VmIntervalEval<N> {
                     v[out] = v[arg].rem_euclid(imm.into());
                 }
                 RegOp::ModImmReg(out, arg, imm) => {
-                    v[out] = Interval::from(imm).rem_euclid(v[arg]);
+                    v[out] = T::from(imm).rem_euclid(v[arg]);
                 }
                 RegOp::AddRegReg(out, lhs, rhs) => v[out] = v[lhs] + v[rhs],
                 RegOp::MulRegReg(out, lhs, rhs) => v[out] = v[lhs] * v[rhs],
                 RegOp::DivRegReg(out, lhs, rhs) => v[out] = v[lhs] / v[rhs],
                 RegOp::SubRegReg(out, lhs, rhs) => v[out] = v[lhs] - v[rhs],
                 RegOp::CompareRegReg(out, lhs, rhs) => {
-                    v[out] = if v[lhs].has_nan() || v[rhs].has_nan() {
-                        f32::NAN.into()
-                    } else if v[lhs].upper() < v[rhs].lower() {
-                        Interval::from(-1.0)
-                    } else if v[lhs].lower() > v[rhs].upper() {
-                        Interval::from(1.0)
-                    } else {
-                        Interval::new(-1.0, 1.0)
-                    };
+                    v[out] = v[lhs].compare(v[rhs]);
                 }
                 RegOp::CompareRegImm(out, arg, imm) => {
-                    v[out] = if v[arg].has_nan() || imm.is_nan() {
-                        f32::NAN.into()
-                    } else if v[arg].upper() < imm {
-                        Interval::from(-1.0)
-                    } else if v[arg].lower() > imm {
-                        Interval::from(1.0)
-                    } else {
-                        Interval::new(-1.0, 1.0)
-                    };
+                    v[out] = v[arg].compare(imm.into());
                 }
                 RegOp::CompareImmReg(out, arg, imm) => {
-                    v[out] = if v[arg].has_nan() || imm.is_nan() {
-                        f32::NAN.into()
-                    } else if imm < v[arg].lower() {
-                        Interval::from(-1.0)
-                    } else if imm > v[arg].upper() {
-                        Interval::from(1.0)
-                    } else {
-                        Interval::new(-1.0, 1.0)
-                    };
+                    v[out] = T::from(imm).compare(v[arg]);
                 }
                 RegOp::MinRegReg(out, lhs, rhs) => {
                     let (value, choice) = v[lhs].min_choice(v[rhs]);
@@ -511,6 +687,25 @@ impl<const N: usize> TracingEvaluator for VmIntervalEval<N> {
                 }
             }
         }
+        Ok(simplify)
+    }
+}
+
+/// VM-based tracing evaluator for intervals
+#[derive(Default)]
+pub struct VmIntervalEval<const N: usize>(TracingVmEval<Interval>);
+impl<const N: usize> TracingEvaluator for VmIntervalEval<N> {
+    type Data = Interval;
+    type Tape = GenericVmTape<N>;
+    type Trace = VmTrace;
+    type TapeStorage = EmptyTapeStorage;
+
+    fn eval(
+        &mut self,
+        tape: &Self::Tape,
+        vars: &[Interval],
+    ) -> Result<(&[Interval], Option<&VmTrace>), Error> {
+        let simplify = self.0.eval_interval(tape, vars)?;
         Ok((
             &self.0.out,
             if simplify {
@@ -522,6 +717,358 @@ impl<const N: usize> TracingEvaluator for VmIntervalEval<N> {
     }
 }
 
+/// Polynomial smooth minimum of two values, blending within a radius `k`
+///
+/// Returns a [`Choice`] indicating whether one side is less than the other by
+/// at least `k` (in which case the result is exactly that side), along with the
+/// result itself.  `NAN` in any argument produces `NAN` and `Choice::Both`.
+fn smooth_min_choice(a: f32, b: f32, k: f32) -> (Choice, f32) {
+    if a.is_nan() || b.is_nan() || k.is_nan() {
+        return (Choice::Both, f32::NAN);
+    }
+    let kk = k.max(0.0);
+    if a + kk < b {
+        (Choice::Left, a)
+    } else if b + kk < a {
+        (Choice::Right, b)
+    } else {
+        let h = k - (a - b).abs();
+        let m = a.min(b);
+        (
+            Choice::Both,
+            if h > 0.0 { m - h * h / (4.0 * k) } else { m },
+        )
+    }
+}
+
+/// Evaluates a single operation at a point
+///
+/// `out` receives the function's outputs.  Returns the [`Choice`] made by
+/// branching operations (e.g. `min` and `max`), in the same order as a
+/// [`VmTrace`].
+fn point_step(
+    op: RegOp,
+    v: &mut SlotArray<'_, f32>,
+    vars: &[f32],
+    out: &mut [f32],
+) -> Option<Choice> {
+    match op {
+        RegOp::Output(arg, i) => {
+            out[i as usize] = v[arg];
+        }
+        RegOp::Input(out, i) => {
+            v[out] = vars[i as usize];
+        }
+        RegOp::NegReg(out, arg) => {
+            v[out] = -v[arg];
+        }
+        RegOp::AbsReg(out, arg) => {
+            v[out] = v[arg].abs();
+        }
+        RegOp::RecipReg(out, arg) => {
+            v[out] = 1.0 / v[arg];
+        }
+        RegOp::SqrtReg(out, arg) => {
+            v[out] = v[arg].sqrt();
+        }
+        RegOp::SquareReg(out, arg) => {
+            let s = v[arg];
+            v[out] = s * s;
+        }
+        RegOp::FloorReg(out, arg) => {
+            v[out] = v[arg].floor();
+        }
+        RegOp::CeilReg(out, arg) => {
+            v[out] = v[arg].ceil();
+        }
+        RegOp::RoundReg(out, arg) => {
+            v[out] = v[arg].round();
+        }
+        RegOp::SinReg(out, arg) => {
+            v[out] = v[arg].sin();
+        }
+        RegOp::CosReg(out, arg) => {
+            v[out] = v[arg].cos();
+        }
+        RegOp::TanReg(out, arg) => {
+            v[out] = v[arg].tan();
+        }
+        RegOp::AsinReg(out, arg) => {
+            v[out] = v[arg].asin();
+        }
+        RegOp::AcosReg(out, arg) => {
+            v[out] = v[arg].acos();
+        }
+        RegOp::AtanReg(out, arg) => {
+            v[out] = v[arg].atan();
+        }
+        RegOp::ExpReg(out, arg) => {
+            v[out] = v[arg].exp();
+        }
+        RegOp::LnReg(out, arg) => {
+            v[out] = v[arg].ln();
+        }
+        RegOp::SinhReg(out, arg) => {
+            v[out] = v[arg].sinh();
+        }
+        RegOp::CoshReg(out, arg) => {
+            v[out] = v[arg].cosh();
+        }
+        RegOp::TanhReg(out, arg) => {
+            v[out] = v[arg].tanh();
+        }
+        RegOp::Exp2Reg(out, arg) => {
+            v[out] = v[arg].exp2();
+        }
+        RegOp::Log2Reg(out, arg) => {
+            v[out] = v[arg].log2();
+        }
+        RegOp::Log10Reg(out, arg) => {
+            v[out] = v[arg].log10();
+        }
+        RegOp::NotReg(out, arg) => v[out] = (v[arg] == 0.0).into(),
+        RegOp::CopyReg(out, arg) => {
+            v[out] = v[arg];
+        }
+        RegOp::AddRegImm(out, arg, imm) => {
+            v[out] = v[arg] + imm;
+        }
+        RegOp::MulRegImm(out, arg, imm) => {
+            v[out] = v[arg] * imm;
+        }
+        RegOp::DivRegImm(out, arg, imm) => {
+            v[out] = v[arg] / imm;
+        }
+        RegOp::DivImmReg(out, arg, imm) => {
+            v[out] = imm / v[arg];
+        }
+        RegOp::AtanRegImm(out, arg, imm) => {
+            v[out] = v[arg].atan2(imm);
+        }
+        RegOp::AtanImmReg(out, arg, imm) => {
+            v[out] = imm.atan2(v[arg]);
+        }
+        RegOp::AtanRegReg(out, lhs, rhs) => {
+            v[out] = v[lhs].atan2(v[rhs]);
+        }
+        RegOp::PowRegImm(out, arg, imm) => {
+            v[out] = match integer_exponent(imm) {
+                Some(n) => v[arg].powi(n),
+                None => v[arg].powf(imm),
+            };
+        }
+        RegOp::PowImmReg(out, arg, imm) => {
+            v[out] = imm.powf(v[arg]);
+        }
+        RegOp::PowRegReg(out, lhs, rhs) => {
+            v[out] = v[lhs].powf(v[rhs]);
+        }
+        RegOp::SubImmReg(out, arg, imm) => {
+            v[out] = imm - v[arg];
+        }
+        RegOp::SubRegImm(out, arg, imm) => {
+            v[out] = v[arg] - imm;
+        }
+        RegOp::MinRegImm(out, arg, imm) => {
+            let a = v[arg];
+            let (choice, value) = if a < imm {
+                (Choice::Left, a)
+            } else if imm < a {
+                (Choice::Right, imm)
+            } else {
+                (
+                    Choice::Both,
+                    if a.is_nan() || imm.is_nan() {
+                        f32::NAN
+                    } else {
+                        imm
+                    },
+                )
+            };
+            v[out] = value;
+            return Some(choice);
+        }
+        RegOp::MaxRegImm(out, arg, imm) => {
+            let a = v[arg];
+            let (choice, value) = if a > imm {
+                (Choice::Left, a)
+            } else if imm > a {
+                (Choice::Right, imm)
+            } else {
+                (
+                    Choice::Both,
+                    if a.is_nan() || imm.is_nan() {
+                        f32::NAN
+                    } else {
+                        imm
+                    },
+                )
+            };
+            v[out] = value;
+            return Some(choice);
+        }
+        RegOp::AndRegImm(out, arg, imm) => {
+            let a = v[arg];
+            let (choice, value) = if a == 0.0 {
+                (Choice::Left, a)
+            } else {
+                (Choice::Right, imm)
+            };
+            v[out] = value;
+            return Some(choice);
+        }
+        RegOp::OrRegImm(out, arg, imm) => {
+            let a = v[arg];
+            let (choice, value) = if a != 0.0 {
+                (Choice::Left, a)
+            } else {
+                (Choice::Right, imm)
+            };
+            v[out] = value;
+            return Some(choice);
+        }
+        RegOp::SelectRegReg(out, cond, arg) => {
+            let (choice, value) = if v[cond] != 0.0 {
+                (Choice::Left, v[arg])
+            } else {
+                (Choice::Right, v[out])
+            };
+            v[out] = value;
+            return Some(choice);
+        }
+        RegOp::SelectRegImm(out, cond, imm) => {
+            let (choice, value) = if v[cond] != 0.0 {
+                (Choice::Left, imm)
+            } else {
+                (Choice::Right, v[out])
+            };
+            v[out] = value;
+            return Some(choice);
+        }
+        RegOp::SmoothMinRegReg(out, lhs, rhs) => {
+            let (choice, value) = smooth_min_choice(v[lhs], v[rhs], v[out]);
+            v[out] = value;
+            return Some(choice);
+        }
+        RegOp::SmoothMinRegImm(out, arg, imm) => {
+            let (choice, value) = smooth_min_choice(v[arg], imm, v[out]);
+            v[out] = value;
+            return Some(choice);
+        }
+        RegOp::ModRegReg(out, lhs, rhs) => {
+            v[out] = v[lhs].rem_euclid(v[rhs]);
+        }
+        RegOp::ModRegImm(out, arg, imm) => {
+            v[out] = v[arg].rem_euclid(imm);
+        }
+        RegOp::ModImmReg(out, arg, imm) => {
+            v[out] = imm.rem_euclid(v[arg]);
+        }
+        RegOp::AddRegReg(out, lhs, rhs) => {
+            v[out] = v[lhs] + v[rhs];
+        }
+        RegOp::MulRegReg(out, lhs, rhs) => {
+            v[out] = v[lhs] * v[rhs];
+        }
+        RegOp::DivRegReg(out, lhs, rhs) => {
+            v[out] = v[lhs] / v[rhs];
+        }
+        RegOp::CompareRegReg(out, lhs, rhs) => {
+            v[out] = v[lhs]
+                .partial_cmp(&v[rhs])
+                .map(|c| c as i8 as f32)
+                .unwrap_or(f32::NAN)
+        }
+        RegOp::CompareRegImm(out, arg, imm) => {
+            v[out] = v[arg]
+                .partial_cmp(&imm)
+                .map(|c| c as i8 as f32)
+                .unwrap_or(f32::NAN)
+        }
+        RegOp::CompareImmReg(out, arg, imm) => {
+            v[out] = imm
+                .partial_cmp(&v[arg])
+                .map(|c| c as i8 as f32)
+                .unwrap_or(f32::NAN)
+        }
+        RegOp::SubRegReg(out, lhs, rhs) => {
+            v[out] = v[lhs] - v[rhs];
+        }
+        RegOp::MinRegReg(out, lhs, rhs) => {
+            let a = v[lhs];
+            let b = v[rhs];
+            let (choice, value) = if a < b {
+                (Choice::Left, a)
+            } else if b < a {
+                (Choice::Right, b)
+            } else {
+                (
+                    Choice::Both,
+                    if a.is_nan() || b.is_nan() {
+                        f32::NAN
+                    } else {
+                        b
+                    },
+                )
+            };
+            v[out] = value;
+            return Some(choice);
+        }
+        RegOp::MaxRegReg(out, lhs, rhs) => {
+            let a = v[lhs];
+            let b = v[rhs];
+            let (choice, value) = if a > b {
+                (Choice::Left, a)
+            } else if b > a {
+                (Choice::Right, b)
+            } else {
+                (
+                    Choice::Both,
+                    if a.is_nan() || b.is_nan() {
+                        f32::NAN
+                    } else {
+                        b
+                    },
+                )
+            };
+            v[out] = value;
+            return Some(choice);
+        }
+        RegOp::AndRegReg(out, lhs, rhs) => {
+            let a = v[lhs];
+            let b = v[rhs];
+            let (choice, value) = if a == 0.0 {
+                (Choice::Left, a)
+            } else {
+                (Choice::Right, b)
+            };
+            v[out] = value;
+            return Some(choice);
+        }
+        RegOp::OrRegReg(out, lhs, rhs) => {
+            let a = v[lhs];
+            let b = v[rhs];
+            let (choice, value) = if a != 0.0 {
+                (Choice::Left, a)
+            } else {
+                (Choice::Right, b)
+            };
+            v[out] = value;
+            return Some(choice);
+        }
+        RegOp::CopyImm(out, imm) => {
+            v[out] = imm;
+        }
+        RegOp::Load(out, mem) => {
+            v[out] = v[mem];
+        }
+        RegOp::Store(out, mem) => {
+            v[mem] = v[out];
+        }
+    }
+    None
+}
+
 /// VM-based tracing evaluator for single points
 #[derive(Default)]
 pub struct VmPointEval<const N: usize>(TracingVmEval<f32>);
@@ -544,269 +1091,10 @@ impl<const N: usize> TracingEvaluator for VmPointEval<N> {
         let mut simplify = false;
         let mut v = SlotArray(&mut self.0.slots);
         for op in tape.iter_asm() {
-            match op {
-                RegOp::Output(arg, i) => {
-                    self.0.out[i as usize] = v[arg];
-                }
-                RegOp::Input(out, i) => {
-                    v[out] = vars[i as usize];
-                }
-                RegOp::NegReg(out, arg) => {
-                    v[out] = -v[arg];
-                }
-                RegOp::AbsReg(out, arg) => {
-                    v[out] = v[arg].abs();
-                }
-                RegOp::RecipReg(out, arg) => {
-                    v[out] = 1.0 / v[arg];
-                }
-                RegOp::SqrtReg(out, arg) => {
-                    v[out] = v[arg].sqrt();
-                }
-                RegOp::SquareReg(out, arg) => {
-                    let s = v[arg];
-                    v[out] = s * s;
-                }
-                RegOp::FloorReg(out, arg) => {
-                    v[out] = v[arg].floor();
-                }
-                RegOp::CeilReg(out, arg) => {
-                    v[out] = v[arg].ceil();
-                }
-                RegOp::RoundReg(out, arg) => {
-                    v[out] = v[arg].round();
-                }
-                RegOp::SinReg(out, arg) => {
-                    v[out] = v[arg].sin();
-                }
-                RegOp::CosReg(out, arg) => {
-                    v[out] = v[arg].cos();
-                }
-                RegOp::TanReg(out, arg) => {
-                    v[out] = v[arg].tan();
-                }
-                RegOp::AsinReg(out, arg) => {
-                    v[out] = v[arg].asin();
-                }
-                RegOp::AcosReg(out, arg) => {
-                    v[out] = v[arg].acos();
-                }
-                RegOp::AtanReg(out, arg) => {
-                    v[out] = v[arg].atan();
-                }
-                RegOp::ExpReg(out, arg) => {
-                    v[out] = v[arg].exp();
-                }
-                RegOp::LnReg(out, arg) => {
-                    v[out] = v[arg].ln();
-                }
-                RegOp::NotReg(out, arg) => v[out] = (v[arg] == 0.0).into(),
-                RegOp::CopyReg(out, arg) => {
-                    v[out] = v[arg];
-                }
-                RegOp::AddRegImm(out, arg, imm) => {
-                    v[out] = v[arg] + imm;
-                }
-                RegOp::MulRegImm(out, arg, imm) => {
-                    v[out] = v[arg] * imm;
-                }
-                RegOp::DivRegImm(out, arg, imm) => {
-                    v[out] = v[arg] / imm;
-                }
-                RegOp::DivImmReg(out, arg, imm) => {
-                    v[out] = imm / v[arg];
-                }
-                RegOp::AtanRegImm(out, arg, imm) => {
-                    v[out] = v[arg].atan2(imm);
-                }
-                RegOp::AtanImmReg(out, arg, imm) => {
-                    v[out] = imm.atan2(v[arg]);
-                }
-                RegOp::AtanRegReg(out, lhs, rhs) => {
-                    v[out] = v[lhs].atan2(v[rhs]);
-                }
-                RegOp::SubImmReg(out, arg, imm) => {
-                    v[out] = imm - v[arg];
-                }
-                RegOp::SubRegImm(out, arg, imm) => {
-                    v[out] = v[arg] - imm;
-                }
-                RegOp::MinRegImm(out, arg, imm) => {
-                    let a = v[arg];
-                    let (choice, value) = if a < imm {
-                        (Choice::Left, a)
-                    } else if imm < a {
-                        (Choice::Right, imm)
-                    } else {
-                        (
-                            Choice::Both,
-                            if a.is_nan() || imm.is_nan() {
-                                f32::NAN
-                            } else {
-                                imm
-                            },
-                        )
-                    };
-                    v[out] = value;
-                    *choices.next().unwrap() |= choice;
-                    simplify |= choice != Choice::Both;
-                }
-                RegOp::MaxRegImm(out, arg, imm) => {
-                    let a = v[arg];
-                    let (choice, value) = if a > imm {
-                        (Choice::Left, a)
-                    } else if imm > a {
-                        (Choice::Right, imm)
-                    } else {
-                        (
-                            Choice::Both,
-                            if a.is_nan() || imm.is_nan() {
-                                f32::NAN
-                            } else {
-                                imm
-                            },
-                        )
-                    };
-                    v[out] = value;
-                    *choices.next().unwrap() |= choice;
-                    simplify |= choice != Choice::Both;
-                }
-                RegOp::AndRegImm(out, arg, imm) => {
-                    let a = v[arg];
-                    let (choice, value) = if a == 0.0 {
-                        (Choice::Left, a)
-                    } else {
-                        (Choice::Right, imm)
-                    };
-                    v[out] = value;
-                    *choices.next().unwrap() |= choice;
-                    simplify |= choice != Choice::Both;
-                }
-                RegOp::OrRegImm(out, arg, imm) => {
-                    let a = v[arg];
-                    let (choice, value) = if a != 0.0 {
-                        (Choice::Left, a)
-                    } else {
-                        (Choice::Right, imm)
-                    };
-                    v[out] = value;
-                    *choices.next().unwrap() |= choice;
-                    simplify |= choice != Choice::Both;
-                }
-                RegOp::ModRegReg(out, lhs, rhs) => {
-                    v[out] = v[lhs].rem_euclid(v[rhs]);
-                }
-                RegOp::ModRegImm(out, arg, imm) => {
-                    v[out] = v[arg].rem_euclid(imm);
-                }
-                RegOp::ModImmReg(out, arg, imm) => {
-                    v[out] = imm.rem_euclid(v[arg]);
-                }
-                RegOp::AddRegReg(out, lhs, rhs) => {
-                    v[out] = v[lhs] + v[rhs];
-                }
-                RegOp::MulRegReg(out, lhs, rhs) => {
-                    v[out] = v[lhs] * v[rhs];
-                }
-                RegOp::DivRegReg(out, lhs, rhs) => {
-                    v[out] = v[lhs] / v[rhs];
-                }
-                RegOp::CompareRegReg(out, lhs, rhs) => {
-                    v[out] = v[lhs]
-                        .partial_cmp(&v[rhs])
-                        .map(|c| c as i8 as f32)
-                        .unwrap_or(f32::NAN)
-                }
-                RegOp::CompareRegImm(out, arg, imm) => {
-                    v[out] = v[arg]
-                        .partial_cmp(&imm)
-                        .map(|c| c as i8 as f32)
-                        .unwrap_or(f32::NAN)
-                }
-                RegOp::CompareImmReg(out, arg, imm) => {
-                    v[out] = imm
-                        .partial_cmp(&v[arg])
-                        .map(|c| c as i8 as f32)
-                        .unwrap_or(f32::NAN)
-                }
-                RegOp::SubRegReg(out, lhs, rhs) => {
-                    v[out] = v[lhs] - v[rhs];
-                }
-                RegOp::MinRegReg(out, lhs, rhs) => {
-                    let a = v[lhs];
-                    let b = v[rhs];
-                    let (choice, value) = if a < b {
-                        (Choice::Left, a)
-                    } else if b < a {
-                        (Choice::Right, b)
-                    } else {
-                        (
-                            Choice::Both,
-                            if a.is_nan() || b.is_nan() {
-                                f32::NAN
-                            } else {
-                                b
-                            },
-                        )
-                    };
-                    v[out] = value;
-                    *choices.next().unwrap() |= choice;
-                    simplify |= choice != Choice::Both;
-                }
-                RegOp::MaxRegReg(out, lhs, rhs) => {
-                    let a = v[lhs];
-                    let b = v[rhs];
-                    let (choice, value) = if a > b {
-                        (Choice::Left, a)
-                    } else if b > a {
-                        (Choice::Right, b)
-                    } else {
-                        (
-                            Choice::Both,
-                            if a.is_nan() || b.is_nan() {
-                                f32::NAN
-                            } else {
-                                b
-                            },
-                        )
-                    };
-                    v[out] = value;
-                    *choices.next().unwrap() |= choice;
-                    simplify |= choice != Choice::Both;
-                }
-                RegOp::AndRegReg(out, lhs, rhs) => {
-                    let a = v[lhs];
-                    let b = v[rhs];
-                    let (choice, value) = if a == 0.0 {
-                        (Choice::Left, a)
-                    } else {
-                        (Choice::Right, b)
-                    };
-                    v[out] = value;
-                    *choices.next().unwrap() |= choice;
-                    simplify |= choice != Choice::Both;
-                }
-                RegOp::OrRegReg(out, lhs, rhs) => {
-                    let a = v[lhs];
-                    let b = v[rhs];
-                    let (choice, value) = if a != 0.0 {
-                        (Choice::Left, a)
-                    } else {
-                        (Choice::Right, b)
-                    };
-                    v[out] = value;
-                    *choices.next().unwrap() |= choice;
-                    simplify |= choice != Choice::Both;
-                }
-                RegOp::CopyImm(out, imm) => {
-                    v[out] = imm;
-                }
-                RegOp::Load(out, mem) => {
-                    v[out] = v[mem];
-                }
-                RegOp::Store(out, mem) => {
-                    v[mem] = v[out];
-                }
+            if let Some(choice) = point_step(op, &mut v, vars, &mut self.0.out)
+            {
+                *choices.next().unwrap() |= choice;
+                simplify |= choice != Choice::Both;
             }
         }
         Ok((
@@ -862,7 +1150,7 @@ impl<const N: usize> BulkEvaluator for VmFloatSliceEval<N> {
         &mut self,
         tape: &Self::Tape,
         vars: &[V],
-    ) -> Result<BulkOutput<f32>, Error> {
+    ) -> Result<BulkOutput<'_, f32>, Error> {
         tape.vars().check_bulk_arguments(vars)?;
         let tape = tape.data();
 
@@ -960,6 +1248,36 @@ impl<const N: usize> BulkEvaluator for VmFloatSliceEval<N> {
                         v[out][i] = v[arg][i].ln();
                     }
                 }
+                RegOp::SinhReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].sinh();
+                    }
+                }
+                RegOp::CoshReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].cosh();
+                    }
+                }
+                RegOp::TanhReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].tanh();
+                    }
+                }
+                RegOp::Exp2Reg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].exp2();
+                    }
+                }
+                RegOp::Log2Reg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].log2();
+                    }
+                }
+                RegOp::Log10Reg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].log10();
+                    }
+                }
                 RegOp::NotReg(out, arg) => {
                     for i in 0..size {
                         v[out][i] = (v[arg][i] == 0.0).into();
@@ -1005,6 +1323,27 @@ impl<const N: usize> BulkEvaluator for VmFloatSliceEval<N> {
                         v[out][i] = v[lhs][i].atan2(v[rhs][i]);
                     }
                 }
+                RegOp::PowRegImm(out, arg, imm) => {
+                    if let Some(n) = integer_exponent(imm) {
+                        for i in 0..size {
+                            v[out][i] = v[arg][i].powi(n);
+                        }
+                    } else {
+                        for i in 0..size {
+                            v[out][i] = v[arg][i].powf(imm);
+                        }
+                    }
+                }
+                RegOp::PowImmReg(out, arg, imm) => {
+                    for i in 0..size {
+                        v[out][i] = imm.powf(v[arg][i]);
+                    }
+                }
+                RegOp::PowRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        v[out][i] = v[lhs][i].powf(v[rhs][i]);
+                    }
+                }
                 RegOp::SubImmReg(out, arg, imm) => {
                     for i in 0..size {
                         v[out][i] = imm - v[arg][i];
@@ -1061,6 +1400,33 @@ impl<const N: usize> BulkEvaluator for VmFloatSliceEval<N> {
                             if v[arg][i] != 0.0 { v[arg][i] } else { imm };
                     }
                 }
+                RegOp::SelectRegReg(out, cond, arg) => {
+                    for i in 0..size {
+                        if v[cond][i] != 0.0 {
+                            v[out][i] = v[arg][i];
+                        }
+                    }
+                }
+                RegOp::SelectRegImm(out, cond, imm) => {
+                    for i in 0..size {
+                        if v[cond][i] != 0.0 {
+                            v[out][i] = imm;
+                        }
+                    }
+                }
+                RegOp::SmoothMinRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        v[out][i] =
+                            smooth_min_choice(v[lhs][i], v[rhs][i], v[out][i])
+                                .1;
+                    }
+                }
+                RegOp::SmoothMinRegImm(out, arg, imm) => {
+                    for i in 0..size {
+                        v[out][i] =
+                            smooth_min_choice(v[arg][i], imm, v[out][i]).1;
+                    }
+                }
                 RegOp::ModRegReg(out, lhs, rhs) => {
                     for i in 0..size {
                         v[out][i] = v[lhs][i].rem_euclid(v[rhs][i]);
@@ -1163,29 +1529,132 @@ impl<const N: usize> BulkEvaluator for VmFloatSliceEval<N> {
     }
 }
 
-/// VM-based bulk evaluator for arrays of points, yielding gradient values
-#[derive(Default)]
-pub struct VmGradSliceEval<const N: usize>(BulkVmEval<Grad>);
-impl<const N: usize> BulkEvaluator for VmGradSliceEval<N> {
-    type Data = Grad;
-    type Tape = GenericVmTape<N>;
-    type TapeStorage = EmptyTapeStorage;
+/// Value type for the bulk evaluator loop shared by derivative-carrying
+/// evaluators ([`VmGradSliceEval`], [`VmGradNSliceEval`], and
+/// [`VmHessianSliceEval`])
+///
+/// Math functions forward to the type's inherent methods, so they have the
+/// same semantics as calling them directly.
+trait GradValue:
+    Copy
+    + From<f32>
+    + std::ops::Add<Output = Self>
+    + std::ops::Sub<Output = Self>
+    + std::ops::Mul<Output = Self>
+    + std::ops::Div<Output = Self>
+    + std::ops::Neg<Output = Self>
+{
+    /// Returns the value (without derivatives)
+    fn value(&self) -> f32;
+    /// Multiplies value and derivatives by a constant
+    fn scale(self, s: f32) -> Self;
 
-    fn eval<V: std::ops::Deref<Target = [Self::Data]>>(
+    /// Raises this value to an integer power
+    ///
+    /// By default, this is a [`pow`](GradValue::pow) with a constant exponent
+    fn powi(self, n: i32) -> Self {
+        self.pow((n as f32).into())
+    }
+
+    fn abs(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn tan(self) -> Self;
+    fn asin(self) -> Self;
+    fn acos(self) -> Self;
+    fn atan(self) -> Self;
+    fn exp(self) -> Self;
+    fn ln(self) -> Self;
+    fn sinh(self) -> Self;
+    fn cosh(self) -> Self;
+    fn tanh(self) -> Self;
+    fn exp2(self) -> Self;
+    fn log2(self) -> Self;
+    fn log10(self) -> Self;
+    fn floor(self) -> Self;
+    fn ceil(self) -> Self;
+    fn round(self) -> Self;
+    fn min(self, rhs: Self) -> Self;
+    fn max(self, rhs: Self) -> Self;
+    fn atan2(self, x: Self) -> Self;
+    fn pow(self, rhs: Self) -> Self;
+    fn rem_euclid(self, rhs: Self) -> Self;
+    fn smooth_min(self, rhs: Self, k: Self) -> Self;
+}
+
+/// Implements the math functions of [`GradValue`] by forwarding to inherent
+/// methods of the given type
+macro_rules! forward_grad_value {
+    ($ty:ty) => {
+        forward_grad_value!(@self $ty; abs, sqrt, sin, cos, tan, asin, acos,
+            atan, exp, ln, sinh, cosh, tanh, exp2, log2, log10);
+        forward_grad_value!(@ref $ty; floor, ceil, round);
+        forward_grad_value!(@binary $ty; min, max, atan2, pow);
+        fn rem_euclid(self, rhs: Self) -> Self {
+            <$ty>::rem_euclid(&self, rhs)
+        }
+        fn smooth_min(self, rhs: Self, k: Self) -> Self {
+            <$ty>::smooth_min(self, rhs, k)
+        }
+    };
+    (@self $ty:ty; $($f:ident),*) => {
+        $(fn $f(self) -> Self {
+            <$ty>::$f(self)
+        })*
+    };
+    (@ref $ty:ty; $($f:ident),*) => {
+        $(fn $f(self) -> Self {
+            <$ty>::$f(&self)
+        })*
+    };
+    (@binary $ty:ty; $($f:ident),*) => {
+        $(fn $f(self, rhs: Self) -> Self {
+            <$ty>::$f(self, rhs)
+        })*
+    };
+}
+use forward_grad_value;
+impl GradValue for Grad {
+    fn value(&self) -> f32 {
+        self.v
+    }
+    fn scale(self, s: f32) -> Self {
+        self * s
+    }
+    fn powi(self, n: i32) -> Self {
+        Grad::powi(self, n)
+    }
+    forward_grad_value!(Grad);
+}
+
+impl<const M: usize> GradValue for GradN<M> {
+    fn value(&self) -> f32 {
+        self.v
+    }
+    fn scale(self, s: f32) -> Self {
+        self * s
+    }
+    forward_grad_value!(GradN<M>);
+}
+
+impl<G: GradValue> BulkVmEval<G> {
+    /// Evaluates a tape over slices of derivative-carrying values
+    fn eval_grad<const N: usize, V: std::ops::Deref<Target = [G]>>(
         &mut self,
-        tape: &Self::Tape,
+        tape: &GenericVmTape<N>,
         vars: &[V],
-    ) -> Result<BulkOutput<Grad>, Error> {
+    ) -> Result<BulkOutput<'_, G>, Error> {
         tape.vars().check_bulk_arguments(vars)?;
         let tape = tape.data();
         let size = vars.first().map(|v| v.len()).unwrap_or(0);
-        self.0.resize_slots(tape, size);
+        self.resize_slots(tape, size);
 
-        let mut v = SlotArray(&mut self.0.slots);
+        let mut v = SlotArray(&mut self.slots);
         for op in tape.iter_asm() {
             match op {
                 RegOp::Output(arg, i) => {
-                    self.0.out[i as usize][0..size]
+                    self.out[i as usize][0..size]
                         .copy_from_slice(&v[arg][0..size]);
                 }
                 RegOp::Input(out, i) => {
@@ -1202,7 +1671,7 @@ impl<const N: usize> BulkEvaluator for VmGradSliceEval<N> {
                     }
                 }
                 RegOp::RecipReg(out, arg) => {
-                    let one: Grad = 1.0.into();
+                    let one: G = 1.0.into();
                     for i in 0..size {
                         v[out][i] = one / v[arg][i];
                     }
@@ -1273,9 +1742,39 @@ impl<const N: usize> BulkEvaluator for VmGradSliceEval<N> {
                         v[out][i] = v[arg][i].ln();
                     }
                 }
+                RegOp::SinhReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].sinh();
+                    }
+                }
+                RegOp::CoshReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].cosh();
+                    }
+                }
+                RegOp::TanhReg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].tanh();
+                    }
+                }
+                RegOp::Exp2Reg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].exp2();
+                    }
+                }
+                RegOp::Log2Reg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].log2();
+                    }
+                }
+                RegOp::Log10Reg(out, arg) => {
+                    for i in 0..size {
+                        v[out][i] = v[arg][i].log10();
+                    }
+                }
                 RegOp::NotReg(out, arg) => {
                     for i in 0..size {
-                        v[out][i] = f32::from(v[arg][i].v == 0.0).into();
+                        v[out][i] = f32::from(v[arg][i].value() == 0.0).into();
                     }
                 }
                 RegOp::CopyReg(out, arg) => {
@@ -1290,7 +1789,7 @@ impl<const N: usize> BulkEvaluator for VmGradSliceEval<N> {
                 }
                 RegOp::MulRegImm(out, arg, imm) => {
                     for i in 0..size {
-                        v[out][i] = v[arg][i] * imm;
+                        v[out][i] = v[arg][i].scale(imm);
                     }
                 }
                 RegOp::DivRegImm(out, arg, imm) => {
@@ -1299,19 +1798,19 @@ impl<const N: usize> BulkEvaluator for VmGradSliceEval<N> {
                     }
                 }
                 RegOp::DivImmReg(out, arg, imm) => {
-                    let imm = Grad::from(imm);
+                    let imm = G::from(imm);
                     for i in 0..size {
                         v[out][i] = imm / v[arg][i];
                     }
                 }
                 RegOp::AtanRegImm(out, arg, imm) => {
-                    let imm = Grad::from(imm);
+                    let imm = G::from(imm);
                     for i in 0..size {
                         v[out][i] = v[arg][i].atan2(imm);
                     }
                 }
                 RegOp::AtanImmReg(out, arg, imm) => {
-                    let imm = Grad::from(imm);
+                    let imm = G::from(imm);
                     for i in 0..size {
                         v[out][i] = imm.atan2(v[arg][i]);
                     }
@@ -1321,14 +1820,37 @@ impl<const N: usize> BulkEvaluator for VmGradSliceEval<N> {
                         v[out][i] = v[lhs][i].atan2(v[rhs][i]);
                     }
                 }
+                RegOp::PowRegImm(out, arg, imm) => {
+                    if let Some(n) = integer_exponent(imm) {
+                        for i in 0..size {
+                            v[out][i] = v[arg][i].powi(n);
+                        }
+                    } else {
+                        let imm = G::from(imm);
+                        for i in 0..size {
+                            v[out][i] = v[arg][i].pow(imm);
+                        }
+                    }
+                }
+                RegOp::PowImmReg(out, arg, imm) => {
+                    let imm = G::from(imm);
+                    for i in 0..size {
+                        v[out][i] = imm.pow(v[arg][i]);
+                    }
+                }
+                RegOp::PowRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        v[out][i] = v[lhs][i].pow(v[rhs][i]);
+                    }
+                }
                 RegOp::SubImmReg(out, arg, imm) => {
-                    let imm: Grad = imm.into();
+                    let imm: G = imm.into();
                     for i in 0..size {
                         v[out][i] = imm - v[arg][i];
                     }
                 }
                 RegOp::SubRegImm(out, arg, imm) => {
-                    let imm: Grad = imm.into();
+                    let imm: G = imm.into();
                     for i in 0..size {
                         v[out][i] = v[arg][i] - imm;
                     }
@@ -1336,26 +1858,28 @@ impl<const N: usize> BulkEvaluator for VmGradSliceEval<N> {
                 RegOp::CompareImmReg(out, arg, imm) => {
                     for i in 0..size {
                         let p = imm
-                            .partial_cmp(&v[arg][i].v)
+                            .partial_cmp(&v[arg][i].value())
                             .map(|c| c as i8 as f32)
                             .unwrap_or(f32::NAN);
-                        v[out][i] = Grad::new(p, 0.0, 0.0, 0.0);
+                        v[out][i] = G::from(p);
                     }
                 }
                 RegOp::CompareRegImm(out, arg, imm) => {
                     for i in 0..size {
                         let p = v[arg][i]
-                            .v
+                            .value()
                             .partial_cmp(&imm)
                             .map(|c| c as i8 as f32)
                             .unwrap_or(f32::NAN);
-                        v[out][i] = Grad::new(p, 0.0, 0.0, 0.0);
+                        v[out][i] = G::from(p);
                     }
                 }
                 RegOp::MinRegImm(out, arg, imm) => {
-                    let imm: Grad = imm.into();
+                    let imm: G = imm.into();
                     for i in 0..size {
-                        v[out][i] = if v[arg][i].v.is_nan() || imm.v.is_nan() {
+                        v[out][i] = if v[arg][i].value().is_nan()
+                            || imm.value().is_nan()
+                        {
                             f32::NAN.into()
                         } else {
                             v[arg][i].min(imm)
@@ -1363,9 +1887,11 @@ impl<const N: usize> BulkEvaluator for VmGradSliceEval<N> {
                     }
                 }
                 RegOp::MaxRegImm(out, arg, imm) => {
-                    let imm: Grad = imm.into();
+                    let imm: G = imm.into();
                     for i in 0..size {
-                        v[out][i] = if v[arg][i].v.is_nan() || imm.v.is_nan() {
+                        v[out][i] = if v[arg][i].value().is_nan()
+                            || imm.value().is_nan()
+                        {
                             f32::NAN.into()
                         } else {
                             v[arg][i].max(imm)
@@ -1384,7 +1910,7 @@ impl<const N: usize> BulkEvaluator for VmGradSliceEval<N> {
                 }
                 RegOp::ModImmReg(out, arg, imm) => {
                     for i in 0..size {
-                        v[out][i] = Grad::from(imm).rem_euclid(v[arg][i]);
+                        v[out][i] = G::from(imm).rem_euclid(v[arg][i]);
                     }
                 }
                 RegOp::AddRegReg(out, lhs, rhs) => {
@@ -1399,7 +1925,7 @@ impl<const N: usize> BulkEvaluator for VmGradSliceEval<N> {
                 }
                 RegOp::AndRegReg(out, lhs, rhs) => {
                     for i in 0..size {
-                        v[out][i] = if v[lhs][i].v == 0.0 {
+                        v[out][i] = if v[lhs][i].value() == 0.0 {
                             v[lhs][i]
                         } else {
                             v[rhs][i]
@@ -1408,7 +1934,7 @@ impl<const N: usize> BulkEvaluator for VmGradSliceEval<N> {
                 }
                 RegOp::AndRegImm(out, arg, imm) => {
                     for i in 0..size {
-                        v[out][i] = if v[arg][i].v == 0.0 {
+                        v[out][i] = if v[arg][i].value() == 0.0 {
                             v[arg][i]
                         } else {
                             imm.into()
@@ -1417,7 +1943,7 @@ impl<const N: usize> BulkEvaluator for VmGradSliceEval<N> {
                 }
                 RegOp::OrRegReg(out, lhs, rhs) => {
                     for i in 0..size {
-                        v[out][i] = if v[lhs][i].v != 0.0 {
+                        v[out][i] = if v[lhs][i].value() != 0.0 {
                             v[lhs][i]
                         } else {
                             v[rhs][i]
@@ -1426,13 +1952,53 @@ impl<const N: usize> BulkEvaluator for VmGradSliceEval<N> {
                 }
                 RegOp::OrRegImm(out, arg, imm) => {
                     for i in 0..size {
-                        v[out][i] = if v[arg][i].v != 0.0 {
+                        v[out][i] = if v[arg][i].value() != 0.0 {
                             v[arg][i]
                         } else {
                             imm.into()
                         };
                     }
                 }
+                RegOp::SelectRegReg(out, cond, arg) => {
+                    for i in 0..size {
+                        if v[cond][i].value() != 0.0 {
+                            v[out][i] = v[arg][i];
+                        }
+                    }
+                }
+                RegOp::SelectRegImm(out, cond, imm) => {
+                    for i in 0..size {
+                        if v[cond][i].value() != 0.0 {
+                            v[out][i] = imm.into();
+                        }
+                    }
+                }
+                RegOp::SmoothMinRegReg(out, lhs, rhs) => {
+                    for i in 0..size {
+                        let (a, b, k) = (v[lhs][i], v[rhs][i], v[out][i]);
+                        v[out][i] = if a.value().is_nan()
+                            || b.value().is_nan()
+                            || k.value().is_nan()
+                        {
+                            f32::NAN.into()
+                        } else {
+                            a.smooth_min(b, k)
+                        };
+                    }
+                }
+                RegOp::SmoothMinRegImm(out, arg, imm) => {
+                    for i in 0..size {
+                        let (a, k) = (v[arg][i], v[out][i]);
+                        v[out][i] = if a.value().is_nan()
+                            || imm.is_nan()
+                            || k.value().is_nan()
+                        {
+                            f32::NAN.into()
+                        } else {
+                            a.smooth_min(imm.into(), k)
+                        };
+                    }
+                }
                 RegOp::DivRegReg(out, lhs, rhs) => {
                     for i in 0..size {
                         v[out][i] = v[lhs][i] / v[rhs][i];
@@ -1446,35 +2012,37 @@ impl<const N: usize> BulkEvaluator for VmGradSliceEval<N> {
                 RegOp::CompareRegReg(out, lhs, rhs) => {
                     for i in 0..size {
                         let p = v[lhs][i]
-                            .v
-                            .partial_cmp(&v[rhs][i].v)
+                            .value()
+                            .partial_cmp(&v[rhs][i].value())
                             .map(|c| c as i8 as f32)
                             .unwrap_or(f32::NAN);
-                        v[out][i] = Grad::new(p, 0.0, 0.0, 0.0);
+                        v[out][i] = G::from(p);
                     }
                 }
                 RegOp::MinRegReg(out, lhs, rhs) => {
                     for i in 0..size {
-                        v[out][i] =
-                            if v[lhs][i].v.is_nan() || v[rhs][i].v.is_nan() {
-                                f32::NAN.into()
-                            } else {
-                                v[lhs][i].min(v[rhs][i])
-                            };
+                        v[out][i] = if v[lhs][i].value().is_nan()
+                            || v[rhs][i].value().is_nan()
+                        {
+                            f32::NAN.into()
+                        } else {
+                            v[lhs][i].min(v[rhs][i])
+                        };
                     }
                 }
                 RegOp::MaxRegReg(out, lhs, rhs) => {
                     for i in 0..size {
-                        v[out][i] =
-                            if v[lhs][i].v.is_nan() || v[rhs][i].v.is_nan() {
-                                f32::NAN.into()
-                            } else {
-                                v[lhs][i].max(v[rhs][i])
-                            };
+                        v[out][i] = if v[lhs][i].value().is_nan()
+                            || v[rhs][i].value().is_nan()
+                        {
+                            f32::NAN.into()
+                        } else {
+                            v[lhs][i].max(v[rhs][i])
+                        };
                     }
                 }
                 RegOp::CopyImm(out, imm) => {
-                    let imm: Grad = imm.into();
+                    let imm: G = imm.into();
                     for i in 0..size {
                         v[out][i] = imm;
                     }
@@ -1491,7 +2059,47 @@ impl<const N: usize> BulkEvaluator for VmGradSliceEval<N> {
                 }
             }
         }
-        Ok(BulkOutput::new(&self.0.out, size))
+        Ok(BulkOutput::new(&self.out, size))
+    }
+}
+
+/// VM-based bulk evaluator for arrays of points, yielding gradient values
+#[derive(Default)]
+pub struct VmGradSliceEval<const N: usize>(BulkVmEval<Grad>);
+impl<const N: usize> BulkEvaluator for VmGradSliceEval<N> {
+    type Data = Grad;
+    type Tape = GenericVmTape<N>;
+    type TapeStorage = EmptyTapeStorage;
+
+    fn eval<V: std::ops::Deref<Target = [Self::Data]>>(
+        &mut self,
+        tape: &Self::Tape,
+        vars: &[V],
+    ) -> Result<BulkOutput<'_, Grad>, Error> {
+        self.0.eval_grad(tape, vars)
+    }
+}
+
+/// VM-based bulk evaluator for arrays of points, yielding `M`-dimensional
+/// gradient values
+///
+/// Partial derivatives are with respect to whichever inputs are seeded by the
+/// caller (see [`GradN::input`]).
+#[derive(Default)]
+pub struct VmGradNSliceEval<const N: usize, const M: usize>(
+    BulkVmEval<GradN<M>>,
+);
+impl<const N: usize, const M: usize> BulkEvaluator for VmGradNSliceEval<N, M> {
+    type Data = GradN<M>;
+    type Tape = GenericVmTape<N>;
+    type TapeStorage = EmptyTapeStorage;
+
+    fn eval<V: std::ops::Deref<Target = [Self::Data]>>(
+        &mut self,
+        tape: &Self::Tape,
+        vars: &[V],
+    ) -> Result<BulkOutput<'_, GradN<M>>, Error> {
+        self.0.eval_grad(tape, vars)
     }
 }
 
@@ -1499,6 +2107,7 @@ impl<const N: usize> BulkEvaluator for VmGradSliceEval<N> {
 mod test {
     use super::*;
     crate::grad_slice_tests!(VmFunction);
+    crate::grad_n_slice_tests!(VmFunction);
     crate::interval_tests!(VmFunction);
     crate::float_slice_tests!(VmFunction);
     crate::point_tests!(VmFunction);
@@ -41,6 +41,25 @@ pub enum Error {
     #[error("unknown variable {0}")]
     UnknownVariable(String),
 
+    /// Invalid variable name
+    #[error("invalid variable name `{0}`")]
+    BadVarName(String),
+
+    /// Variable name is already used by a different variable
+    #[error("variable name `{0}` is already in use")]
+    DuplicateVarName(String),
+
+    /// Error while parsing a text file
+    #[error("parse error at line {line}, column {column}: {reason}")]
+    ParseError {
+        /// Line number (1-indexed)
+        line: usize,
+        /// Column number (1-indexed)
+        column: usize,
+        /// Description of the problem
+        reason: String,
+    },
+
     /// Empty file
     #[error("empty file")]
     EmptyFile,
@@ -61,6 +80,10 @@ pub enum Error {
     #[error("variable index ({0}) exceeds max var index for this tape ({1})")]
     BadVarIndex(usize, usize),
 
+    /// Code generation requires a tape with exactly one output
+    #[error("expected a tape with exactly one output, not {0}")]
+    BadOutputCount(usize),
+
     /// Could not solve for matrix pseudo-inverse
     #[error("could not solve for matrix pseudo-inverse: {0}")]
     SingularMatrix(&'static str),
@@ -224,6 +224,42 @@ impl Assembler for FloatSliceAssembler {
         }
         self.call_fn_unary(out_reg, lhs_reg, float_ln);
     }
+    fn build_sinh(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn float_sinh(f: f32) -> f32 {
+            f.sinh()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, float_sinh);
+    }
+    fn build_cosh(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn float_cosh(f: f32) -> f32 {
+            f.cosh()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, float_cosh);
+    }
+    fn build_tanh(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn float_tanh(f: f32) -> f32 {
+            f.tanh()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, float_tanh);
+    }
+    fn build_exp2(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn float_exp2(f: f32) -> f32 {
+            f.exp2()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, float_exp2);
+    }
+    fn build_log2(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn float_log2(f: f32) -> f32 {
+            f.log2()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, float_log2);
+    }
+    fn build_log10(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn float_log10(f: f32) -> f32 {
+            f.log10()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, float_log10);
+    }
     fn build_copy(&mut self, out_reg: u8, lhs_reg: u8) {
         dynasm!(self.0.ops ; mov V(reg(out_reg)).b16, V(reg(lhs_reg)).b16)
     }
@@ -318,6 +354,12 @@ impl Assembler for FloatSliceAssembler {
         }
         self.call_fn_binary(out_reg, lhs_reg, rhs_reg, float_atan2);
     }
+    fn build_pow(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "C" fn float_pow(lhs: f32, rhs: f32) -> f32 {
+            lhs.powf(rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, float_pow);
+    }
     fn build_max(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
         dynasm!(self.0.ops
             ; fmax V(reg(out_reg)).s4, V(reg(lhs_reg)).s4, V(reg(rhs_reg)).s4
@@ -364,6 +406,38 @@ impl Assembler for FloatSliceAssembler {
         );
     }
 
+    fn build_select(&mut self, out_reg: u8, cond_reg: u8, arg_reg: u8) {
+        dynasm!(self.0.ops
+            ; fcmeq v6.s4, V(reg(cond_reg)).s4, 0.0
+            ; mvn v7.b16, v6.b16
+            ; and v7.b16, v7.b16, V(reg(arg_reg)).b16
+            ; and v6.b16, v6.b16, V(reg(out_reg)).b16
+            ; orr V(reg(out_reg)).b16, v6.b16, v7.b16
+        );
+    }
+
+    fn build_smooth_min(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            // The blend radius k is in out_reg
+            ; fabd v5.s4, V(reg(lhs_reg)).s4, V(reg(rhs_reg)).s4
+            ; fsub v5.s4, V(reg(out_reg)).s4, v5.s4 // v5 = h = k - abs(lhs - rhs)
+            ; fmul v6.s4, v5.s4, v5.s4
+            ; fadd v7.s4, V(reg(out_reg)).s4, V(reg(out_reg)).s4
+            ; fadd v7.s4, v7.s4, v7.s4
+            ; fdiv v6.s4, v6.s4, v7.s4 // v6 = h^2 / 4k
+            ; fcmgt v5.s4, v5.s4, 0.0
+            ; and v6.b16, v6.b16, v5.b16 // only apply the blend if h > 0
+
+            // Build a mask of NANs in k; conveniently, all 1s is a NAN
+            ; fcmeq v5.s4, V(reg(out_reg)).s4, V(reg(out_reg)).s4
+            ; mvn v5.b16, v5.b16
+
+            ; fmin v7.s4, V(reg(lhs_reg)).s4, V(reg(rhs_reg)).s4
+            ; fsub v7.s4, v7.s4, v6.s4
+            ; orr V(reg(out_reg)).b16, v7.b16, v5.b16
+        );
+    }
+
     fn build_compare(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
         dynasm!(self.0.ops
             // Build a mask of valid positions (not NAN)
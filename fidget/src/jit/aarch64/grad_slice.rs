@@ -226,6 +226,42 @@ impl Assembler for GradSliceAssembler {
         }
         self.call_fn_unary(out_reg, lhs_reg, float_ln);
     }
+    fn build_sinh(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn grad_sinh(f: Grad) -> Grad {
+            f.sinh()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, grad_sinh);
+    }
+    fn build_cosh(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn grad_cosh(f: Grad) -> Grad {
+            f.cosh()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, grad_cosh);
+    }
+    fn build_tanh(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn grad_tanh(f: Grad) -> Grad {
+            f.tanh()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, grad_tanh);
+    }
+    fn build_exp2(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn grad_exp2(f: Grad) -> Grad {
+            f.exp2()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, grad_exp2);
+    }
+    fn build_log2(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn grad_log2(f: Grad) -> Grad {
+            f.log2()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, grad_log2);
+    }
+    fn build_log10(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn grad_log10(f: Grad) -> Grad {
+            f.log10()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, grad_log10);
+    }
     fn build_copy(&mut self, out_reg: u8, lhs_reg: u8) {
         dynasm!(self.0.ops ; mov V(reg(out_reg)).b16, V(reg(lhs_reg)).b16)
     }
@@ -374,6 +410,12 @@ impl Assembler for GradSliceAssembler {
         }
         self.call_fn_binary(out_reg, lhs_reg, rhs_reg, grad_atan2);
     }
+    fn build_pow(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "C" fn grad_pow(lhs: Grad, rhs: Grad) -> Grad {
+            lhs.pow(rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, grad_pow);
+    }
 
     fn build_max(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
         dynasm!(self.0.ops
@@ -451,6 +493,63 @@ impl Assembler for GradSliceAssembler {
         );
     }
 
+    fn build_select(&mut self, out_reg: u8, cond_reg: u8, arg_reg: u8) {
+        dynasm!(self.0.ops
+            ; fcmeq s6, S(reg(cond_reg)), 0.0
+            ; dup v6.s4, v6.s[0]
+            ; mvn v7.b16, v6.b16
+            ; and v7.b16, v7.b16, V(reg(arg_reg)).b16
+            ; and v6.b16, v6.b16, V(reg(out_reg)).b16
+            ; orr V(reg(out_reg)).b16, v6.b16, v7.b16
+        );
+    }
+
+    fn build_smooth_min(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            // The blend radius k is in out_reg
+            ; fcmp S(reg(lhs_reg)), S(reg(rhs_reg))
+            ; b.vs 100 // -> NaN
+            ; fcmp S(reg(out_reg)), S(reg(out_reg))
+            ; b.vs 92 // -> NaN
+
+            // v4 = min(lhs, rhs), including partial derivatives
+            ; fcmp S(reg(lhs_reg)), S(reg(rhs_reg))
+            ; b.mi 12 // -> lhs
+            ; mov v4.b16, V(reg(rhs_reg)).b16
+            ; b 8
+            ; mov v4.b16, V(reg(lhs_reg)).b16
+
+            // v5 = h = k - abs(lhs - rhs)
+            ; fsub v5.s4, V(reg(lhs_reg)).s4, V(reg(rhs_reg)).s4
+            ; fcmp s5, 0.0
+            ; b.ge 8
+            ; fneg v5.s4, v5.s4
+            ; fsub v5.s4, V(reg(out_reg)).s4, v5.s4
+            ; fcmp s5, 0.0
+            ; b.le 36 // -> no blend
+
+            // With r = h / 2k, the blend term h^2 / 4k is (h * r - k * r^2),
+            // which also gives us the correct partial derivatives.
+            ; fadd s6, S(reg(out_reg)), S(reg(out_reg))
+            ; fdiv s6, s5, s6
+            ; dup v6.s4, v6.s[0]
+            ; fmul v5.s4, v5.s4, v6.s4
+            ; fsub v4.s4, v4.s4, v5.s4
+            ; fmul v6.s4, v6.s4, v6.s4
+            ; fmul v6.s4, v6.s4, V(reg(out_reg)).s4
+            ; fadd v4.s4, v4.s4, v6.s4
+
+            // <- no blend
+            ; mov V(reg(out_reg)).b16, v4.b16
+            ; b 12 // -> end
+
+            // NaN handler
+            ; mov w15, f32::NAN.to_bits()
+            ; fmov S(reg(out_reg)), w15
+            // end:
+        )
+    }
+
     fn build_compare(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
         dynasm!(self.0.ops
             // Check whether either argument is NAN
@@ -184,6 +184,42 @@ impl Assembler for IntervalAssembler {
         }
         self.call_fn_unary(out_reg, lhs_reg, float_ln);
     }
+    fn build_sinh(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn interval_sinh(f: Interval) -> Interval {
+            f.sinh()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_sinh);
+    }
+    fn build_cosh(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn interval_cosh(f: Interval) -> Interval {
+            f.cosh()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_cosh);
+    }
+    fn build_tanh(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn interval_tanh(f: Interval) -> Interval {
+            f.tanh()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_tanh);
+    }
+    fn build_exp2(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn interval_exp2(f: Interval) -> Interval {
+            f.exp2()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_exp2);
+    }
+    fn build_log2(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn interval_log2(f: Interval) -> Interval {
+            f.log2()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_log2);
+    }
+    fn build_log10(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn interval_log10(f: Interval) -> Interval {
+            f.log10()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_log10);
+    }
     fn build_copy(&mut self, out_reg: u8, lhs_reg: u8) {
         dynasm!(self.0.ops ; fmov D(reg(out_reg)), D(reg(lhs_reg)))
     }
@@ -532,6 +568,21 @@ impl Assembler for IntervalAssembler {
         }
         self.call_fn_binary(out_reg, lhs_reg, rhs_reg, interval_atan2);
     }
+    fn build_pow(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "C" fn interval_pow(lhs: Interval, rhs: Interval) -> Interval {
+            lhs.pow(rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, interval_pow);
+    }
+    fn build_powi(&mut self, out_reg: u8, lhs_reg: u8, n: i32) {
+        // Repeated multiplication would widen the bounds of odd powers, so
+        // call into `Interval::powi` instead
+        extern "C" fn interval_powi(lhs: Interval, rhs: Interval) -> Interval {
+            lhs.powi(rhs.lower() as i32)
+        }
+        let imm = self.load_imm(n as f32);
+        self.call_fn_binary(out_reg, lhs_reg, imm, interval_powi);
+    }
 
     fn build_not(&mut self, out_reg: u8, arg_reg: u8) {
         dynasm!(self.0.ops
@@ -686,6 +737,159 @@ impl Assembler for IntervalAssembler {
         )
     }
 
+    fn build_select(&mut self, out_reg: u8, cond_reg: u8, arg_reg: u8) {
+        dynasm!(self.0.ops
+            // Check whether the condition has a NAN
+            ; fcmeq v5.s2, V(reg(cond_reg)).s2, V(reg(cond_reg)).s2
+            ; fmov x15, d5
+
+            // Load the choice bit
+            ; ldrb w14, [x1]
+
+            // check the NAN flag
+            ; cmp x15, 0
+            ; b.ne 20 // -> skip over NAN handling into main logic
+
+            // NAN handling
+            ; orr w14, w14, CHOICE_BOTH
+            ; mov w15, f32::NAN.to_bits()
+            ; dup V(reg(out_reg)).s2, w15
+            ; b 144 // -> exit
+
+            // v7 = !cond.contains(0.0)
+            ; fcmgt s6, S(reg(cond_reg)), 0.0 // s6 = lower > 0.0
+            ; mov s5, V(reg(cond_reg)).s[1]   // s5 = upper
+            ; fcmlt s7, s5, 0.0               // s7 = upper < 0.0
+            ; orr v7.b8, v6.b8, v7.b8 // (lower > 0) || (upper < 0)
+            ; fmov w9, s7
+            ; cmp w9, 0
+            ; b.eq 20 // skip the !cond.contains(0.0) branch
+
+            // !cond.contains(0.0) -> arg
+            ; fmov D(reg(out_reg)), D(reg(arg_reg))
+            ; orr w14, w14, CHOICE_LEFT
+            ; strb w14, [x2, 0] // write a non-zero value to simplify
+            ; b 100 // -> exit
+
+            // v6 = (lower == 0) && (upper == 0)
+            ; fcmeq s6, S(reg(cond_reg)), 0.0
+            ; fcmeq s5, s5, 0.0
+            ; and v6.b8, v6.b8, v5.b8 // (lower == 0) && (upper == 0)
+            ; fmov w9, s6
+            ; cmp w9, 0
+            ; b.eq 16 // skip the (lower == 0) && (upper == 0) branch
+
+            // (cond.lower == 0) && (cond.upper == 0) -> keep the output
+            ; orr w14, w14, CHOICE_RIGHT
+            ; strb w14, [x2, 0] // write a non-zero value to simplify
+            ; b 64 // -> exit
+
+            // Check whether either branch has a NAN
+            ; orr w14, w14, CHOICE_BOTH
+            ; fcmeq v5.s2, V(reg(out_reg)).s2, V(reg(out_reg)).s2
+            ; fmov x15, d5
+            ; fcmeq v5.s2, V(reg(arg_reg)).s2, V(reg(arg_reg)).s2
+            ; fmov x9, d5
+            ; and x15, x15, x9
+            ; cmp x15, 0
+            ; b.ne 16 // -> skip over NAN handling
+
+            ; mov w15, f32::NAN.to_bits()
+            ; dup V(reg(out_reg)).s2, w15
+            ; b 20 // -> exit
+
+            // s5 = min(out.lower, arg.lower)
+            // s6 = max(out.upper, arg.upper)
+            ; fmin s5, S(reg(out_reg)), S(reg(arg_reg))
+            ; fmax v6.s2, V(reg(out_reg)).s2, V(reg(arg_reg)).s2
+            ; mov s6, v6.s[1]
+            ; zip1 V(reg(out_reg)).s2, v5.s2, v6.s2
+
+            // exit
+            ; strb w14, [x1], 1 // post-increment
+        );
+    }
+
+    fn build_smooth_min(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            //  if any argument is NAN
+            //      *choices++ |= CHOICE_BOTH
+            //      out = NAN
+            //  k = max(k.upper, 0)
+            //  if lhs.upper + k < rhs.lower
+            //      *choices++ |= CHOICE_LEFT
+            //      out = lhs
+            //  elif rhs.upper + k < lhs.lower
+            //      *choices++ |= CHOICE_RIGHT
+            //      out = rhs
+            //  else
+            //      *choices++ |= CHOICE_BOTH
+            //      out = fmin(lhs, rhs) - [k / 4, 0]
+
+            // Check whether any argument has a NAN (k is in out_reg)
+            ; fcmeq v4.s2, V(reg(lhs_reg)).s2, V(reg(lhs_reg)).s2
+            ; fcmeq v5.s2, V(reg(rhs_reg)).s2, V(reg(rhs_reg)).s2
+            ; and v4.b8, v4.b8, v5.b8
+            ; fcmeq v5.s2, V(reg(out_reg)).s2, V(reg(out_reg)).s2
+            ; and v4.b8, v4.b8, v5.b8
+            ; fmov x15, d4
+            ; mvn x15, x15
+
+            // Load the choice bit
+            ; ldrb w14, [x1]
+
+            // check the NAN flag
+            ; cmp x15, 0
+            ; b.eq 20 // -> skip over NAN handling into main logic
+
+            // NAN handling
+            ; orr w14, w14, CHOICE_BOTH
+            ; mov w15, f32::NAN.to_bits()
+            ; dup V(reg(out_reg)).s2, w15
+            ; b 104 // -> exit
+
+            // s4 = max(k.upper, 0)
+            ; mov s4, V(reg(out_reg)).s[1]
+            ; fmov s5, wzr
+            ; fmax s4, s4, s5
+
+            // lhs.upper + k < rhs.lower -> lhs
+            ; mov s5, V(reg(lhs_reg)).s[1]
+            ; fadd s5, s5, s4
+            ; fcmp s5, S(reg(rhs_reg))
+            ; b.mi 48 // -> lhs
+
+            // rhs.upper + k < lhs.lower -> rhs
+            ; mov s5, V(reg(rhs_reg)).s[1]
+            ; fadd s5, s5, s4
+            ; fcmp s5, S(reg(lhs_reg))
+            ; b.mi 48 // -> rhs
+
+            // Fallthrough: ambiguous case, which only modifies the lower bound
+            ; orr w14, w14, CHOICE_BOTH
+            ; fmin V(reg(out_reg)).s2, V(reg(lhs_reg)).s2, V(reg(rhs_reg)).s2
+            ; fmov s5, 0.25
+            ; fmul s4, s4, s5
+            ; fsub s5, S(reg(out_reg)), s4
+            ; mov V(reg(out_reg)).s[0], v5.s[0]
+            ; b 32 // -> exit
+
+            // <- lhs
+            ; fmov D(reg(out_reg)), D(reg(lhs_reg))
+            ; orr w14, w14, CHOICE_LEFT
+            ; strb w14, [x2, 0] // write a non-zero value to simplify
+            ; b 16 // -> exit
+
+            // <- rhs
+            ; fmov D(reg(out_reg)), D(reg(rhs_reg))
+            ; orr w14, w14, CHOICE_RIGHT
+            ; strb w14, [x2, 0] // write a non-zero value to simplify
+
+            // exit
+            ; strb w14, [x1], 1 // post-increment
+        );
+    }
+
     fn build_compare(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
         dynasm!(self.0.ops
             // Very similar to build_min, but without writing choices
@@ -184,6 +184,42 @@ impl Assembler for PointAssembler {
         }
         self.call_fn_unary(out_reg, lhs_reg, float_ln);
     }
+    fn build_sinh(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn float_sinh(f: f32) -> f32 {
+            f.sinh()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, float_sinh);
+    }
+    fn build_cosh(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn float_cosh(f: f32) -> f32 {
+            f.cosh()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, float_cosh);
+    }
+    fn build_tanh(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn float_tanh(f: f32) -> f32 {
+            f.tanh()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, float_tanh);
+    }
+    fn build_exp2(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn float_exp2(f: f32) -> f32 {
+            f.exp2()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, float_exp2);
+    }
+    fn build_log2(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn float_log2(f: f32) -> f32 {
+            f.log2()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, float_log2);
+    }
+    fn build_log10(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "C" fn float_log10(f: f32) -> f32 {
+            f.log10()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, float_log10);
+    }
     fn build_neg(&mut self, out_reg: u8, lhs_reg: u8) {
         dynasm!(self.0.ops ; fneg S(reg(out_reg)), S(reg(lhs_reg)))
     }
@@ -272,6 +308,12 @@ impl Assembler for PointAssembler {
         }
         self.call_fn_binary(out_reg, lhs_reg, rhs_reg, float_atan2);
     }
+    fn build_pow(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "C" fn float_pow(lhs: f32, rhs: f32) -> f32 {
+            lhs.powf(rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, float_pow);
+    }
     fn build_max(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
         dynasm!(self.0.ops
             ; ldrb w14, [x1]
@@ -399,6 +441,89 @@ impl Assembler for PointAssembler {
         );
     }
 
+    fn build_select(&mut self, out_reg: u8, cond_reg: u8, arg_reg: u8) {
+        dynasm!(self.0.ops
+            ; fcmeq s6, S(reg(cond_reg)), 0.0
+
+            ; fmov w10, s6 // s6 = w10 = (cond == 0)
+            ; mov w9, CHOICE_RIGHT
+            ; and w9, w9, w10 // w9 = (cond == 0) ? CHOICE_RIGHT : 0
+
+            ; mvn w10, w10
+            ; mov w11, CHOICE_LEFT
+            ; and w11, w11, w10 // w11 = (cond != 0) ? CHOICE_LEFT : 0
+            ; orr w11, w11, w9  // w11 = choice to write
+
+            ; ldrb w14, [x1]
+            ; orr w14, w14, w11
+            ; strb w14, [x1], 1 // post-increment
+
+            ; strb w14, [x2, 0] // store any non-zero value to `simplify`
+
+            // Keep the output value if (cond == 0), otherwise copy the arg
+            ; and v5.b8, v6.b8, V(reg(out_reg)).b8
+            ; mvn v6.b8, v6.b8
+            ; and v6.b8, v6.b8, V(reg(arg_reg)).b8
+            ; orr V(reg(out_reg)).b8, v5.b8, v6.b8
+        );
+    }
+
+    fn build_smooth_min(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            // The blend radius k is in out_reg.  We start by computing the
+            // blended result into s7, which is used if we end up in the
+            // ambiguous case.
+            ; fabd s5, S(reg(lhs_reg)), S(reg(rhs_reg))
+            ; fsub s5, S(reg(out_reg)), s5 // s5 = h = k - abs(lhs - rhs)
+            ; fmul s6, s5, s5
+            ; fadd s7, S(reg(out_reg)), S(reg(out_reg))
+            ; fadd s7, s7, s7
+            ; fdiv s6, s6, s7 // s6 = h^2 / 4k
+            ; fcmgt s5, s5, 0.0
+            ; and v6.b8, v6.b8, v5.b8 // only apply the blend if h > 0
+            ; fmin s7, S(reg(lhs_reg)), S(reg(rhs_reg))
+            ; fsub s7, s7, s6
+
+            // Propagate a NAN in k (all 1s is a NAN)
+            ; fcmeq s6, S(reg(out_reg)), S(reg(out_reg))
+            ; mvn v6.b8, v6.b8
+            ; orr v7.b8, v7.b8, v6.b8
+
+            // s4 = max(k, 0)
+            ; ldrb w14, [x1]
+            ; fmov s4, wzr
+            ; fmax s4, S(reg(out_reg)), s4
+
+            ; fadd s5, s4, S(reg(lhs_reg))
+            ; fcmp s5, S(reg(rhs_reg))
+            ; b.mi 28 // -> lhs
+
+            ; fadd s5, s4, S(reg(rhs_reg))
+            ; fcmp s5, S(reg(lhs_reg))
+            ; b.mi 32 // -> rhs
+
+            // Ambiguous or NaN; use the blended value
+            ; fmov S(reg(out_reg)), s7
+            ; orr w14, w14, CHOICE_BOTH
+            ; b 32 // -> end
+
+            // LHS
+            ; fmov S(reg(out_reg)), S(reg(lhs_reg))
+            ; orr w14, w14, CHOICE_LEFT
+            ; strb w14, [x2, 0] // write a non-zero value to simplify
+            ; b 16
+
+            // RHS
+            ; fmov S(reg(out_reg)), S(reg(rhs_reg))
+            ; orr w14, w14, CHOICE_RIGHT
+            ; strb w14, [x2, 0]
+            // fall-through to end
+
+            // <- end
+            ; strb w14, [x1], 1 // post-increment
+        )
+    }
+
     fn build_compare(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
         // This is using SIMD instructions to avoid branch; dunno if it's faster
         // but it means we can use very similar code to float / grad slice
@@ -24,7 +24,7 @@
 //! ```
 
 use crate::{
-    compiler::RegOp,
+    compiler::{integer_exponent, RegOp},
     context::{Context, Node},
     eval::{
         BulkEvaluator, BulkOutput, Function, MathFunction, Tape,
@@ -182,6 +182,24 @@ trait Assembler {
     /// Natural log
     fn build_ln(&mut self, out_reg: u8, lhs_reg: u8);
 
+    /// Hyperbolic sine
+    fn build_sinh(&mut self, out_reg: u8, lhs_reg: u8);
+
+    /// Hyperbolic cosine
+    fn build_cosh(&mut self, out_reg: u8, lhs_reg: u8);
+
+    /// Hyperbolic tangent
+    fn build_tanh(&mut self, out_reg: u8, lhs_reg: u8);
+
+    /// Base-2 exponent
+    fn build_exp2(&mut self, out_reg: u8, lhs_reg: u8);
+
+    /// Base-2 log
+    fn build_log2(&mut self, out_reg: u8, lhs_reg: u8);
+
+    /// Base-10 log
+    fn build_log10(&mut self, out_reg: u8, lhs_reg: u8);
+
     /// Less than
     fn build_compare(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8);
 
@@ -212,6 +230,19 @@ trait Assembler {
     /// Logical or (short-circuiting)
     fn build_or(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8);
 
+    /// Conditional copy
+    ///
+    /// If `cond_reg` is non-zero, copies `arg_reg` into `out_reg`; otherwise,
+    /// `out_reg` is left unchanged (because it already contains the other
+    /// branch of the select).
+    fn build_select(&mut self, out_reg: u8, cond_reg: u8, arg_reg: u8);
+
+    /// Polynomial smooth minimum
+    ///
+    /// The blend radius `k` is already in `out_reg`, and is overwritten by the
+    /// result.
+    fn build_smooth_min(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8);
+
     /// Addition
     fn build_add(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8);
 
@@ -227,6 +258,41 @@ trait Assembler {
     /// Four-quadrant arctangent
     fn build_atan2(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8);
 
+    /// Raises the left-hand value to the power of the right-hand value
+    fn build_pow(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8);
+
+    /// Raises a value to a small non-zero integer power
+    ///
+    /// The default implementation uses repeated multiplication (keeping the
+    /// running power of the base in `IMM_REG`), then takes the reciprocal if
+    /// the exponent is negative.  This matches the order of operations in
+    /// [`f32::powi`], but should be overloaded if repeated multiplication
+    /// isn't exact (for example, in interval arithmetic).
+    fn build_powi(&mut self, out_reg: u8, lhs_reg: u8, n: i32) {
+        let base = IMM_REG.wrapping_sub(OFFSET);
+        self.build_copy(base, lhs_reg);
+        let mut m = n.unsigned_abs();
+        let mut first = true;
+        loop {
+            if m & 1 != 0 {
+                if first {
+                    self.build_copy(out_reg, base);
+                    first = false;
+                } else {
+                    self.build_mul(out_reg, out_reg, base);
+                }
+            }
+            m >>= 1;
+            if m == 0 {
+                break;
+            }
+            self.build_mul(base, base, base);
+        }
+        if n < 0 {
+            self.build_recip(out_reg, out_reg);
+        }
+    }
+
     /// Maximum of two values
     ///
     /// In a tracing evaluator, this function must also write to the `choices`
@@ -737,6 +803,24 @@ fn build_asm_fn_with_storage<A: Assembler>(
             RegOp::LnReg(out, arg) => {
                 asm.build_ln(out, arg);
             }
+            RegOp::SinhReg(out, arg) => {
+                asm.build_sinh(out, arg);
+            }
+            RegOp::CoshReg(out, arg) => {
+                asm.build_cosh(out, arg);
+            }
+            RegOp::TanhReg(out, arg) => {
+                asm.build_tanh(out, arg);
+            }
+            RegOp::Exp2Reg(out, arg) => {
+                asm.build_exp2(out, arg);
+            }
+            RegOp::Log2Reg(out, arg) => {
+                asm.build_log2(out, arg);
+            }
+            RegOp::Log10Reg(out, arg) => {
+                asm.build_log10(out, arg);
+            }
             RegOp::CopyReg(out, arg) => {
                 asm.build_copy(out, arg);
             }
@@ -767,6 +851,9 @@ fn build_asm_fn_with_storage<A: Assembler>(
             RegOp::AtanRegReg(out, lhs, rhs) => {
                 asm.build_atan2(out, lhs, rhs);
             }
+            RegOp::PowRegReg(out, lhs, rhs) => {
+                asm.build_pow(out, lhs, rhs);
+            }
             RegOp::SubRegReg(out, lhs, rhs) => {
                 asm.build_sub(out, lhs, rhs);
             }
@@ -798,6 +885,18 @@ fn build_asm_fn_with_storage<A: Assembler>(
                 let reg = asm.load_imm(imm);
                 asm.build_atan2(out, reg, arg);
             }
+            RegOp::PowRegImm(out, arg, imm) => {
+                if let Some(n) = integer_exponent(imm) {
+                    asm.build_powi(out, arg, n);
+                } else {
+                    let reg = asm.load_imm(imm);
+                    asm.build_pow(out, arg, reg);
+                }
+            }
+            RegOp::PowImmReg(out, arg, imm) => {
+                let reg = asm.load_imm(imm);
+                asm.build_pow(out, reg, arg);
+            }
             RegOp::SubImmReg(out, arg, imm) => {
                 asm.build_sub_imm_reg(out, arg, imm);
             }
@@ -837,6 +936,20 @@ fn build_asm_fn_with_storage<A: Assembler>(
                 let reg = asm.load_imm(imm);
                 asm.build_or(out, arg, reg);
             }
+            RegOp::SelectRegReg(out, cond, arg) => {
+                asm.build_select(out, cond, arg);
+            }
+            RegOp::SelectRegImm(out, cond, imm) => {
+                let reg = asm.load_imm(imm);
+                asm.build_select(out, cond, reg);
+            }
+            RegOp::SmoothMinRegReg(out, lhs, rhs) => {
+                asm.build_smooth_min(out, lhs, rhs);
+            }
+            RegOp::SmoothMinRegImm(out, arg, imm) => {
+                let reg = asm.load_imm(imm);
+                asm.build_smooth_min(out, arg, reg);
+            }
             RegOp::CopyImm(out, imm) => {
                 let reg = asm.load_imm(imm);
                 asm.build_copy(out, reg);
@@ -1223,7 +1336,7 @@ impl<T: From<f32> + Copy + SimdSize> JitBulkEval<T> {
         &mut self,
         tape: &JitBulkFn<T>,
         vars: &[V],
-    ) -> BulkOutput<T> {
+    ) -> BulkOutput<'_, T> {
         let n = vars.first().map(|v| v.deref().len()).unwrap_or(0);
 
         const OUTPUT_COUNT: usize = 1;
@@ -1317,7 +1430,7 @@ impl BulkEvaluator for JitFloatSliceEval {
         &mut self,
         tape: &Self::Tape,
         vars: &[V],
-    ) -> Result<BulkOutput<f32>, Error> {
+    ) -> Result<BulkOutput<'_, f32>, Error> {
         tape.vars().check_bulk_arguments(vars)?;
         Ok(self.0.eval(tape, vars))
     }
@@ -1335,7 +1448,7 @@ impl BulkEvaluator for JitGradSliceEval {
         &mut self,
         tape: &Self::Tape,
         vars: &[V],
-    ) -> Result<BulkOutput<Grad>, Error> {
+    ) -> Result<BulkOutput<'_, Grad>, Error> {
         tape.vars().check_bulk_arguments(vars)?;
         Ok(self.0.eval(tape, vars))
     }
@@ -164,6 +164,42 @@ impl Assembler for FloatSliceAssembler {
         }
         self.call_fn_unary(out_reg, lhs_reg, float_ln);
     }
+    fn build_sinh(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn float_sinh(f: f32) -> f32 {
+            f.sinh()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, float_sinh);
+    }
+    fn build_cosh(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn float_cosh(f: f32) -> f32 {
+            f.cosh()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, float_cosh);
+    }
+    fn build_tanh(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn float_tanh(f: f32) -> f32 {
+            f.tanh()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, float_tanh);
+    }
+    fn build_exp2(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn float_exp2(f: f32) -> f32 {
+            f.exp2()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, float_exp2);
+    }
+    fn build_log2(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn float_log2(f: f32) -> f32 {
+            f.log2()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, float_log2);
+    }
+    fn build_log10(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn float_log10(f: f32) -> f32 {
+            f.log10()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, float_log10);
+    }
     fn build_copy(&mut self, out_reg: u8, lhs_reg: u8) {
         dynasm!(self.0.ops
             ; vmovups Ry(reg(out_reg)), Ry(reg(lhs_reg))
@@ -255,6 +291,12 @@ impl Assembler for FloatSliceAssembler {
         }
         self.call_fn_binary(out_reg, lhs_reg, rhs_reg, float_atan2);
     }
+    fn build_pow(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn float_pow(lhs: f32, rhs: f32) -> f32 {
+            lhs.powf(rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, float_pow);
+    }
     fn build_max(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
         dynasm!(self.0.ops
             // Build a mask of NANs; conveniently, all 1s is a NAN
@@ -335,6 +377,47 @@ impl Assembler for FloatSliceAssembler {
         );
     }
 
+    fn build_select(&mut self, out_reg: u8, cond_reg: u8, arg_reg: u8) {
+        dynasm!(self.0.ops
+            // Build the (cond == 0) mask in ymm1, then use it to pick between
+            // the existing output value and the argument
+            ; vxorps ymm1, ymm1, ymm1
+            ; vcmpeqps ymm1, ymm1, Ry(reg(cond_reg))
+            ; vblendvps Ry(reg(out_reg)),
+                Ry(reg(arg_reg)), Ry(reg(out_reg)), ymm1
+        );
+    }
+
+    fn build_smooth_min(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            // The blend radius k is in out_reg
+            ; vsubps ymm1, Ry(reg(lhs_reg)), Ry(reg(rhs_reg))
+            ; vsubps ymm2, Ry(reg(rhs_reg)), Ry(reg(lhs_reg))
+            ; vmaxps ymm1, ymm1, ymm2 // ymm1 = abs(lhs - rhs)
+            ; vsubps ymm1, Ry(reg(out_reg)), ymm1 // ymm1 = h = k - abs(lhs - rhs)
+
+            // Build the (h > 0) mask in ymm3
+            ; vxorps ymm2, ymm2, ymm2
+            ; vcmpltps ymm3, ymm2, ymm1
+
+            // ymm1 = h^2 / 4k, masked to zero where h <= 0
+            ; vmulps ymm1, ymm1, ymm1
+            ; vaddps ymm2, Ry(reg(out_reg)), Ry(reg(out_reg))
+            ; vaddps ymm2, ymm2, ymm2
+            ; vdivps ymm1, ymm1, ymm2
+            ; vandps ymm1, ymm1, ymm3
+
+            ; vminps ymm2, Ry(reg(lhs_reg)), Ry(reg(rhs_reg))
+            ; vsubps ymm1, ymm2, ymm1
+
+            // Build a mask of NANs; conveniently, all 1s is a NAN
+            ; vcmpunordps ymm2, Ry(reg(lhs_reg)), Ry(reg(rhs_reg))
+            ; vcmpunordps ymm3, Ry(reg(out_reg)), Ry(reg(out_reg))
+            ; vorps ymm2, ymm2, ymm3
+            ; vorps Ry(reg(out_reg)), ymm1, ymm2
+        );
+    }
+
     fn build_compare(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
         dynasm!(self.0.ops
             // Build a mask of NANs; conveniently, all 1s is a NAN
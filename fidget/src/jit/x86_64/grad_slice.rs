@@ -156,6 +156,42 @@ impl Assembler for GradSliceAssembler {
         }
         self.call_fn_unary(out_reg, lhs_reg, float_ln);
     }
+    fn build_sinh(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn grad_sinh(f: Grad) -> Grad {
+            f.sinh()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, grad_sinh);
+    }
+    fn build_cosh(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn grad_cosh(f: Grad) -> Grad {
+            f.cosh()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, grad_cosh);
+    }
+    fn build_tanh(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn grad_tanh(f: Grad) -> Grad {
+            f.tanh()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, grad_tanh);
+    }
+    fn build_exp2(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn grad_exp2(f: Grad) -> Grad {
+            f.exp2()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, grad_exp2);
+    }
+    fn build_log2(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn grad_log2(f: Grad) -> Grad {
+            f.log2()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, grad_log2);
+    }
+    fn build_log10(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn grad_log10(f: Grad) -> Grad {
+            f.log10()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, grad_log10);
+    }
     fn build_copy(&mut self, out_reg: u8, lhs_reg: u8) {
         dynasm!(self.0.ops
             ; vmovups Rx(reg(out_reg)), Rx(reg(lhs_reg))
@@ -346,6 +382,12 @@ impl Assembler for GradSliceAssembler {
         }
         self.call_fn_binary(out_reg, lhs_reg, rhs_reg, grad_atan2);
     }
+    fn build_pow(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn grad_pow(lhs: Grad, rhs: Grad) -> Grad {
+            lhs.pow(rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, grad_pow);
+    }
 
     fn build_max(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
         dynasm!(self.0.ops
@@ -433,6 +475,65 @@ impl Assembler for GradSliceAssembler {
             ; vorpd Rx(reg(out_reg)), xmm1, xmm2
         );
     }
+    fn build_select(&mut self, out_reg: u8, cond_reg: u8, arg_reg: u8) {
+        dynasm!(self.0.ops
+            ; vpxor xmm1, xmm1, xmm1
+            ; vcmpeqss xmm1, Rx(reg(cond_reg)), xmm1
+            ; vbroadcastss xmm1, xmm1
+            ; vblendvps Rx(reg(out_reg)),
+                Rx(reg(arg_reg)), Rx(reg(out_reg)), xmm1
+        );
+    }
+    fn build_smooth_min(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            // The blend radius k is in out_reg
+            ; vcomiss Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
+            ; jp >N
+            ; vcomiss Rx(reg(out_reg)), Rx(reg(out_reg))
+            ; jp >N
+
+            // xmm1 = min(lhs, rhs), including partial derivatives
+            ; vcomiss Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
+            ; jb >A
+            ; vmovaps xmm1, Rx(reg(rhs_reg))
+            ; jmp >B
+            ; A:
+            ; vmovaps xmm1, Rx(reg(lhs_reg))
+            ; B:
+
+            // xmm2 = h = k - abs(lhs - rhs)
+            ; vsubps xmm2, Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
+            ; vxorps xmm3, xmm3, xmm3
+            ; vcomiss xmm2, xmm3
+            ; jae >C
+            ; vsubps xmm2, xmm3, xmm2
+            ; C:
+            ; vsubps xmm2, Rx(reg(out_reg)), xmm2
+            ; vcomiss xmm2, xmm3
+            ; jbe >M // h <= 0, so there's no blend
+
+            // With r = h / 2k, the blend term h^2 / 4k is (h * r - k * r^2),
+            // which also gives us the correct partial derivatives.
+            ; vaddss xmm3, Rx(reg(out_reg)), Rx(reg(out_reg))
+            ; vdivss xmm3, xmm2, xmm3
+            ; vbroadcastss xmm3, xmm3
+            ; vmulps xmm2, xmm2, xmm3
+            ; vsubps xmm1, xmm1, xmm2
+            ; vmulps xmm3, xmm3, xmm3
+            ; vmulps xmm3, xmm3, Rx(reg(out_reg))
+            ; vaddps xmm1, xmm1, xmm3
+
+            ; M:
+            ; vmovaps Rx(reg(out_reg)), xmm1
+            ; jmp >E
+
+            ; N:
+            ; vpxor Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(out_reg))
+            ; vcmpeqss Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(out_reg))
+            ; E:
+        );
+        self.0.ops.commit_local().unwrap();
+    }
     fn build_compare(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
         dynasm!(self.0.ops
             ; vcomiss Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
@@ -147,6 +147,42 @@ impl Assembler for IntervalAssembler {
         }
         self.call_fn_unary(out_reg, lhs_reg, float_ln);
     }
+    fn build_sinh(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn interval_sinh(f: Interval) -> Interval {
+            f.sinh()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_sinh);
+    }
+    fn build_cosh(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn interval_cosh(f: Interval) -> Interval {
+            f.cosh()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_cosh);
+    }
+    fn build_tanh(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn interval_tanh(f: Interval) -> Interval {
+            f.tanh()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_tanh);
+    }
+    fn build_exp2(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn interval_exp2(f: Interval) -> Interval {
+            f.exp2()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_exp2);
+    }
+    fn build_log2(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn interval_log2(f: Interval) -> Interval {
+            f.log2()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_log2);
+    }
+    fn build_log10(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn interval_log10(f: Interval) -> Interval {
+            f.log10()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, interval_log10);
+    }
     fn build_copy(&mut self, out_reg: u8, lhs_reg: u8) {
         dynasm!(self.0.ops
             ; vmovq Rx(reg(out_reg)), Rx(reg(lhs_reg))
@@ -532,6 +568,27 @@ impl Assembler for IntervalAssembler {
         }
         self.call_fn_binary(out_reg, lhs_reg, rhs_reg, interval_atan2);
     }
+    fn build_pow(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn interval_pow(
+            lhs: Interval,
+            rhs: Interval,
+        ) -> Interval {
+            lhs.pow(rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, interval_pow);
+    }
+    fn build_powi(&mut self, out_reg: u8, lhs_reg: u8, n: i32) {
+        // Repeated multiplication would widen the bounds of odd powers, so
+        // call into `Interval::powi` instead
+        extern "sysv64" fn interval_powi(
+            lhs: Interval,
+            rhs: Interval,
+        ) -> Interval {
+            lhs.powi(rhs.lower() as i32)
+        }
+        let imm = self.load_imm(n as f32);
+        self.call_fn_binary(out_reg, lhs_reg, imm, interval_powi);
+    }
 
     fn build_not(&mut self, out_reg: u8, arg_reg: u8) {
         dynasm!(self.0.ops
@@ -705,6 +762,156 @@ impl Assembler for IntervalAssembler {
             ; add rsi, 1
         );
     }
+    fn build_select(&mut self, out_reg: u8, cond_reg: u8, arg_reg: u8) {
+        assert_ne!(reg(cond_reg), IMM_REG);
+        dynasm!(self.0.ops
+            ; mov ax, [rsi] // load the choice flag
+
+            // check for NANs in the condition
+            ; vcomiss Rx(reg(cond_reg)), Rx(reg(cond_reg))
+            ; jp >N
+
+            ; vpxor xmm1, xmm1, xmm1 // xmm1 = 0.0
+
+            // xmm2 = !cond.contains(0.0)
+            ; vcmpgtss xmm3, Rx(reg(cond_reg)), xmm1 // lower > 0.0
+            ; vpshufd xmm2, Rx(reg(cond_reg)), 0b11111101u8 as i8 // upper
+            ; vcmpltss xmm2, xmm2, xmm1 // upper < 0.0
+            ; vorps xmm2, xmm2, xmm3 // (lower > 0) || (upper < 0)
+            ; vcomiss xmm1, xmm2 // compare against 0.0
+            ; jnp >A // skip this branch (jnp because xmm2 will be NAN, all 1s)
+
+            // !cond.contains(0.0) -> arg
+            ; vmovq Rx(reg(out_reg)), Rx(reg(arg_reg))
+            ; or ax, CHOICE_LEFT as i16
+            ; mov r8w, 1 // TODO: why can't we write 1 to [rdx] directly?
+            ; mov [rdx], r8w
+            ; jmp >E
+
+            // xmm3 = (lower == 0) && (upper == 0)
+            ; A:
+            ; vcmpeqss xmm3, Rx(reg(cond_reg)), xmm1
+            ; vpshufd xmm2, Rx(reg(cond_reg)), 0b11111101u8 as i8 // upper
+            ; vcmpeqss xmm2, xmm2, xmm1
+            ; vandps xmm3, xmm2, xmm3
+            ; vcomiss xmm1, xmm3
+            ; jnp >C // skip this branch
+
+            // (cond.lower == 0) && (cond.upper == 0) -> keep the output
+            ; or ax, CHOICE_RIGHT as i16
+            ; mov r8w, 1 // TODO: why can't we write 1 to [rdx] directly?
+            ; mov [rdx], r8w
+            ; jmp >E
+
+            // Normal case, combining the outputs (unless either is a NAN)
+            ; C:
+            ; vcomiss Rx(reg(arg_reg)), Rx(reg(arg_reg))
+            ; jp >N
+            ; vcomiss Rx(reg(out_reg)), Rx(reg(out_reg))
+            ; jp >N
+            ; or ax, CHOICE_BOTH as i16
+            ; vpshufd xmm2, Rx(reg(out_reg)), 0b11111101u8 as i8 // out.upper
+            ; vpshufd xmm1, Rx(reg(arg_reg)), 0b11111101u8 as i8 // arg.upper
+            ; vmaxss xmm1, xmm1, xmm2 // xmm1 = max(out.upper, arg.upper)
+            ; vminss xmm2, Rx(reg(out_reg)), Rx(reg(arg_reg))
+            ; vunpcklps Rx(reg(out_reg)), xmm2, xmm1
+            ; jmp >E
+
+            // Load NAN into out_reg
+            ; N:
+            ; or ax, CHOICE_BOTH as i16
+            ; vpcmpeqw Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(out_reg))
+            ; vpslld Rx(reg(out_reg)), Rx(reg(out_reg)), 23
+            ; vpsrld Rx(reg(out_reg)), Rx(reg(out_reg)), 1
+
+            ; E: // exit
+            ; mov [rsi], ax
+            ; add rsi, 1
+        );
+        self.0.ops.commit_local().unwrap();
+    }
+    fn build_smooth_min(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            //  if any argument is NAN
+            //      *choices++ |= CHOICE_BOTH
+            //      out = NAN
+            //  k = max(k.upper, 0)
+            //  if lhs.upper + k < rhs.lower
+            //      *choices++ |= CHOICE_LEFT
+            //      out = lhs
+            //  elif rhs.upper + k < lhs.lower
+            //      *choices++ |= CHOICE_RIGHT
+            //      out = rhs
+            //  else
+            //      *choices++ |= CHOICE_BOTH
+            //      out = fmin(lhs, rhs) - [k / 4, 0]
+
+            ; mov ax, [rsi] // load the choice flag
+
+            // check for NANs in the arguments (and in k, in out_reg)
+            ; vcomiss Rx(reg(lhs_reg)), Rx(reg(lhs_reg))
+            ; jp >N
+            ; vcomiss Rx(reg(rhs_reg)), Rx(reg(rhs_reg))
+            ; jp >N
+            ; vcomiss Rx(reg(out_reg)), Rx(reg(out_reg))
+            ; jp >N
+
+            // xmm1 = max(k.upper, 0)
+            ; vpxor xmm1, xmm1, xmm1
+            ; vpshufd xmm2, Rx(reg(out_reg)), 0b11111101u8 as i8 // k.upper
+            ; vmaxss xmm1, xmm1, xmm2
+
+            // lhs.upper + k < rhs.lower -> LHS
+            ; vpshufd xmm2, Rx(reg(lhs_reg)), 0b11111101u8 as i8 // lhs.upper
+            ; vaddss xmm2, xmm2, xmm1
+            ; vcomiss xmm2, Rx(reg(rhs_reg))
+            ; jb >L
+
+            // rhs.upper + k < lhs.lower -> RHS
+            ; vpshufd xmm2, Rx(reg(rhs_reg)), 0b11111101u8 as i8 // rhs.upper
+            ; vaddss xmm2, xmm2, xmm1
+            ; vcomiss xmm2, Rx(reg(lhs_reg))
+            ; jb >R
+
+            // Fallthrough: ambiguous case
+            ; or ax, CHOICE_BOTH as i16
+            ; vminps xmm2, Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
+            ; mov r8d, 0.25f32.to_bits() as i32
+            ; vmovd xmm3, r8d
+            ; vmulss xmm1, xmm1, xmm3 // xmm1 = k / 4
+            ; vsubss xmm2, xmm2, xmm1 // only modifies the lower bound
+            ; vmovq Rx(reg(out_reg)), xmm2
+            ; jmp >E
+
+            // lhs.upper + k < rhs.lower
+            ; L:
+            ; vmovq Rx(reg(out_reg)), Rx(reg(lhs_reg))
+            ; or ax, CHOICE_LEFT as i16
+            ; mov r8w, 1 // TODO: why can't we write 1 to [rdx] directly?
+            ; mov [rdx], r8w
+            ; jmp >E
+
+            // rhs.upper + k < lhs.lower
+            ; R:
+            ; vmovq Rx(reg(out_reg)), Rx(reg(rhs_reg))
+            ; or ax, CHOICE_RIGHT as i16
+            ; mov r8w, 1 // TODO: why can't we write 1 to [rdx] directly?
+            ; mov [rdx], r8w
+            ; jmp >E
+
+            // Load NAN into out_reg
+            ; N:
+            ; or ax, CHOICE_BOTH as i16
+            ; vpcmpeqw Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(out_reg))
+            ; vpslld Rx(reg(out_reg)), Rx(reg(out_reg)), 23
+            ; vpsrld Rx(reg(out_reg)), Rx(reg(out_reg)), 1
+
+            ; E: // exit
+            ; mov [rsi], ax
+            ; add rsi, 1
+        );
+        self.0.ops.commit_local().unwrap();
+    }
     fn build_compare(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
         // TODO: Godbolt uses unpcklps ?
         dynasm!(self.0.ops
@@ -151,6 +151,42 @@ impl Assembler for PointAssembler {
         }
         self.call_fn_unary(out_reg, lhs_reg, float_ln);
     }
+    fn build_sinh(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn float_sinh(f: f32) -> f32 {
+            f.sinh()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, float_sinh);
+    }
+    fn build_cosh(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn float_cosh(f: f32) -> f32 {
+            f.cosh()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, float_cosh);
+    }
+    fn build_tanh(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn float_tanh(f: f32) -> f32 {
+            f.tanh()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, float_tanh);
+    }
+    fn build_exp2(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn float_exp2(f: f32) -> f32 {
+            f.exp2()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, float_exp2);
+    }
+    fn build_log2(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn float_log2(f: f32) -> f32 {
+            f.log2()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, float_log2);
+    }
+    fn build_log10(&mut self, out_reg: u8, lhs_reg: u8) {
+        extern "sysv64" fn float_log10(f: f32) -> f32 {
+            f.log10()
+        }
+        self.call_fn_unary(out_reg, lhs_reg, float_log10);
+    }
     fn build_neg(&mut self, out_reg: u8, lhs_reg: u8) {
         // Flip the sign bit in the float
         dynasm!(self.0.ops
@@ -236,6 +272,12 @@ impl Assembler for PointAssembler {
         }
         self.call_fn_binary(out_reg, lhs_reg, rhs_reg, float_atan2);
     }
+    fn build_pow(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        extern "sysv64" fn float_pow(lhs: f32, rhs: f32) -> f32 {
+            lhs.powf(rhs)
+        }
+        self.call_fn_binary(out_reg, lhs_reg, rhs_reg, float_pow);
+    }
     fn build_max(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
         dynasm!(self.0.ops
             ; vcomiss Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
@@ -371,6 +413,90 @@ impl Assembler for PointAssembler {
         );
         self.0.ops.commit_local().unwrap()
     }
+    fn build_select(&mut self, out_reg: u8, cond_reg: u8, arg_reg: u8) {
+        dynasm!(self.0.ops
+            ; vxorps xmm2, xmm2, xmm2
+            ; vucomiss xmm2, Rx(reg(cond_reg))
+            ; setnp r8b
+            ; sete al
+            ; and al, r8b // al = (cond == 0)
+            ; jnz >E // keep the existing value in out_reg
+            ; vmovss Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(arg_reg))
+
+            ; E:
+            ; inc al
+            ; or [rsi], al // write the choice flag, based on the condition
+            ; or [rdx], 1 // write the simplify bit
+        );
+        self.0.ops.commit_local().unwrap()
+    }
+    fn build_smooth_min(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
+        dynasm!(self.0.ops
+            // The blend radius k is in out_reg
+            ; vcomiss Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
+            ; jp >N
+            ; vcomiss Rx(reg(out_reg)), Rx(reg(out_reg))
+            ; jp >N
+
+            // xmm1 = max(k, 0)
+            ; vxorps xmm1, xmm1, xmm1
+            ; vmaxss xmm1, xmm1, Rx(reg(out_reg))
+
+            // lhs + k < rhs -> LHS
+            ; vaddss xmm2, xmm1, Rx(reg(lhs_reg))
+            ; vcomiss xmm2, Rx(reg(rhs_reg))
+            ; jb >L
+
+            // rhs + k < lhs -> RHS
+            ; vaddss xmm2, xmm1, Rx(reg(rhs_reg))
+            ; vcomiss xmm2, Rx(reg(lhs_reg))
+            ; jb >R
+
+            // Otherwise, we're within the blend region
+            ; or [rsi], CHOICE_BOTH as i8
+            ; vsubss xmm2, Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
+            ; vsubss xmm3, Rx(reg(rhs_reg)), Rx(reg(lhs_reg))
+            ; vmaxss xmm2, xmm2, xmm3 // xmm2 = abs(lhs - rhs)
+            ; vsubss xmm2, Rx(reg(out_reg)), xmm2 // xmm2 = h = k - abs(lhs - rhs)
+            ; vminss xmm3, Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
+            ; vxorps xmm1, xmm1, xmm1
+            ; vcomiss xmm2, xmm1
+            ; jbe >M // h <= 0, so there's no blend
+
+            ; vmulss xmm2, xmm2, xmm2 // h^2
+            ; vaddss xmm1, Rx(reg(out_reg)), Rx(reg(out_reg))
+            ; vaddss xmm1, xmm1, xmm1 // 4k
+            ; vdivss xmm2, xmm2, xmm1
+            ; vsubss xmm3, xmm3, xmm2 // min(lhs, rhs) - h^2 / 4k
+
+            ; M:
+            ; vmovss Rx(reg(out_reg)), Rx(reg(out_reg)), xmm3
+            ; jmp >O
+
+            // Fallthrough for NaN; do a float addition to propagate it to the
+            // output register.
+            ; N:
+            ; or [rsi], CHOICE_BOTH as i8
+            ; vaddss xmm1, Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
+            ; vaddss Rx(reg(out_reg)), Rx(reg(out_reg)), xmm1
+            ; jmp >O
+
+            ; L:
+            ; vmovss Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(lhs_reg))
+            ; or [rsi], CHOICE_LEFT as i8
+            ; or [rdx], 1
+            ; jmp >O
+
+            ; R:
+            ; vmovss Rx(reg(out_reg)), Rx(reg(out_reg)), Rx(reg(rhs_reg))
+            ; or [rsi], CHOICE_RIGHT as i8
+            ; or [rdx], 1
+            // fallthrough to out
+
+            ; O:
+        );
+        self.0.ops.commit_local().unwrap()
+    }
     fn build_compare(&mut self, out_reg: u8, lhs_reg: u8, rhs_reg: u8) {
         dynasm!(self.0.ops
             ; vcomiss Rx(reg(lhs_reg)), Rx(reg(rhs_reg))
@@ -112,6 +112,7 @@ impl<F: Function> RenderHandle<F> {
     ///
     /// As an internal optimization, this may reuse a previous simplification if
     /// the trace matches.
+    #[allow(clippy::unnecessary_unwrap)]
     pub fn simplify(
         &mut self,
         trace: &F::Trace,
@@ -221,7 +222,7 @@ impl TileSizes {
         for i in 1..sizes.len() {
             if sizes[i - 1] <= sizes[i] {
                 return Err(Error::BadTileOrder(sizes[i - 1], sizes[i]));
-            } else if sizes[i - 1] % sizes[i] != 0 {
+            } else if !sizes[i - 1].is_multiple_of(sizes[i]) {
                 return Err(Error::BadTileSize(sizes[i - 1], sizes[i]));
             }
         }
@@ -302,7 +302,7 @@ impl<F: Function, M: RenderMode> Worker<'_, F, M> {
                     let v0 = vs[0] * (1.0 - y_frac) + vs[1] * y_frac;
                     let v1 = vs[2] * (1.0 - y_frac) + vs[3] * y_frac;
 
-                    let mut i = self
+                    let i = self
                         .tile_sizes
                         .pixel_offset(tile.add(Vector2::new(0, y)));
                     for x in 0..tile_size {
@@ -311,8 +311,7 @@ impl<F: Function, M: RenderMode> Worker<'_, F, M> {
                         let v = v0 * (1.0 - x_frac) + v1 * x_frac;
 
                         // Write out the pixel
-                        self.image[i] = M::pixel(v);
-                        i += 1;
+                        self.image[i + x] = M::pixel(v);
                     }
                 }
                 return;
@@ -48,9 +48,43 @@
 //! which defines a few simple shapes and transforms.  `x`, `y`, and `z` are
 //! defined in the root scope, and `axes()` returns an object with `x`/`y`/`z`
 //! members.
+//!
+//! `variable(name)` returns a named variable, whose metadata is recorded in
+//! [`ScriptContext::vars`].  Metadata may be passed as a second argument,
+//! either as a default value or as an object map with `value` (the default
+//! value), `min`, `max`, and `description` members:
+//!
+//! ```
+//! use fidget::rhai::Engine;
+//!
+//! let mut engine = Engine::new();
+//! let out = engine.run(r#"
+//!     let r = variable("radius", #{ value: 1.0, min: 0.0, max: 5.0 });
+//!     draw(sqrt(x * x + y * y) - r);
+//! "#)?;
+//! let v = out.vars.find("radius").unwrap();
+//! assert_eq!(out.vars.get(&v).unwrap().range, Some((0.0, 5.0)));
+//! # Ok::<(), fidget::Error>(())
+//! ```
+//!
+//! Variables keep their identity across calls to [`Engine::run`] on the same
+//! engine, so re-running an edited script doesn't invalidate values bound to
+//! them.
+//!
+//! [`Tree::to_rhai`] prints a tree as a script which evaluates back to the same
+//! tree.  Unnamed variables are written as `var_index("v_3")` or
+//! `var_index("V(…)")` (using [`Var`]'s `Display` representation), which
+//! refers to that exact [`Var`] without registering it.  Substitutions are
+//! written as `substitute(shape, [[var, value], …])`; the object map form
+//! (`substitute(shape, #{ x: …, radius: … })`) may also be used in scripts.
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
-use crate::{context::Tree, Error};
+use crate::{
+    context::Tree,
+    var::{Var, VarInfo, VarRegistry},
+    Error,
+};
 use rhai::{CustomType, NativeCallContext, TypeBuilder};
 
 /// Engine for evaluating a Rhai script with Fidget-specific bindings
@@ -77,12 +111,20 @@ impl Engine {
         let mut engine = rhai::Engine::new();
         engine
             .register_type::<Tree>()
-            .register_fn("remap_xyz", remap_xyz);
+            .register_fn("remap_xyz", remap_xyz)
+            .register_fn("remap_affine", remap_affine)
+            .register_fn("substitute", substitute)
+            .register_fn("substitute", substitute_pairs);
 
         engine.build_type::<Axes>();
         engine.register_fn("axes", axes);
         engine.register_fn("draw", draw);
         engine.register_fn("draw_rgb", draw_rgb);
+        engine.register_fn("variable", var);
+        engine.register_fn("variable", var_default);
+        engine.register_fn("variable", var_default_int);
+        engine.register_fn("variable", var_map);
+        engine.register_fn("var_index", var_index);
 
         macro_rules! register_binary_fns {
             ($op:literal, $name:ident, $engine:ident) => {
@@ -90,6 +132,11 @@ impl Engine {
                 $engine.register_fn($op, $name::dyn_node);
             };
         }
+        macro_rules! register_ternary_fns {
+            ($op:literal, $name:ident, $engine:ident) => {
+                $engine.register_fn($op, $name::node);
+            };
+        }
         macro_rules! register_unary_fns {
             ($op:literal, $name:ident, $engine:ident) => {
                 $engine.register_fn($op, $name::node);
@@ -107,7 +154,13 @@ impl Engine {
         register_binary_fns!("and", and, engine);
         register_binary_fns!("or", or, engine);
         register_binary_fns!("atan2", atan2, engine);
+        register_binary_fns!("pow", pow, engine);
+        register_binary_fns!("**", pow, engine);
+        register_ternary_fns!("smooth_min", smooth_min, engine);
+        register_ternary_fns!("smooth_max", smooth_max, engine);
+        register_ternary_fns!("if_nonzero_else", if_nonzero_else, engine);
         register_unary_fns!("abs", abs, engine);
+        register_unary_fns!("recip", recip, engine);
         register_unary_fns!("sqrt", sqrt, engine);
         register_unary_fns!("square", square, engine);
         register_unary_fns!("sin", sin, engine);
@@ -118,6 +171,12 @@ impl Engine {
         register_unary_fns!("atan", atan, engine);
         register_unary_fns!("exp", exp, engine);
         register_unary_fns!("ln", ln, engine);
+        register_unary_fns!("sinh", sinh, engine);
+        register_unary_fns!("cosh", cosh, engine);
+        register_unary_fns!("tanh", tanh, engine);
+        register_unary_fns!("exp2", exp2, engine);
+        register_unary_fns!("log2", log2, engine);
+        register_unary_fns!("log10", log10, engine);
         register_unary_fns!("not", not, engine);
         register_unary_fns!("ceil", ceil, engine);
         register_unary_fns!("floor", floor, engine);
@@ -166,9 +225,14 @@ impl Engine {
         scope.push("z", Tree::z());
         self.engine.run_with_scope(&mut scope, script)?;
 
-        // Steal the ScriptContext's contents
+        // Steal the ScriptContext's contents, keeping the cache of known
+        // variables for subsequent runs
         let mut lock = self.context.lock().unwrap();
-        Ok(std::mem::take(&mut lock))
+        Ok(ScriptContext {
+            shapes: std::mem::take(&mut lock.shapes),
+            vars: std::mem::take(&mut lock.vars),
+            known: HashMap::new(),
+        })
     }
 
     /// Evaluates a single expression, in terms of `x`, `y`, and `z`
@@ -186,9 +250,26 @@ impl Engine {
             scope
         };
 
-        let out = self.engine.eval_ast_with_scope::<Tree>(&mut scope, &ast)?;
+        let out = self
+            .engine
+            .eval_ast_with_scope::<rhai::Dynamic>(&mut scope, &ast)?;
 
-        Ok(out)
+        // Expressions without any variables evaluate to plain numbers
+        if let Some(v) = out.clone().try_cast::<f64>() {
+            Ok(Tree::constant(v))
+        } else if let Some(v) = out.clone().try_cast::<i64>() {
+            Ok(Tree::constant(v as f64))
+        } else {
+            let t = out.type_name();
+            out.try_cast::<Tree>().ok_or_else(|| {
+                let e = rhai::EvalAltResult::ErrorMismatchOutputType(
+                    std::any::type_name::<Tree>().into(),
+                    t.into(),
+                    rhai::Position::NONE,
+                );
+                Box::new(e).into()
+            })
+        }
     }
 }
 
@@ -207,10 +288,15 @@ pub struct DrawShape {
 /// Context for shape evaluation
 ///
 /// This object stores a set of shapes, which is populated by calls to `draw` or
-/// `draw_rgb` during script evaluation.
+/// `draw_rgb` during script evaluation, and a registry of named variables,
+/// which is populated by calls to `variable`.
 pub struct ScriptContext {
     /// List of shapes populated since the last call to [`clear`](Self::clear)
     pub shapes: Vec<DrawShape>,
+    /// Variables declared since the last call to [`clear`](Self::clear)
+    pub vars: VarRegistry,
+    /// Variables from previous runs, to preserve their identity
+    known: HashMap<String, Var>,
 }
 
 impl Default for ScriptContext {
@@ -222,11 +308,33 @@ impl Default for ScriptContext {
 impl ScriptContext {
     /// Builds a new empty script context
     pub fn new() -> Self {
-        Self { shapes: vec![] }
+        Self {
+            shapes: vec![],
+            vars: VarRegistry::new(),
+            known: HashMap::new(),
+        }
     }
     /// Resets the script context
     pub fn clear(&mut self) {
         self.shapes.clear();
+        self.vars.clear();
+    }
+    /// Finds or declares a named variable
+    ///
+    /// If `info` is `None`, then an existing variable's metadata is preserved.
+    fn var(
+        &mut self,
+        name: &str,
+        info: Option<VarInfo>,
+    ) -> Result<Tree, Error> {
+        let v = match self.vars.find(name) {
+            Some(v) if info.is_none() => return Ok(Tree::from(v)),
+            Some(v) => v,
+            None => *self.known.entry(name.to_owned()).or_insert_with(Var::new),
+        };
+        self.vars
+            .insert(v, info.unwrap_or_else(|| VarInfo::new(name)))?;
+        Ok(Tree::from(v))
     }
 }
 
@@ -252,6 +360,84 @@ fn remap_xyz(shape: Tree, x: Tree, y: Tree, z: Tree) -> Tree {
     shape.remap_xyz(x, y, z)
 }
 
+fn remap_affine(
+    shape: Tree,
+    mat: rhai::Array,
+) -> Result<Tree, Box<rhai::EvalAltResult>> {
+    if mat.len() != 12 {
+        let e = format!("remap_affine expects 12 values, got {}", mat.len());
+        return Err(e.into());
+    }
+    let mut out = nalgebra::Matrix4::identity();
+    for (i, v) in mat.into_iter().enumerate() {
+        out[(i / 4, i % 4)] = v
+            .as_float()
+            .or_else(|_| v.as_int().map(|i| i as f64))
+            .map_err(|t| format!("invalid type for remap_affine: {t}"))?;
+    }
+    Ok(shape.remap_affine(nalgebra::Affine3::from_matrix_unchecked(out)))
+}
+
+fn substitute(
+    ctx: NativeCallContext,
+    shape: Tree,
+    map: rhai::Map,
+) -> Result<Tree, Box<rhai::EvalAltResult>> {
+    let mut vars = vec![];
+    for (k, v) in map {
+        let var = match k.as_str() {
+            "x" => Var::X,
+            "y" => Var::Y,
+            "z" => Var::Z,
+            name => {
+                let ctx = ctx
+                    .tag()
+                    .unwrap()
+                    .clone_cast::<Arc<Mutex<ScriptContext>>>();
+                let t = ctx.lock().unwrap().var(name, None);
+                t.map_err(|e| e.to_string())?.var().unwrap()
+            }
+        };
+        vars.push((var, substitute_value(&k, v)?));
+    }
+    Ok(shape.substitute(&vars))
+}
+
+fn substitute_pairs(
+    shape: Tree,
+    pairs: rhai::Array,
+) -> Result<Tree, Box<rhai::EvalAltResult>> {
+    let mut vars = vec![];
+    for p in pairs {
+        let err = || format!("expected [var, value] pair, got {p}");
+        let pair = p.clone().try_cast::<rhai::Array>().ok_or_else(err)?;
+        let [k, v] = <[rhai::Dynamic; 2]>::try_from(pair).map_err(|_| err())?;
+        let var = k
+            .try_cast::<Tree>()
+            .and_then(|t| t.var())
+            .ok_or("substitute keys must be variables")?;
+        vars.push((var, substitute_value(var, v)?));
+    }
+    Ok(shape.substitute(&vars))
+}
+
+/// Converts a substitution value (a number or shape) into a [`Tree`]
+fn substitute_value(
+    k: impl std::fmt::Display,
+    v: rhai::Dynamic,
+) -> Result<Tree, Box<rhai::EvalAltResult>> {
+    if let Some(v) = v.clone().try_cast::<f64>() {
+        Ok(Tree::constant(v))
+    } else if let Some(v) = v.clone().try_cast::<i64>() {
+        Ok(Tree::constant(v as f64))
+    } else if let Some(t) = v.clone().try_cast::<Tree>() {
+        Ok(t)
+    } else {
+        let e = format!("invalid type for substitute `{k}`: {}", v.type_name());
+        Err(e.into())
+    }
+}
+
 fn draw(ctx: NativeCallContext, tree: Tree) {
     let ctx = ctx.tag().unwrap().clone_cast::<Arc<Mutex<ScriptContext>>>();
     ctx.lock().unwrap().shapes.push(DrawShape {
@@ -277,6 +463,86 @@ fn draw_rgb(ctx: NativeCallContext, tree: Tree, r: f64, g: f64, b: f64) {
     });
 }
 
+fn declare_var(
+    ctx: NativeCallContext,
+    name: &str,
+    info: Option<VarInfo>,
+) -> Result<Tree, Box<rhai::EvalAltResult>> {
+    let ctx = ctx.tag().unwrap().clone_cast::<Arc<Mutex<ScriptContext>>>();
+    let out = ctx.lock().unwrap().var(name, info);
+    out.map_err(|e| e.to_string().into())
+}
+
+fn var(
+    ctx: NativeCallContext,
+    name: &str,
+) -> Result<Tree, Box<rhai::EvalAltResult>> {
+    declare_var(ctx, name, None)
+}
+
+fn var_index(name: &str) -> Result<Tree, Box<rhai::EvalAltResult>> {
+    match name.parse::<Var>() {
+        Ok(v @ Var::V(..)) => Ok(Tree::from(v)),
+        _ => Err(format!("invalid variable index `{name}`").into()),
+    }
+}
+
+fn var_default(
+    ctx: NativeCallContext,
+    name: &str,
+    default: f64,
+) -> Result<Tree, Box<rhai::EvalAltResult>> {
+    let info = VarInfo {
+        default,
+        ..VarInfo::new(name)
+    };
+    declare_var(ctx, name, Some(info))
+}
+
+fn var_default_int(
+    ctx: NativeCallContext,
+    name: &str,
+    default: i64,
+) -> Result<Tree, Box<rhai::EvalAltResult>> {
+    var_default(ctx, name, default as f64)
+}
+
+fn var_map(
+    ctx: NativeCallContext,
+    name: &str,
+    map: rhai::Map,
+) -> Result<Tree, Box<rhai::EvalAltResult>> {
+    let mut info = VarInfo::new(name);
+    let mut min = None;
+    let mut max = None;
+    for (k, v) in map {
+        let f = || {
+            v.as_float()
+                .or_else(|_| v.as_int().map(|i| i as f64))
+                .map_err(|t| format!("invalid type for variable `{k}`: {t}"))
+        };
+        match k.as_str() {
+            "value" => info.default = f()?,
+            "min" => min = Some(f()?),
+            "max" => max = Some(f()?),
+            "description" => {
+                info.description = v.into_string().map_err(|t| {
+                    format!("invalid type for variable `description`: {t}")
+                })?
+            }
+            _ => return Err(format!("unknown variable field `{k}`").into()),
+        }
+    }
+    info.range = match (min, max) {
+        (None, None) => None,
+        (min, max) => Some((
+            min.unwrap_or(f64::NEG_INFINITY),
+            max.unwrap_or(f64::INFINITY),
+        )),
+    };
+    declare_var(ctx, name, Some(info))
+}
+
 macro_rules! define_binary_fns {
     ($name:ident $(, $op:ident)?) => {
         mod $name {
@@ -331,6 +597,38 @@ macro_rules! define_binary_fns {
     };
 }
 
+macro_rules! define_ternary_fns {
+    ($name:ident) => {
+        mod $name {
+            use super::*;
+            pub fn node(
+                _ctx: NativeCallContext,
+                a: rhai::Dynamic,
+                b: rhai::Dynamic,
+                c: rhai::Dynamic,
+            ) -> Result<Tree, Box<rhai::EvalAltResult>> {
+                let f = |v: rhai::Dynamic| {
+                    if let Some(v) = v.clone().try_cast::<f64>() {
+                        Ok(Tree::constant(v))
+                    } else if let Some(v) = v.clone().try_cast::<i64>() {
+                        Ok(Tree::constant(v as f64))
+                    } else if let Some(t) = v.clone().try_cast::<Tree>() {
+                        Ok(t)
+                    } else {
+                        let e = format!(
+                            "invalid type for {}(a, b, c): {}",
+                            stringify!($name),
+                            v.type_name()
+                        );
+                        Err(e)
+                    }
+                };
+                Ok(f(a)?.$name(f(b)?, f(c)?))
+            }
+        }
+    };
+}
+
 macro_rules! define_unary_fns {
     ($name:ident) => {
         mod $name {
@@ -371,6 +669,11 @@ define_binary_fns!(modulo);
 define_binary_fns!(and);
 define_binary_fns!(or);
 define_binary_fns!(atan2);
+define_binary_fns!(pow);
+define_ternary_fns!(smooth_min);
+define_ternary_fns!(smooth_max);
+define_ternary_fns!(if_nonzero_else);
+define_unary_fns!(recip);
 define_unary_fns!(sqrt);
 define_unary_fns!(square);
 define_unary_fns!(neg);
@@ -382,6 +685,12 @@ define_unary_fns!(acos);
 define_unary_fns!(atan);
 define_unary_fns!(exp);
 define_unary_fns!(ln);
+define_unary_fns!(sinh);
+define_unary_fns!(cosh);
+define_unary_fns!(tanh);
+define_unary_fns!(exp2);
+define_unary_fns!(log2);
+define_unary_fns!(log10);
 define_unary_fns!(not);
 define_unary_fns!(abs);
 define_unary_fns!(floor);
@@ -402,7 +711,7 @@ pub fn eval(s: &str) -> Result<Tree, Error> {
 mod test {
     use super::*;
     use crate::{
-        context::{BinaryOpcode, Op},
+        context::{BinaryOpcode, Op, TreeOp},
         Context,
     };
 
@@ -448,6 +757,131 @@ mod test {
         assert_eq!(ctx.eval_xyz(sum, 1.0, 3.0, 0.0).unwrap(), -2.0);
     }
 
+    #[test]
+    fn test_pow() {
+        let mut engine = Engine::new();
+        let t = engine.eval("x ** 3 + pow(2, y)").unwrap();
+        let mut ctx = Context::new();
+        let node = ctx.import(&t);
+        assert_eq!(ctx.eval_xyz(node, -2.0, 3.0, 0.0).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_smooth_min() {
+        let mut engine = Engine::new();
+        let t = engine.eval("smooth_min(x, 1, 0.5)").unwrap();
+        let mut ctx = Context::new();
+        let node = ctx.import(&t);
+        assert_eq!(ctx.eval_xyz(node, -2.0, 0.0, 0.0).unwrap(), -2.0);
+        assert_eq!(ctx.eval_xyz(node, 1.0, 0.0, 0.0).unwrap(), 0.875);
+
+        let t = engine.eval("smooth_max(0, y, x)").unwrap();
+        let node = ctx.import(&t);
+        assert_eq!(ctx.eval_xyz(node, 0.5, 0.0, 0.0).unwrap(), 0.125);
+        assert_eq!(ctx.eval_xyz(node, 0.5, 1.0, 0.0).unwrap(), 1.0);
+    }
+
+    #[test]
+    fn test_var() {
+        let mut engine = Engine::new();
+        let script = r#"
+            let r = variable("radius", #{ value: 2, min: 0, description: "size" });
+            let t = variable("thickness", 0.5);
+            draw(x * x + y * y - r * r + t - variable("radius"));
+        "#;
+        let out = engine.run(script).unwrap();
+        assert_eq!(out.vars.len(), 2);
+        let r = out.vars.find("radius").unwrap();
+        let info = out.vars.get(&r).unwrap();
+        assert_eq!(info.default, 2.0);
+        assert_eq!(info.range, Some((0.0, f64::INFINITY)));
+        assert_eq!(info.description, "size");
+        let t = out.vars.find("thickness").unwrap();
+        assert_eq!(out.vars.get(&t).unwrap().default, 0.5);
+
+        let mut ctx = Context::new();
+        let node = ctx.import(&out.shapes[0].tree);
+        let vars =
+            HashMap::from([(Var::X, 3.0), (Var::Y, 0.0), (r, 1.0), (t, 0.5)]);
+        assert_eq!(ctx.eval(node, &vars).unwrap(), 7.5);
+
+        // Names can be copied into a Context and round-trip through text
+        ctx.var_registry_mut().merge(&out.vars).unwrap();
+        let mut txt = vec![];
+        ctx.write_text(node, &mut txt).unwrap();
+        let (ctx, _) = Context::from_text(txt.as_slice()).unwrap();
+        assert!(ctx.var_registry().find("radius").is_some());
+        assert!(ctx.var_registry().find("thickness").is_some());
+
+        // Variables keep their identity across runs
+        let out = engine.run(script).unwrap();
+        assert_eq!(out.vars.find("radius"), Some(r));
+
+        assert!(engine.run(r#"variable("two words")"#).is_err());
+        assert!(engine.run(r#"variable("v_3")"#).is_err());
+        assert!(engine.run(r#"var_index("radius")"#).is_err());
+        assert!(engine.run(r#"var_index("X")"#).is_err());
+        assert!(engine.run(r#"variable("r", #{ dflt: 1.0 })"#).is_err());
+    }
+
+    #[test]
+    fn test_print_round_trip() {
+        // Deep trees are split up to stay within Rhai's depth limits
+        crate::context::check_round_trip(Tree::to_rhai, eval, 200);
+
+        // Affine remappings are printed natively (rather than as `remap_xyz`)
+        let mat =
+            nalgebra::Affine3::from_matrix_unchecked(nalgebra::Matrix4::new(
+                2.0, 0.0, 0.5, 1.0, //
+                0.0, 1.0, 0.0, -3.0, //
+                0.0, 0.0, 1e-12, 0.0, //
+                0.0, 0.0, 0.0, 1.0,
+            ));
+        let t = (Tree::x().square() + Tree::y()).remap_affine(mat);
+        let script = t.to_rhai();
+        let out = eval(&script).unwrap();
+        let mut ctx = Context::new();
+        assert_eq!(
+            ctx.import(&t),
+            ctx.import(&out),
+            "round-trip failed for {script}"
+        );
+    }
+
+    #[test]
+    fn test_print_round_trip_names() {
+        let mut ctx = Context::new();
+        let mut sum = ctx.x();
+        for name in ["rädius", "a\u{1}b\u{7f}", r#"a"b\c"#, "x", "🦀"] {
+            let v = ctx.named_var(name).unwrap();
+            sum = ctx.add(sum, v).unwrap();
+        }
+        let anon = ctx.var(Var::new());
+        let out = ctx.mul(sum, anon).unwrap();
+
+        let script = ctx.to_rhai(out).unwrap();
+        assert!(script.contains(r#"variable("a\u0001b\u007f")"#), "{script}");
+        assert!(script.contains(r#"variable("rädius")"#), "{script}");
+        assert!(script.contains("var_index("), "{script}");
+        let mut engine = Engine::new();
+        let run = engine.run(&format!("draw({{ {script} }});")).unwrap();
+        assert_eq!(run.vars.len(), 5);
+
+        let mut ctx2 = Context::new();
+        ctx2.var_registry_mut().merge(&run.vars).unwrap();
+        let node = ctx2.import(&run.shapes[0].tree);
+        assert_eq!(ctx2.to_rhai(node).unwrap(), script);
+    }
+
+    #[test]
+    fn test_eval_const() {
+        let t = eval("1 + 2.5").unwrap();
+        assert!(matches!(*t, TreeOp::Const(3.5)));
+        let t = eval("1 + 2").unwrap();
+        assert!(matches!(*t, TreeOp::Const(3.0)));
+        assert!(eval(r#""hello""#).is_err());
+    }
+
     #[test]
     fn test_no_comparison() {
         let mut engine = Engine::new();
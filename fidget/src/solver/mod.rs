@@ -1,7 +1,8 @@
-//! Solver for systems of equations expressed as sets of [Function] objects
+//! Solver for systems of equations expressed as sets of
+//! [Function](crate::eval::Function) objects
 use crate::{
-    eval::{BulkEvaluator, Function, Tape, TracingEvaluator},
-    types::Grad,
+    eval::{BulkEvaluator, GradNFunction, Tape, TracingEvaluator},
+    types::GradN,
     var::Var,
     Error,
 };
@@ -17,36 +18,41 @@ pub enum Parameter {
 }
 
 /// Workspace for solvers
-struct Solver<'a, F: Function> {
+///
+/// `W` is the number of partial derivatives computed in each gradient
+/// evaluation; systems with more free variables are evaluated in multiple
+/// passes.
+struct Solver<'a, F: GradNFunction, const W: usize> {
     /// Input parameters
     vars: &'a HashMap<Var, Parameter>,
 
     /// Tapes for bulk gradient evaluation of each constraint
-    grad_tapes: Vec<<F::GradSliceEval as BulkEvaluator>::Tape>,
+    grad_tapes: Vec<<F::GradNSliceEval<W> as BulkEvaluator>::Tape>,
 
     /// Tapes for single-point evaluation of each constraint
     point_tapes: Vec<<F::PointEval as TracingEvaluator>::Tape>,
 
     /// Bulk gradient evaluator, for use in computing the Jacobian
-    grad_eval: F::GradSliceEval,
+    grad_eval: F::GradNSliceEval<W>,
 
     /// Single-point evaluator, for use in checking our current error
     point_eval: F::PointEval,
 
     /// Input data for use when calling the gradient bulk evaluator
-    input_grad: Vec<Vec<Grad>>,
+    input_grad: Vec<Vec<GradN<W>>>,
 
     /// Input data for use when calling the single-point evaluator
     input_point: Vec<f32>,
 
     /// Map from (free) variables to the index of their gradient
     ///
-    /// We evaluate 3x gradients per sample, so for `grad_index = gi`, the
-    /// relevant derivative will be `out[gi / 3].d(gi % 3)`
+    /// We evaluate `W` gradients per sample, so for `grad_index = gi`,
+    /// the relevant derivative will be
+    /// `out[gi / W].d[gi % W]`
     grad_index: HashMap<Var, usize>,
 }
 
-impl<'a, F: Function> Solver<'a, F> {
+impl<'a, F: GradNFunction, const W: usize> Solver<'a, F, W> {
     fn new(
         eqs: &'a [F],
         vars: &'a HashMap<Var, Parameter>,
@@ -54,7 +60,7 @@ impl<'a, F: Function> Solver<'a, F> {
         // Build our per-constraint
         let grad_tapes = eqs
             .iter()
-            .map(|f| f.grad_slice_tape(Default::default()))
+            .map(|f| f.grad_n_slice_tape(Default::default()))
             .collect::<Vec<_>>();
         let point_tapes = eqs
             .iter()
@@ -76,7 +82,7 @@ impl<'a, F: Function> Solver<'a, F> {
         // to simultaneously compute all of the gradients that we need
         let input_grad =
             vec![
-                vec![Grad::from(0f32); grad_index.len().div_ceil(3)];
+                vec![GradN::from(0f32); grad_index.len().div_ceil(W)];
                 vars.len()
             ];
         let input_point = vec![0f32; vars.len()];
@@ -117,16 +123,15 @@ impl<'a, F: Function> Solver<'a, F> {
                     Parameter::Free(..) => {
                         let gi = self.grad_index[v];
                         for (j, v) in slice.iter_mut().enumerate() {
-                            *v = Grad::new(
-                                cur[gi],
-                                if j * 3 == gi { 1.0 } else { 0.0 },
-                                if j * 3 + 1 == gi { 1.0 } else { 0.0 },
-                                if j * 3 + 2 == gi { 1.0 } else { 0.0 },
-                            );
+                            *v = if gi / W == j {
+                                GradN::input(cur[gi], gi % W)
+                            } else {
+                                GradN::from(cur[gi])
+                            };
                         }
                     }
                     Parameter::Fixed(f) => {
-                        slice.fill(Grad::new(*f, 0.0, 0.0, 0.0));
+                        slice.fill(GradN::from(*f));
                     }
                 };
             }
@@ -135,7 +140,7 @@ impl<'a, F: Function> Solver<'a, F> {
 
             // Populate this row of the Jacobian
             for gi in 0..self.grad_index.len() {
-                *jacobian.get_mut((ti, gi)).unwrap() = out[0][gi / 3].d(gi % 3);
+                *jacobian.get_mut((ti, gi)).unwrap() = out[0][gi / W].d[gi % W];
             }
             result[ti] = out[0][0].v;
         }
@@ -186,15 +191,30 @@ impl<'a, F: Function> Solver<'a, F> {
 /// - [Basics on Continuous Optimization § Levenberg-Marquardt](https://www.brnt.eu/phd/node10.html#SECTION00622700000000000000)
 /// - [Improvements to the Levenberg-Marquardt algorithm for nonlinear
 ///   least-squares minimization (Transtrum 2012)](https://arxiv.org/pdf/1201.5885)
-pub fn solve<F: Function>(
+pub fn solve<F: GradNFunction>(
     eqs: &[F],
     vars: &HashMap<Var, Parameter>,
 ) -> Result<HashMap<Var, f32>, Error> {
-    let tapes = eqs
-        .iter()
-        .map(|f| f.grad_slice_tape(Default::default()))
-        .collect::<Vec<_>>();
+    // Pick a gradient width which fits every free variable, so that we don't
+    // compute unused partial derivatives; this matters for backends which
+    // evaluate wide gradients in multiple passes (e.g. the JIT).
+    let free = vars
+        .values()
+        .filter(|p| matches!(p, Parameter::Free(..)))
+        .count();
+    match free {
+        0..=3 => solve_with::<F, 3>(eqs, vars),
+        4..=6 => solve_with::<F, 6>(eqs, vars),
+        7..=12 => solve_with::<F, 12>(eqs, vars),
+        _ => solve_with::<F, 24>(eqs, vars),
+    }
+}
 
+/// Least-squares minimization, computing `W` partial derivatives per pass
+fn solve_with<F: GradNFunction, const W: usize>(
+    eqs: &[F],
+    vars: &HashMap<Var, Parameter>,
+) -> Result<HashMap<Var, f32>, Error> {
     // Current values for free variables
     let mut cur = HashMap::new();
     for (v, p) in vars {
@@ -203,7 +223,7 @@ pub fn solve<F: Function>(
         }
     }
 
-    let mut solver = Solver::new(eqs, vars)?;
+    let mut solver = Solver::<F, W>::new(eqs, vars)?;
 
     // Build an array of current values for each free variable
     let mut cur = vec![0f32; solver.grad_index.len()];
@@ -215,8 +235,8 @@ pub fn solve<F: Function>(
     }
 
     // Working arrays for the current Jacobian and result
-    let mut jacobian = nalgebra::DMatrix::repeat(tapes.len(), cur.len(), 0f32);
-    let mut result = nalgebra::DVector::repeat(tapes.len(), 0f32);
+    let mut jacobian = nalgebra::DMatrix::repeat(eqs.len(), cur.len(), 0f32);
+    let mut result = nalgebra::DVector::repeat(eqs.len(), 0f32);
 
     let mut damping = 1.0;
     let mut prev_err = f32::INFINITY;